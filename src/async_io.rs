@@ -0,0 +1,176 @@
+//! Reading and writing tags through the asynchronous I/O traits of tokio.
+use std::cmp;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use byteorder::{BigEndian, ByteOrder};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use ::stream::unsynch;
+use ::tag::Tag;
+
+
+/// The number of bytes that is read at once, which limits the memory that is allocated up front
+/// for tags that declare a large size.
+const CHUNK_SIZE: usize = 0x1_0000;
+
+/// A future that reads a tag from an asynchronous reader, see `Tag::async_read_from`.
+///
+/// The tag is buffered as a whole and then decoded. Exactly the tag is read, including its
+/// footer, so the reader is left positioned directly after it.
+#[derive(Debug)]
+pub struct ReadTag<R> {
+    reader: R,
+    /// The data of the tag that has been read so far.
+    buf: Vec<u8>,
+    /// The length of the tag including its header and footer, once the header has been read.
+    len: Option<usize>,
+}
+
+impl<R> ReadTag<R> {
+    pub(crate) fn new(reader: R) -> ReadTag<R> {
+        ReadTag { reader, buf: Vec::with_capacity(10), len: None }
+    }
+}
+
+impl<R> Future for ReadTag<R>
+    where R: AsyncRead + Unpin {
+    type Output = ::Result<Tag>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<::Result<Tag>> {
+        let this = &mut *self;
+        loop {
+            let wanted = this.len.unwrap_or(10);
+            if this.buf.len() == wanted {
+                if this.len.is_some() {
+                    return Poll::Ready(::stream::tag::decode(&this.buf[..]));
+                }
+                let header = &this.buf[..10];
+                if &header[..3] != b"ID3" {
+                    return Poll::Ready(Err(::Error::new(::ErrorKind::NoTag, "reader does not contain an id3 tag")));
+                }
+                let mut len = 10 + unsynch::decode_u32(BigEndian::read_u32(&header[6..10])) as usize;
+                if header[3] == 4 && header[5] & 0x10 != 0 {
+                    len += 10;
+                }
+                this.len = Some(len);
+                continue;
+            }
+
+            let start = this.buf.len();
+            this.buf.resize(cmp::min(wanted, start + CHUNK_SIZE), 0);
+            let mut read_buf = ReadBuf::new(&mut this.buf[start..]);
+            let result = Pin::new(&mut this.reader).poll_read(cx, &mut read_buf);
+            let nread = read_buf.filled().len();
+            this.buf.truncate(start + nread);
+            match result {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err.into())),
+                Poll::Ready(Ok(())) if nread == 0 => {
+                    let err = match this.len {
+                        None => ::Error::new(::ErrorKind::NoTag, "reader does not contain an id3 tag"),
+                        Some(_) => io::Error::new(io::ErrorKind::UnexpectedEof, "the tag is truncated").into(),
+                    };
+                    return Poll::Ready(Err(err));
+                },
+                Poll::Ready(Ok(())) => (),
+            }
+        }
+    }
+}
+
+/// A future that writes a tag to an asynchronous writer, see `Tag::async_write_to`.
+///
+/// The tag is encoded up front, the writer is flushed once all of it has been written.
+#[derive(Debug)]
+pub struct WriteTag<W> {
+    writer: W,
+    /// The encoded tag, or the error that occurred while encoding it.
+    data: Result<Vec<u8>, Option<::Error>>,
+    /// The number of bytes that have been written.
+    written: usize,
+}
+
+impl<W> WriteTag<W> {
+    pub(crate) fn new(writer: W, data: ::Result<Vec<u8>>) -> WriteTag<W> {
+        WriteTag { writer, data: data.map_err(Some), written: 0 }
+    }
+}
+
+impl<W> Future for WriteTag<W>
+    where W: AsyncWrite + Unpin {
+    type Output = ::Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<::Result<()>> {
+        let this = &mut *self;
+        let data = match this.data {
+            Ok(ref data) => data,
+            Err(ref mut err) => {
+                let err = err.take()
+                    .unwrap_or_else(|| ::Error::new(::ErrorKind::InvalidInput, "the future was polled after completion"));
+                return Poll::Ready(Err(err));
+            },
+        };
+        while this.written < data.len() {
+            match Pin::new(&mut this.writer).poll_write(cx, &data[this.written..]) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err.into())),
+                Poll::Ready(Ok(0)) => {
+                    let err = io::Error::new(io::ErrorKind::WriteZero, "failed to write the whole tag");
+                    return Poll::Ready(Err(err.into()));
+                },
+                Poll::Ready(Ok(n)) => this.written += n,
+            }
+        }
+        match Pin::new(&mut this.writer).poll_flush(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(result) => Poll::Ready(result.map_err(::Error::from)),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::{RawWaker, RawWakerVTable, Waker};
+    use ::tag::Version;
+
+    fn noop_waker() -> Waker {
+        fn clone(_: *const ()) -> RawWaker {
+            RawWaker::new(::std::ptr::null(), &VTABLE)
+        }
+        fn noop(_: *const ()) { }
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+        unsafe { Waker::from_raw(clone(::std::ptr::null())) }
+    }
+
+    /// Polls the future to completion. The readers and writers of the tests never block.
+    fn block_on<F: Future + Unpin>(mut future: F) -> F::Output {
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = Pin::new(&mut future).poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn async_roundtrip() {
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+        tag.set_artist("Artist");
+        let mut data = Vec::new();
+        block_on(tag.async_write_to(&mut data, Version::Id3v24)).unwrap();
+        data.extend_from_slice(b"audio");
+
+        let mut reader = &data[..];
+        assert_eq!(tag, block_on(Tag::async_read_from(&mut reader)).unwrap());
+        assert_eq!(b"audio", reader);
+
+        let err = block_on(Tag::async_read_from(&b"audio"[..])).unwrap_err();
+        assert!(matches!(err.kind, ::ErrorKind::NoTag));
+        assert!(block_on(Tag::async_read_from(&data[..20])).is_err());
+    }
+}
@@ -0,0 +1,863 @@
+//! Detection of the container formats that may hold an ID3 tag, and the location of the tag within
+//! each of them.
+//!
+//! MPEG audio streams have the tag prepended to the audio data. WAV and AIFF files store the tag in
+//! a dedicated chunk, while DSF files have a pointer to the tag in their header. MP4 files store
+//! the tag in an "ID32" box as defined by 3GPP.
+
+use std::io;
+use std::ops;
+use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt, WriteBytesExt};
+
+
+/// The container formats that can be recognized by their magic bytes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// An MPEG audio stream, or any other file where the tag is expected at the start.
+    Mpeg,
+    /// An AIFF or AIFF-C file, storing the tag in an "ID3 " chunk.
+    Aiff,
+    /// A RIFF WAVE file, or an RF64/BW64 file with 64-bit sizes, storing the tag in an "id3 " chunk.
+    Wav,
+    /// A DSD stream file, storing the tag at the offset in its metadata pointer.
+    Dsf,
+    /// A FLAC stream. FLAC has its own metadata format and should not contain an ID3 tag.
+    Flac,
+    /// An Ogg stream. Ogg has its own metadata format and should not contain an ID3 tag.
+    Ogg,
+    /// An MP4 or 3GPP file, storing the tag in an "ID32" box in the movie box.
+    Mp4,
+}
+
+impl Format {
+    /// Returns true if the format is not supposed to contain ID3 tags at all.
+    pub fn forbids_id3(self) -> bool {
+        matches!(self, Format::Flac | Format::Ogg)
+    }
+}
+
+/// Detects the format of the data in the reader by looking at its first bytes.
+///
+/// The reader position will be reset back to the previous position before returning.
+pub fn sniff<R>(mut reader: R) -> io::Result<Format>
+    where R: io::Read + io::Seek {
+    let initial_position = reader.stream_position()?;
+    let mut magic = [0; 12];
+    let nread = ::util::read_fully(&mut reader, &mut magic)?;
+    reader.seek(io::SeekFrom::Start(initial_position))?;
+    let magic = &magic[..nread];
+
+    let riff = magic.len() >= 12 && (&magic[0..4] == b"RIFF" || &magic[0..4] == b"RF64" || &magic[0..4] == b"BW64");
+    let format = if riff && &magic[8..12] == b"WAVE" {
+        Format::Wav
+    } else if magic.len() >= 12 && &magic[0..4] == b"FORM" && (&magic[8..12] == b"AIFF" || &magic[8..12] == b"AIFC") {
+        Format::Aiff
+    } else if magic.len() >= 4 && &magic[0..4] == b"DSD " {
+        Format::Dsf
+    } else if magic.len() >= 4 && &magic[0..4] == b"fLaC" {
+        Format::Flac
+    } else if magic.len() >= 4 && &magic[0..4] == b"OggS" {
+        Format::Ogg
+    } else if magic.len() >= 8 && &magic[4..8] == b"ftyp" {
+        Format::Mp4
+    } else {
+        Format::Mpeg
+    };
+    Ok(format)
+}
+
+/// The kinds of audio frames that are recognized at the start of a raw audio stream.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AudioFrame {
+    /// An MPEG audio (MP1, MP2 or MP3) frame.
+    Mpeg,
+    /// An AAC frame in an ADTS stream.
+    Adts,
+}
+
+/// Checks whether the bytes start with a plausible audio frame header.
+///
+/// Both MPEG audio and ADTS frames start with a sync word of set bits. They are told apart by the
+/// layer bits, which are always zero for ADTS but are a reserved value for MPEG audio.
+pub fn audio_frame(header: &[u8]) -> Option<AudioFrame> {
+    if header.len() < 4 || header[0] != 0xff || header[1] & 0xe0 != 0xe0 {
+        return None;
+    }
+    let layer = (header[1] >> 1) & 0x03;
+    if layer == 0 {
+        // ADTS has a 12 bit sync word, a sampling frequency index below 13 and a 13 bit frame
+        // length which includes the 7 byte header.
+        if header.len() < 6 || header[1] & 0xf0 != 0xf0 || (header[2] >> 2) & 0x0f >= 13 {
+            return None;
+        }
+        let frame_len = (u32::from(header[3] & 0x03) << 11)
+            | (u32::from(header[4]) << 3)
+            | (u32::from(header[5]) >> 5);
+        if frame_len < 7 {
+            return None;
+        }
+        Some(AudioFrame::Adts)
+    } else {
+        let version = (header[1] >> 3) & 0x03;
+        let bitrate = header[2] >> 4;
+        let sample_rate = (header[2] >> 2) & 0x03;
+        if version == 0x01 || bitrate == 0x0f || sample_rate == 0x03 {
+            return None;
+        }
+        Some(AudioFrame::Mpeg)
+    }
+}
+
+/// Locates the bytes of the ID3 tag in the container. The returned range is relative to the start
+/// of the reader and spans the complete tag, excluding any container specific headers.
+///
+/// Returns `None` if the container does not contain a tag. For `Format::Mpeg` this is always
+/// `None`, the tag is expected at the start of the file. FLAC and Ogg streams never contain a tag.
+pub fn locate<R>(mut reader: R, format: Format) -> ::Result<Option<ops::Range<u64>>>
+    where R: io::Read + io::Seek {
+    let file_len = reader.seek(io::SeekFrom::End(0))?;
+    let location = match format {
+        Format::Mpeg | Format::Flac | Format::Ogg => None,
+        Format::Wav => wav_layout(&mut reader, file_len)?.id3_chunk().map(|chunk| chunk.data.clone()),
+        Format::Aiff => {
+            reader.seek(io::SeekFrom::Start(4))?;
+            let mut size = [0; 4];
+            reader.read_exact(&mut size)?;
+            let end = file_len.min(8 + u64::from(BigEndian::read_u32(&size)));
+            find_chunk::<_, BigEndian>(&mut reader, 12..end, &[b"ID3 "])?
+        },
+        Format::Dsf => {
+            let mut header = [0; 28];
+            reader.seek(io::SeekFrom::Start(0))?;
+            reader.read_exact(&mut header)?;
+            match LittleEndian::read_u64(&header[20..28]) {
+                0 => None,
+                offset if offset < file_len => Some(offset..file_len),
+                _ => return Err(::Error::new(::ErrorKind::Parsing, "DSF metadata pointer is out of bounds")),
+            }
+        },
+        Format::Mp4 => {
+            let location = locate_id32(&mut reader, file_len)?;
+            location.id32.map(|id32| id32.data_start + ID32_PREFIX_LEN..id32.end)
+        },
+    };
+    Ok(location)
+}
+
+/// Finds the first chunk within `range` which has one of the specified IDs and returns the
+/// location of its data.
+///
+/// RIFF and IFF chunks consist of a 4 byte ID and a 4 byte size followed by the data, padded to an
+/// even number of bytes. The two formats only differ in the byte order of the size.
+fn find_chunk<R, B>(mut reader: R, range: ops::Range<u64>, ids: &[&[u8; 4]]) -> io::Result<Option<ops::Range<u64>>>
+    where R: io::Read + io::Seek, B: ByteOrder {
+    let mut pos = range.start;
+    while pos + 8 <= range.end {
+        reader.seek(io::SeekFrom::Start(pos))?;
+        let mut chunk_header = [0; 8];
+        reader.read_exact(&mut chunk_header)?;
+        let size = u64::from(B::read_u32(&chunk_header[4..8]));
+        let data = pos + 8..range.end.min(pos + 8 + size);
+        if ids.iter().any(|id| id[..] == chunk_header[0..4]) {
+            return Ok(Some(data));
+        }
+        pos += 8 + size + size % 2;
+    }
+    Ok(None)
+}
+
+/// A chunk of a WAV file.
+struct WavChunk {
+    id: [u8; 4],
+    start: u64,
+    /// The data of the chunk, excluding the pad byte.
+    data: ops::Range<u64>,
+}
+
+/// The chunks of a WAV file and the sizes that need to be updated if the file is resized.
+struct WavLayout {
+    chunks: Vec<WavChunk>,
+    riff_end: u64,
+    /// The position of the 64-bit RIFF size in the ds64 chunk of RF64 and BW64 files.
+    ds64_riff_size: Option<u64>,
+}
+
+impl WavLayout {
+    fn id3_chunk(&self) -> Option<&WavChunk> {
+        self.chunks.iter().find(|chunk| &chunk.id == b"id3 " || &chunk.id == b"ID3 ")
+    }
+}
+
+/// Reads the chunk layout of a WAV file.
+///
+/// RF64 and BW64 files set the 32-bit sizes of the RIFF header and of large chunks to 0xFFFFFFFF.
+/// The actual sizes are stored in the ds64 chunk, which must be the first chunk of the file.
+fn wav_layout<R>(mut reader: R, file_len: u64) -> ::Result<WavLayout>
+    where R: io::Read + io::Seek {
+    let mut header = [0; 12];
+    reader.seek(io::SeekFrom::Start(0))?;
+    reader.read_exact(&mut header)?;
+    let mut layout = WavLayout {
+        chunks: Vec::new(),
+        riff_end: file_len.min(8 + u64::from(LittleEndian::read_u32(&header[4..8]))),
+        ds64_riff_size: None,
+    };
+
+    let mut data_size = None;
+    let mut table = Vec::new();
+    if &header[0..4] != b"RIFF" {
+        let mut ds64 = [0; 36];
+        reader.read_exact(&mut ds64)?;
+        if &ds64[0..4] != b"ds64" || LittleEndian::read_u32(&ds64[4..8]) < 28 {
+            return Err(::Error::new(::ErrorKind::Parsing, "RF64 file does not start with a ds64 chunk"));
+        }
+        layout.riff_end = file_len.min(8u64.saturating_add(LittleEndian::read_u64(&ds64[8..16])));
+        layout.ds64_riff_size = Some(20);
+        data_size = Some(LittleEndian::read_u64(&ds64[16..24]));
+        let table_len = LittleEndian::read_u32(&ds64[32..36]);
+        for _ in 0..table_len.min((LittleEndian::read_u32(&ds64[4..8]) - 28) / 12) {
+            let mut entry = [0; 12];
+            reader.read_exact(&mut entry)?;
+            table.push((entry[0..4].to_vec(), LittleEndian::read_u64(&entry[4..12])));
+        }
+    }
+
+    let mut pos = 12;
+    while pos + 8 <= layout.riff_end {
+        reader.seek(io::SeekFrom::Start(pos))?;
+        let mut chunk_header = [0; 8];
+        reader.read_exact(&mut chunk_header)?;
+        let mut id = [0; 4];
+        id.copy_from_slice(&chunk_header[0..4]);
+        let size = match LittleEndian::read_u32(&chunk_header[4..8]) {
+            0xffff_ffff if layout.ds64_riff_size.is_some() => match &id {
+                b"data" => data_size.unwrap_or(0),
+                _ => table.iter().find(|entry| entry.0 == id).map_or(0, |entry| entry.1),
+            },
+            size => u64::from(size),
+        };
+        let end = layout.riff_end.min((pos + 8).saturating_add(size));
+        layout.chunks.push(WavChunk { id, start: pos, data: pos + 8..end });
+        pos = end.saturating_add(size % 2);
+    }
+    Ok(layout)
+}
+
+/// Computes the edits that store the encoded tag in the ID3 chunk of a WAV file.
+///
+/// If the existing chunk is large enough, the tag is padded to fill it. Otherwise the chunk is
+/// replaced, or a new chunk is appended, with `padding` bytes of padding after the tag and the
+/// size of the RIFF chunk is updated. For RF64 and BW64 files that size is stored in the ds64
+/// chunk.
+pub fn wav_id3_edits<R>(mut reader: R, tag_data: &[u8], padding: usize) -> ::Result<Vec<(ops::Range<u64>, Vec<u8>)>>
+    where R: io::Read + io::Seek {
+    let file_len = reader.seek(io::SeekFrom::End(0))?;
+    let layout = wav_layout(&mut reader, file_len)?;
+
+    let existing = layout.id3_chunk();
+    if let Some(chunk) = existing {
+        let region_len = (chunk.data.end - chunk.data.start) as usize;
+        if tag_data.len() <= region_len {
+            return Ok(vec![(chunk.data.clone(), pad_tag(tag_data, region_len))]);
+        }
+    }
+
+    // Chunks are padded to an even size. Padding the tag itself keeps the size field exact.
+    let padded_len = tag_data.len() + padding;
+    let tag_data = pad_tag(tag_data, padded_len + padded_len % 2);
+    let mut chunk = Vec::with_capacity(9 + tag_data.len());
+    let region = match existing {
+        Some(old) => {
+            let pad = (old.data.end - old.data.start) % 2;
+            old.start + 4..layout.riff_end.min(old.data.end + pad)
+        },
+        None => {
+            if layout.riff_end % 2 == 1 {
+                chunk.push(0);
+            }
+            chunk.extend_from_slice(b"id3 ");
+            layout.riff_end..layout.riff_end
+        },
+    };
+    chunk.write_u32::<LittleEndian>(tag_data.len() as u32)?;
+    chunk.extend(tag_data);
+    let riff_size = (layout.riff_end - 8) + chunk.len() as u64 - (region.end - region.start);
+
+    let mut size_field = Vec::new();
+    let size_edit = match layout.ds64_riff_size {
+        Some(pos) => {
+            size_field.write_u64::<LittleEndian>(riff_size)?;
+            pos..pos + 8
+        },
+        None if riff_size <= u64::from(u32::MAX) => {
+            size_field.write_u32::<LittleEndian>(riff_size as u32)?;
+            4..8
+        },
+        None => return Err(::Error::new(::ErrorKind::UnsupportedFeature, "WAV file would exceed 4GiB, convert it to RF64")),
+    };
+    Ok(vec![(size_edit, size_field), (region, chunk)])
+}
+
+/// Computes the edits that store the encoded tag in the ID3 chunk of an AIFF or AIFF-C file.
+///
+/// Like `wav_id3_edits`, an existing chunk is reused if the tag fits, otherwise the chunk is
+/// replaced or a new chunk is appended and the size of the FORM chunk is updated.
+pub fn aiff_id3_edits<R>(mut reader: R, tag_data: &[u8], padding: usize) -> ::Result<Vec<(ops::Range<u64>, Vec<u8>)>>
+    where R: io::Read + io::Seek {
+    let file_len = reader.seek(io::SeekFrom::End(0))?;
+    reader.seek(io::SeekFrom::Start(4))?;
+    let form_end = file_len.min(8 + u64::from(reader.read_u32::<BigEndian>()?));
+
+    let existing = find_chunk::<_, BigEndian>(&mut reader, 12..form_end, &[b"ID3 "])?;
+    if let Some(ref data) = existing {
+        let region_len = (data.end - data.start) as usize;
+        if tag_data.len() <= region_len {
+            return Ok(vec![(data.clone(), pad_tag(tag_data, region_len))]);
+        }
+    }
+
+    let padded_len = tag_data.len() + padding;
+    let tag_data = pad_tag(tag_data, padded_len + padded_len % 2);
+    let mut chunk = Vec::with_capacity(9 + tag_data.len());
+    let region = match existing {
+        Some(old) => {
+            let pad = (old.end - old.start) % 2;
+            old.start - 4..form_end.min(old.end + pad)
+        },
+        None => {
+            if form_end % 2 == 1 {
+                chunk.push(0);
+            }
+            chunk.extend_from_slice(b"ID3 ");
+            form_end..form_end
+        },
+    };
+    chunk.write_u32::<BigEndian>(tag_data.len() as u32)?;
+    chunk.extend(tag_data);
+    let form_size = (form_end - 8) + chunk.len() as u64 - (region.end - region.start);
+    if form_size > u64::from(u32::MAX) {
+        return Err(::Error::new(::ErrorKind::UnsupportedFeature, "AIFF file would exceed 4GiB"));
+    }
+    let mut size_field = Vec::new();
+    size_field.write_u32::<BigEndian>(form_size as u32)?;
+    Ok(vec![(4..8, size_field), (region, chunk)])
+}
+
+/// Computes the edits that store the encoded tag at the end of a DSF file.
+///
+/// The tag replaces the metadata the pointer in the DSD chunk refers to, or is appended if there
+/// is none. The pointer and the total file size in the DSD chunk are updated.
+pub fn dsf_id3_edits<R>(mut reader: R, tag_data: &[u8], padding: usize) -> ::Result<Vec<(ops::Range<u64>, Vec<u8>)>>
+    where R: io::Read + io::Seek {
+    let file_len = reader.seek(io::SeekFrom::End(0))?;
+    let region = locate(&mut reader, Format::Dsf)?.unwrap_or(file_len..file_len);
+    let region_len = (region.end - region.start) as usize;
+    let tag_data = if tag_data.len() <= region_len {
+        pad_tag(tag_data, region_len)
+    } else {
+        pad_tag(tag_data, tag_data.len() + padding)
+    };
+
+    let mut header = Vec::new();
+    header.write_u64::<LittleEndian>(region.start + tag_data.len() as u64)?;
+    header.write_u64::<LittleEndian>(region.start)?;
+    Ok(vec![(12..28, header), (region, tag_data)])
+}
+
+/// The length of the fields in an ID32 box before the tag: the version and flags of the full box
+/// and the language code.
+const ID32_PREFIX_LEN: u64 = 6;
+
+/// The header of an MP4 box.
+#[derive(Copy, Clone, Debug)]
+struct Mp4Box {
+    kind: [u8; 4],
+    start: u64,
+    /// The start of the contents, after the size, type and optional 64-bit size.
+    data_start: u64,
+    end: u64,
+    /// A size of zero means that the box extends to the end of the file.
+    open_ended: bool,
+}
+
+/// Reads the headers of all boxes in the range.
+fn read_boxes<R>(mut reader: R, range: ops::Range<u64>) -> ::Result<Vec<Mp4Box>>
+    where R: io::Read + io::Seek {
+    let mut boxes = Vec::new();
+    let mut pos = range.start;
+    while pos + 8 <= range.end {
+        reader.seek(io::SeekFrom::Start(pos))?;
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        let mut kind = [0; 4];
+        kind.copy_from_slice(&header[4..8]);
+        let (size, data_start) = match BigEndian::read_u32(&header[0..4]) {
+            0 => (range.end - pos, pos + 8),
+            1 => {
+                let mut large_size = [0; 8];
+                reader.read_exact(&mut large_size)?;
+                (BigEndian::read_u64(&large_size), pos + 16)
+            },
+            size => (u64::from(size), pos + 8),
+        };
+        let end = pos.checked_add(size)
+            .filter(|end| *end >= data_start && *end <= range.end)
+            .ok_or_else(|| ::Error::new(::ErrorKind::Parsing, "MP4 box size is out of bounds"))?;
+        boxes.push(Mp4Box { kind, start: pos, data_start, end, open_ended: BigEndian::read_u32(&header[0..4]) == 0 });
+        pos = end;
+    }
+    Ok(boxes)
+}
+
+/// The location of the ID32 box and the boxes that contain it.
+struct Id32Location {
+    top_level: Vec<Mp4Box>,
+    moov: Mp4Box,
+    /// The user data box, or the meta box, in the movie box that contains the ID32 box.
+    parent: Option<Mp4Box>,
+    id32: Option<Mp4Box>,
+}
+
+fn locate_id32<R>(mut reader: R, file_len: u64) -> ::Result<Id32Location>
+    where R: io::Read + io::Seek {
+    let top_level = read_boxes(&mut reader, 0..file_len)?;
+    let moov = *top_level.iter()
+        .find(|b| &b.kind == b"moov")
+        .ok_or_else(|| ::Error::new(::ErrorKind::Parsing, "MP4 file does not have a movie box"))?;
+    let mut location = Id32Location { top_level, moov, parent: None, id32: None };
+    for child in read_boxes(&mut reader, moov.data_start..moov.end)? {
+        let children = match &child.kind {
+            b"udta" => child.data_start..child.end,
+            // The meta box is a full box, its children follow the version and flags.
+            b"meta" if child.end - child.data_start >= 4 => child.data_start + 4..child.end,
+            _ => continue,
+        };
+        if let Some(id32) = read_boxes(&mut reader, children)?.into_iter().find(|b| &b.kind == b"ID32") {
+            location.parent = Some(child);
+            location.id32 = Some(id32);
+            return Ok(location);
+        }
+        if &child.kind == b"udta" && location.parent.is_none() {
+            location.parent = Some(child);
+        }
+    }
+    Ok(location)
+}
+
+/// Computes the edits that store the encoded tag in the ID32 box of an MP4 file.
+///
+/// If the existing box is large enough, the tag is padded to fill it. Otherwise, the box is
+/// enlarged or created with `padding` bytes of padding after the tag, which changes the position of all data after it. Since media data is
+/// referenced by absolute offsets, this is only done if the movie box is at the end of the file.
+pub fn mp4_id32_edits<R>(mut reader: R, tag_data: &[u8], padding: usize) -> ::Result<Vec<(ops::Range<u64>, Vec<u8>)>>
+    where R: io::Read + io::Seek {
+    let file_len = reader.seek(io::SeekFrom::End(0))?;
+    let location = locate_id32(&mut reader, file_len)?;
+
+    if let Some(id32) = location.id32 {
+        let region = id32.data_start + ID32_PREFIX_LEN..id32.end;
+        if tag_data.len() as u64 <= region.end - region.start {
+            let padded = pad_tag(tag_data, (region.end - region.start) as usize);
+            return Ok(vec![(region, padded)]);
+        }
+    }
+
+    let moves_media = location.top_level.iter()
+        .filter(|b| b.start > location.moov.start)
+        .any(|b| &b.kind != b"free" && &b.kind != b"skip");
+    if moves_media {
+        return Err(::Error::new(::ErrorKind::UnsupportedFeature, "the ID32 box can only be enlarged if the movie box is at the end of the file"));
+    }
+
+    let tag_data = pad_tag(tag_data, tag_data.len() + padding);
+    let mut id32 = Vec::with_capacity(8 + ID32_PREFIX_LEN as usize + tag_data.len());
+    id32.write_u32::<BigEndian>((8 + ID32_PREFIX_LEN as usize + tag_data.len()) as u32)?;
+    id32.extend_from_slice(b"ID32");
+    id32.extend_from_slice(&[0, 0, 0, 0]);
+    // The language is packed into three 5-bit fields, "und" stands for undetermined.
+    id32.extend_from_slice(&[0x55, 0xc4]);
+    id32.extend(tag_data);
+
+    let (region, data, ancestors) = match (location.parent, location.id32) {
+        (Some(parent), Some(old)) => (old.start..old.end, id32, vec![location.moov, parent]),
+        (Some(udta), None) => (udta.end..udta.end, id32, vec![location.moov, udta]),
+        (None, _) => {
+            let mut udta = Vec::with_capacity(8 + id32.len());
+            udta.write_u32::<BigEndian>(8 + id32.len() as u32)?;
+            udta.extend_from_slice(b"udta");
+            udta.extend(id32);
+            (location.moov.end..location.moov.end, udta, vec![location.moov])
+        },
+    };
+    let delta = data.len() as i64 - (region.end - region.start) as i64;
+
+    let mut edits = Vec::new();
+    for ancestor in ancestors.iter().filter(|b| !b.open_ended) {
+        let size = (ancestor.end - ancestor.start) as i64 + delta;
+        if ancestor.data_start - ancestor.start == 16 {
+            let mut field = Vec::new();
+            field.write_u64::<BigEndian>(size as u64)?;
+            edits.push((ancestor.start + 8..ancestor.start + 16, field));
+        } else if size <= i64::from(u32::MAX) {
+            let mut field = Vec::new();
+            field.write_u32::<BigEndian>(size as u32)?;
+            edits.push((ancestor.start..ancestor.start + 4, field));
+        } else {
+            return Err(::Error::new(::ErrorKind::UnsupportedFeature, "MP4 box would exceed 4GiB"));
+        }
+    }
+    edits.push((region, data));
+    Ok(edits)
+}
+
+/// Pads the encoded tag with zeros up to `len` bytes. The padding is included in the tag size,
+/// except for ID3v2.4 tags with a footer which must not hold padding. Their size is left as is and
+/// the zeros follow the footer.
+fn pad_tag(tag_data: &[u8], len: usize) -> Vec<u8> {
+    let mut padded = tag_data.to_vec();
+    padded.resize(len, 0);
+    let footer = tag_data.len() >= 10 && tag_data[3] == 4 && tag_data[5] & 0x10 != 0;
+    if padded.len() >= 10 && !footer {
+        let size = ::stream::unsynch::encode_u32((padded.len() - 10) as u32);
+        BigEndian::write_u32(&mut padded[6..10], size);
+    }
+    padded
+}
+
+
+#[cfg(test)]
+mod tests {
+    extern crate tempdir;
+    use super::*;
+    use std::fs;
+    use std::io::Cursor;
+    use ::tag::Tag;
+
+    fn id3v24() -> Vec<u8> {
+        fs::read("testdata/id3v24.id3").unwrap()
+    }
+
+    fn riff(id: &[u8; 4], form: &[u8; 4], chunks: &[(&[u8; 4], &[u8])], big_endian: bool) -> Vec<u8> {
+        let mut body = form.to_vec();
+        for &(chunk_id, data) in chunks {
+            body.extend_from_slice(chunk_id);
+            if big_endian {
+                body.write_u32::<BigEndian>(data.len() as u32).unwrap();
+            } else {
+                body.write_u32::<LittleEndian>(data.len() as u32).unwrap();
+            }
+            body.extend_from_slice(data);
+            if data.len() % 2 == 1 {
+                body.push(0);
+            }
+        }
+        let mut file = id.to_vec();
+        if big_endian {
+            file.write_u32::<BigEndian>(body.len() as u32).unwrap();
+        } else {
+            file.write_u32::<LittleEndian>(body.len() as u32).unwrap();
+        }
+        file.extend(body);
+        file
+    }
+
+    #[test]
+    fn sniff_formats() {
+        let wav = riff(b"RIFF", b"WAVE", &[], false);
+        assert_eq!(Format::Wav, sniff(Cursor::new(&wav)).unwrap());
+        let aiff = riff(b"FORM", b"AIFC", &[], true);
+        assert_eq!(Format::Aiff, sniff(Cursor::new(&aiff)).unwrap());
+        assert_eq!(Format::Dsf, sniff(Cursor::new(b"DSD \x1c\0\0\0")).unwrap());
+        assert_eq!(Format::Flac, sniff(Cursor::new(b"fLaC\0\0\0\x22")).unwrap());
+        assert_eq!(Format::Ogg, sniff(Cursor::new(b"OggS\0\x02")).unwrap());
+        assert_eq!(Format::Mp4, sniff(Cursor::new(b"\0\0\0\x14ftypM4A ")).unwrap());
+        assert_eq!(Format::Mpeg, sniff(Cursor::new(id3v24())).unwrap());
+        assert_eq!(Format::Mpeg, sniff(Cursor::new(b"ID")).unwrap());
+    }
+
+    #[test]
+    fn detect_audio_frames() {
+        assert_eq!(Some(AudioFrame::Mpeg), audio_frame(&[0xff, 0xfb, 0x90, 0x00]));
+        assert_eq!(Some(AudioFrame::Mpeg), audio_frame(&[0xff, 0xf3, 0x44, 0xc4]));
+        assert_eq!(Some(AudioFrame::Adts), audio_frame(&[0xff, 0xf1, 0x50, 0x80, 0x2e, 0x7f]));
+        assert_eq!(Some(AudioFrame::Adts), audio_frame(&[0xff, 0xf9, 0x4c, 0x80, 0x1d, 0x3f]));
+        // Reserved MPEG version, bad bitrate, bad ADTS sampling frequency, short ADTS frame.
+        assert_eq!(None, audio_frame(&[0xff, 0xeb, 0x90, 0x00]));
+        assert_eq!(None, audio_frame(&[0xff, 0xfb, 0xf0, 0x00]));
+        assert_eq!(None, audio_frame(&[0xff, 0xf1, 0x3c, 0x80, 0x2e, 0x7f]));
+        assert_eq!(None, audio_frame(&[0xff, 0xf1, 0x50, 0x80, 0x00, 0x1f]));
+        assert_eq!(None, audio_frame(&[0xff, 0xf1, 0x50, 0x80]));
+        assert_eq!(None, audio_frame(b"junk"));
+    }
+
+    #[test]
+    fn locate_wav() {
+        let tag = id3v24();
+        let wav = riff(b"RIFF", b"WAVE", &[(b"fmt ", b"odd"), (b"id3 ", &tag)], false);
+        let location = locate(Cursor::new(&wav), Format::Wav).unwrap().unwrap();
+        assert_eq!(&tag[..], &wav[location.start as usize..location.end as usize]);
+
+        let wav = riff(b"RIFF", b"WAVE", &[(b"data", b"")], false);
+        assert!(locate(Cursor::new(&wav), Format::Wav).unwrap().is_none());
+    }
+
+    #[test]
+    fn locate_aiff() {
+        let tag = id3v24();
+        let aiff = riff(b"FORM", b"AIFF", &[(b"COMM", &[0; 18]), (b"ID3 ", &tag)], true);
+        let location = locate(Cursor::new(&aiff), Format::Aiff).unwrap().unwrap();
+        assert_eq!(&tag[..], &aiff[location.start as usize..location.end as usize]);
+    }
+
+    #[test]
+    fn locate_dsf() {
+        let tag = id3v24();
+        let mut dsf = b"DSD ".to_vec();
+        dsf.write_u64::<LittleEndian>(28).unwrap();
+        dsf.write_u64::<LittleEndian>(28 + 16 + tag.len() as u64).unwrap();
+        dsf.write_u64::<LittleEndian>(28 + 16).unwrap();
+        dsf.extend_from_slice(&[0; 16]);
+        dsf.extend_from_slice(&tag);
+        let location = locate(Cursor::new(&dsf), Format::Dsf).unwrap().unwrap();
+        assert_eq!(44..dsf.len() as u64, location);
+
+        dsf[20..28].copy_from_slice(&[0; 8]);
+        assert!(locate(Cursor::new(&dsf), Format::Dsf).unwrap().is_none());
+    }
+
+    #[test]
+    fn read_from_path_wav() {
+        let tmp = tempdir::TempDir::new("id3_container").unwrap();
+        let path = tmp.path().join("test.wav");
+        fs::write(&path, riff(b"RIFF", b"WAVE", &[(b"data", &[0; 4]), (b"ID3 ", &id3v24())], false)).unwrap();
+        assert_eq!("Title", Tag::read_from_path(&path).unwrap().title().unwrap());
+
+        fs::write(&path, riff(b"RIFF", b"WAVE", &[(b"data", &[0; 4])], false)).unwrap();
+        assert!(Tag::read_from_path(&path).is_err());
+    }
+
+    #[test]
+    fn read_write_wav_chunk() {
+        let tmp = tempdir::TempDir::new("id3_container").unwrap();
+        let path = tmp.path().join("test.wav");
+        fs::write(&path, riff(b"RIFF", b"WAVE", &[(b"data", &[0; 3]), (b"LIST", &[0; 4])], false)).unwrap();
+        let err = Tag::read_from_wav(&path).unwrap_err();
+        assert!(matches!(err.kind, ::ErrorKind::NoTag));
+
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+        tag.write_to_wav(&path, ::Version::Id3v23).unwrap();
+        assert_eq!(tag, Tag::read_from_wav(&path).unwrap());
+        let written = fs::read(&path).unwrap();
+        assert_eq!(written.len() as u64, 8 + u64::from(LittleEndian::read_u32(&written[4..8])));
+        // The odd sized data chunk is still followed by its pad byte and the LIST chunk.
+        assert_eq!(b"LIST", &written[24..28]);
+
+        tag.set_artist("a".repeat(4096));
+        tag.write_to_wav(&path, ::Version::Id3v24).unwrap();
+        assert_eq!(tag, Tag::read_from_wav(&path).unwrap());
+        let written = fs::read(&path).unwrap();
+        assert_eq!(written.len() as u64, 8 + u64::from(LittleEndian::read_u32(&written[4..8])));
+
+        fs::write(&path, id3v24()).unwrap();
+        assert!(matches!(Tag::read_from_wav(&path).unwrap_err().kind, ::ErrorKind::InvalidInput));
+        assert!(matches!(tag.write_to_wav(&path, ::Version::Id3v24).unwrap_err().kind, ::ErrorKind::InvalidInput));
+    }
+
+    #[test]
+    fn write_wav_with_footer() {
+        let tmp = tempdir::TempDir::new("id3_container").unwrap();
+        let path = tmp.path().join("test.wav");
+        fs::write(&path, riff(b"RIFF", b"WAVE", &[(b"data", &[0; 4])], false)).unwrap();
+        let encoder = ::stream::tag::EncoderBuilder::default().footer(true).build().unwrap();
+
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+        tag.set_artist("Artist");
+        encoder.encode_to_path(&tag, &path).unwrap();
+        // The old chunk is reused for the smaller tag, which must not gain padding.
+        tag.remove_artist();
+        encoder.encode_to_path(&tag, &path).unwrap();
+        assert_eq!(tag, Tag::read_from_path(&path).unwrap());
+
+        let written = fs::read(&path).unwrap();
+        let location = locate(Cursor::new(&written), Format::Wav).unwrap().unwrap();
+        let data = &written[location.start as usize..location.end as usize];
+        let size = ::stream::unsynch::decode_u32(BigEndian::read_u32(&data[6..10])) as usize;
+        assert_eq!(b"3DI", &data[10 + size..13 + size]);
+        assert!(data[20 + size..].iter().all(|&b| b == 0));
+    }
+
+    fn mp4_box(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut b = Vec::new();
+        b.write_u32::<BigEndian>(8 + data.len() as u32).unwrap();
+        b.extend_from_slice(kind);
+        b.extend_from_slice(data);
+        b
+    }
+
+    fn mp4(moov_first: bool, tag: &[u8]) -> Vec<u8> {
+        let mut id32 = vec![0, 0, 0, 0, 0x55, 0xc4];
+        id32.extend_from_slice(tag);
+        let udta = mp4_box(b"udta", &mp4_box(b"ID32", &id32));
+        let moov = mp4_box(b"moov", &[mp4_box(b"mvhd", &[0; 4]), udta].concat());
+        let mdat = mp4_box(b"mdat", &[0; 16]);
+        let mut file = mp4_box(b"ftyp", b"M4A \0\0\0\0");
+        if moov_first {
+            file.extend(moov);
+            file.extend(mdat);
+        } else {
+            file.extend(mdat);
+            file.extend(moov);
+        }
+        file
+    }
+
+    #[test]
+    fn locate_mp4() {
+        let tag = id3v24();
+        let file = mp4(true, &tag);
+        let location = locate(Cursor::new(&file), Format::Mp4).unwrap().unwrap();
+        assert_eq!(&tag[..], &file[location.start as usize..location.end as usize]);
+
+        let mut moov = mp4_box(b"moov", &mp4_box(b"mvhd", &[0; 4]));
+        let mut file = mp4_box(b"ftyp", b"M4A \0\0\0\0");
+        file.append(&mut moov);
+        assert!(locate(Cursor::new(&file), Format::Mp4).unwrap().is_none());
+    }
+
+    #[test]
+    fn write_mp4() {
+        let tmp = tempdir::TempDir::new("id3_container").unwrap();
+        let path = tmp.path().join("test.m4a");
+        let mut tag = Tag::read_from(Cursor::new(id3v24())).unwrap();
+        tag.set_artist("a".repeat(4096));
+
+        // The movie box is at the end of the file, so the ID32 box can grow.
+        fs::write(&path, mp4(false, &id3v24())).unwrap();
+        tag.write_to_path(&path, ::Version::Id3v24).unwrap();
+        assert_eq!(tag.artist(), Tag::read_from_path(&path).unwrap().artist());
+        let file = fs::read(&path).unwrap();
+        assert_eq!(file.len(), read_boxes(Cursor::new(&file), 0..file.len() as u64).unwrap()
+            .last().unwrap().end as usize);
+
+        // The new tag fits in the existing box.
+        tag.set_artist("b");
+        tag.write_to_path(&path, ::Version::Id3v24).unwrap();
+        assert_eq!(file.len(), fs::read(&path).unwrap().len());
+        assert_eq!(Some("b"), Tag::read_from_path(&path).unwrap().artist());
+
+        // Enlarging the box would move the media data.
+        fs::write(&path, mp4(true, &id3v24())).unwrap();
+        tag.set_artist("a".repeat(4096));
+        assert!(tag.write_to_path(&path, ::Version::Id3v24).is_err());
+
+        // A user data box is created if there is none.
+        let moov = mp4_box(b"moov", &mp4_box(b"mvhd", &[0; 4]));
+        fs::write(&path, [mp4_box(b"ftyp", b"M4A \0\0\0\0"), moov].concat()).unwrap();
+        tag.write_to_path(&path, ::Version::Id3v24).unwrap();
+        assert_eq!(tag.artist(), Tag::read_from_path(&path).unwrap().artist());
+    }
+
+    fn rf64(tag: &[u8]) -> Vec<u8> {
+        let mut ds64 = Vec::new();
+        ds64.write_u64::<LittleEndian>(0).unwrap(); // Updated below.
+        ds64.write_u64::<LittleEndian>(6).unwrap();
+        ds64.write_u64::<LittleEndian>(0).unwrap();
+        ds64.write_u32::<LittleEndian>(1).unwrap();
+        ds64.extend_from_slice(b"big ");
+        ds64.write_u64::<LittleEndian>(2).unwrap();
+        let mut file = riff(b"RF64", b"WAVE", &[(b"ds64", &ds64), (b"big ", b"xx"), (b"data", &[0; 6]), (b"id3 ", tag)], false);
+        // Large chunks have their size in the ds64 table.
+        for id in &[b"big ", b"data"] {
+            let pos = file.windows(4).rposition(|w| w == &id[..]).unwrap();
+            file[pos + 4..pos + 8].copy_from_slice(&[0xff; 4]);
+        }
+        let riff_size = file.len() as u64 - 8;
+        file[4..8].copy_from_slice(&[0xff; 4]);
+        LittleEndian::write_u64(&mut file[20..28], riff_size);
+        file
+    }
+
+    #[test]
+    fn locate_rf64() {
+        let tag = id3v24();
+        let file = rf64(&tag);
+        assert_eq!(Format::Wav, sniff(Cursor::new(&file)).unwrap());
+        let location = locate(Cursor::new(&file), Format::Wav).unwrap().unwrap();
+        assert_eq!(&tag[..], &file[location.start as usize..location.end as usize]);
+    }
+
+    #[test]
+    fn write_wav() {
+        let tmp = tempdir::TempDir::new("id3_container").unwrap();
+        let path = tmp.path().join("test.wav");
+        let mut tag = Tag::read_from(Cursor::new(id3v24())).unwrap();
+        tag.set_artist("a".repeat(4096));
+
+        for file in &[rf64(&id3v24()), riff(b"RIFF", b"WAVE", &[(b"data", &[0; 3])], false)] {
+            fs::write(&path, file).unwrap();
+            tag.write_to_path(&path, ::Version::Id3v24).unwrap();
+            assert_eq!(tag.artist(), Tag::read_from_path(&path).unwrap().artist());
+            let written = fs::read(&path).unwrap();
+            let riff_size = match &written[..4] {
+                b"RF64" => LittleEndian::read_u64(&written[20..28]),
+                _ => u64::from(LittleEndian::read_u32(&written[4..8])),
+            };
+            assert_eq!(written.len() as u64, 8 + riff_size);
+            assert_eq!(&file[..4], &written[..4]);
+            assert_eq!(0, written.len() % 2);
+        }
+    }
+
+    #[test]
+    fn write_aiff() {
+        let tmp = tempdir::TempDir::new("id3_container").unwrap();
+        let path = tmp.path().join("test.aiff");
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+
+        let files = [
+            riff(b"FORM", b"AIFF", &[(b"COMM", &[0; 18]), (b"SSND", &[0; 3])], true),
+            riff(b"FORM", b"AIFC", &[(b"ID3 ", &id3v24()), (b"SSND", &[0; 4])], true),
+        ];
+        for file in &files {
+            fs::write(&path, file).unwrap();
+            for artist in &["Artist".to_string(), "a".repeat(4096)] {
+                tag.set_artist(&artist[..]);
+                tag.write_to_path(&path, ::Version::Id3v24).unwrap();
+                assert_eq!(tag, Tag::read_from_path(&path).unwrap());
+                let written = fs::read(&path).unwrap();
+                assert_eq!((&file[..4], &file[8..12]), (&written[..4], &written[8..12]));
+                assert_eq!(written.len() as u64, 8 + u64::from(BigEndian::read_u32(&written[4..8])));
+                assert_eq!(0, written.len() % 2);
+                assert!(find_chunk::<_, BigEndian>(Cursor::new(&written), 12..written.len() as u64, &[b"SSND"]).unwrap().is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn write_dsf() {
+        let tmp = tempdir::TempDir::new("id3_container").unwrap();
+        let path = tmp.path().join("test.dsf");
+        let mut dsf = b"DSD ".to_vec();
+        dsf.write_u64::<LittleEndian>(28).unwrap();
+        dsf.write_u64::<LittleEndian>(28 + 16).unwrap();
+        dsf.write_u64::<LittleEndian>(0).unwrap();
+        dsf.extend_from_slice(&[0xaa; 16]);
+        fs::write(&path, &dsf).unwrap();
+
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+        for artist in &["Artist".to_string(), "a".repeat(4096), "Short".to_string()] {
+            tag.set_artist(&artist[..]);
+            tag.write_to_path(&path, ::Version::Id3v24).unwrap();
+            assert_eq!(tag, Tag::read_from_path(&path).unwrap());
+            let written = fs::read(&path).unwrap();
+            assert_eq!(&dsf[..12], &written[..12]);
+            assert_eq!(&[0xaa; 16], &written[28..44]);
+            assert_eq!(written.len() as u64, LittleEndian::read_u64(&written[12..20]));
+            assert_eq!(44, LittleEndian::read_u64(&written[20..28]));
+        }
+    }
+}
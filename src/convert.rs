@@ -0,0 +1,159 @@
+use ::frame::{ChannelType, Equalisation, Frame, InterpolationMethod, RelativeVolumeAdjustment, VolumeAdjustment};
+use ::tag::Version;
+
+
+/// The frames that could not be represented in the version a tag was converted to, or only
+/// partially, see `Tag::convert_to`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConversionReport {
+    /// The frames that were removed from the tag.
+    pub dropped: Vec<Frame>,
+    /// The frames that were converted at the cost of some information, as pairs of the frame ID
+    /// and a description of what was lost.
+    pub lossy: Vec<(String, String)>,
+}
+
+/// The frames that only exist in ID3v2.4.
+const ID3V24_FRAMES: &[&str] = &[
+    "ASPI", "EQU2", "RVA2", "SEEK", "SIGN", "TDEN", "TDOR", "TDRC", "TDRL", "TDTG", "TIPL", "TMCL",
+    "TMOO", "TPRO", "TSOA", "TSOP", "TSOT", "TSST",
+];
+
+/// The frames of ID3v2.2 and ID3v2.3 that were removed in ID3v2.4.
+const ID3V23_FRAMES: &[&str] = &["EQUA", "IPLS", "RVAD", "TDAT", "TIME", "TORY", "TRDA", "TSIZ", "TYER"];
+
+/// The roles of the involved people list (IPLS) that belong in the TIPL frame of ID3v2.4. All
+/// other roles are taken to be instruments, which belong in the musician credits list (TMCL).
+const INVOLVEMENT_ROLES: &[&str] = &["arranger", "dj-mix", "engineer", "mix", "producer"];
+
+/// Returns whether the role of an involved people list belongs in TIPL rather than TMCL.
+pub(crate) fn is_involvement_role(role: &str) -> bool {
+    INVOLVEMENT_ROLES.iter().any(|known| known.eq_ignore_ascii_case(role.trim()))
+}
+
+/// Returns whether a frame with the specified ID exists in the specified version.
+pub(crate) fn is_representable(id: &str, version: Version) -> bool {
+    match version {
+        Version::Id3v24 => !ID3V23_FRAMES.contains(&id),
+        Version::Id3v22 => !ID3V24_FRAMES.contains(&id) && ::util::convert_id_3_to_2(id).is_some(),
+        Version::Id3v23 => !ID3V24_FRAMES.contains(&id),
+    }
+}
+
+/// Converts a relative volume change of RVAD and EQUA frames to 1/512 dB. The change is a linear
+/// factor of the volume, in units of 1/2^bits.
+fn linear_to_adjustment(increment: bool, change: u64, bits: u8) -> i16 {
+    let ratio = change as f64 / (1u64 << bits.min(63)) as f64;
+    let factor = if increment { 1.0 + ratio } else { 1.0 - ratio };
+    let db = if factor > 0.0 { 20.0 * factor.log10() } else { -64.0 };
+    (db * 512.0).round().clamp(f64::from(i16::MIN), f64::from(i16::MAX)) as i16
+}
+
+/// Reads the big-endian number of `len` bytes at `*i`.
+fn read_number(data: &[u8], i: &mut usize, len: usize) -> Option<u64> {
+    let bytes = data.get(*i..*i + len)?;
+    *i += len;
+    Some(bytes.iter().fold(0, |n, b| n << 8 | u64::from(*b)))
+}
+
+/// Converts the data of an ID3v2.3 relative volume adjustment frame (RVAD) to an RVA2 frame.
+/// Returns `None` if the data is invalid.
+pub(crate) fn rvad_to_rva2(data: &[u8]) -> Option<RelativeVolumeAdjustment> {
+    let (&flags, &bits) = (data.first()?, data.get(1)?);
+    if bits == 0 || bits > 64 {
+        return None;
+    }
+    let len = (bits as usize).div_ceil(8);
+    let mut i = 2;
+    // The channels in the order of their fields, with the bit of their increment flag. Each pair
+    // of channels is followed by their peaks, the rear channels, centre and bass are optional.
+    let groups: &[&[(ChannelType, u8)]] = &[
+        &[(ChannelType::FrontRight, 0x01), (ChannelType::FrontLeft, 0x02)],
+        &[(ChannelType::BackRight, 0x04), (ChannelType::BackLeft, 0x08)],
+        &[(ChannelType::FrontCentre, 0x10)],
+        &[(ChannelType::Subwoofer, 0x20)],
+    ];
+    let mut channels = Vec::new();
+    for (n, group) in groups.iter().enumerate() {
+        if n > 0 && i == data.len() {
+            break;
+        }
+        let mut changes = Vec::new();
+        for &(channel, flag) in group.iter() {
+            changes.push((channel, flags & flag != 0, read_number(data, &mut i, len)?));
+        }
+        for (channel, increment, change) in changes {
+            let peak = data.get(i..i + len)?.to_vec();
+            i += len;
+            let (peak_bits, peak) = if peak.iter().all(|b| *b == 0) { (0, Vec::new()) } else { (bits, peak) };
+            channels.push(VolumeAdjustment {
+                channel,
+                adjustment: linear_to_adjustment(increment, change, bits),
+                peak_bits,
+                peak,
+            });
+        }
+    }
+    Some(RelativeVolumeAdjustment { identification: String::new(), channels })
+}
+
+/// Converts the data of an ID3v2.3 equalisation frame (EQUA) to an EQU2 frame. Returns `None` if
+/// the data is invalid.
+pub(crate) fn equa_to_equ2(data: &[u8]) -> Option<Equalisation> {
+    let bits = *data.first()?;
+    if bits == 0 || bits > 64 {
+        return None;
+    }
+    let len = (bits as usize).div_ceil(8);
+    let mut i = 1;
+    let mut adjustments = Vec::new();
+    while i < data.len() {
+        let frequency = read_number(data, &mut i, 2)? as u16;
+        let change = read_number(data, &mut i, len)?;
+        // EQU2 frequencies are in units of 1/2 Hz.
+        adjustments.push(((frequency & 0x7fff) * 2, linear_to_adjustment(frequency & 0x8000 != 0, change, bits)));
+    }
+    Some(Equalisation { method: InterpolationMethod::Band, identification: String::new(), adjustments })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_rvad() {
+        // The right channel is raised by half its volume, the left lowered by half.
+        let data = [0x01, 16, 0x80, 0x00, 0x80, 0x00, 0x12, 0x34, 0x00, 0x00];
+        let rva2 = rvad_to_rva2(&data).unwrap();
+        assert_eq!(2, rva2.channels.len());
+        assert_eq!(ChannelType::FrontRight, rva2.channels[0].channel);
+        assert!((rva2.channels[0].adjustment_db() - 3.52).abs() < 0.01);
+        assert_eq!((16, vec![0x12, 0x34]), (rva2.channels[0].peak_bits, rva2.channels[0].peak.clone()));
+        assert!((rva2.channels[1].adjustment_db() + 6.02).abs() < 0.01);
+        assert_eq!(0, rva2.channels[1].peak_bits);
+
+        let mut data = data.to_vec();
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+        let channels: Vec<_> = rvad_to_rva2(&data).unwrap().channels.iter().map(|c| c.channel).collect();
+        assert_eq!(vec![ChannelType::FrontRight, ChannelType::FrontLeft, ChannelType::BackRight, ChannelType::BackLeft, ChannelType::FrontCentre], channels);
+        assert!(rvad_to_rva2(&data[..7]).is_none());
+    }
+
+    #[test]
+    fn convert_equa() {
+        let data = [16, 0x80, 0x64, 0x80, 0x00, 0x03, 0xe8, 0x00, 0x00];
+        let equ2 = equa_to_equ2(&data).unwrap();
+        assert_eq!(vec![(200, 1803), (2000, 0)], equ2.adjustments);
+        assert!(equa_to_equ2(&data[..4]).is_none());
+    }
+
+    #[test]
+    fn representable() {
+        assert!(is_representable("TIT2", Version::Id3v22));
+        assert!(!is_representable("TDRC", Version::Id3v23));
+        assert!(!is_representable("TYER", Version::Id3v24));
+        assert!(!is_representable("TSO2", Version::Id3v22));
+    }
+}
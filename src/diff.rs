@@ -0,0 +1,168 @@
+use std::fmt;
+use ::frame::{Content, Frame};
+use ::tag::Tag;
+
+
+/// The differences between two tags, see `Tag::diff`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TagDiff {
+    /// The frames that only exist in the new tag.
+    pub added: Vec<Frame>,
+    /// The frames that only exist in the old tag.
+    pub removed: Vec<Frame>,
+    /// The frames that exist in both tags with a different content.
+    pub changed: Vec<FrameChange>,
+}
+
+/// A frame of which the content differs between two tags.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameChange {
+    /// The frame in the old tag.
+    pub old: Frame,
+    /// The frame in the new tag.
+    pub new: Frame,
+    /// The text fields that changed. Empty if the frame has no text fields or only other parts of
+    /// the content changed.
+    pub fields: Vec<FieldChange>,
+}
+
+/// A text field of a frame that changed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldChange {
+    /// The name of the field, like "text" or "description".
+    pub field: &'static str,
+    /// The old value of the field.
+    pub old: String,
+    /// The new value of the field.
+    pub new: String,
+}
+
+impl TagDiff {
+    /// Returns whether the tags have the same frames.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for TagDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for frame in &self.removed {
+            writeln!(f, "- {}: {}", frame.id(), frame)?;
+        }
+        for frame in &self.added {
+            writeln!(f, "+ {}: {}", frame.id(), frame)?;
+        }
+        for change in &self.changed {
+            if change.fields.is_empty() {
+                writeln!(f, "~ {}: {} -> {}", change.new.id(), change.old, change.new)?;
+            }
+            for field in &change.fields {
+                writeln!(f, "~ {} {}: {} -> {}", change.new.id(), field.field, field.old, field.new)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether the contents differ. The `PartialEq` implementations of many contents only
+/// compare the fields that tell frames apart in a tag, so the encoded contents are compared.
+fn has_changed(old: &Content, new: &Content) -> bool {
+    match (::stream::frame::encode_content(old), ::stream::frame::encode_content(new)) {
+        (Ok(a), Ok(b)) => a != b,
+        _ => old != new,
+    }
+}
+
+/// Lists the text fields that differ between the contents.
+fn field_changes(old: &Content, new: &Content) -> Vec<FieldChange> {
+    let fields: Vec<(&'static str, &str, &str)> = match (old, new) {
+        (Content::Text(_), Content::Text(_)) => {
+            let join = |content: &Content| content.text_values().collect::<Vec<_>>().join("; ");
+            return vec![FieldChange { field: "text", old: join(old), new: join(new) }];
+        },
+        (Content::Link(a), Content::Link(b)) => vec![("link", a, b)],
+        (Content::ExtendedText(a), Content::ExtendedText(b)) => vec![("value", &a.value, &b.value)],
+        (Content::ExtendedLink(a), Content::ExtendedLink(b)) => vec![("link", &a.link, &b.link)],
+        (Content::Comment(a), Content::Comment(b)) => vec![("text", &a.text, &b.text)],
+        (Content::Lyrics(a), Content::Lyrics(b)) => vec![("text", &a.text, &b.text)],
+        (Content::Picture(a), Content::Picture(b)) => vec![("mime_type", &a.mime_type, &b.mime_type)],
+        (Content::EncapsulatedObject(a), Content::EncapsulatedObject(b)) => vec![("mime_type", &a.mime_type, &b.mime_type), ("filename", &a.filename, &b.filename)],
+        _ => Vec::new(),
+    };
+    fields.into_iter()
+        .filter(|&(_, old, new)| old != new)
+        .map(|(field, old, new)| FieldChange { field, old: old.to_string(), new: new.to_string() })
+        .collect()
+}
+
+/// Compares the frames of the tags, see `Tag::diff`.
+pub(crate) fn diff(old: &Tag, new: &Tag) -> TagDiff {
+    let mut diff = TagDiff::default();
+    let mut unmatched: Vec<&Frame> = new.frames().collect();
+    for old_frame in old.frames() {
+        match unmatched.iter().position(|new_frame| *new_frame == old_frame) {
+            Some(i) => {
+                let new_frame = unmatched.remove(i);
+                if has_changed(old_frame.content(), new_frame.content()) {
+                    diff.changed.push(FrameChange {
+                        old: old_frame.clone(),
+                        new: new_frame.clone(),
+                        fields: field_changes(old_frame.content(), new_frame.content()),
+                    });
+                }
+            },
+            None => diff.removed.push(old_frame.clone()),
+        }
+    }
+    diff.added = unmatched.into_iter().cloned().collect();
+    diff
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::frame::{Comment, Picture, PictureType};
+
+    #[test]
+    fn diff_frames() {
+        let mut old = Tag::new();
+        old.set_title("Title");
+        old.set_artist("Artist");
+        old.add_extended_text("Key", "Old");
+        old.add_comment(Comment { lang: "eng".to_string(), description: "".to_string(), text: "Comment".to_string() });
+        let mut new = old.clone();
+        new.set_title("New Title");
+        new.remove("TPE1");
+        new.remove_extended_text(Some("Key"), None);
+        new.add_extended_text("Key", "New");
+        new.set_album("Album");
+        new.add_picture(Picture {
+            mime_type: "image/png".to_string(),
+            picture_type: PictureType::CoverFront,
+            description: "".to_string(),
+            data: vec![1, 2, 3],
+        });
+
+        let diff = old.diff(&new);
+        assert_eq!(vec!["TPE1"], diff.removed.iter().map(|f| f.id()).collect::<Vec<_>>());
+        assert_eq!(vec!["TALB", "APIC"], diff.added.iter().map(|f| f.id()).collect::<Vec<_>>());
+        assert_eq!(2, diff.changed.len());
+        assert_eq!(vec![FieldChange { field: "text", old: "Title".to_string(), new: "New Title".to_string() }], diff.changed[0].fields);
+        assert_eq!(vec![FieldChange { field: "value", old: "Old".to_string(), new: "New".to_string() }], diff.changed[1].fields);
+        assert_eq!(
+            "- TPE1: Artist\n+ TALB: Album\n+ APIC: : CoverFront (\"image/png\")\n~ TIT2 text: Title -> New Title\n~ TXXX value: Old -> New\n",
+            diff.to_string(),
+        );
+
+        assert!(old.diff(&old.clone()).is_empty());
+        let mut picture = new.pictures().next().unwrap().clone();
+        picture.data = vec![4];
+        let mut with_picture = new.clone();
+        with_picture.remove_picture_by_type(PictureType::CoverFront);
+        with_picture.add_picture(picture);
+        let diff = new.diff(&with_picture);
+        assert_eq!((0, 0, 1), (diff.added.len(), diff.removed.len(), diff.changed.len()));
+        assert!(diff.changed[0].fields.is_empty());
+    }
+}
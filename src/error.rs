@@ -16,6 +16,9 @@ pub enum ErrorKind {
     /// bytes.
     StringDecoding(Vec<u8>),
     /// An error kind indicating that the reader does not contain an ID3 tag.
+    ///
+    /// This is not necessarily a failure, untagged files can be treated as having an empty tag,
+    /// see `Tag::read_from_path_or_default`.
     NoTag,
     /// An error kind indicating that the reader contains an unsupported ID3 tag version. Contains
     /// the major and minor versions that were detected in the tag.
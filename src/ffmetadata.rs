@@ -0,0 +1,310 @@
+use std::fmt;
+use std::str::FromStr;
+
+
+/// A chapter as described by an ffmpeg metadata file. Times are in milliseconds, like those of
+/// ID3 chapter frames.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Chapter {
+    /// The start of the chapter in milliseconds.
+    pub start: u32,
+    /// The end of the chapter in milliseconds.
+    pub end: u32,
+    /// The title of the chapter, if any.
+    pub title: Option<String>,
+}
+
+/// The contents of an ffmpeg metadata file (FFMETADATA1), as read and written by
+/// `ffmpeg -f ffmetadata`.
+///
+/// Only global metadata and chapters are retained, stream sections are skipped.
+///
+/// # Example
+/// ```
+/// use id3::ffmetadata::{Chapter, FfMetadata};
+///
+/// let text = ";FFMETADATA1\ntitle=Book\n\n[CHAPTER]\nTIMEBASE=1/1\nSTART=0\nEND=90\ntitle=One\n";
+/// let metadata: FfMetadata = text.parse().unwrap();
+/// assert_eq!(vec![("title".to_string(), "Book".to_string())], metadata.metadata);
+/// assert_eq!(vec![Chapter { start: 0, end: 90000, title: Some("One".to_string()) }], metadata.chapters);
+///
+/// let text = metadata.to_string();
+/// assert!(text.starts_with(";FFMETADATA1\n"));
+/// assert_eq!(metadata, text.parse().unwrap());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FfMetadata {
+    /// The global key/value pairs in the order of appearance.
+    pub metadata: Vec<(String, String)>,
+    /// The chapters in the order of appearance.
+    pub chapters: Vec<Chapter>,
+}
+
+impl<'a> From<&'a ::frame::Chapter> for Chapter {
+    /// Takes the times and the title (TIT2) of a chapter frame (CHAP).
+    fn from(chapter: &'a ::frame::Chapter) -> Chapter {
+        Chapter {
+            start: chapter.start_time,
+            end: chapter.end_time,
+            title: chapter.title().map(|title| title.to_string()),
+        }
+    }
+}
+
+impl From<Chapter> for ::frame::Chapter {
+    /// Creates a chapter frame (CHAP) without byte offsets, the title is stored in a TIT2 frame.
+    /// The element ID is left empty, `Tag::set_chapters` assigns it.
+    fn from(chapter: Chapter) -> ::frame::Chapter {
+        ::frame::Chapter {
+            element_id: String::new(),
+            start_time: chapter.start,
+            end_time: chapter.end,
+            start_offset: ::frame::Chapter::NO_OFFSET,
+            end_offset: ::frame::Chapter::NO_OFFSET,
+            frames: chapter.title.into_iter()
+                .map(|title| ::frame::Frame::with_content("TIT2", ::frame::Content::Text(title.into())))
+                .collect(),
+        }
+    }
+}
+
+/// The section of the file that key/value pairs are currently assigned to.
+enum Section {
+    Global,
+    Chapter {
+        timebase: (u64, u64),
+        start: Option<u64>,
+        end: Option<u64>,
+        title: Option<String>,
+    },
+    Other,
+}
+
+impl FromStr for FfMetadata {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<FfMetadata> {
+        let mut lines = logical_lines(s).into_iter();
+        match lines.next() {
+            Some(ref header) if header.starts_with(";FFMETADATA") => (),
+            _ => return Err(::Error::new(::ErrorKind::Parsing, "missing FFMETADATA header")),
+        }
+
+        let mut metadata = FfMetadata::default();
+        let mut section = Section::Global;
+        for line in lines {
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                metadata.finish_section(section)?;
+                section = match line.as_str() {
+                    "[CHAPTER]" => Section::Chapter {
+                        // ffmpeg assumes nanoseconds when no timebase is given.
+                        timebase: (1, 1_000_000_000),
+                        start: None,
+                        end: None,
+                        title: None,
+                    },
+                    _ => Section::Other,
+                };
+                continue;
+            }
+            let (key, value) = split_key_value(&line)?;
+            match section {
+                Section::Global => metadata.metadata.push((key, value)),
+                Section::Chapter { ref mut timebase, ref mut start, ref mut end, ref mut title } => {
+                    match key.as_str() {
+                        "TIMEBASE" => *timebase = parse_timebase(&value)?,
+                        "START" => *start = Some(parse_number(&value)?),
+                        "END" => *end = Some(parse_number(&value)?),
+                        "title" => *title = Some(value),
+                        _ => (),
+                    }
+                },
+                Section::Other => (),
+            }
+        }
+        metadata.finish_section(section)?;
+        Ok(metadata)
+    }
+}
+
+impl FfMetadata {
+    fn finish_section(&mut self, section: Section) -> ::Result<()> {
+        if let Section::Chapter { timebase, start, end, title } = section {
+            let (start, end) = match (start, end) {
+                (Some(start), Some(end)) => (start, end),
+                _ => return Err(::Error::new(::ErrorKind::Parsing, "chapter is missing START or END")),
+            };
+            self.chapters.push(Chapter {
+                start: to_millis(start, timebase)?,
+                end: to_millis(end, timebase)?,
+                title,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for FfMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, ";FFMETADATA1")?;
+        for (key, value) in &self.metadata {
+            writeln!(f, "{}={}", escape(key), escape(value))?;
+        }
+        for chapter in &self.chapters {
+            writeln!(f)?;
+            writeln!(f, "[CHAPTER]")?;
+            writeln!(f, "TIMEBASE=1/1000")?;
+            writeln!(f, "START={}", chapter.start)?;
+            writeln!(f, "END={}", chapter.end)?;
+            if let Some(ref title) = chapter.title {
+                writeln!(f, "title={}", escape(title))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Splits the text into lines. Escaped newlines do not end a line, escape sequences are otherwise
+/// kept so that comments, section headers and separators can be told apart from escaped text.
+fn logical_lines(s: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                line.push(c);
+                line.extend(chars.next());
+            },
+            '\n' => lines.push(line.split_off(0)),
+            '\r' => (),
+            c => line.push(c),
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Splits a line at the first unescaped '=' and unescapes the key and value.
+fn split_key_value(line: &str) -> ::Result<(String, String)> {
+    let mut key = String::new();
+    let mut value = String::new();
+    let mut in_value = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        let part = if in_value { &mut value } else { &mut key };
+        match c {
+            '\\' => part.extend(chars.next()),
+            '=' if !in_value => in_value = true,
+            c => part.push(c),
+        }
+    }
+    if !in_value {
+        return Err(::Error::new(::ErrorKind::Parsing, "FFMETADATA line is not a key=value pair"));
+    }
+    Ok((key, value))
+}
+
+fn parse_number(value: &str) -> ::Result<u64> {
+    value.trim().parse()
+        .map_err(|_| ::Error::new(::ErrorKind::Parsing, "invalid number in FFMETADATA chapter"))
+}
+
+fn parse_timebase(value: &str) -> ::Result<(u64, u64)> {
+    let mut parts = value.splitn(2, '/');
+    let num = parse_number(parts.next().unwrap_or(""))?;
+    let den = parse_number(parts.next().unwrap_or(""))?;
+    if num == 0 || den == 0 {
+        return Err(::Error::new(::ErrorKind::Parsing, "invalid FFMETADATA chapter timebase"));
+    }
+    Ok((num, den))
+}
+
+fn to_millis(value: u64, (num, den): (u64, u64)) -> ::Result<u32> {
+    u128::from(value).checked_mul(u128::from(num))
+        .and_then(|product| product.checked_mul(1000))
+        .map(|product| product / u128::from(den))
+        .filter(|millis| *millis <= u128::from(u32::MAX))
+        .map(|millis| millis as u32)
+        .ok_or_else(|| ::Error::new(::ErrorKind::Parsing, "FFMETADATA chapter time is out of range"))
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if let '=' | ';' | '#' | '\\' | '\n' = c {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ffmpeg_output() {
+        let text = ";FFMETADATA1\n\
+                    title=A \\= B\\;\\\nsecond line\n\
+                    # comment\n\
+                    \\;a\\=b=c\n\
+                    [STREAM]\n\
+                    title=ignored\n\
+                    [CHAPTER]\n\
+                    START=1500000000\n\
+                    END=3000000000\n\
+                    [CHAPTER]\n\
+                    TIMEBASE=1/44100\n\
+                    START=132300\n\
+                    END=176400\n\
+                    title=Two\n";
+        let metadata: FfMetadata = text.parse().unwrap();
+        assert_eq!(vec![
+            ("title".to_string(), "A = B;\nsecond line".to_string()),
+            (";a=b".to_string(), "c".to_string()),
+        ], metadata.metadata);
+        assert_eq!(vec![
+            Chapter { start: 1500, end: 3000, title: None },
+            Chapter { start: 3000, end: 4000, title: Some("Two".to_string()) },
+        ], metadata.chapters);
+        assert_eq!(metadata, metadata.to_string().parse().unwrap());
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!("title=x\n".parse::<FfMetadata>().is_err());
+        assert!(";FFMETADATA1\n[CHAPTER]\nSTART=0\n".parse::<FfMetadata>().is_err());
+        assert!(";FFMETADATA1\n[CHAPTER]\nTIMEBASE=1/0\nSTART=0\nEND=1\n".parse::<FfMetadata>().is_err());
+        assert!(";FFMETADATA1\nnot a pair\n".parse::<FfMetadata>().is_err());
+        // The product of the time and a large timebase does not fit in 128 bits.
+        let max = u64::MAX;
+        let text = format!(";FFMETADATA1\n[CHAPTER]\nTIMEBASE={}/1\nSTART={}\nEND={}\n", max, max, max);
+        assert!(text.parse::<FfMetadata>().is_err());
+    }
+
+    #[test]
+    fn convert_chapters() {
+        let text = ";FFMETADATA1\n[CHAPTER]\nTIMEBASE=1/1\nSTART=60\nEND=120\ntitle=Two\n\n\
+                    [CHAPTER]\nTIMEBASE=1/1\nSTART=0\nEND=60\n";
+        let metadata: FfMetadata = text.parse().unwrap();
+        let mut tag = ::Tag::new();
+        tag.set_chapters_from_ffmetadata(&metadata);
+        let chapters = tag.chapters();
+        assert_eq!(vec![(0, 60000, None), (60000, 120000, Some("Two"))],
+                   chapters.iter().map(|c| (c.start_time, c.end_time, c.title())).collect::<Vec<_>>());
+        assert_eq!(::frame::Chapter::NO_OFFSET, chapters[0].start_offset);
+        assert_eq!(2, tag.get("CTOC").unwrap().content().table_of_contents().unwrap().elements.len());
+
+        let exported = tag.chapters_to_ffmetadata();
+        assert_eq!(vec![metadata.chapters[1].clone(), metadata.chapters[0].clone()], exported.chapters);
+        assert_eq!(exported, exported.to_string().parse().unwrap());
+    }
+}
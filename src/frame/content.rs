@@ -1,11 +1,17 @@
+use std::borrow::Cow;
 use std::hash::{Hash, Hasher};
+use std::iter;
 
 
 /// The decoded contents of a frame.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub enum Content {
     /// A value containing the parsed contents of a text frame.
-    Text(String),
+    ///
+    /// Text can be borrowed from static strings, so frames built from literals do not allocate:
+    /// `Content::Text("Title".into())`. Owned strings are converted the same way.
+    Text(Cow<'static, str>),
     /// A value containing the parsed contents of a user defined text frame (TXXX).
     ExtendedText(ExtendedText),
     /// A value containing the parsed contents of a web link frame.
@@ -16,8 +22,52 @@ pub enum Content {
     Comment(Comment),
     /// A value containing the parsed contents of a lyrics frame (USLT).
     Lyrics(Lyrics),
+    /// A value containing the parsed contents of a synchronised lyrics frame (SYLT).
+    SynchronisedLyrics(SynchronisedLyrics),
     /// A value containing the parsed contents of a picture frame (APIC).
     Picture(Picture),
+    /// A value containing the parsed contents of a general encapsulated object frame (GEOB).
+    EncapsulatedObject(EncapsulatedObject),
+    /// A value containing the parsed contents of an encryption method registration frame (ENCR).
+    EncryptionMethod(EncryptionMethod),
+    /// A value containing the parsed contents of a group identification registration frame
+    /// (GRID).
+    GroupIdentifier(GroupIdentifier),
+    /// A value containing the parsed contents of a unique file identifier frame (UFID).
+    UniqueFileIdentifier(UniqueFileIdentifier),
+    /// A value containing the parsed contents of a private frame (PRIV).
+    Private(Private),
+    /// A value containing the parsed contents of a relative volume adjustment frame (RVA2).
+    RelativeVolumeAdjustment(RelativeVolumeAdjustment),
+    /// A value containing the parsed contents of an equalisation frame (EQU2).
+    Equalisation(Equalisation),
+    /// A value containing the parsed contents of an event timing codes frame (ETCO).
+    EventTimingCodes(EventTimingCodes),
+    /// A value containing the parsed contents of a linked information frame (LINK).
+    LinkedInfo(LinkedInfo),
+    /// A value containing the parsed contents of a terms of use frame (USER).
+    TermsOfUse(TermsOfUse),
+    /// A value containing the parsed contents of a signature frame (SIGN).
+    Signature(Signature),
+    /// A value containing the parsed contents of a commercial frame (COMR).
+    Commercial(Commercial),
+    /// A value containing the table of contents of a music CD identifier frame (MCDI).
+    MusicCdIdentifier(MusicCdIdentifier),
+    /// A value containing the parsed contents of a recommended buffer size frame (RBUF).
+    RecommendedBufferSize(RecommendedBufferSize),
+    /// A value containing the parsed contents of an audio seek point index frame (ASPI).
+    AudioSeekPointIndex(AudioSeekPointIndex),
+    /// A value containing the offset of a seek frame (SEEK): the number of bytes from the end of
+    /// the tag to the next tag in the file.
+    SeekOffset(u32),
+    /// A value containing the counter of a play counter frame (PCNT).
+    PlayCounter(u64),
+    /// A value containing the parsed contents of a popularimeter frame (POPM).
+    Popularimeter(Popularimeter),
+    /// A value containing the parsed contents of a chapter frame (CHAP).
+    Chapter(Chapter),
+    /// A value containing the parsed contents of a table of contents frame (CTOC).
+    TableOfContents(TableOfContents),
     /// A value containing the bytes of a unknown frame.
     Unknown(Vec<u8>),
 }
@@ -31,6 +81,20 @@ impl Content {
         }
     }
 
+    /// Returns the values of a `Text` content, which are separated by null characters. ID3v2.4
+    /// allows text frames to hold multiple values, like the artists of a collaboration.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Content;
+    ///
+    /// let content = Content::Text("Artist 1\0Artist 2".into());
+    /// assert_eq!(vec!["Artist 1", "Artist 2"], content.text_values().collect::<Vec<_>>());
+    /// ```
+    pub fn text_values(&self) -> impl Iterator<Item = &str> {
+        self.text().into_iter().flat_map(|text| text.split('\0'))
+    }
+
     /// Returns the `ExtendedText` or None if the value is not `ExtendedText`.
     pub fn extended_text(&self) -> Option<&super::ExtendedText> {
         match *self {
@@ -71,6 +135,14 @@ impl Content {
         }
     }
 
+    /// Returns the `SynchronisedLyrics` or None if the value is not `SynchronisedLyrics`.
+    pub fn synchronised_lyrics(&self) -> Option<&super::SynchronisedLyrics> {
+        match *self {
+            Content::SynchronisedLyrics(ref content) => Some(content),
+            _ => None,
+        }
+    }
+
     /// Returns the `Picture` or None if the value is not `Picture`.
     pub fn picture(&self) -> Option<&super::Picture> {
         match *self {
@@ -79,6 +151,167 @@ impl Content {
         }
     }
 
+    /// Returns the `GroupIdentifier` or None if the value is not `GroupIdentifier`.
+    pub fn group_identifier(&self) -> Option<&super::GroupIdentifier> {
+        match *self {
+            Content::GroupIdentifier(ref content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Returns the `EncapsulatedObject` or None if the value is not `EncapsulatedObject`.
+    pub fn encapsulated_object(&self) -> Option<&super::EncapsulatedObject> {
+        match *self {
+            Content::EncapsulatedObject(ref content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Returns the `UniqueFileIdentifier` or None if the value is not `UniqueFileIdentifier`.
+    pub fn unique_file_identifier(&self) -> Option<&super::UniqueFileIdentifier> {
+        match *self {
+            Content::UniqueFileIdentifier(ref content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Returns the `Private` or None if the value is not `Private`.
+    pub fn private(&self) -> Option<&super::Private> {
+        match *self {
+            Content::Private(ref content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Returns the `RelativeVolumeAdjustment` or None if the value is not
+    /// `RelativeVolumeAdjustment`.
+    pub fn relative_volume_adjustment(&self) -> Option<&super::RelativeVolumeAdjustment> {
+        match *self {
+            Content::RelativeVolumeAdjustment(ref content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Returns the `Equalisation` or None if the value is not `Equalisation`.
+    pub fn equalisation(&self) -> Option<&super::Equalisation> {
+        match *self {
+            Content::Equalisation(ref content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Returns the `EventTimingCodes` or None if the value is not `EventTimingCodes`.
+    pub fn event_timing_codes(&self) -> Option<&super::EventTimingCodes> {
+        match *self {
+            Content::EventTimingCodes(ref content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Returns the `LinkedInfo` or None if the value is not `LinkedInfo`.
+    pub fn linked_info(&self) -> Option<&super::LinkedInfo> {
+        match *self {
+            Content::LinkedInfo(ref content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Returns the `TermsOfUse` or None if the value is not `TermsOfUse`.
+    pub fn terms_of_use(&self) -> Option<&super::TermsOfUse> {
+        match *self {
+            Content::TermsOfUse(ref content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Returns the `Signature` or None if the value is not `Signature`.
+    pub fn signature(&self) -> Option<&super::Signature> {
+        match *self {
+            Content::Signature(ref content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Returns the `Commercial` or None if the value is not `Commercial`.
+    pub fn commercial(&self) -> Option<&super::Commercial> {
+        match *self {
+            Content::Commercial(ref content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Returns the `MusicCdIdentifier` or None if the value is not `MusicCdIdentifier`.
+    pub fn music_cd_identifier(&self) -> Option<&super::MusicCdIdentifier> {
+        match *self {
+            Content::MusicCdIdentifier(ref content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Returns the `RecommendedBufferSize` or None if the value is not `RecommendedBufferSize`.
+    pub fn recommended_buffer_size(&self) -> Option<&super::RecommendedBufferSize> {
+        match *self {
+            Content::RecommendedBufferSize(ref content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Returns the `AudioSeekPointIndex` or None if the value is not `AudioSeekPointIndex`.
+    pub fn audio_seek_point_index(&self) -> Option<&super::AudioSeekPointIndex> {
+        match *self {
+            Content::AudioSeekPointIndex(ref content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Returns the `SeekOffset` or None if the value is not `SeekOffset`.
+    pub fn seek_offset(&self) -> Option<u32> {
+        match *self {
+            Content::SeekOffset(offset) => Some(offset),
+            _ => None,
+        }
+    }
+
+    /// Returns the `PlayCounter` or None if the value is not `PlayCounter`.
+    pub fn play_counter(&self) -> Option<u64> {
+        match *self {
+            Content::PlayCounter(counter) => Some(counter),
+            _ => None,
+        }
+    }
+
+    /// Returns the `Popularimeter` or None if the value is not `Popularimeter`.
+    pub fn popularimeter(&self) -> Option<&super::Popularimeter> {
+        match *self {
+            Content::Popularimeter(ref content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Returns the `EncryptionMethod` or None if the value is not `EncryptionMethod`.
+    pub fn encryption_method(&self) -> Option<&super::EncryptionMethod> {
+        match *self {
+            Content::EncryptionMethod(ref content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Returns the `Chapter` or None if the value is not `Chapter`.
+    pub fn chapter(&self) -> Option<&super::Chapter> {
+        match *self {
+            Content::Chapter(ref content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Returns the `TableOfContents` or None if the value is not `TableOfContents`.
+    pub fn table_of_contents(&self) -> Option<&super::TableOfContents> {
+        match *self {
+            Content::TableOfContents(ref content) => Some(content),
+            _ => None,
+        }
+    }
+
     /// Returns the `Unknown` or None if the value is not `Unknown`.
     pub fn unknown(&self) -> Option<&[u8]> {
         match *self {
@@ -91,6 +324,7 @@ impl Content {
 
 /// The parsed contents of an extended text frame.
 #[derive(Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 #[allow(missing_docs)]
 pub struct ExtendedText {
     pub description: String,
@@ -112,6 +346,7 @@ impl Hash for ExtendedText {
 
 /// The parsed contents of an extended link frame.
 #[derive(Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 #[allow(missing_docs)]
 pub struct ExtendedLink {
     pub description: String,
@@ -133,6 +368,7 @@ impl Hash for ExtendedLink {
 
 /// The parsed contents of a comment frame.
 #[derive(Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 #[allow(missing_docs)]
 pub struct Comment {
     pub lang: String,
@@ -156,6 +392,7 @@ impl Hash for Comment {
 
 /// The parsed contents of an unsynchronized lyrics frame.
 #[derive(Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 #[allow(missing_docs)]
 pub struct Lyrics {
     pub lang: String,
@@ -177,8 +414,104 @@ impl Hash for Lyrics {
 }
 
 
+/// The unit of the timestamps in a SYLT frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum TimestampFormat {
+    /// Absolute time, using MPEG frames as unit.
+    Mpeg,
+    /// Absolute time, using milliseconds as unit.
+    Ms,
+}
+
+/// The type of text in a SYLT frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[allow(missing_docs)]
+pub enum SynchronisedLyricsType {
+    Other,
+    Lyrics,
+    Transcription,
+    PartName,
+    Events,
+    Chord,
+    Trivia,
+    WebpageUrls,
+    ImageUrls,
+}
+
+/// The parsed contents of a synchronised lyrics frame.
+///
+/// The text is split into syllables, each with the time at which it starts. A syllable that
+/// starts a new line begins with a newline character, as described by the ID3v2 specification.
+/// Karaoke tools use this to highlight each syllable in time, while other tools may store one
+/// entry per line.
+///
+/// # Example
+/// ```
+/// use id3::frame::{SynchronisedLyrics, SynchronisedLyricsType, TimestampFormat};
+///
+/// let lyrics = SynchronisedLyrics {
+///     lang: "eng".to_string(),
+///     timestamp_format: TimestampFormat::Ms,
+///     content_type: SynchronisedLyricsType::Lyrics,
+///     description: "".to_string(),
+///     content: vec![
+///         (0, "Strang".to_string()),
+///         (500, "ers ".to_string()),
+///         (800, "in".to_string()),
+///         (1200, "\nthe night".to_string()),
+///     ],
+/// };
+/// assert_eq!(lyrics.lines(), vec![(0, "Strangers in".to_string()), (1200, "the night".to_string())]);
+/// ```
+#[derive(Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct SynchronisedLyrics {
+    /// The language of the text as an ISO-639-2 code.
+    pub lang: String,
+    /// The unit of the timestamps.
+    pub timestamp_format: TimestampFormat,
+    /// The type of text.
+    pub content_type: SynchronisedLyricsType,
+    /// The content descriptor.
+    pub description: String,
+    /// The syllables of the text, each with their timestamp.
+    pub content: Vec<(u32, String)>,
+}
+
+impl SynchronisedLyrics {
+    /// Joins the syllables into lines. Each line has the timestamp of its first syllable and does
+    /// not include the newline marker.
+    pub fn lines(&self) -> Vec<(u32, String)> {
+        let mut lines: Vec<(u32, String)> = Vec::new();
+        for &(timestamp, ref syllable) in &self.content {
+            match (syllable.starts_with('\n'), lines.last_mut()) {
+                (false, Some(line)) => line.1.push_str(syllable),
+                _ => lines.push((timestamp, syllable.trim_start_matches('\n').to_string())),
+            }
+        }
+        lines
+    }
+}
+
+impl PartialEq for SynchronisedLyrics {
+    fn eq(&self, other: &Self) -> bool {
+        self.lang == other.lang && self.description == other.description
+    }
+}
+
+impl Hash for SynchronisedLyrics {
+    fn hash<H>(&self, state: &mut H) where H: Hasher {
+        self.lang.hash(state);
+        self.description.hash(state);
+    }
+}
+
+
 /// Types of pictures used in APIC frames.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum PictureType {
     Other,
@@ -207,6 +540,7 @@ pub enum PictureType {
 
 /// A structure representing an ID3 picture frame's contents.
 #[derive(Clone, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct Picture {
     /// The picture's MIME type.
     pub mime_type: String,
@@ -229,3 +563,660 @@ impl Hash for Picture {
         self.picture_type.hash(state);
     }
 }
+
+
+/// The parsed contents of a general encapsulated object frame (GEOB).
+///
+/// Applications use these to store binary data of any kind, like the cue points and beat grids
+/// of DJ software. The description identifies the object and is unique within the tag.
+#[derive(Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct EncapsulatedObject {
+    /// The MIME type of the data.
+    pub mime_type: String,
+    /// The name of the file the data was taken from, may be empty.
+    pub filename: String,
+    /// The content descriptor.
+    pub description: String,
+    /// The object data.
+    pub data: Vec<u8>,
+}
+
+impl PartialEq for EncapsulatedObject {
+    fn eq(&self, other: &Self) -> bool {
+        self.description == other.description
+    }
+}
+
+impl Hash for EncapsulatedObject {
+    fn hash<H>(&self, state: &mut H) where H: Hasher {
+        self.description.hash(state);
+    }
+}
+
+
+/// The parsed contents of an encryption method registration frame.
+///
+/// The frame registers a method symbol, which marks encrypted frames, on behalf of the owner. See
+/// `Frame::encryption_method` and `Decoder::decryptor`.
+#[derive(Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct EncryptionMethod {
+    /// A URL containing an email address, or a link to a location where an email address can be
+    /// found, that belongs to the organisation responsible for the encryption method.
+    pub owner_identifier: String,
+    /// The method symbol in the range `0x80..=0xF0`.
+    pub symbol: u8,
+    /// Data specific to the encryption method, like an encrypted key.
+    pub data: Vec<u8>,
+}
+
+impl PartialEq for EncryptionMethod {
+    fn eq(&self, other: &Self) -> bool {
+        self.symbol == other.symbol
+    }
+}
+
+impl Hash for EncryptionMethod {
+    fn hash<H>(&self, state: &mut H) where H: Hasher {
+        self.symbol.hash(state);
+    }
+}
+
+
+/// The parsed contents of a group identification registration frame.
+///
+/// The frame registers a group symbol, which can be set on other frames with
+/// `Frame::set_group_identifier`, on behalf of the owner.
+#[derive(Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct GroupIdentifier {
+    /// A URL containing an email address, or a link to a location where an email address can be
+    /// found, that belongs to the organisation responsible for the grouping.
+    pub owner_identifier: String,
+    /// The group symbol in the range `0x80..=0xF0`.
+    pub symbol: u8,
+    /// Data specific to the group, like a checksum or signature.
+    pub data: Vec<u8>,
+}
+
+impl PartialEq for GroupIdentifier {
+    fn eq(&self, other: &Self) -> bool {
+        self.symbol == other.symbol
+    }
+}
+
+impl Hash for GroupIdentifier {
+    fn hash<H>(&self, state: &mut H) where H: Hasher {
+        self.symbol.hash(state);
+    }
+}
+
+
+/// The parsed contents of a unique file identifier frame (UFID).
+///
+/// The identifier is assigned by the database of the owner, like the MusicBrainz recording ID
+/// with the owner `http://musicbrainz.org`. There is only one identifier per owner.
+#[derive(Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct UniqueFileIdentifier {
+    /// A URL or email address identifying the database the identifier belongs to.
+    pub owner_identifier: String,
+    /// The identifier, up to 64 bytes of binary data.
+    pub identifier: Vec<u8>,
+}
+
+impl PartialEq for UniqueFileIdentifier {
+    fn eq(&self, other: &Self) -> bool {
+        self.owner_identifier == other.owner_identifier
+    }
+}
+
+impl Hash for UniqueFileIdentifier {
+    fn hash<H>(&self, state: &mut H) where H: Hasher {
+        self.owner_identifier.hash(state);
+    }
+}
+
+
+/// The parsed contents of a private frame (PRIV).
+///
+/// The data is only meaningful to the application identified by the owner, like
+/// `www.amazon.com` or the `WM/` values of Windows Media Player.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Private {
+    /// A URL or email address identifying the organisation that owns the frame.
+    pub owner: String,
+    /// The private data.
+    pub data: Vec<u8>,
+}
+
+
+/// The channels of an RVA2 frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[allow(missing_docs)]
+pub enum ChannelType {
+    Other,
+    MasterVolume,
+    FrontRight,
+    FrontLeft,
+    BackRight,
+    BackLeft,
+    FrontCentre,
+    BackCentre,
+    Subwoofer,
+}
+
+/// The adjustment of a single channel in an RVA2 frame.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct VolumeAdjustment {
+    /// The channel the adjustment applies to.
+    pub channel: ChannelType,
+    /// The volume adjustment in units of 1/512 dB, ranging from -64 dB to +64 dB.
+    pub adjustment: i16,
+    /// The number of bits used to represent the peak volume, may be 0 if there is no peak.
+    pub peak_bits: u8,
+    /// The peak volume as a big-endian number of `peak_bits` bits, rounded up to whole bytes.
+    pub peak: Vec<u8>,
+}
+
+impl VolumeAdjustment {
+    /// Returns the volume adjustment in decibels.
+    pub fn adjustment_db(&self) -> f32 {
+        f32::from(self.adjustment) / 512.0
+    }
+
+    /// Sets the volume adjustment in decibels, values outside of the range of the frame are
+    /// clamped.
+    pub fn set_adjustment_db(&mut self, db: f32) {
+        self.adjustment = (db * 512.0).round().clamp(f32::from(i16::MIN), f32::from(i16::MAX)) as i16;
+    }
+}
+
+/// The parsed contents of a relative volume adjustment frame (RVA2), as used to store ReplayGain
+/// information.
+///
+/// The identification tells different adjustments apart, like "track" and "album" for
+/// ReplayGain. There is only one frame per identification.
+#[derive(Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct RelativeVolumeAdjustment {
+    /// The situation in which the adjustment should be used.
+    pub identification: String,
+    /// The adjustments of each channel.
+    pub channels: Vec<VolumeAdjustment>,
+}
+
+impl PartialEq for RelativeVolumeAdjustment {
+    fn eq(&self, other: &Self) -> bool {
+        self.identification == other.identification
+    }
+}
+
+impl Hash for RelativeVolumeAdjustment {
+    fn hash<H>(&self, state: &mut H) where H: Hasher {
+        self.identification.hash(state);
+    }
+}
+
+
+/// How the adjustments of an EQU2 frame are interpolated between frequencies.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum InterpolationMethod {
+    /// No interpolation, an adjustment applies from its frequency up to the next one.
+    Band,
+    /// The adjustment changes linearly between two frequencies.
+    Linear,
+}
+
+/// The parsed contents of an equalisation frame (EQU2).
+///
+/// There is only one frame per identification.
+#[derive(Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Equalisation {
+    /// The interpolation method between the frequencies.
+    pub method: InterpolationMethod,
+    /// The situation in which the equalisation should be used.
+    pub identification: String,
+    /// The frequencies in units of 1/2 Hz, each with its volume adjustment in units of 1/512 dB.
+    /// The frequencies should be ordered and unique.
+    pub adjustments: Vec<(u16, i16)>,
+}
+
+impl PartialEq for Equalisation {
+    fn eq(&self, other: &Self) -> bool {
+        self.identification == other.identification
+    }
+}
+
+impl Hash for Equalisation {
+    fn hash<H>(&self, state: &mut H) where H: Hasher {
+        self.identification.hash(state);
+    }
+}
+
+
+/// The types of events in an ETCO frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[allow(missing_docs)]
+pub enum EventType {
+    Padding,
+    EndOfInitialSilence,
+    IntroStart,
+    MainPartStart,
+    OutroStart,
+    OutroEnd,
+    VerseStart,
+    RefrainStart,
+    InterludeStart,
+    ThemeStart,
+    VariationStart,
+    KeyChange,
+    TimeChange,
+    MomentaryUnwantedNoise,
+    SustainedNoise,
+    SustainedNoiseEnd,
+    IntroEnd,
+    MainPartEnd,
+    VerseEnd,
+    RefrainEnd,
+    ThemeEnd,
+    Profanity,
+    ProfanityEnd,
+    AudioEnd,
+    AudioFileEnd,
+    /// One of the 16 synchronisation events without a predefined meaning, from 0 to 15.
+    NotPredefinedSync(u8),
+    /// A value that is reserved for future use.
+    Reserved(u8),
+}
+
+/// An event of an ETCO frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Event {
+    /// The type of the event.
+    pub event_type: EventType,
+    /// The time at which the event occurs, in the unit of the frame's timestamp format.
+    pub timestamp: u32,
+}
+
+/// The parsed contents of an event timing codes frame (ETCO).
+///
+/// Broadcast and radio automation software uses these to find cue points like the start of the
+/// main part or the outro. There is only one frame per tag.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct EventTimingCodes {
+    /// The unit of the timestamps.
+    pub timestamp_format: TimestampFormat,
+    /// The events in chronological order.
+    pub events: Vec<Event>,
+}
+
+
+/// The parsed contents of a linked information frame (LINK).
+///
+/// The frame refers to a frame in a tag of another file, so that information which is the same
+/// for several files, like an album cover, does not have to be repeated.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct LinkedInfo {
+    /// The ID of the linked frame. The ID is converted when the frame is written in another
+    /// version.
+    pub frame_id: String,
+    /// The URL of the file holding the linked frame.
+    pub url: String,
+    /// Additional data identifying the linked frame, like the language and description of a
+    /// comment, as a list of strings.
+    pub additional_data: Vec<u8>,
+}
+
+
+/// The parsed contents of a terms of use frame (USER).
+///
+/// There is only one frame per language.
+#[derive(Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct TermsOfUse {
+    /// The language of the text as an ISO-639-2 code.
+    pub lang: String,
+    /// The terms of use and ownership of the file.
+    pub text: String,
+}
+
+impl PartialEq for TermsOfUse {
+    fn eq(&self, other: &Self) -> bool {
+        self.lang == other.lang
+    }
+}
+
+impl Hash for TermsOfUse {
+    fn hash<H>(&self, state: &mut H) where H: Hasher {
+        self.lang.hash(state);
+    }
+}
+
+
+/// The parsed contents of a signature frame (SIGN).
+///
+/// The signature covers the frames that are marked with the group symbol, see
+/// `Tag::verify_signatures`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Signature {
+    /// The group symbol of the signed frames, as registered by a GRID frame.
+    pub group_symbol: u8,
+    /// The signature data.
+    pub signature: Vec<u8>,
+}
+
+
+/// How the audio was delivered when it was bought, as stored in a COMR frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[allow(missing_docs)]
+pub enum ReceivedAs {
+    Other,
+    StandardCdAlbum,
+    CompressedAudioOnCd,
+    FileOverInternet,
+    StreamOverInternet,
+    AsNoteSheets,
+    AsNoteSheetsInBook,
+    MusicOnOtherMedia,
+    NonMusicalMerchandise,
+}
+
+/// The parsed contents of a commercial frame (COMR).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Commercial {
+    /// The prices, each a three letter ISO-4217 currency code followed by the amount, separated
+    /// by a slash, e.g. "USD9.99/EUR8.99".
+    pub price: String,
+    /// The date until which the price is valid, in the form "YYYYMMDD".
+    pub valid_until: String,
+    /// A URL or email address to contact the seller.
+    pub contact_url: String,
+    /// How the audio was delivered.
+    pub received_as: ReceivedAs,
+    /// The name of the seller.
+    pub seller: String,
+    /// A short description of the product.
+    pub description: String,
+    /// The MIME type and the data of the logo of the seller, if any. Only "image/png" and
+    /// "image/jpeg" are allowed by the specification.
+    pub logo: Option<(String, Vec<u8>)>,
+}
+
+
+/// The parsed contents of a recommended buffer size frame (RBUF).
+///
+/// Streaming servers use this to tell clients how much to buffer and where to find the next tag
+/// in the stream. There is only one frame per tag.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct RecommendedBufferSize {
+    /// The recommended buffer size in bytes, at most 24 bits.
+    pub buffer_size: u32,
+    /// Whether information that replaces this tag may be embedded in the audio stream, for
+    /// example by a tag in the middle of the stream.
+    pub embedded_info: bool,
+    /// The number of bytes from the end of this tag to the next tag, if known.
+    pub next_tag_offset: Option<u32>,
+}
+
+
+/// The parsed contents of an audio seek point index frame (ASPI).
+///
+/// The indexed audio is divided into equally sized parts, and for each part the index holds the
+/// offset of its start as a fraction of the length of the indexed data, scaled to the range of
+/// `bits_per_point` bits. There is only one frame per tag.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct AudioSeekPointIndex {
+    /// The offset of the indexed data from the start of the audio file, in bytes.
+    pub start: u32,
+    /// The length of the indexed data in bytes.
+    pub length: u32,
+    /// The number of bits per index point, 8 or 16.
+    pub bits_per_point: u8,
+    /// The index points, at most 65535.
+    pub points: Vec<u16>,
+}
+
+
+/// The contents of a music CD identifier frame (MCDI): the table of contents of the CD the audio
+/// was taken from, in the format of the CD-ROM READ TOC command.
+///
+/// The table starts with a 4 byte header holding the data length and the first and last track
+/// numbers, followed by an 8 byte descriptor for every track and the lead-out, each holding the
+/// track number and its start address in sectors (LBA).
+///
+/// # Example
+/// ```
+/// use id3::frame::MusicCdIdentifier;
+///
+/// let mcdi = MusicCdIdentifier::new(1, &[0, 15000, 30000], 45000);
+/// assert_eq!(Some(0x0c02_5803), mcdi.freedb_disc_id());
+/// assert_eq!(Some("MXsnrcgOkbb2H34IbwspNVMJMwA-".to_string()), mcdi.musicbrainz_disc_id());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct MusicCdIdentifier {
+    /// The raw table of contents.
+    pub toc: Vec<u8>,
+}
+
+impl MusicCdIdentifier {
+    /// The track number of the lead-out.
+    pub const LEAD_OUT: u8 = 0xaa;
+
+    /// Builds the table of contents of a CD from the start addresses of its tracks and the
+    /// lead-out, in sectors (LBA). The tracks are numbered from `first_track`.
+    pub fn new(first_track: u8, track_offsets: &[u32], lead_out: u32) -> MusicCdIdentifier {
+        let last_track = first_track.saturating_add(track_offsets.len().saturating_sub(1) as u8);
+        let len = 2 + 8 * (track_offsets.len() + 1);
+        let mut toc = Vec::with_capacity(len + 2);
+        toc.extend_from_slice(&(len as u16).to_be_bytes());
+        toc.push(first_track);
+        toc.push(last_track);
+        let tracks = track_offsets.iter().enumerate()
+            .map(|(i, offset)| (first_track.saturating_add(i as u8), *offset))
+            .chain(iter::once((Self::LEAD_OUT, lead_out)));
+        for (number, offset) in tracks {
+            // The ADR field is 1 (position data), the control field marks audio tracks.
+            toc.extend_from_slice(&[0, 0x10, number, 0]);
+            toc.extend_from_slice(&offset.to_be_bytes());
+        }
+        MusicCdIdentifier { toc }
+    }
+
+    /// Returns the track numbers and their start addresses in sectors (LBA), including the
+    /// lead-out. Returns `None` if the table of contents is malformed.
+    pub fn tracks(&self) -> Option<Vec<(u8, u32)>> {
+        if self.toc.len() < 4 || (self.toc.len() - 4) % 8 != 0 {
+            return None;
+        }
+        let tracks = self.toc[4..].chunks(8)
+            .map(|desc| (desc[2], u32::from_be_bytes([desc[4], desc[5], desc[6], desc[7]])))
+            .collect();
+        Some(tracks)
+    }
+
+    /// Returns the first track number, the track offsets in order and the lead-out offset.
+    fn layout(&self) -> Option<(u8, Vec<u32>, u32)> {
+        let tracks = self.tracks()?;
+        let (first, last) = (self.toc[2], self.toc[3]);
+        if first == 0 || last < first || last > 99 {
+            return None;
+        }
+        let offsets = (first..=last)
+            .map(|number| tracks.iter().find(|track| track.0 == number).map(|track| track.1))
+            .collect::<Option<Vec<u32>>>()?;
+        let lead_out = tracks.iter().find(|track| track.0 == Self::LEAD_OUT)?.1;
+        Some((first, offsets, lead_out))
+    }
+
+    /// Computes the FreeDB (CDDB) disc ID. Returns `None` if the table of contents is malformed.
+    pub fn freedb_disc_id(&self) -> Option<u32> {
+        let (_, offsets, lead_out) = self.layout()?;
+        // The addresses are converted to seconds including the 2 second pregap.
+        let seconds = |offset: u32| offset.checked_add(150).map(|frames| frames / 75);
+        let starts = offsets.iter()
+            .map(|offset| seconds(*offset))
+            .collect::<Option<Vec<u32>>>()?;
+        let digit_sum: u32 = starts.iter()
+            .map(|start| start.to_string().bytes().map(|b| u32::from(b - b'0')).sum::<u32>())
+            .sum();
+        let length = seconds(lead_out)?.saturating_sub(starts[0]);
+        Some((digit_sum % 0xff) << 24 | length << 8 | offsets.len() as u32)
+    }
+
+    /// Computes the MusicBrainz disc ID. Returns `None` if the table of contents is malformed.
+    pub fn musicbrainz_disc_id(&self) -> Option<String> {
+        let (first, offsets, lead_out) = self.layout()?;
+        let last = first + offsets.len() as u8 - 1;
+        let mut text = format!("{:02X}{:02X}{:08X}", first, last, lead_out.checked_add(150)?);
+        for number in 1..100 {
+            let offset = match number {
+                n if n >= first && n <= last => offsets[(n - first) as usize].checked_add(150)?,
+                _ => 0,
+            };
+            text.push_str(&format!("{:08X}", offset));
+        }
+        let alphabet = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789._";
+        Some(::util::base64(&::util::sha1(text.as_bytes()), alphabet, '-'))
+    }
+}
+
+
+/// The parsed contents of a popularimeter frame (POPM).
+///
+/// Applications keep their own popularimeter, identified by an email address, with a rating and a
+/// play counter. How the rating byte maps to stars depends on the application, see
+/// `RatingScale`.
+#[derive(Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Popularimeter {
+    /// The email address of the user or application the rating belongs to.
+    pub email: String,
+    /// The rating from 1 (worst) to 255 (best), or 0 if unknown.
+    pub rating: u8,
+    /// The number of times the file has been played.
+    pub counter: u64,
+}
+
+impl PartialEq for Popularimeter {
+    fn eq(&self, other: &Self) -> bool {
+        self.email == other.email
+    }
+}
+
+impl Hash for Popularimeter {
+    fn hash<H>(&self, state: &mut H) where H: Hasher {
+        self.email.hash(state);
+    }
+}
+
+
+/// The parsed contents of a chapter frame (CHAP), as defined by the ID3v2 Chapter Frame Addendum.
+///
+/// # Example
+/// ```
+/// use id3::{Content, Frame};
+/// use id3::frame::Chapter;
+///
+/// let chapter = Chapter {
+///     element_id: "chp0".to_string(),
+///     start_time: 0,
+///     end_time: 60_000,
+///     start_offset: Chapter::NO_OFFSET,
+///     end_offset: Chapter::NO_OFFSET,
+///     frames: vec![Frame::with_content("TIT2", Content::Text("Introduction".into()))],
+/// };
+/// assert_eq!(Some("Introduction"), chapter.title());
+/// ```
+#[derive(Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Chapter {
+    /// The identifier of the chapter, which is unique within the tag and is referenced by table
+    /// of contents frames (CTOC).
+    pub element_id: String,
+    /// The start of the chapter in milliseconds.
+    pub start_time: u32,
+    /// The end of the chapter in milliseconds.
+    pub end_time: u32,
+    /// The byte offset of the first audio frame of the chapter from the start of the file, or
+    /// `NO_OFFSET` if only the times should be used.
+    pub start_offset: u32,
+    /// The byte offset of the first audio frame after the chapter, or `NO_OFFSET`.
+    pub end_offset: u32,
+    /// Frames that describe the chapter, like its title (TIT2), an image (APIC) or a link
+    /// (WXXX).
+    pub frames: Vec<super::Frame>,
+}
+
+impl Chapter {
+    /// The value of an offset that is not set.
+    pub const NO_OFFSET: u32 = 0xffff_ffff;
+
+    /// Returns the title (TIT2) of the chapter.
+    pub fn title(&self) -> Option<&str> {
+        self.frames.iter()
+            .find(|frame| frame.id() == "TIT2")
+            .and_then(|frame| frame.content().text())
+    }
+}
+
+impl PartialEq for Chapter {
+    fn eq(&self, other: &Self) -> bool {
+        self.element_id == other.element_id
+    }
+}
+
+impl Hash for Chapter {
+    fn hash<H>(&self, state: &mut H) where H: Hasher {
+        self.element_id.hash(state);
+    }
+}
+
+
+/// The parsed contents of a table of contents frame (CTOC), as defined by the ID3v2 Chapter Frame
+/// Addendum.
+///
+/// The entries refer to chapters (CHAP) or to other tables of contents by their element ID, which
+/// allows for a hierarchy of chapters. There should be only one top-level table.
+#[derive(Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct TableOfContents {
+    /// The identifier of the table, which is unique within the tag.
+    pub element_id: String,
+    /// Whether this is the root of the hierarchy.
+    pub top_level: bool,
+    /// Whether the entries are to be played in order.
+    pub ordered: bool,
+    /// The element IDs of the chapters and nested tables of contents.
+    pub elements: Vec<String>,
+    /// Frames that describe the table, like its title (TIT2).
+    pub frames: Vec<super::Frame>,
+}
+
+impl PartialEq for TableOfContents {
+    fn eq(&self, other: &Self) -> bool {
+        self.element_id == other.element_id
+    }
+}
+
+impl Hash for TableOfContents {
+    fn hash<H>(&self, state: &mut H) where H: Hasher {
+        self.element_id.hash(state);
+    }
+}
@@ -1,22 +1,36 @@
 use std::borrow::Cow;
 use std::fmt;
 use std::hash::{Hash, Hasher};
+use std::ops::Range;
 use std::str;
+use std::sync::{Arc, OnceLock};
 use ::tag::Version;
 
-pub use self::content::{Content, ExtendedText, ExtendedLink, Comment, Lyrics, Picture, PictureType};
+pub use self::content::{Content, ExtendedText, ExtendedLink, Comment, Lyrics, SynchronisedLyrics, SynchronisedLyricsType, TimestampFormat, Picture, PictureType, GroupIdentifier, EncryptionMethod, EncapsulatedObject, UniqueFileIdentifier, Private, MusicCdIdentifier, ChannelType, VolumeAdjustment, RelativeVolumeAdjustment, InterpolationMethod, Equalisation, EventType, Event, EventTimingCodes, ReceivedAs, Commercial, TermsOfUse, LinkedInfo, Signature, AudioSeekPointIndex, RecommendedBufferSize, Popularimeter, Chapter, TableOfContents};
 pub use self::timestamp::Timestamp;
 
 mod content;
 mod timestamp;
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// Frame IDs are stored inline to avoid a heap allocation for every frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 enum ID {
     /// A valid 4-byte frame ID.
-    Valid(String),
+    Valid([u8; 4]),
     /// If an ID3v2.2 ID could not be mapped to its ID3v2.4 counterpart, it is stored as is. This
     /// allows invalid ID3v2.2 frames to be retained.
-    Invalid(String),
+    Invalid([u8; 3]),
+}
+
+impl ID {
+    fn as_str(&self) -> &str {
+        let bytes = match *self {
+            ID::Valid(ref id) => &id[..],
+            ID::Invalid(ref id) => &id[..],
+        };
+        // IDs are only ever constructed by copying the bytes of a str of the same length.
+        str::from_utf8(bytes).expect("frame ID is not valid UTF-8")
+    }
 }
 
 /// A structure representing an ID3 frame.
@@ -24,20 +38,55 @@ enum ID {
 /// It is imporant to note that the (Partial)Eq and Hash implementations are based on the ID3 spec.
 /// This means that text frames with equal ID's are equal but picture frames with both "APIC" as ID
 /// are not because their uniqueness is also defined by their content.
-#[derive(Clone, Debug, Eq)]
+#[derive(Clone, Eq)]
 pub struct Frame {
     id: ID,
-    content: Content,
+    /// The content, which is only set once it has been decoded if the frame was read lazily.
+    content: OnceLock<Content>,
+    /// The encoded content of a frame that was read lazily, see `DecoderBuilder::lazy`.
+    encoded: Option<Arc<EncodedContent>>,
     tag_alter_preservation: bool,
     file_alter_preservation: bool,
+    group_identifier: Option<u8>,
+    encryption_method: Option<u8>,
+}
+
+/// The synchronised and decompressed data of a frame. Frames that are read lazily from a tag
+/// share the buffer of the tag and only hold the range of their data.
+#[derive(Debug, PartialEq, Eq)]
+struct EncodedContent {
+    version: Version,
+    buffer: Arc<Vec<u8>>,
+    range: Range<usize>,
+}
+
+impl EncodedContent {
+    fn data(&self) -> &[u8] {
+        &self.buffer[self.range.clone()]
+    }
+}
+
+impl fmt::Debug for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Frame")
+            .field("id", &self.id)
+            .field("content", self.content())
+            .field("tag_alter_preservation", &self.tag_alter_preservation)
+            .field("file_alter_preservation", &self.file_alter_preservation)
+            .field("group_identifier", &self.group_identifier)
+            .field("encryption_method", &self.encryption_method)
+            .finish()
+    }
 }
 
 impl PartialEq for Frame {
     fn eq(&self, other: &Frame) -> bool {
-        match self.content {
-            Content::Text(_) => self.id == other.id,
+        match *self.content() {
+            // There is only one frame of these per tag.
+            Content::Text(_) | Content::PlayCounter(_) | Content::MusicCdIdentifier(_) | Content::EventTimingCodes(_) | Content::SeekOffset(_) | Content::AudioSeekPointIndex(_)
+            | Content::RecommendedBufferSize(_) => self.id == other.id,
             _ => {
-                self.id == other.id && self.content == other.content
+                self.id == other.id && self.content() == other.content()
             },
         }
     }
@@ -45,11 +94,12 @@ impl PartialEq for Frame {
 
 impl Hash for Frame {
     fn hash<H>(&self, state: &mut H) where H: Hasher {
-        match self.content {
-            Content::Text(_) => self.id.hash(state),
+        match *self.content() {
+            Content::Text(_) | Content::PlayCounter(_) | Content::MusicCdIdentifier(_) | Content::EventTimingCodes(_) | Content::SeekOffset(_) | Content::AudioSeekPointIndex(_)
+            | Content::RecommendedBufferSize(_) => self.id.hash(state),
             _ => {
                 self.id.hash(state);
-                self.content.hash(state);
+                self.content().hash(state);
             },
         }
     }
@@ -77,20 +127,30 @@ impl Frame {
             let l = id.bytes().count();
             l == 3 || l == 4
         });
+        let valid = |id: &str| {
+            let mut bytes = [0; 4];
+            bytes.copy_from_slice(id.as_bytes());
+            ID::Valid(bytes)
+        };
         Frame {
             id: if id.len() == 3 {
                 match ::util::convert_id_2_to_3(id) {
-                    Some(translated) => {
-                        ID::Valid(translated.to_string())
+                    Some(translated) => valid(translated),
+                    None => {
+                        let mut bytes = [0; 3];
+                        bytes.copy_from_slice(id.as_bytes());
+                        ID::Invalid(bytes)
                     },
-                    None => ID::Invalid(id.to_string()),
                 }
             } else {
-                ID::Valid(id.to_string())
+                valid(id)
             },
-            content: content,
+            content: OnceLock::from(content),
+            encoded: None,
             tag_alter_preservation: false,
             file_alter_preservation: false,
+            group_identifier: None,
+            encryption_method: None,
         }
     }
 
@@ -99,27 +159,87 @@ impl Frame {
     /// The string returned us usually 4 bytes long except when the frame was read from an ID3v2.2
     /// tag and the ID could not be mapped to an ID3v2.3 ID.
     pub fn id(&self) -> &str {
-        match self.id {
-              ID::Valid(ref id)
-            | ID::Invalid(ref id) => id,
-        }
+        self.id.as_str()
     }
 
     /// Returns the ID that is compatible with specified version or None if no ID is available in
     /// that version.
     pub fn id_for_version(&self, version: Version) -> Option<&str> {
-        match (version, &self.id) {
-            (Version::Id3v22, &ID::Valid(ref id)) => ::util::convert_id_3_to_2(id),
-            (Version::Id3v23, &ID::Valid(ref id))
-            | (Version::Id3v24, &ID::Valid(ref id))
-            | (Version::Id3v22, &ID::Invalid(ref id)) => Some(id),
-            (_, &ID::Invalid(_)) => None,
+        match (version, self.id) {
+            (Version::Id3v22, ID::Valid(_)) => ::util::convert_id_3_to_2(self.id()),
+            (Version::Id3v23, ID::Valid(_))
+            | (Version::Id3v24, ID::Valid(_))
+            | (Version::Id3v22, ID::Invalid(_)) => Some(self.id()),
+            (_, ID::Invalid(_)) => None,
         }
     }
 
+    /// Creates a frame whose content is decoded from the data of an ID3 frame of the specified
+    /// version when it is first accessed.
+    pub(crate) fn with_encoded_content(id: &str, version: Version, data: Vec<u8>) -> Frame {
+        let range = 0..data.len();
+        Frame::with_shared_content(id, version, &Arc::new(data), range)
+    }
+
+    /// Creates a frame like `with_encoded_content` whose data is the `range` of a buffer that is
+    /// shared with other frames, so that the frames of a tag do not need an allocation each.
+    pub(crate) fn with_shared_content(id: &str, version: Version, buffer: &Arc<Vec<u8>>, range: Range<usize>) -> Frame {
+        let mut frame = Frame::with_content(id, Content::Unknown(Vec::new()));
+        frame.content = OnceLock::new();
+        frame.encoded = Some(Arc::new(EncodedContent { version, buffer: buffer.clone(), range }));
+        frame
+    }
+
     /// Returns the content of the frame.
+    ///
+    /// The content of a frame that was read lazily is decoded here, see `DecoderBuilder::lazy`.
+    /// Unlike when decoding eagerly, content that fails to decode is not an error but is returned
+    /// as `Content::Unknown` holding the data of the frame. Use `try_content` to get the error.
     pub fn content(&self) -> &Content {
-        &self.content
+        self.content.get_or_init(|| {
+            let encoded = self.encoded.as_ref()
+                .expect("frame without content has no encoded content");
+            self.decode_encoded(encoded)
+                .unwrap_or_else(|_| Content::Unknown(encoded.data().to_vec()))
+        })
+    }
+
+    /// Returns the content of the frame like `content`, but returns an error if the content of a
+    /// frame that was read lazily fails to decode.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{DecoderBuilder, Tag, Version};
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_title("Title");
+    /// let mut data = Vec::new();
+    /// tag.write_to(&mut data, Version::Id3v24).unwrap();
+    ///
+    /// let tag = DecoderBuilder::default().lazy(true).build().unwrap().decode(&data[..]).unwrap();
+    /// assert_eq!(Some("Title"), tag.get("TIT2").unwrap().try_content().unwrap().text());
+    /// ```
+    pub fn try_content(&self) -> ::Result<&Content> {
+        let encoded = match self.encoded {
+            Some(ref encoded) => encoded,
+            None => return Ok(self.content()),
+        };
+        match self.content.get() {
+            // Unknown content may stand in for a decoding error, which is reproduced below.
+            Some(&Content::Unknown(_)) | None => (),
+            Some(content) => return Ok(content),
+        }
+        let content = self.decode_encoded(encoded)?;
+        Ok(self.content.get_or_init(|| content))
+    }
+
+    fn decode_encoded(&self, encoded: &EncodedContent) -> ::Result<Content> {
+        // The original ID is needed to decode frames from ID3v2.2 tags.
+        let id = match encoded.version {
+            Version::Id3v22 => self.id_for_version(Version::Id3v22).unwrap_or(self.id()),
+            _ => self.id(),
+        };
+        ::stream::frame::decode_content(encoded.data(), encoded.version, id, false, false)
     }
 
     /// Returns whether the tag_alter_preservation flag is set.
@@ -142,6 +262,44 @@ impl Frame {
         self.file_alter_preservation = file_alter_preservation;
     }
 
+    /// Returns the group symbol of the frame, if it belongs to a group.
+    pub fn group_identifier(&self) -> Option<u8> {
+        self.group_identifier
+    }
+
+    /// Sets the group symbol of the frame. Frames with the same symbol belong together, the
+    /// meaning of the group is registered with a group identification registration frame (GRID),
+    /// see `Tag::register_group`.
+    ///
+    /// Valid symbols are in the range `0x80..=0xF0`. The symbol is written in the frame header of
+    /// ID3v2.3 and ID3v2.4 tags. ID3v2.2 has no frame flags, so the symbol is dropped when writing
+    /// such a tag.
+    ///
+    /// # Panics
+    /// If the symbol is out of range.
+    pub fn set_group_identifier(&mut self, symbol: Option<u8>) {
+        if let Some(symbol) = symbol {
+            assert!((0x80..=0xf0).contains(&symbol), "group symbols must be in the range 0x80..=0xF0");
+        }
+        self.group_identifier = symbol;
+    }
+
+    /// Returns the encryption method symbol if the frame holds encrypted data.
+    ///
+    /// Encrypted frames are decrypted while decoding if a `FrameDecryptor` is registered for their
+    /// method. Otherwise, the content is `Content::Unknown` holding the encrypted data, which is
+    /// written back as is.
+    pub fn encryption_method(&self) -> Option<u8> {
+        self.encryption_method
+    }
+
+    /// Marks the frame as encrypted with the specified method. The content should be
+    /// `Content::Unknown` holding the encrypted data. ID3v2.2 tags do not support encryption,
+    /// encrypted frames are omitted when writing them.
+    pub fn set_encryption_method(&mut self, method: Option<u8>) {
+        self.encryption_method = method;
+    }
+
     /// Returns a string representing the parsed content.
     ///
     /// Returns `None` if the parsed content can not be represented as text.
@@ -150,7 +308,7 @@ impl Frame {
     /// ```
     /// use id3::frame::{self, Frame, Content};
     ///
-    /// let title_frame = Frame::with_content("TIT2", Content::Text("title".to_owned()));
+    /// let title_frame = Frame::with_content("TIT2", Content::Text("title".into()));
     /// assert_eq!(&title_frame.text().unwrap()[..], "title");
     ///
     /// let mut txxx_frame = Frame::with_content("TXXX", Content::ExtendedText(frame::ExtendedText {
@@ -167,15 +325,76 @@ impl Frame {
 
 impl fmt::Display for Frame {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        match self.content {
-              Content::Text(ref content)
-            | Content::Link(ref content) => write!(f, "{}", content),
+        match *self.content() {
+            Content::Text(ref content) => write!(f, "{}", content.replace('\0', "; ")),
+            Content::Link(ref content) => write!(f, "{}", content),
             Content::Lyrics(ref content) => write!(f, "{}", content.text),
+            Content::SynchronisedLyrics(ref content) => {
+                let lines: Vec<String> = content.lines().into_iter().map(|(_, line)| line).collect();
+                write!(f, "{}", lines.join("\n"))
+            },
             Content::ExtendedText(ref content) => write!(f, "{}: {}", content.description, content.value),
             Content::ExtendedLink(ref content) => write!(f, "{}: {}", content.description, content.link),
             Content::Comment(ref content) => write!(f, "{}: {}", content.description, content.text),
             Content::Picture(ref content) => write!(f, "{}: {:?} ({:?})", content.description, content.picture_type, content.mime_type),
+            Content::EncapsulatedObject(ref content) => write!(f, "{}: {} ({}, {} bytes)", content.description, content.filename, content.mime_type, content.data.len()),
+            Content::EncryptionMethod(ref content) => write!(f, "{}: {:#x}", content.owner_identifier, content.symbol),
+            Content::GroupIdentifier(ref content) => write!(f, "{}: {:#x}", content.owner_identifier, content.symbol),
+            Content::UniqueFileIdentifier(ref content) => write!(f, "{}: {}", content.owner_identifier, String::from_utf8_lossy(&content.identifier)),
+            Content::Private(ref content) => write!(f, "{}: {} bytes", content.owner, content.data.len()),
+            Content::RelativeVolumeAdjustment(ref content) => {
+                let channels: Vec<String> = content.channels.iter()
+                    .map(|channel| format!("{:?} {:+.2} dB", channel.channel, channel.adjustment_db()))
+                    .collect();
+                write!(f, "{}: {}", content.identification, channels.join(", "))
+            },
+            Content::Equalisation(ref content) => write!(f, "{}: {:?}, {} adjustments", content.identification, content.method, content.adjustments.len()),
+            Content::EventTimingCodes(ref content) => write!(f, "{} events", content.events.len()),
+            Content::LinkedInfo(ref content) => write!(f, "{}: {}", content.frame_id, content.url),
+            Content::TermsOfUse(ref content) => write!(f, "{}", content.text),
+            Content::Signature(ref content) => write!(f, "{:#x}: {} bytes", content.group_symbol, content.signature.len()),
+            Content::Commercial(ref content) => write!(f, "{}: {} ({})", content.seller, content.description, content.price),
+            Content::MusicCdIdentifier(ref content) => write!(f, "{} bytes", content.toc.len()),
+            Content::RecommendedBufferSize(ref content) => write!(f, "{} bytes", content.buffer_size),
+            Content::AudioSeekPointIndex(ref content) => write!(f, "{} points", content.points.len()),
+            Content::SeekOffset(offset) => write!(f, "{}", offset),
+            Content::PlayCounter(counter) => write!(f, "{}", counter),
+            Content::Popularimeter(ref content) => write!(f, "{}: {} ({} plays)", content.email, content.rating, content.counter),
+            Content::Chapter(ref content) => write!(f, "{}: {}", content.element_id, content.title().unwrap_or("")),
+            Content::TableOfContents(ref content) => write!(f, "{}: {}", content.element_id, content.elements.join(", ")),
             Content::Unknown(ref content) => write!(f, "unknown, {} bytes", content.len()),
         }
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::stream::tag::DecoderBuilder;
+    use ::tag::Tag;
+
+    #[test]
+    fn lazy_frames_share_tag_buffer() {
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+        tag.set_artist("Artist");
+        let mut album = Frame::with_content("TALB", Content::Text("Album".into()));
+        album.set_group_identifier(Some(0x80));
+        tag.add_frame(album);
+        let decoder = DecoderBuilder::default().lazy(true).build().unwrap();
+        for &version in &[Version::Id3v22, Version::Id3v23, Version::Id3v24] {
+            let mut data = Vec::new();
+            tag.write_to(&mut data, version).unwrap();
+            let lazy = decoder.decode(&data[..]).unwrap();
+            let buffers: Vec<_> = lazy.frames()
+                .map(|frame| frame.encoded.as_ref().unwrap().buffer.clone())
+                .collect();
+            assert_eq!(3, buffers.len());
+            assert!(buffers.iter().all(|buffer| Arc::ptr_eq(buffer, &buffers[0])));
+            assert_eq!(Some("Album"), lazy.album());
+            let group = if version == Version::Id3v22 { None } else { Some(0x80) };
+            assert_eq!(group, lazy.get("TALB").unwrap().group_identifier());
+        }
+    }
+}
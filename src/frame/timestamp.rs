@@ -5,6 +5,7 @@ use std::str;
 use regex::Regex;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 #[allow(missing_docs)]
 /// Represents a date and time according to the ID3v2.4 spec:
 ///
@@ -0,0 +1,80 @@
+use std::fmt;
+use std::str::FromStr;
+
+
+/// Gapless playback information as stored by iTunes in the "iTunSMPB" comment.
+///
+/// The comment consists of twelve hexadecimal fields. The second and third field are the number of
+/// samples that the encoder added before and after the audio and the fourth field is the number of
+/// samples of the original audio. The remaining fields are unused and written as zeros.
+///
+/// # Example
+/// ```
+/// use id3::GaplessInfo;
+///
+/// let text = " 00000000 00000840 000001CA 00000000003F31F6 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000";
+/// let info: GaplessInfo = text.parse().unwrap();
+/// assert_eq!(info.encoder_delay, 0x840);
+/// assert_eq!(info.padding, 0x1ca);
+/// assert_eq!(info.sample_count, 0x3f31f6);
+/// assert_eq!(info.to_string(), text);
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct GaplessInfo {
+    /// The number of samples the encoder added before the audio.
+    pub encoder_delay: u32,
+    /// The number of samples the encoder added after the audio.
+    pub padding: u32,
+    /// The number of samples of the original audio.
+    pub sample_count: u64,
+}
+
+impl FromStr for GaplessInfo {
+    type Err = ::Error;
+
+    fn from_str(s: &str) -> ::Result<GaplessInfo> {
+        let mut fields = s.split_whitespace();
+        let mut next = || fields.next()
+            .ok_or_else(|| ::Error::new(::ErrorKind::Parsing, "iTunSMPB has too few fields"));
+        let _reserved = next()?;
+        let encoder_delay = next()?;
+        let padding = next()?;
+        let sample_count = next()?;
+        let invalid = |_| ::Error::new(::ErrorKind::Parsing, "iTunSMPB contains an invalid field");
+        Ok(GaplessInfo {
+            encoder_delay: u32::from_str_radix(encoder_delay, 16).map_err(invalid)?,
+            padding: u32::from_str_radix(padding, 16).map_err(invalid)?,
+            sample_count: u64::from_str_radix(sample_count, 16).map_err(invalid)?,
+        })
+    }
+}
+
+impl fmt::Display for GaplessInfo {
+    /// Formats the information the way iTunes writes it.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, " 00000000 {:08X} {:08X} {:016X}", self.encoder_delay, self.padding, self.sample_count)?;
+        for _ in 0..8 {
+            write!(f, " 00000000")?;
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_invalid() {
+        assert!("".parse::<GaplessInfo>().is_err());
+        assert!(" 00000000 00000840 000001CA".parse::<GaplessInfo>().is_err());
+        assert!(" 00000000 0000084X 000001CA 00000000003F31F6".parse::<GaplessInfo>().is_err());
+    }
+
+    #[test]
+    fn parse_lowercase() {
+        let info: GaplessInfo = "00000000 00000840 000001ca 00000000003f31f6".parse().unwrap();
+        assert_eq!(GaplessInfo { encoder_delay: 0x840, padding: 0x1ca, sample_count: 0x3f31f6 }, info);
+    }
+}
@@ -0,0 +1,256 @@
+use serde_json::{self, Map, Value};
+use ::frame::{Comment, Content, ExtendedLink, ExtendedText, Frame, Lyrics};
+use ::tag::{self, Tag};
+use ::util::BASE64_ALPHABET;
+
+
+fn parsing_error(description: &'static str) -> ::Error {
+    ::Error::new(::ErrorKind::Parsing, description)
+}
+
+/// Serializes the tag, see `Tag::to_json` for the schema.
+pub fn tag_to_json(tag: &Tag) -> ::Result<String> {
+    let frames = tag.frames().map(frame_to_json).collect::<::Result<Vec<Value>>>()?;
+    let mut object = Map::new();
+    // Tags are stored as ID3v2.4, which is the version of the binary frame data.
+    object.insert("version".to_string(), Value::from(tag::Id3v24.minor()));
+    object.insert("frames".to_string(), Value::Array(frames));
+    let mut json = serde_json::to_string_pretty(&Value::Object(object))
+        .map_err(|_| ::Error::new(::ErrorKind::InvalidInput, "tag can not be serialized as JSON"))?;
+    json.push('\n');
+    Ok(json)
+}
+
+fn frame_to_json(frame: &Frame) -> ::Result<Value> {
+    let mut object = Map::new();
+    let mut member = |key: &str, value: &str| {
+        object.insert(key.to_string(), Value::String(value.to_string()));
+    };
+    member("id", frame.id());
+    match (frame.id(), frame.content()) {
+        ("TXXX", Content::ExtendedText(ext)) => {
+            member("description", &ext.description);
+            member("value", &ext.value);
+        },
+        ("WXXX", Content::ExtendedLink(ext)) => {
+            member("description", &ext.description);
+            member("link", &ext.link);
+        },
+        ("COMM", Content::Comment(Comment { lang, description, text }))
+            | ("USLT", Content::Lyrics(Lyrics { lang, description, text })) => {
+            member("lang", lang);
+            member("description", description);
+            member("text", text);
+        },
+        (id, Content::Text(_)) if !is_structured(id) => {
+            let values = frame.content().text_values()
+                .map(|value| Value::String(value.to_string()))
+                .collect();
+            object.insert("text".to_string(), Value::Array(values));
+        },
+        (id, Content::Link(link)) if !is_structured(id) => member("link", link),
+        (_, content) => {
+            let data = ::stream::frame::encode_content(content)?;
+            member("binary", &::util::base64(&data, BASE64_ALPHABET, '='));
+        },
+    }
+    if frame.tag_alter_preservation() {
+        object.insert("tag_alter_preservation".to_string(), Value::Bool(true));
+    }
+    if frame.file_alter_preservation() {
+        object.insert("file_alter_preservation".to_string(), Value::Bool(true));
+    }
+    if let Some(symbol) = frame.group_identifier() {
+        object.insert("group_identifier".to_string(), Value::from(symbol));
+    }
+    if let Some(method) = frame.encryption_method() {
+        object.insert("encryption_method".to_string(), Value::from(method));
+    }
+    Ok(Value::Object(object))
+}
+
+/// Parses a tag, see `Tag::to_json` for the schema.
+pub fn tag_from_json(json: &str) -> ::Result<Tag> {
+    let value: Value = serde_json::from_str(json)
+        .map_err(|_| parsing_error("invalid JSON"))?;
+    let version = match value.get("version").map(Value::as_u64) {
+        None | Some(Some(4)) => tag::Id3v24,
+        Some(Some(3)) => tag::Id3v23,
+        Some(Some(2)) => tag::Id3v22,
+        Some(_) => return Err(parsing_error("JSON tag has an unsupported version")),
+    };
+    let frames = match value.get("frames") {
+        Some(Value::Array(frames)) => frames,
+        _ => return Err(parsing_error("JSON tag has no frames array")),
+    };
+    frames.iter().map(|frame| frame_from_json(frame, version)).collect()
+}
+
+/// Returns whether frames with the ID have a content other than text or a link.
+fn is_structured(id: &str) -> bool {
+    ["TXXX", "WXXX", "COMM", "USLT"].contains(&id)
+}
+
+fn frame_from_json(value: &Value, version: tag::Version) -> ::Result<Frame> {
+    let id = value.get("id").and_then(Value::as_str)
+        .ok_or_else(|| parsing_error("JSON frame has no id"))?;
+    if !(3..=4).contains(&id.len()) || !id.bytes().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit()) {
+        return Err(::Error::new(::ErrorKind::InvalidInput, "invalid frame ID in JSON frame"));
+    }
+    let string = |key: &'static str| -> ::Result<String> {
+        match value.get(key) {
+            Some(Value::String(s)) => Ok(s.clone()),
+            None => Ok(String::new()),
+            Some(_) => Err(parsing_error("JSON frame member is not a string")),
+        }
+    };
+    let encryption_method = symbol(value, "encryption_method")?;
+    let content = if let Some(binary) = value.get("binary") {
+        let data = binary.as_str()
+            .and_then(|s| ::util::base64_decode(s, BASE64_ALPHABET, '='))
+            .ok_or_else(|| parsing_error("JSON frame has invalid base64 binary data"))?;
+        // The original ID is needed to decode frames from ID3v2.2 tags.
+        let decode_id = match version {
+            tag::Id3v22 if id.len() == 4 => ::util::convert_id_3_to_2(id).unwrap_or(id),
+            _ => id,
+        };
+        match encryption_method {
+            // Encrypted frames hold their encrypted data as is.
+            Some(_) => Content::Unknown(data),
+            None => ::stream::frame::decode_content(&data[..], version, decode_id, false, false)?,
+        }
+    } else {
+        match id {
+            "TXXX" => Content::ExtendedText(ExtendedText { description: string("description")?, value: string("value")? }),
+            "WXXX" => Content::ExtendedLink(ExtendedLink { description: string("description")?, link: string("link")? }),
+            "COMM" => Content::Comment(Comment { lang: string("lang")?, description: string("description")?, text: string("text")? }),
+            "USLT" => Content::Lyrics(Lyrics { lang: string("lang")?, description: string("description")?, text: string("text")? }),
+            _ => match (value.get("text"), value.get("link")) {
+                (Some(Value::Array(values)), None) => {
+                    let values = values.iter()
+                        .map(|v| v.as_str().ok_or_else(|| parsing_error("JSON text value is not a string")))
+                        .collect::<::Result<Vec<_>>>()?;
+                    Content::Text(values.join("\0").into())
+                },
+                (Some(Value::String(text)), None) => Content::Text(text.clone().into()),
+                (None, Some(Value::String(link))) => Content::Link(link.clone()),
+                _ => return Err(parsing_error("JSON frame has no text, link or binary data")),
+            },
+        }
+    };
+    let group_identifier = symbol(value, "group_identifier")?;
+    if group_identifier.is_some_and(|symbol| !(0x80..=0xf0).contains(&symbol)) {
+        return Err(::Error::new(::ErrorKind::InvalidInput, "group symbols must be in the range 0x80..=0xF0"));
+    }
+    let mut frame = Frame::with_content(id, content);
+    frame.set_tag_alter_preservation(flag(value, "tag_alter_preservation")?);
+    frame.set_file_alter_preservation(flag(value, "file_alter_preservation")?);
+    frame.set_group_identifier(group_identifier);
+    frame.set_encryption_method(encryption_method);
+    Ok(frame)
+}
+
+/// Reads a frame flag, which is unset if it is missing.
+fn flag(value: &Value, key: &str) -> ::Result<bool> {
+    match value.get(key) {
+        Some(Value::Bool(b)) => Ok(*b),
+        None => Ok(false),
+        Some(_) => Err(parsing_error("JSON frame flag is not a boolean")),
+    }
+}
+
+/// Reads a group or encryption method symbol of a frame.
+fn symbol(value: &Value, key: &str) -> ::Result<Option<u8>> {
+    match value.get(key) {
+        Some(Value::Null) | None => Ok(None),
+        Some(v) => match v.as_u64() {
+            Some(symbol) if symbol <= 0xff => Ok(Some(symbol as u8)),
+            _ => Err(parsing_error("JSON frame symbol is not a byte")),
+        },
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::frame::{Picture, PictureType};
+
+    #[test]
+    fn roundtrip() {
+        let mut tag = Tag::new();
+        tag.set_title("Title \"quoted\"\n");
+        tag.set_text_values("TPE1", vec!["One", "Two"]);
+        tag.add_extended_text("Key", "Value");
+        tag.add_comment(Comment { lang: "eng".to_string(), description: "".to_string(), text: "Ünïcödé \u{1F600}".to_string() });
+        tag.add_frame(Frame::with_content("WOAR", Content::Link("http://example.com".to_string())));
+        tag.add_picture(Picture {
+            mime_type: "image/png".to_string(),
+            picture_type: PictureType::CoverFront,
+            description: "".to_string(),
+            data: vec![0, 1, 2, 255],
+        });
+        let json = tag_to_json(&tag).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        let frames = value["frames"].as_array().unwrap();
+        assert_eq!(&Value::from(vec!["Title \"quoted\"\n"]), &frames[0]["text"]);
+        assert_eq!(&Value::from(vec!["One", "Two"]), &frames[1]["text"]);
+        assert_eq!(Some("APIC"), frames[5]["id"].as_str());
+        assert!(frames[5]["binary"].is_string());
+        assert_eq!(tag, tag_from_json(&json).unwrap());
+    }
+
+    #[test]
+    fn roundtrip_flags() {
+        let mut tag = Tag::new();
+        let mut frame = Frame::with_content("TIT2", Content::Text("Title".into()));
+        frame.set_tag_alter_preservation(true);
+        frame.set_group_identifier(Some(0x80));
+        tag.add_frame(frame);
+        let mut frame = Frame::with_content("PRIV", Content::Unknown(vec![1, 2, 3]));
+        frame.set_file_alter_preservation(true);
+        frame.set_encryption_method(Some(0x81));
+        tag.add_frame(frame);
+
+        let read = tag_from_json(&tag_to_json(&tag).unwrap()).unwrap();
+        let frames: Vec<_> = read.frames().collect();
+        assert!(frames[0].tag_alter_preservation() && !frames[0].file_alter_preservation());
+        assert_eq!(Some(0x80), frames[0].group_identifier());
+        assert!(frames[1].file_alter_preservation());
+        assert_eq!(Some(0x81), frames[1].encryption_method());
+        assert_eq!(tag, read);
+
+        assert!(tag_from_json(r#"{"frames": [{"id": "TALB", "text": [], "group_identifier": 5}]}"#).is_err());
+        assert!(tag_from_json(r#"{"frames": [{"id": "TALB", "text": [], "encryption_method": 256}]}"#).is_err());
+    }
+
+    #[test]
+    fn binary_version() {
+        // An ID3v2.2 picture stores an image format instead of a MIME type.
+        let tag = tag_from_json(r#"{"version": 2, "frames": [{"id": "APIC", "binary": "AFBORwMAAQI="}]}"#).unwrap();
+        let picture = tag.pictures().next().unwrap();
+        assert_eq!("image/png", picture.mime_type);
+        assert_eq!(vec![1, 2], picture.data);
+        assert!(tag_from_json(r#"{"version": 5, "frames": []}"#).is_err());
+    }
+
+    #[test]
+    fn parse() {
+        let json = r#" { "ignored": [1, -2.5e3, null, true, {"a": false}],
+            "frames": [
+                {"id": "TALB", "text": "Album 😀é\/"},
+                {"id": "TXXX", "value": "no description"}
+            ] } "#;
+        let tag = tag_from_json(json).unwrap();
+        assert_eq!(Some("Album \u{1F600}é/"), tag.album());
+        assert_eq!(Some("no description"), tag.extended_texts().next().map(|e| &e.value[..]));
+
+        assert!(tag_from_json(r#"{"frames": []}"#).unwrap().frames().next().is_none());
+        assert!(tag_from_json(r#"{"frames": []} x"#).is_err());
+        assert!(tag_from_json(r#"{"frames": [{"id": "TALB"}]}"#).is_err());
+        assert!(tag_from_json(r#"{"frames": [{"id": "talb", "text": []}]}"#).is_err());
+        assert!(tag_from_json(r#"{"frames": [{"id": "APIC", "binary": "!"}]}"#).is_err());
+        assert!(tag_from_json(r#"{"frames": [{"id": "TALB", "text": "\ud83d"}]}"#).is_err());
+        assert!(tag_from_json(&format!("{}{}", "[".repeat(1000), "]".repeat(1000))).is_err());
+    }
+}
@@ -29,7 +29,7 @@
 //! let mut tag = Tag::with_version(Version::Id3v24);
 //!
 //! // set the album the hard way
-//! let frame = Frame::with_content("TALB", Content::Text("album".to_string()));
+//! let frame = Frame::with_content("TALB", Content::Text("album".into()));
 //! tag.add_frame(frame);
 //!
 //! // or set it the easy way
@@ -52,19 +52,57 @@ extern crate flate2;
 #[macro_use]
 extern crate lazy_static;
 extern crate regex;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(any(feature = "json", all(test, feature = "serde")))]
+extern crate serde_json;
+#[cfg(feature = "tokio")]
+extern crate tokio;
 
+#[cfg(feature = "tokio")]
+pub use async_io::{ReadTag, WriteTag};
+pub use convert::ConversionReport;
+pub use diff::{FieldChange, FrameChange, TagDiff};
 pub use error::{Error, ErrorKind, Result};
 pub use frame::{Content, Frame, Timestamp};
-pub use stream::tag::{Encoder, EncoderBuilder};
-pub use tag::{Tag, Version};
+pub use gapless::GaplessInfo;
+pub use musicbrainz::MusicBrainzId;
+pub use rating::{RatingConvention, RatingScale};
+pub use stream::frame::{FrameDecryptor, FrameHeader};
+pub use stream::tag::{AppendedTag, BudgetReport, Decoder, DecoderBuilder, Encoder, EncoderBuilder, ExtendedHeader, FrameDecision, ImageSizeRestriction, Padding, PaddingStrategy, Restrictions, SizeBudget, StreamedObject, TagHeader, TagSizeRestriction, TagStream, TextSizeRestriction};
+pub use tag::{FileTags, GenreStyle, MergePolicy, MergeStrategy, ScrubProfile, SignatureVerifier, Tag, TagPolicy, Version};
+pub use windows_media::WindowsMediaInfo;
 
+/// Import and export of ffmpeg metadata files.
+pub mod ffmetadata;
 /// Contains types and methods for operating on ID3 frames.
 pub mod frame;
+/// Utilities for reading the tags of all files in a directory tree.
+pub mod scan;
+/// Decoding of the cue points, beat grids and waveform overviews that Serato DJ stores in
+/// encapsulated objects (GEOB).
+#[cfg(feature = "serato")]
+pub mod serato;
+/// Conversion between synchronised lyrics and WebVTT or SRT subtitle files.
+pub mod subtitles;
 /// Utilities for working with ID3v1 tags.
 pub mod v1;
 
+#[cfg(feature = "tokio")]
+mod async_io;
+mod container;
+mod convert;
+mod diff;
 mod error;
+mod gapless;
+#[cfg(feature = "json")]
+mod json;
+mod musicbrainz;
+mod rating;
+#[cfg(feature = "serde")]
+mod serialization;
 mod storage;
 mod stream;
 mod tag;
 mod util;
+mod windows_media;
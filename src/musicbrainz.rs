@@ -0,0 +1,57 @@
+/// The MusicBrainz identifiers that MusicBrainz Picard stores in a tag.
+///
+/// The recording identifier is stored in a unique file identifier frame (UFID) owned by
+/// "http://musicbrainz.org", the others in user defined text frames (TXXX).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MusicBrainzId {
+    /// The recording of the track.
+    Recording,
+    /// The track on the release.
+    ReleaseTrack,
+    /// The release, stored as "MusicBrainz Album Id".
+    Release,
+    /// The release group of the release.
+    ReleaseGroup,
+    /// The original release of the release, stored as "MusicBrainz Original Album Id".
+    OriginalRelease,
+    /// The artists of the track, there may be several.
+    Artist,
+    /// The artists of the release, there may be several.
+    AlbumArtist,
+    /// The artists of the original release, there may be several.
+    OriginalArtist,
+    /// The works of the recording, there may be several.
+    Work,
+    /// The disc identifier computed from the table of contents of a CD.
+    Disc,
+}
+
+/// The owner of the unique file identifier (UFID) that holds the recording identifier.
+pub(crate) const UFID_OWNER: &str = "http://musicbrainz.org";
+
+impl MusicBrainzId {
+    /// Returns the description of the user defined text frame (TXXX) that holds the identifier,
+    /// or `None` for the recording identifier, which is stored in a UFID frame.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::MusicBrainzId;
+    ///
+    /// assert_eq!(Some("MusicBrainz Album Id"), MusicBrainzId::Release.description());
+    /// assert_eq!(None, MusicBrainzId::Recording.description());
+    /// ```
+    pub fn description(self) -> Option<&'static str> {
+        Some(match self {
+            MusicBrainzId::Recording => return None,
+            MusicBrainzId::ReleaseTrack => "MusicBrainz Release Track Id",
+            MusicBrainzId::Release => "MusicBrainz Album Id",
+            MusicBrainzId::ReleaseGroup => "MusicBrainz Release Group Id",
+            MusicBrainzId::OriginalRelease => "MusicBrainz Original Album Id",
+            MusicBrainzId::Artist => "MusicBrainz Artist Id",
+            MusicBrainzId::AlbumArtist => "MusicBrainz Album Artist Id",
+            MusicBrainzId::OriginalArtist => "MusicBrainz Original Artist Id",
+            MusicBrainzId::Work => "MusicBrainz Work Id",
+            MusicBrainzId::Disc => "MusicBrainz Disc Id",
+        })
+    }
+}
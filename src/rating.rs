@@ -0,0 +1,145 @@
+
+/// The scales that applications use to map the rating byte of a popularimeter frame (POPM) to a
+/// number of stars.
+///
+/// Ratings are expressed in half stars from 0 (unrated) to 10 (five stars).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RatingScale {
+    /// Windows Media Player writes 1, 64, 128, 196 and 255 for one to five stars. Other values are
+    /// read as the closest star.
+    WindowsMediaPlayer,
+    /// MediaMonkey uses the Windows Media Player values for whole stars and writes 13, 54, 118,
+    /// 186 and 242 for half stars.
+    MediaMonkey,
+    /// foobar2000 divides the range evenly, writing 51, 102, 153, 204 and 255 for one to five
+    /// stars.
+    Foobar2000,
+}
+
+/// The POPM values written by MediaMonkey, indexed by the number of half stars.
+static MEDIA_MONKEY: [u8; 11] = [0, 13, 1, 54, 64, 118, 128, 186, 196, 242, 255];
+
+impl RatingScale {
+    /// Converts a rating in half stars to the POPM rating byte. Ratings above 10 are clamped.
+    /// Scales without half stars round up.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::RatingScale;
+    ///
+    /// assert_eq!(RatingScale::WindowsMediaPlayer.to_popm(8), 196);
+    /// assert_eq!(RatingScale::MediaMonkey.to_popm(7), 186);
+    /// assert_eq!(RatingScale::Foobar2000.to_popm(2), 51);
+    /// ```
+    pub fn to_popm(self, half_stars: u8) -> u8 {
+        let half_stars = half_stars.min(10);
+        let stars = half_stars.div_ceil(2);
+        match self {
+            RatingScale::WindowsMediaPlayer => [0, 1, 64, 128, 196, 255][stars as usize],
+            RatingScale::MediaMonkey => MEDIA_MONKEY[half_stars as usize],
+            RatingScale::Foobar2000 => stars * 51,
+        }
+    }
+
+    /// Converts a POPM rating byte to a rating in half stars.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::RatingScale;
+    ///
+    /// assert_eq!(RatingScale::WindowsMediaPlayer.from_popm(196), 8);
+    /// assert_eq!(RatingScale::WindowsMediaPlayer.from_popm(100), 6);
+    /// assert_eq!(RatingScale::MediaMonkey.from_popm(13), 1);
+    /// assert_eq!(RatingScale::Foobar2000.from_popm(153), 6);
+    /// ```
+    pub fn from_popm(self, value: u8) -> u8 {
+        match self {
+            RatingScale::WindowsMediaPlayer => match value {
+                0 => 0,
+                1..=31 => 2,
+                32..=95 => 4,
+                96..=159 => 6,
+                160..=223 => 8,
+                _ => 10,
+            },
+            RatingScale::MediaMonkey => {
+                if value == 0 {
+                    return 0;
+                }
+                (1..MEDIA_MONKEY.len())
+                    .min_by_key(|&i| (i32::from(MEDIA_MONKEY[i]) - i32::from(value)).abs())
+                    .unwrap() as u8
+            },
+            RatingScale::Foobar2000 => ((u32::from(value) + 25) / 51 * 2) as u8,
+        }
+    }
+}
+
+/// Determines how a rating is stored, see `Tag::rating` and `Tag::set_rating`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RatingConvention {
+    /// The email address that identifies the popularimeter frame (POPM) of the application.
+    pub email: String,
+    /// The scale that is used to convert the POPM rating byte.
+    pub scale: RatingScale,
+    /// Whether the rating is also stored as a number of stars from 1 to 5 in a user defined text
+    /// frame (TXXX) with the description "RATING".
+    pub text_frame: bool,
+}
+
+impl RatingConvention {
+    /// Returns the convention of Windows Media Player.
+    pub fn windows_media_player() -> RatingConvention {
+        RatingConvention {
+            email: "Windows Media Player 9 Series".to_string(),
+            scale: RatingScale::WindowsMediaPlayer,
+            text_frame: false,
+        }
+    }
+
+    /// Returns the convention of MediaMonkey.
+    pub fn media_monkey() -> RatingConvention {
+        RatingConvention {
+            email: "no@email".to_string(),
+            scale: RatingScale::MediaMonkey,
+            text_frame: false,
+        }
+    }
+
+    /// Returns the convention of the application that uses the email address, if it is known.
+    pub fn known(email: &str) -> Option<RatingConvention> {
+        [RatingConvention::windows_media_player(), RatingConvention::media_monkey()]
+            .iter()
+            .find(|convention| convention.email == email)
+            .cloned()
+    }
+}
+
+impl Default for RatingConvention {
+    /// Uses the Windows Media Player popularimeter, which is understood by most applications, and
+    /// also writes the "RATING" text frame.
+    fn default() -> RatingConvention {
+        RatingConvention {
+            text_frame: true,
+            ..RatingConvention::windows_media_player()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scales_roundtrip() {
+        for &scale in &[RatingScale::WindowsMediaPlayer, RatingScale::Foobar2000] {
+            for stars in 0..6 {
+                assert_eq!(stars * 2, scale.from_popm(scale.to_popm(stars * 2)));
+            }
+        }
+        for half_stars in 0..11 {
+            let scale = RatingScale::MediaMonkey;
+            assert_eq!(half_stars, scale.from_popm(scale.to_popm(half_stars)));
+        }
+    }
+}
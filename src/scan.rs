@@ -0,0 +1,210 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use ::tag::Tag;
+
+
+/// Determines how errors are handled while scanning a directory tree.
+///
+/// Errors include files that do not contain a tag, which are reported with `ErrorKind::NoTag`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Silently skip files and directories that could not be read.
+    Skip,
+    /// Yield errors alongside the path they occurred for and continue scanning.
+    Collect,
+    /// Yield the first error that occurs and stop scanning.
+    Abort,
+}
+
+type PathFilter = Box<dyn Fn(&Path) -> bool>;
+
+/// An iterator over the tags of all files in a directory tree.
+///
+/// Entries are visited depth first and in lexicographical order within a directory. This type is
+/// created by the `scan` function.
+pub struct Scan {
+    /// Paths that are yet to be visited. The next entry is at the end.
+    pending: Vec<PathBuf>,
+    follow_symlinks: bool,
+    errors: ErrorPolicy,
+    filter: Option<PathFilter>,
+    /// The canonical paths of directories that have been visited, used to break symlink cycles.
+    visited: HashSet<PathBuf>,
+    done: bool,
+}
+
+/// Recursively scans the directory at `root` and reads the tag of every file in it.
+///
+/// By default, symbolic links are not followed and errors are collected.
+///
+/// # Example
+/// ```no_run
+/// use std::ffi::OsStr;
+/// use id3::scan::{self, ErrorPolicy};
+///
+/// let tags = scan::scan("music")
+///     .errors(ErrorPolicy::Skip)
+///     .filter_paths(|path| path.extension() == Some(OsStr::new("mp3")));
+/// for (path, tag) in tags {
+///     println!("{}: {:?}", path.display(), tag.unwrap().title());
+/// }
+/// ```
+pub fn scan<P: AsRef<Path>>(root: P) -> Scan {
+    Scan {
+        pending: vec![root.as_ref().to_path_buf()],
+        follow_symlinks: false,
+        errors: ErrorPolicy::Collect,
+        filter: None,
+        visited: HashSet::new(),
+        done: false,
+    }
+}
+
+impl Scan {
+    /// Sets whether symbolic links should be followed. Cycles are detected and visited only once.
+    pub fn follow_symlinks(mut self, follow_symlinks: bool) -> Scan {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// Sets the policy for handling errors.
+    pub fn errors(mut self, policy: ErrorPolicy) -> Scan {
+        self.errors = policy;
+        self
+    }
+
+    /// Only reads the files for which the filter returns true. Directories are always traversed.
+    pub fn filter_paths<F>(mut self, filter: F) -> Scan
+        where F: Fn(&Path) -> bool + 'static {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Visits a single path. Returns `None` if the path did not produce an item.
+    fn visit(&mut self, path: PathBuf) -> Option<(PathBuf, ::Result<Tag>)> {
+        let metadata = if self.follow_symlinks {
+            fs::metadata(&path)
+        } else {
+            fs::symlink_metadata(&path)
+        };
+        let metadata = match metadata {
+            Ok(metadata) => metadata,
+            Err(err) => return Some((path, Err(err.into()))),
+        };
+
+        if metadata.is_dir() {
+            if self.follow_symlinks {
+                match fs::canonicalize(&path) {
+                    Ok(canonical) => {
+                        if !self.visited.insert(canonical) {
+                            return None;
+                        }
+                    },
+                    Err(err) => return Some((path, Err(err.into()))),
+                }
+            }
+            let entries: Result<Vec<PathBuf>, _> = fs::read_dir(&path)
+                .and_then(|dir| dir.map(|entry| entry.map(|e| e.path())).collect());
+            match entries {
+                Ok(mut entries) => {
+                    entries.sort_unstable_by(|a, b| b.cmp(a));
+                    self.pending.extend(entries);
+                    None
+                },
+                Err(err) => Some((path, Err(err.into()))),
+            }
+        } else if metadata.is_file() {
+            if !self.filter.as_ref().map(|f| f(&path)).unwrap_or(true) {
+                return None;
+            }
+            let tag = Tag::read_from_path(&path);
+            Some((path, tag))
+        } else {
+            // Not following symlinks or some special file.
+            None
+        }
+    }
+}
+
+impl Iterator for Scan {
+    type Item = (PathBuf, ::Result<Tag>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        while let Some(path) = self.pending.pop() {
+            match self.visit(path) {
+                Some((path, Ok(tag))) => return Some((path, Ok(tag))),
+                Some((path, Err(err))) => match self.errors {
+                    ErrorPolicy::Skip => continue,
+                    ErrorPolicy::Collect => return Some((path, Err(err))),
+                    ErrorPolicy::Abort => {
+                        self.done = true;
+                        return Some((path, Err(err)));
+                    },
+                },
+                None => continue,
+            }
+        }
+        None
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    extern crate tempdir;
+    use super::*;
+    use std::ffi::OsStr;
+    use ::ErrorKind;
+
+    fn make_tree() -> tempdir::TempDir {
+        let tmp = tempdir::TempDir::new("id3_scan").unwrap();
+        fs::create_dir(tmp.path().join("b")).unwrap();
+        fs::copy("testdata/id3v24.id3", tmp.path().join("a.mp3")).unwrap();
+        fs::copy("testdata/id3v23.id3", tmp.path().join("b").join("c.mp3")).unwrap();
+        fs::write(tmp.path().join("b").join("d.txt"), b"no tag here").unwrap();
+        tmp
+    }
+
+    #[test]
+    fn scan_collect() {
+        let tmp = make_tree();
+        let results: Vec<_> = scan(tmp.path()).collect();
+        assert_eq!(3, results.len());
+        assert_eq!(tmp.path().join("a.mp3"), results[0].0);
+        assert_eq!("Title", results[0].1.as_ref().unwrap().title().unwrap());
+        assert_eq!(tmp.path().join("b").join("c.mp3"), results[1].0);
+        match results[2].1 {
+            Err(ref err) => match err.kind {
+                ErrorKind::NoTag => (),
+                _ => panic!("unexpected error kind"),
+            },
+            Ok(_) => panic!("d.txt does not contain a tag"),
+        }
+    }
+
+    #[test]
+    fn scan_skip_and_filter() {
+        let tmp = make_tree();
+        let skipped: Vec<_> = scan(tmp.path()).errors(ErrorPolicy::Skip).collect();
+        assert_eq!(2, skipped.len());
+        assert!(skipped.iter().all(|(_, tag)| tag.is_ok()));
+
+        let filtered: Vec<_> = scan(tmp.path())
+            .filter_paths(|path| path.extension() == Some(OsStr::new("txt")))
+            .collect();
+        assert_eq!(1, filtered.len());
+    }
+
+    #[test]
+    fn scan_abort() {
+        let tmp = make_tree();
+        fs::write(tmp.path().join("0.txt"), b"no tag here").unwrap();
+        let results: Vec<_> = scan(tmp.path()).errors(ErrorPolicy::Abort).collect();
+        assert_eq!(1, results.len());
+        assert!(results[0].1.is_err());
+    }
+}
@@ -0,0 +1,324 @@
+use std::str;
+use byteorder::{BigEndian, ByteOrder};
+use ::tag::Tag;
+
+
+/// The description of the encapsulated object (GEOB) holding the cue points and loops.
+pub const MARKERS2: &str = "Serato Markers2";
+/// The description of the encapsulated object (GEOB) holding the beat grid.
+pub const BEAT_GRID: &str = "Serato BeatGrid";
+/// The description of the encapsulated object (GEOB) holding the waveform overview.
+pub const OVERVIEW: &str = "Serato Overview";
+
+/// The cue points, loops and track settings that Serato DJ stores in the "Serato Markers2"
+/// encapsulated object.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Markers {
+    /// The color of the track in the library as RGB.
+    pub color: Option<[u8; 3]>,
+    /// Whether the tempo of the track is locked.
+    pub bpm_locked: Option<bool>,
+    /// The cue points, ordered as they are stored.
+    pub cues: Vec<CuePoint>,
+    /// The saved loops, ordered as they are stored.
+    pub loops: Vec<Loop>,
+}
+
+/// A cue point of a track.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CuePoint {
+    /// The slot of the cue point, starting at 0.
+    pub index: u8,
+    /// The position in milliseconds.
+    pub position: u32,
+    /// The color of the cue point as RGB.
+    pub color: [u8; 3],
+    /// The name of the cue point, may be empty.
+    pub name: String,
+}
+
+/// A saved loop of a track.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Loop {
+    /// The slot of the loop, starting at 0.
+    pub index: u8,
+    /// The start position in milliseconds.
+    pub start: u32,
+    /// The end position in milliseconds.
+    pub end: u32,
+    /// The color of the loop as RGB.
+    pub color: [u8; 3],
+    /// Whether the loop is locked against changes.
+    pub locked: bool,
+    /// The name of the loop, may be empty.
+    pub name: String,
+}
+
+/// The beat grid that Serato DJ stores in the "Serato BeatGrid" encapsulated object.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BeatGrid {
+    /// The markers of the grid, the last one holds the tempo for the rest of the track.
+    pub markers: Vec<BeatGridMarker>,
+}
+
+/// A marker of a beat grid.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BeatGridMarker {
+    /// A marker followed by another marker.
+    NonTerminal {
+        /// The position in seconds.
+        position: f32,
+        /// The number of beats up to the next marker.
+        beats_till_next_marker: u32,
+    },
+    /// The last marker of the grid.
+    Terminal {
+        /// The position in seconds.
+        position: f32,
+        /// The tempo from this marker on, in beats per minute.
+        bpm: f32,
+    },
+}
+
+/// The waveform overview that Serato DJ stores in the "Serato Overview" encapsulated object.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Overview {
+    /// The blocks of the waveform from the start to the end of the track, each holding the
+    /// amplitudes of 16 frequency bands.
+    pub blocks: Vec<[u8; 16]>,
+}
+
+fn parsing_error(description: &'static str) -> ::Error {
+    ::Error::new(::ErrorKind::Parsing, description)
+}
+
+/// Decodes base64 data, ignoring line breaks. Serato leaves out the padding and sometimes the
+/// last character, so a single character at the end that can not encode a byte is dropped.
+fn decode_base64(data: &[u8]) -> ::Result<Vec<u8>> {
+    let end = data.iter().position(|b| *b == b'=' || *b == 0).unwrap_or(data.len());
+    let text = str::from_utf8(&data[..end]).map_err(|_| parsing_error("invalid base64 data"))?;
+    let mut text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if text.len() % 4 == 1 && text.ends_with(|c: char| c.is_ascii() && ::util::BASE64_ALPHABET.contains(&(c as u8))) {
+        text.pop();
+    }
+    ::util::base64_decode(&text, ::util::BASE64_ALPHABET, '=')
+        .ok_or_else(|| parsing_error("invalid base64 data"))
+}
+
+/// Splits a null terminated UTF-8 string off the start of the data.
+fn split_string(data: &[u8]) -> ::Result<(String, &[u8])> {
+    let end = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+    let text = String::from_utf8(data[..end].to_vec())?;
+    Ok((text, data.get(end + 1..).unwrap_or(&[])))
+}
+
+impl Markers {
+    /// Parses the data of the "Serato Markers2" encapsulated object (GEOB). Entries of unknown
+    /// types are skipped.
+    pub fn parse(data: &[u8]) -> ::Result<Markers> {
+        if !data.starts_with(&[0x01, 0x01]) {
+            return Err(parsing_error("unknown Serato Markers2 version"));
+        }
+        let payload = decode_base64(&data[2..])?;
+        if !payload.starts_with(&[0x01, 0x01]) {
+            return Err(parsing_error("unknown Serato Markers2 payload version"));
+        }
+        let mut markers = Markers::default();
+        let mut rest = &payload[2..];
+        // The entries end with a null byte, the payload may be padded after it.
+        while !rest.is_empty() && rest[0] != 0 {
+            let (name, after_name) = split_string(rest)?;
+            let len = after_name.get(..4)
+                .map(|len| BigEndian::read_u32(len) as usize)
+                .ok_or_else(|| parsing_error("Serato Markers2 entry is truncated"))?;
+            let entry = after_name.get(4..4 + len)
+                .ok_or_else(|| parsing_error("Serato Markers2 entry is truncated"))?;
+            rest = &after_name[4 + len..];
+            match name.as_str() {
+                "COLOR" if entry.len() >= 4 => markers.color = Some([entry[1], entry[2], entry[3]]),
+                "BPMLOCK" if !entry.is_empty() => markers.bpm_locked = Some(entry[0] != 0),
+                "CUE" if entry.len() >= 12 => markers.cues.push(CuePoint {
+                    index: entry[1],
+                    position: BigEndian::read_u32(&entry[2..6]),
+                    color: [entry[7], entry[8], entry[9]],
+                    name: split_string(&entry[12..])?.0,
+                }),
+                "LOOP" if entry.len() >= 20 => markers.loops.push(Loop {
+                    index: entry[1],
+                    start: BigEndian::read_u32(&entry[2..6]),
+                    end: BigEndian::read_u32(&entry[6..10]),
+                    color: [entry[15], entry[16], entry[17]],
+                    locked: entry[19] != 0,
+                    name: split_string(&entry[20..])?.0,
+                }),
+                "COLOR" | "BPMLOCK" | "CUE" | "LOOP" => return Err(parsing_error("Serato Markers2 entry is too short")),
+                _ => (),
+            }
+        }
+        Ok(markers)
+    }
+}
+
+impl BeatGrid {
+    /// Parses the data of the "Serato BeatGrid" encapsulated object (GEOB).
+    pub fn parse(data: &[u8]) -> ::Result<BeatGrid> {
+        if !data.starts_with(&[0x01, 0x00]) {
+            return Err(parsing_error("unknown Serato BeatGrid version"));
+        }
+        let count = data.get(2..6)
+            .map(BigEndian::read_u32)
+            .ok_or_else(|| parsing_error("Serato BeatGrid is truncated"))? as usize;
+        let marker_data = data.get(6..)
+            .filter(|rest| rest.len() / 8 >= count)
+            .ok_or_else(|| parsing_error("Serato BeatGrid is truncated"))?;
+        let markers = marker_data.chunks(8).take(count).enumerate()
+            .map(|(i, marker)| {
+                let position = BigEndian::read_f32(&marker[..4]);
+                if i + 1 == count {
+                    BeatGridMarker::Terminal { position, bpm: BigEndian::read_f32(&marker[4..]) }
+                } else {
+                    BeatGridMarker::NonTerminal { position, beats_till_next_marker: BigEndian::read_u32(&marker[4..]) }
+                }
+            })
+            .collect();
+        Ok(BeatGrid { markers })
+    }
+}
+
+impl Overview {
+    /// Parses the data of the "Serato Overview" encapsulated object (GEOB).
+    pub fn parse(data: &[u8]) -> ::Result<Overview> {
+        if !data.starts_with(&[0x01, 0x05]) {
+            return Err(parsing_error("unknown Serato Overview version"));
+        }
+        let blocks = data[2..].chunks(16)
+            .filter(|block| block.len() == 16)
+            .map(|block| {
+                let mut values = [0; 16];
+                values.copy_from_slice(block);
+                values
+            })
+            .collect();
+        Ok(Overview { blocks })
+    }
+}
+
+/// Returns the data of the encapsulated object (GEOB) with the specified description.
+fn object<'a>(tag: &'a Tag, description: &str) -> Option<&'a [u8]> {
+    tag.encapsulated_objects()
+        .find(|object| object.description == description)
+        .map(|object| &object.data[..])
+}
+
+/// Reads the cue points and loops of the tag, if there are any.
+///
+/// # Example
+/// ```no_run
+/// use id3::Tag;
+/// use id3::serato;
+///
+/// let tag = Tag::read_from_path("music.mp3").unwrap();
+/// if let Some(markers) = serato::markers(&tag).unwrap() {
+///     for cue in &markers.cues {
+///         println!("{}: {} ms", cue.name, cue.position);
+///     }
+/// }
+/// ```
+pub fn markers(tag: &Tag) -> ::Result<Option<Markers>> {
+    object(tag, MARKERS2).map(Markers::parse).transpose()
+}
+
+/// Reads the beat grid of the tag, if there is one.
+pub fn beat_grid(tag: &Tag) -> ::Result<Option<BeatGrid>> {
+    object(tag, BEAT_GRID).map(BeatGrid::parse).transpose()
+}
+
+/// Reads the waveform overview of the tag, if there is one.
+pub fn overview(tag: &Tag) -> ::Result<Option<Overview>> {
+    object(tag, OVERVIEW).map(Overview::parse).transpose()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::frame::EncapsulatedObject;
+
+    fn encode_base64(data: &[u8]) -> Vec<u8> {
+        let encoded = ::util::base64(data, ::util::BASE64_ALPHABET, '=');
+        encoded.trim_end_matches('=').as_bytes().to_vec()
+    }
+
+    fn entry(name: &str, data: &[u8]) -> Vec<u8> {
+        let mut entry = name.as_bytes().to_vec();
+        entry.push(0);
+        let mut len = [0; 4];
+        BigEndian::write_u32(&mut len, data.len() as u32);
+        entry.extend_from_slice(&len);
+        entry.extend_from_slice(data);
+        entry
+    }
+
+    #[test]
+    fn parse_markers() {
+        let mut payload = vec![0x01, 0x01];
+        payload.extend(entry("COLOR", &[0x00, 0xff, 0x99, 0xff]));
+        payload.extend(entry("CUE", b"\x00\x01\x00\x00\x30\x39\x00\xcc\x00\x00\x00\x00Drop\x00"));
+        payload.extend(entry("FLIP", b"\x00\x01"));
+        payload.extend(entry("LOOP", b"\x00\x00\x00\x00\x03\xe8\x00\x00\x07\xd0\xff\xff\xff\xff\x00\x27\xaa\xe1\x00\x01\x00"));
+        payload.extend(entry("BPMLOCK", &[0x01]));
+        payload.push(0x00);
+        let mut data = vec![0x01, 0x01];
+        let encoded = encode_base64(&payload);
+        for line in encoded.chunks(72) {
+            data.extend_from_slice(line);
+            data.push(b'\n');
+        }
+        data.push(0);
+
+        let mut tag = Tag::new();
+        assert_eq!(None, markers(&tag).unwrap());
+        tag.add_frame(::frame::Frame::with_content("GEOB", ::frame::Content::EncapsulatedObject(EncapsulatedObject {
+            mime_type: "application/octet-stream".to_string(),
+            filename: "".to_string(),
+            description: MARKERS2.to_string(),
+            data,
+        })));
+        let markers = markers(&tag).unwrap().unwrap();
+        assert_eq!(Some([0xff, 0x99, 0xff]), markers.color);
+        assert_eq!(Some(true), markers.bpm_locked);
+        assert_eq!(vec![CuePoint { index: 1, position: 12345, color: [0xcc, 0, 0], name: "Drop".to_string() }], markers.cues);
+        assert_eq!(vec![Loop { index: 0, start: 1000, end: 2000, color: [0x27, 0xaa, 0xe1], locked: true, name: "".to_string() }], markers.loops);
+
+        assert!(Markers::parse(b"\x02\x01").is_err());
+        assert!(Markers::parse(b"\x01\x01AQEA!").is_err());
+    }
+
+    #[test]
+    fn parse_beat_grid() {
+        let mut data = vec![0x01, 0x00, 0x00, 0x00, 0x00, 0x02];
+        let mut marker = [0; 8];
+        BigEndian::write_f32(&mut marker[..4], 0.5);
+        BigEndian::write_u32(&mut marker[4..], 16);
+        data.extend_from_slice(&marker);
+        BigEndian::write_f32(&mut marker[..4], 8.0);
+        BigEndian::write_f32(&mut marker[4..], 128.0);
+        data.extend_from_slice(&marker);
+        data.push(0x00);
+        let grid = BeatGrid::parse(&data).unwrap();
+        assert_eq!(vec![
+            BeatGridMarker::NonTerminal { position: 0.5, beats_till_next_marker: 16 },
+            BeatGridMarker::Terminal { position: 8.0, bpm: 128.0 },
+        ], grid.markers);
+        assert!(BeatGrid::parse(&data[..14]).is_err());
+    }
+
+    #[test]
+    fn parse_overview() {
+        let mut data = vec![0x01, 0x05];
+        data.extend((0..32).map(|i| i as u8));
+        let overview = Overview::parse(&data).unwrap();
+        assert_eq!(2, overview.blocks.len());
+        assert_eq!(31, overview.blocks[1][15]);
+    }
+}
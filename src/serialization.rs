@@ -0,0 +1,129 @@
+//! Serialization of tags and frames with serde.
+//!
+//! A tag is serialized as a structure holding its frames, a frame as a structure of its
+//! identifier, content and flags. Frames that were decoded lazily are decoded while they are
+//! serialized.
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use ::frame::{Content, Frame};
+use ::tag::Tag;
+
+
+#[derive(Serialize)]
+#[serde(rename = "Frame")]
+struct FrameRef<'a> {
+    id: &'a str,
+    content: &'a Content,
+    tag_alter_preservation: bool,
+    file_alter_preservation: bool,
+    group_identifier: Option<u8>,
+    encryption_method: Option<u8>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Frame")]
+struct FrameData {
+    id: String,
+    content: Content,
+    #[serde(default)]
+    tag_alter_preservation: bool,
+    #[serde(default)]
+    file_alter_preservation: bool,
+    #[serde(default)]
+    group_identifier: Option<u8>,
+    #[serde(default)]
+    encryption_method: Option<u8>,
+}
+
+impl Serialize for Frame {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer {
+        FrameRef {
+            id: self.id(),
+            content: self.content(),
+            tag_alter_preservation: self.tag_alter_preservation(),
+            file_alter_preservation: self.file_alter_preservation(),
+            group_identifier: self.group_identifier(),
+            encryption_method: self.encryption_method(),
+        }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Frame {
+    fn deserialize<D>(deserializer: D) -> Result<Frame, D::Error>
+        where D: Deserializer<'de> {
+        let data = FrameData::deserialize(deserializer)?;
+        if !(3..=4).contains(&data.id.len()) || !data.id.bytes().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit()) {
+            return Err(::serde::de::Error::custom(format!("invalid frame identifier {:?}", data.id)));
+        }
+        if data.group_identifier.is_some_and(|symbol| !(0x80..=0xf0).contains(&symbol)) {
+            return Err(::serde::de::Error::custom("group symbols must be in the range 0x80..=0xF0"));
+        }
+        let mut frame = Frame::with_content(&data.id, data.content);
+        frame.set_tag_alter_preservation(data.tag_alter_preservation);
+        frame.set_file_alter_preservation(data.file_alter_preservation);
+        frame.set_group_identifier(data.group_identifier);
+        frame.set_encryption_method(data.encryption_method);
+        Ok(frame)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename = "Tag")]
+struct TagRef<'a> {
+    frames: Vec<&'a Frame>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Tag")]
+struct TagData {
+    frames: Vec<Frame>,
+}
+
+impl Serialize for Tag {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer {
+        TagRef { frames: self.frames().collect() }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Tag {
+    fn deserialize<D>(deserializer: D) -> Result<Tag, D::Error>
+        where D: Deserializer<'de> {
+        Ok(TagData::deserialize(deserializer)?.frames.into_iter().collect())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+    use ::frame::{Picture, PictureType};
+
+    #[test]
+    fn roundtrip() {
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+        tag.set_date_recorded("2014-05-04".parse().unwrap());
+        tag.add_picture(Picture {
+            mime_type: "image/png".to_string(),
+            picture_type: PictureType::CoverFront,
+            description: "".to_string(),
+            data: vec![1, 2, 3],
+        });
+        let mut frame = Frame::with_content("TPE1", Content::Text("Artist".into()));
+        frame.set_group_identifier(Some(0x80));
+        tag.add_frame(frame);
+
+        let json = serde_json::to_string(&tag).unwrap();
+        assert!(json.starts_with(r#"{"frames":[{"id":"TIT2","content":{"Text":"Title"},"#));
+        let read: Tag = serde_json::from_str(&json).unwrap();
+        assert_eq!(tag, read);
+        assert_eq!(Some(0x80), read.get("TPE1").unwrap().group_identifier());
+
+        let frame: Frame = serde_json::from_str(r#"{"id":"TALB","content":{"Text":"Album"}}"#).unwrap();
+        assert_eq!(Some("Album"), frame.content().text());
+        assert!(serde_json::from_str::<Frame>(r#"{"id":"talb","content":{"Text":"Album"}}"#).is_err());
+        assert!(serde_json::from_str::<Frame>(r#"{"id":"TALB","content":{"Text":"Album"},"group_identifier":5}"#).is_err());
+    }
+}
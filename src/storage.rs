@@ -7,8 +7,10 @@
 
 use std::cmp;
 use std::fs;
-use std::io::{self, Write};
+use std::io::{self, Read, Seek, Write};
 use std::ops;
+use std::path::{Path, PathBuf};
+use std::process;
 
 
 /// Refer to the module documentation.
@@ -80,11 +82,6 @@ impl StorageFile for io::Cursor<Vec<u8>> {
 
 impl<F> PlainStorage<F>
     where F: StorageFile {
-    /// Creates a new storage with a default padding of 2048 bytes and no shrinkage.
-    pub fn new(file: F, region: ops::Range<u64>) -> PlainStorage<F> {
-        PlainStorage::with_padding(file, region, 2048, None)
-    }
-
     /// Creates a new storage with the specified amount of padding.
     ///
     /// # Panics
@@ -279,12 +276,281 @@ impl<'a, F> Drop for PlainWriter<'a, F>
     }
 }
 
+/// Rewrites the file at `path` with the specified regions replaced by new data.
+///
+/// The modified file is staged in a temporary file in the same directory which is then renamed
+/// over the original. If anything fails before the rename, for example because the disk is full,
+/// the temporary file is removed and the original file is left untouched. The temporary file is
+/// synced to disk before the rename, so a crash leaves either the original or the complete new
+/// file. The permissions and, where possible, the ownership of the original are kept.
+///
+/// The regions must be sorted and must not overlap.
+pub fn rewrite_file(path: &Path, edits: &[(ops::Range<u64>, &[u8])]) -> io::Result<()> {
+    rewrite_file_with_progress(path, edits, &|_, _| ())
+}
+
+/// Like `rewrite_file`, but reports the progress after every chunk that is written to the new
+/// file as the number of bytes written so far and the total length of the new file.
+///
+/// The data between the regions is copied in chunks of `COPY_CHUNK_SIZE` bytes, so the memory
+/// used does not depend on the size of the file.
+pub fn rewrite_file_with_progress(path: &Path, edits: &[(ops::Range<u64>, &[u8])], progress: &dyn Fn(u64, u64)) -> io::Result<()> {
+    let mut original = fs::File::open(path)?;
+    let metadata = original.metadata()?;
+    let total = edits.iter().fold(metadata.len(), |len, (region, data)| {
+        (len + data.len() as u64).saturating_sub(region.end - region.start)
+    });
+    let tmp_path = temp_path(path);
+    let tmp = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&tmp_path)?;
+
+    let result = (|| {
+        let mut tmp = ProgressWriter { file: tmp, written: 0, total, progress };
+        let mut buf = vec![0; COPY_CHUNK_SIZE];
+        let mut pos = 0;
+        for (region, data) in edits {
+            assert!(pos <= region.start && region.start <= region.end);
+            let len = region.start - pos;
+            if copy_chunked((&mut original).take(len), &mut tmp, &mut buf)? != len {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "region is out of bounds of the file"));
+            }
+            tmp.write_all(data)?;
+            pos = original.seek(io::SeekFrom::Start(region.end))?;
+        }
+        copy_chunked(&mut original, &mut tmp, &mut buf)?;
+        let tmp = tmp.file;
+        // Changing the owner may clear the setuid and setgid bits, so it goes first.
+        copy_owner(&tmp, &metadata);
+        tmp.set_permissions(metadata.permissions())?;
+        tmp.sync_all()?;
+        Ok(())
+    })();
+    match result.and_then(|()| fs::rename(&tmp_path, path)) {
+        Ok(()) => sync_parent(path),
+        Err(err) => {
+            let _ = fs::remove_file(&tmp_path);
+            Err(err)
+        },
+    }
+}
+
+/// The size of the chunks in which `rewrite_file` copies data.
+pub const COPY_CHUNK_SIZE: usize = 0x10_0000;
+
+/// Copies everything from the reader to the writer through `buf`. Returns the number of bytes
+/// copied.
+fn copy_chunked<R, W>(mut reader: R, mut writer: W, buf: &mut [u8]) -> io::Result<u64>
+    where R: io::Read, W: io::Write {
+    let mut copied = 0;
+    loop {
+        let nread = match reader.read(buf) {
+            Ok(0) => return Ok(copied),
+            Ok(nread) => nread,
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        writer.write_all(&buf[..nread])?;
+        copied += nread as u64;
+    }
+}
+
+/// Reports the number of bytes written to the file after every write.
+struct ProgressWriter<'a> {
+    file: fs::File,
+    written: u64,
+    total: u64,
+    progress: &'a dyn Fn(u64, u64),
+}
+
+impl<'a> io::Write for ProgressWriter<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let nwritten = self.file.write(buf)?;
+        self.written += nwritten as u64;
+        (self.progress)(self.written, self.total);
+        Ok(nwritten)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Overwrites the specified regions of the file at `path` with new data of the same length.
+///
+/// Only the regions themselves are written, which is a lot cheaper than `rewrite_file` for large
+/// files, but a failing write may leave a region partially written.
+///
+/// # Panics
+/// If the length of the data of a region differs from the length of the region.
+pub fn write_regions(path: &Path, edits: &[(ops::Range<u64>, &[u8])]) -> io::Result<()> {
+    let mut file = fs::OpenOptions::new().write(true).open(path)?;
+    for (region, data) in edits {
+        assert_eq!(region.end - region.start, data.len() as u64);
+        file.seek(io::SeekFrom::Start(region.start))?;
+        file.write_all(data)?;
+    }
+    file.sync_data()
+}
+
+/// Opens the file at `path` and takes an exclusive advisory lock on it, blocking until the lock is
+/// available. The lock is released when the returned file is dropped.
+///
+/// Because `rewrite_file` replaces the file, the lock may have been acquired on a file that has
+/// since been renamed over. In that case the new file at `path` is locked instead.
+pub fn lock_file(path: &Path) -> io::Result<fs::File> {
+    loop {
+        let file = fs::File::open(path)?;
+        lock_exclusive(&file)?;
+        if is_same_file(&file, &fs::metadata(path)?)? {
+            return Ok(file);
+        }
+    }
+}
+
+/// Takes an exclusive advisory lock on the file, blocking until it is available. The lock is
+/// released when the file is closed.
+#[cfg(unix)]
+fn lock_exclusive(file: &fs::File) -> io::Result<()> {
+    use std::os::raw::c_int;
+    use std::os::unix::io::AsRawFd;
+    extern "C" {
+        fn flock(fd: c_int, operation: c_int) -> c_int;
+    }
+    const LOCK_EX: c_int = 2;
+    loop {
+        if unsafe { flock(file.as_raw_fd(), LOCK_EX) } == 0 {
+            return Ok(());
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+#[cfg(windows)]
+fn lock_exclusive(file: &fs::File) -> io::Result<()> {
+    use std::os::raw::c_void;
+    use std::os::windows::io::{AsRawHandle, RawHandle};
+    #[repr(C)]
+    struct Overlapped {
+        internal: usize,
+        internal_high: usize,
+        offset: u32,
+        offset_high: u32,
+        event: *mut c_void,
+    }
+    extern "system" {
+        fn LockFileEx(file: RawHandle, flags: u32, reserved: u32, bytes_low: u32, bytes_high: u32, overlapped: *mut Overlapped) -> i32;
+    }
+    const LOCKFILE_EXCLUSIVE_LOCK: u32 = 2;
+    let mut overlapped = Overlapped { internal: 0, internal_high: 0, offset: 0, offset_high: 0, event: ::std::ptr::null_mut() };
+    // Locks the whole file, including any data that is appended later.
+    if unsafe { LockFileEx(file.as_raw_handle(), LOCKFILE_EXCLUSIVE_LOCK, 0, !0, !0, &mut overlapped) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn lock_exclusive(_: &fs::File) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "file locking is not supported on this platform"))
+}
+
+#[cfg(unix)]
+fn is_same_file(file: &fs::File, metadata: &fs::Metadata) -> io::Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+    let file_metadata = file.metadata()?;
+    Ok(file_metadata.dev() == metadata.dev() && file_metadata.ino() == metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn is_same_file(_: &fs::File, _: &fs::Metadata) -> io::Result<bool> {
+    Ok(true)
+}
+
+/// Gives the file the owner and group from `metadata`. Only privileged processes can change the
+/// owner, so failures are ignored and the file keeps the owner of the process.
+#[cfg(unix)]
+fn copy_owner(file: &fs::File, metadata: &fs::Metadata) {
+    use std::os::unix::fs::{fchown, MetadataExt};
+    if fchown(file, Some(metadata.uid()), Some(metadata.gid())).is_err() {
+        let _ = fchown(file, None, Some(metadata.gid()));
+    }
+}
+
+#[cfg(not(unix))]
+fn copy_owner(_: &fs::File, _: &fs::Metadata) { }
+
+/// Syncs the directory that contains `path`, which makes a rename in it durable.
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => fs::File::open(dir)?.sync_all(),
+        _ => fs::File::open(".")?.sync_all(),
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent(_: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Returns the path of a hidden file next to `path` to stage a rewrite in.
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.{}.id3tmp", name, process::id()))
+}
+
 
 #[cfg(test)]
 mod tests {
+    extern crate tempdir;
     use super::*;
     use std::iter;
-    use std::io::{Read, Seek};
+
+    #[test]
+    fn rewrite_file_regions() {
+        let tmp = tempdir::TempDir::new("id3_storage").unwrap();
+        let path = tmp.path().join("file");
+        let buf: Vec<u8> = (0..128).collect();
+        fs::write(&path, &buf).unwrap();
+
+        rewrite_file(&path, &[(0..16, &[0xff; 4]), (64..64, &[0xee; 2]), (120..128, &[])]).unwrap();
+        let mut expected = vec![0xff; 4];
+        expected.extend(16..64);
+        expected.extend(&[0xee; 2]);
+        expected.extend(64..120);
+        assert_eq!(expected, fs::read(&path).unwrap());
+
+        // A failing rewrite leaves the file untouched.
+        assert!(rewrite_file(&path, &[(200..300, &[])]).is_err());
+        assert_eq!(expected, fs::read(&path).unwrap());
+        assert_eq!(1, fs::read_dir(tmp.path()).unwrap().count());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rewrite_file_keeps_metadata() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let tmp = tempdir::TempDir::new("id3_storage").unwrap();
+        let path = tmp.path().join("file");
+        fs::write(&path, [0; 16]).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        let before = fs::metadata(&path).unwrap();
+
+        rewrite_file(&path, &[(0..0, &[0xff; 4])]).unwrap();
+        let after = fs::metadata(&path).unwrap();
+        assert_ne!(before.ino(), after.ino());
+        assert_eq!(0o640, after.mode() & 0o777);
+        assert_eq!((before.uid(), before.gid()), (after.uid(), after.gid()));
+        assert_eq!(20, after.len());
+    }
 
     #[test]
     fn plain_reader_range() {
@@ -292,7 +558,7 @@ mod tests {
             .chain(iter::repeat(0x00).take(128))
             .chain(iter::repeat(0xff).take(128))
             .collect();
-        let mut store = PlainStorage::new(io::Cursor::new(buf), 128..256);
+        let mut store = PlainStorage::with_padding(io::Cursor::new(buf), 128..256, 2048, None);
         assert_eq!(128, store.reader().unwrap().bytes().count());
         assert!(store.reader().unwrap().bytes().all(|b| b.unwrap() == 0x00));
     }
@@ -300,7 +566,7 @@ mod tests {
     #[test]
     fn plain_reader_seek() {
         let buf: Vec<u8> = (0..128).collect();
-        let mut store = PlainStorage::new(io::Cursor::new(buf), 32..64);
+        let mut store = PlainStorage::with_padding(io::Cursor::new(buf), 32..64, 2048, None);
         let mut r = store.reader().unwrap();
         let mut rbuf = [0; 4];
         assert_eq!(28, r.seek(io::SeekFrom::Start(28)).unwrap());
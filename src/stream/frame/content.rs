@@ -1,6 +1,7 @@
 use std::io;
 use std::iter;
-use ::frame::{Picture, PictureType, Content, ExtendedLink};
+use byteorder::{BigEndian, ByteOrder};
+use ::frame::{AudioSeekPointIndex, Chapter, ChannelType, Commercial, Equalisation, Event, EventTimingCodes, EventType, Frame, InterpolationMethod, LinkedInfo, Picture, PictureType, Content, EncryptionMethod, ExtendedLink, GroupIdentifier, MusicCdIdentifier, Popularimeter, Private, ReceivedAs, RecommendedBufferSize, RelativeVolumeAdjustment, Signature, UniqueFileIdentifier, VolumeAdjustment, SynchronisedLyrics, SynchronisedLyricsType, TableOfContents, TimestampFormat};
 use ::stream::encoding::Encoding;
 use ::tag;
 
@@ -40,8 +41,29 @@ pub fn encode<W>(mut writer: W, content: &Content, version: tag::Version, encodi
         Content::Link(_) => weblink_to_bytes(request),
         Content::ExtendedLink(_) => extended_weblink_to_bytes(request),
         Content::Lyrics(_) => lyrics_to_bytes(request),
+        Content::SynchronisedLyrics(_) => synchronised_lyrics_to_bytes(request),
         Content::Comment(_) => comment_to_bytes(request),
-        Content::Picture(_) => picture_to_bytes(request),
+        Content::Picture(_) => picture_to_bytes(request)?,
+        Content::EncapsulatedObject(_) => encapsulated_object_to_bytes(request),
+        Content::EncryptionMethod(_) => encryption_method_to_bytes(request),
+        Content::GroupIdentifier(_) => group_identifier_to_bytes(request),
+        Content::UniqueFileIdentifier(_) => unique_file_identifier_to_bytes(request),
+        Content::Private(_) => private_to_bytes(request),
+        Content::RelativeVolumeAdjustment(_) => relative_volume_adjustment_to_bytes(request)?,
+        Content::Equalisation(_) => equalisation_to_bytes(request),
+        Content::EventTimingCodes(_) => event_timing_codes_to_bytes(request),
+        Content::LinkedInfo(_) => linked_info_to_bytes(request)?,
+        Content::TermsOfUse(_) => terms_of_use_to_bytes(request),
+        Content::Signature(ref sign) => iter::once(sign.group_symbol).chain(sign.signature.iter().cloned()).collect(),
+        Content::Commercial(_) => commercial_to_bytes(request)?,
+        Content::MusicCdIdentifier(ref mcdi) => mcdi.toc.clone(),
+        Content::RecommendedBufferSize(_) => recommended_buffer_size_to_bytes(request)?,
+        Content::AudioSeekPointIndex(_) => audio_seek_point_index_to_bytes(request)?,
+        Content::SeekOffset(offset) => offset.to_be_bytes().to_vec(),
+        Content::PlayCounter(counter) => counter_to_bytes(counter),
+        Content::Popularimeter(_) => popularimeter_to_bytes(request),
+        Content::Chapter(_) => chapter_to_bytes(request)?,
+        Content::TableOfContents(_) => table_of_contents_to_bytes(request)?,
         Content::Unknown(ref data) => data.clone()
     };
     writer.write_all(&bytes)?;
@@ -49,7 +71,7 @@ pub fn encode<W>(mut writer: W, content: &Content, version: tag::Version, encodi
 }
 
 /// Attempts to decode the request.
-pub fn decode<R>(id: &str, mut reader: R) -> ::Result<DecoderResult>
+pub fn decode<R>(id: &str, version: tag::Version, mut reader: R) -> ::Result<DecoderResult>
     where R: io::Read {
     let mut data = Vec::new();
     reader.read_to_end(&mut data)?;
@@ -60,7 +82,29 @@ pub fn decode<R>(id: &str, mut reader: R) -> ::Result<DecoderResult>
         "WXXX" | "WXX" => parse_wxxx(data.as_slice()),
         "COMM" | "COM" => parse_comm(data.as_slice()),
         "USLT" | "ULT" => parse_uslt(data.as_slice()),
-        id if id.starts_with('T') => parse_text(data.as_slice()),
+        "GEOB" | "GEO" => parse_geob(data.as_slice()),
+        "ENCR" => parse_encr(data.as_slice()),
+        "GRID" => parse_grid(data.as_slice()),
+        "SYLT" | "SLT" => parse_sylt(data.as_slice()),
+        "UFID" | "UFI" => parse_ufid(data.as_slice()),
+        "PRIV" => parse_priv(data.as_slice()),
+        "IPLS" | "IPL" => parse_text(data),
+        "RVA2" => parse_rva2(data.as_slice()),
+        "EQU2" => parse_equ2(data.as_slice()),
+        "ETCO" | "ETC" => parse_etco(data.as_slice()),
+        "LINK" | "LNK" => parse_link(data.as_slice(), version),
+        "USER" => parse_user(data.as_slice()),
+        "SIGN" => parse_sign(data),
+        "COMR" => parse_comr(data.as_slice()),
+        "MCDI" | "MCI" => Ok(DecoderResult::new(Encoding::Latin1, Content::MusicCdIdentifier(MusicCdIdentifier { toc: data }))),
+        "RBUF" | "BUF" => parse_rbuf(data.as_slice()),
+        "ASPI" => parse_aspi(data.as_slice()),
+        "SEEK" => parse_seek(data.as_slice()),
+        "PCNT" | "CNT" => Ok(DecoderResult::new(Encoding::Latin1, Content::PlayCounter(parse_counter(data.as_slice())))),
+        "POPM" | "POP" => parse_popm(data.as_slice()),
+        "CHAP" => parse_chap(data.as_slice(), version),
+        "CTOC" => parse_ctoc(data.as_slice(), version),
+        id if id.starts_with('T') => parse_text(data),
         id if id.starts_with('W') => parse_weblink(data.as_slice()),
         _ => Ok(DecoderResult::new(Encoding::UTF16, Content::Unknown(data))),
     }
@@ -116,7 +160,20 @@ macro_rules! encode {
 
 fn text_to_bytes(request: EncoderRequest) -> Vec<u8> {
     let content = request.content.text().unwrap();
-    return encode!(encoding(request.encoding), string(content));
+    if request.encoding != Encoding::UTF16 {
+        return encode!(encoding(request.encoding), string(content));
+    }
+    // Every UTF-16 value starts with its own byte order mark.
+    let mut bytes = vec![request.encoding as u8];
+    for (i, value) in content.split('\0').enumerate() {
+        if i > 0 {
+            bytes.extend_from_slice(&[0, 0]);
+        }
+        if i == 0 || !value.is_empty() {
+            bytes.extend(::util::string_to_utf16(value));
+        }
+    }
+    bytes
 }
 
 fn extended_text_to_bytes(request: EncoderRequest) -> Vec<u8> {
@@ -141,6 +198,22 @@ fn lyrics_to_bytes(request: EncoderRequest) -> Vec<u8> {
                    string(content.description), delim(0), string(content.text));
 }
 
+fn synchronised_lyrics_to_bytes(request: EncoderRequest) -> Vec<u8> {
+    let content = request.content.synchronised_lyrics().unwrap();
+    let mut buf = encode!(encoding(request.encoding),
+                          bytes(content.lang.bytes().chain(iter::repeat(b' ')).take(3).collect::<Vec<u8>>()),
+                          byte(timestamp_format_to_byte(content.timestamp_format)), byte(content.content_type),
+                          string(content.description), delim(0));
+    for &(timestamp, ref text) in &content.content {
+        // Each syllable is a complete string, including a BOM for UTF-16.
+        buf.extend(&encode!(encoding(request.encoding), string(text), delim(0))[1..]);
+        let mut timestamp_buf = [0; 4];
+        BigEndian::write_u32(&mut timestamp_buf, timestamp);
+        buf.extend(&timestamp_buf);
+    }
+    buf
+}
+
 fn comment_to_bytes(request: EncoderRequest) -> Vec<u8> {
     let content = request.content.comment().unwrap();
     return encode!(encoding(request.encoding),
@@ -154,23 +227,308 @@ fn picture_to_bytes_v3(request: EncoderRequest) -> Vec<u8> {
             byte(content.picture_type), string(content.description), delim(0), bytes(content.data));
 }
 
-fn picture_to_bytes_v2(request: EncoderRequest) -> Vec<u8> {
+/// The image formats of ID3v2.2 picture frames (PIC) with their MIME types. Other image types use
+/// the first three letters of their MIME subtype.
+const IMAGE_FORMATS: &[(&str, &str)] = &[
+    ("JPG", "image/jpeg"),
+    ("PNG", "image/png"),
+    ("GIF", "image/gif"),
+    ("BMP", "image/bmp"),
+    ("TIF", "image/tiff"),
+];
+
+fn picture_to_bytes_v2(request: EncoderRequest) -> ::Result<Vec<u8>> {
     let picture = request.content.picture().unwrap();
 
-    let format = match &picture.mime_type[..] {
-        "image/jpeg" => "JPG",
-        "image/png" => "PNG",
-        _ => panic!("unknown MIME type") // TODO handle this better. Return None?
+    let format = match IMAGE_FORMATS.iter().find(|&&(_, mime_type)| mime_type == picture.mime_type) {
+        Some(&(format, _)) => format.to_string(),
+        None => match picture.mime_type.strip_prefix("image/") {
+            Some("jpg") => "JPG".to_string(),
+            Some(subtype) if subtype.len() >= 3 && subtype.is_ascii() => subtype[..3].to_uppercase(),
+            _ => return Err(::Error::new(::ErrorKind::InvalidInput, "the MIME type of the picture has no ID3v2.2 image format")),
+        },
     };
 
-    return encode!(encoding(request.encoding), bytes(format.as_bytes()), byte(picture.picture_type),
-            string(picture.description), delim(0), bytes(picture.data));
+    Ok(encode!(encoding(request.encoding), bytes(format.as_bytes()), byte(picture.picture_type),
+            string(picture.description), delim(0), bytes(picture.data)))
+}
+
+fn chapter_to_bytes(request: EncoderRequest) -> ::Result<Vec<u8>> {
+    let content = request.content.chapter().unwrap();
+    let mut buf = ::util::string_to_latin1(&content.element_id);
+    buf.push(0);
+    for value in &[content.start_time, content.end_time, content.start_offset, content.end_offset] {
+        let mut value_buf = [0; 4];
+        BigEndian::write_u32(&mut value_buf, *value);
+        buf.extend(&value_buf);
+    }
+    sub_frames_to_bytes(&mut buf, &content.frames, request.version)?;
+    Ok(buf)
+}
+
+fn table_of_contents_to_bytes(request: EncoderRequest) -> ::Result<Vec<u8>> {
+    let content = request.content.table_of_contents().unwrap();
+    if content.elements.len() > 0xff {
+        return Err(::Error::new(::ErrorKind::InvalidInput, "table of contents has more than 255 entries"));
+    }
+    let mut buf = ::util::string_to_latin1(&content.element_id);
+    buf.push(0);
+    buf.push((content.top_level as u8) << 1 | content.ordered as u8);
+    buf.push(content.elements.len() as u8);
+    for element in &content.elements {
+        buf.extend(::util::string_to_latin1(element));
+        buf.push(0);
+    }
+    sub_frames_to_bytes(&mut buf, &content.frames, request.version)?;
+    Ok(buf)
+}
+
+fn encapsulated_object_to_bytes(request: EncoderRequest) -> Vec<u8> {
+    let content = request.content.encapsulated_object().unwrap();
+    encode!(encoding(request.encoding), bytes(::util::string_to_latin1(&content.mime_type)), byte(0),
+            string(content.filename), delim(0), string(content.description), delim(0), bytes(content.data))
+}
+
+fn encryption_method_to_bytes(request: EncoderRequest) -> Vec<u8> {
+    let content = request.content.encryption_method().unwrap();
+    let mut buf = ::util::string_to_latin1(&content.owner_identifier);
+    buf.push(0);
+    buf.push(content.symbol);
+    buf.extend(&content.data);
+    buf
+}
+
+fn group_identifier_to_bytes(request: EncoderRequest) -> Vec<u8> {
+    let content = request.content.group_identifier().unwrap();
+    let mut buf = ::util::string_to_latin1(&content.owner_identifier);
+    buf.push(0);
+    buf.push(content.symbol);
+    buf.extend(&content.data);
+    buf
+}
+
+fn unique_file_identifier_to_bytes(request: EncoderRequest) -> Vec<u8> {
+    let content = request.content.unique_file_identifier().unwrap();
+    let mut buf = ::util::string_to_latin1(&content.owner_identifier);
+    buf.push(0);
+    buf.extend(&content.identifier);
+    buf
+}
+
+fn private_to_bytes(request: EncoderRequest) -> Vec<u8> {
+    let content = request.content.private().unwrap();
+    let mut buf = ::util::string_to_latin1(&content.owner);
+    buf.push(0);
+    buf.extend(&content.data);
+    buf
 }
 
-fn picture_to_bytes(request: EncoderRequest) -> Vec<u8> {
+fn relative_volume_adjustment_to_bytes(request: EncoderRequest) -> ::Result<Vec<u8>> {
+    let content = request.content.relative_volume_adjustment().unwrap();
+    let mut buf = ::util::string_to_latin1(&content.identification);
+    buf.push(0);
+    for channel in &content.channels {
+        if channel.peak.len() != (channel.peak_bits as usize).div_ceil(8) {
+            return Err(::Error::new(::ErrorKind::InvalidInput, "peak volume does not match the number of peak bits"));
+        }
+        buf.push(match channel.channel {
+            ChannelType::Other => 0,
+            ChannelType::MasterVolume => 1,
+            ChannelType::FrontRight => 2,
+            ChannelType::FrontLeft => 3,
+            ChannelType::BackRight => 4,
+            ChannelType::BackLeft => 5,
+            ChannelType::FrontCentre => 6,
+            ChannelType::BackCentre => 7,
+            ChannelType::Subwoofer => 8,
+        });
+        buf.extend_from_slice(&channel.adjustment.to_be_bytes());
+        buf.push(channel.peak_bits);
+        buf.extend(&channel.peak);
+    }
+    Ok(buf)
+}
+
+fn equalisation_to_bytes(request: EncoderRequest) -> Vec<u8> {
+    let content = request.content.equalisation().unwrap();
+    let mut buf = vec![match content.method {
+        InterpolationMethod::Band => 0,
+        InterpolationMethod::Linear => 1,
+    }];
+    buf.extend(::util::string_to_latin1(&content.identification));
+    buf.push(0);
+    for &(frequency, adjustment) in &content.adjustments {
+        buf.extend_from_slice(&frequency.to_be_bytes());
+        buf.extend_from_slice(&adjustment.to_be_bytes());
+    }
+    buf
+}
+
+fn timestamp_format_to_byte(format: TimestampFormat) -> u8 {
+    match format {
+        TimestampFormat::Mpeg => 1,
+        TimestampFormat::Ms => 2,
+    }
+}
+
+fn event_timing_codes_to_bytes(request: EncoderRequest) -> Vec<u8> {
+    let content = request.content.event_timing_codes().unwrap();
+    let mut buf = vec![timestamp_format_to_byte(content.timestamp_format)];
+    for event in &content.events {
+        buf.push(match event.event_type {
+            EventType::Padding => 0x00,
+            EventType::EndOfInitialSilence => 0x01,
+            EventType::IntroStart => 0x02,
+            EventType::MainPartStart => 0x03,
+            EventType::OutroStart => 0x04,
+            EventType::OutroEnd => 0x05,
+            EventType::VerseStart => 0x06,
+            EventType::RefrainStart => 0x07,
+            EventType::InterludeStart => 0x08,
+            EventType::ThemeStart => 0x09,
+            EventType::VariationStart => 0x0a,
+            EventType::KeyChange => 0x0b,
+            EventType::TimeChange => 0x0c,
+            EventType::MomentaryUnwantedNoise => 0x0d,
+            EventType::SustainedNoise => 0x0e,
+            EventType::SustainedNoiseEnd => 0x0f,
+            EventType::IntroEnd => 0x10,
+            EventType::MainPartEnd => 0x11,
+            EventType::VerseEnd => 0x12,
+            EventType::RefrainEnd => 0x13,
+            EventType::ThemeEnd => 0x14,
+            EventType::Profanity => 0x15,
+            EventType::ProfanityEnd => 0x16,
+            EventType::AudioEnd => 0xfd,
+            EventType::AudioFileEnd => 0xfe,
+            EventType::NotPredefinedSync(n) => 0xe0 | (n & 0x0f),
+            EventType::Reserved(value) => value,
+        });
+        buf.extend_from_slice(&event.timestamp.to_be_bytes());
+    }
+    buf
+}
+
+fn frame_id_len(version: tag::Version) -> usize {
+    match version {
+        tag::Id3v22 => 3,
+        tag::Id3v23 | tag::Id3v24 => 4,
+    }
+}
+
+fn linked_info_to_bytes(request: EncoderRequest) -> ::Result<Vec<u8>> {
+    let content = request.content.linked_info().unwrap();
+    let frame_id = match (request.version, content.frame_id.len()) {
+        (tag::Id3v22, 4) => ::util::convert_id_3_to_2(&content.frame_id),
+        (tag::Id3v23, 3) | (tag::Id3v24, 3) => ::util::convert_id_2_to_3(&content.frame_id),
+        _ => None,
+    };
+    let frame_id = frame_id.unwrap_or(&content.frame_id);
+    if frame_id.len() != frame_id_len(request.version) {
+        return Err(::Error::new(::ErrorKind::InvalidInput, "linked frame ID is not valid in this version"));
+    }
+    let mut buf = frame_id.as_bytes().to_vec();
+    buf.extend(::util::string_to_latin1(&content.url));
+    buf.push(0);
+    buf.extend(&content.additional_data);
+    Ok(buf)
+}
+
+fn terms_of_use_to_bytes(request: EncoderRequest) -> Vec<u8> {
+    let content = request.content.terms_of_use().unwrap();
+    encode!(encoding(request.encoding),
+            bytes(content.lang.bytes().chain(iter::repeat(b' ')).take(3).collect::<Vec<u8>>()),
+            string(content.text))
+}
+
+fn commercial_to_bytes(request: EncoderRequest) -> ::Result<Vec<u8>> {
+    let content = request.content.commercial().unwrap();
+    if content.valid_until.len() != 8 || !content.valid_until.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(::Error::new(::ErrorKind::InvalidInput, "the valid until date must be of the form YYYYMMDD"));
+    }
+    let received_as = match content.received_as {
+        ReceivedAs::Other => 0,
+        ReceivedAs::StandardCdAlbum => 1,
+        ReceivedAs::CompressedAudioOnCd => 2,
+        ReceivedAs::FileOverInternet => 3,
+        ReceivedAs::StreamOverInternet => 4,
+        ReceivedAs::AsNoteSheets => 5,
+        ReceivedAs::AsNoteSheetsInBook => 6,
+        ReceivedAs::MusicOnOtherMedia => 7,
+        ReceivedAs::NonMusicalMerchandise => 8,
+    };
+    let mut buf = encode!(encoding(request.encoding),
+                          bytes(::util::string_to_latin1(&content.price)), byte(0),
+                          bytes(content.valid_until.as_bytes()),
+                          bytes(::util::string_to_latin1(&content.contact_url)), byte(0),
+                          byte(received_as),
+                          string(content.seller), delim(0), string(content.description), delim(0));
+    if let Some((ref mime_type, ref logo)) = content.logo {
+        buf.extend(::util::string_to_latin1(mime_type));
+        buf.push(0);
+        buf.extend(logo);
+    }
+    Ok(buf)
+}
+
+fn recommended_buffer_size_to_bytes(request: EncoderRequest) -> ::Result<Vec<u8>> {
+    let content = request.content.recommended_buffer_size().unwrap();
+    if content.buffer_size > 0xff_ffff {
+        return Err(::Error::new(::ErrorKind::InvalidInput, "buffer size does not fit in 24 bits"));
+    }
+    let mut buf = content.buffer_size.to_be_bytes()[1..].to_vec();
+    buf.push(content.embedded_info as u8);
+    if let Some(offset) = content.next_tag_offset {
+        buf.extend_from_slice(&offset.to_be_bytes());
+    }
+    Ok(buf)
+}
+
+fn audio_seek_point_index_to_bytes(request: EncoderRequest) -> ::Result<Vec<u8>> {
+    let content = request.content.audio_seek_point_index().unwrap();
+    if content.points.len() > 0xffff {
+        return Err(::Error::new(::ErrorKind::InvalidInput, "audio seek point index has more than 65535 points"));
+    }
+    let mut buf = Vec::with_capacity(11 + content.points.len() * 2);
+    buf.extend_from_slice(&content.start.to_be_bytes());
+    buf.extend_from_slice(&content.length.to_be_bytes());
+    buf.extend_from_slice(&(content.points.len() as u16).to_be_bytes());
+    buf.push(content.bits_per_point);
+    for &point in &content.points {
+        match content.bits_per_point {
+            8 if point <= 0xff => buf.push(point as u8),
+            8 => return Err(::Error::new(::ErrorKind::InvalidInput, "index point does not fit in 8 bits")),
+            16 => buf.extend_from_slice(&point.to_be_bytes()),
+            _ => return Err(::Error::new(::ErrorKind::InvalidInput, "index points must have 8 or 16 bits")),
+        }
+    }
+    Ok(buf)
+}
+
+/// Encodes a play counter in as few bytes as possible, but at least 4.
+fn counter_to_bytes(counter: u64) -> Vec<u8> {
+    let mut buf = [0; 8];
+    BigEndian::write_u64(&mut buf, counter);
+    let start = buf.iter().position(|b| *b != 0).unwrap_or(7).min(4);
+    buf[start..].to_vec()
+}
+
+/// Encodes a popularimeter frame. The counter is omitted if it is zero.
+fn popularimeter_to_bytes(request: EncoderRequest) -> Vec<u8> {
+    let content = request.content.popularimeter().unwrap();
+    let mut buf = ::util::string_to_latin1(&content.email);
+    buf.push(0);
+    buf.push(content.rating);
+    if content.counter > 0 {
+        buf.extend(counter_to_bytes(content.counter));
+    }
+    buf
+}
+
+fn picture_to_bytes(request: EncoderRequest) -> ::Result<Vec<u8>> {
     match request.version {
         tag::Id3v22 => picture_to_bytes_v2(request),
-        tag::Id3v23|tag::Id3v24 => picture_to_bytes_v3(request),
+        tag::Id3v23|tag::Id3v24 => Ok(picture_to_bytes_v3(request)),
     }
 }
 // }}}
@@ -374,10 +732,10 @@ fn parse_apic_v2(data: &[u8]) -> ::Result<DecoderResult> {
 
     let mut i = 1;
     let format = decode_part!(data, params, i, fixed_string(3));
-    let mime_type = match &format[..] {
-        "PNG" => "image/png".to_string(),
-        "JPG" => "image/jpeg".to_string(),
-        _ => {
+    let mime_type = match IMAGE_FORMATS.iter().find(|&&(known, _)| known.eq_ignore_ascii_case(&format)) {
+        Some(&(_, mime_type)) => mime_type.to_string(),
+        None if format.bytes().all(|b| b.is_ascii_alphanumeric()) => format!("image/{}", format.to_lowercase()),
+        None => {
             return Err(::Error::new(::ErrorKind::UnsupportedFeature,
                                      "can't determine MIME type for image format"))
         }
@@ -404,6 +762,13 @@ fn parse_apic_v3(data: &[u8]) -> ::Result<DecoderResult> {
                    description: string(true), data: bytes());
 }
 
+/// Attempts to parse the data as a general encapsulated object frame.
+/// Returns a `Content::EncapsulatedObject`.
+fn parse_geob(data: &[u8]) -> ::Result<DecoderResult> {
+    decode!(data, EncapsulatedObject, mime_type: latin1(true), filename: string(true),
+            description: string(true), data: bytes())
+}
+
 /// Attempts to parse the data as a comment frame.
 /// Returns a `Content::Comment`.
 fn parse_comm(data: &[u8]) -> ::Result<DecoderResult> {
@@ -413,13 +778,59 @@ fn parse_comm(data: &[u8]) -> ::Result<DecoderResult> {
 
 /// Attempts to parse the data as a text frame.
 /// Returns a `Content::Text`.
-fn parse_text(data: &[u8]) -> ::Result<DecoderResult> {
+///
+/// The frame buffer is taken by value so that UTF-8 and plain ASCII text can be turned into a
+/// `String` without copying it.
+fn parse_text(mut data: Vec<u8>) -> ::Result<DecoderResult> {
     assert_data!(data);
     let encoding = encoding_from_byte(data[0])?;
 
+    // Multiple values are kept separated by null characters, trailing null characters are not
+    // part of the text.
+    let reusable = match encoding {
+        Encoding::UTF8 => true,
+        Encoding::Latin1 => data[1..].is_ascii(),
+        Encoding::UTF16 | Encoding::UTF16BE => false,
+    };
+    if reusable {
+        while data.len() > 1 && data[data.len() - 1] == 0 {
+            data.pop();
+        }
+        data.remove(0);
+        return Ok(DecoderResult::new(encoding, Content::Text(String::from_utf8(data)?.into())));
+    }
+
     let params = DecodingParams::for_encoding(encoding);
+    let mut values = Vec::new();
+    let mut big_endian = true;
     let mut i = 1;
-    Ok(DecoderResult::new(encoding, Content::Text(decode_part!(data, params, i, text()))))
+    while i < data.len() {
+        let (end, next) = match ::util::find_delim(encoding, &data, i) {
+            Some(end) => (end, end + ::util::delim_len(encoding)),
+            None => (data.len(), data.len()),
+        };
+        let part = &data[i..end];
+        // Values of UTF-16 text without a byte order mark have the byte order of the value
+        // before them.
+        let value = if part.is_empty() {
+            String::new()
+        } else if encoding != Encoding::UTF16 {
+            (params.string_func)(part)?
+        } else if values.is_empty() || part.starts_with(&[0xff, 0xfe]) || part.starts_with(&[0xfe, 0xff]) {
+            big_endian = !part.starts_with(&[0xff, 0xfe]);
+            ::util::string_from_utf16(part)?
+        } else if big_endian {
+            ::util::string_from_utf16be(part)?
+        } else {
+            ::util::string_from_utf16le(part)?
+        };
+        values.push(value);
+        i = next;
+    }
+    while values.last().is_some_and(|value| value.is_empty()) {
+        values.pop();
+    }
+    Ok(DecoderResult::new(encoding, Content::Text(values.join("\0").into())))
 }
 
 /// Attempts to parse the data as a user defined text frame.
@@ -458,6 +869,468 @@ fn parse_uslt(data: &[u8]) -> ::Result<DecoderResult> {
     return decode!(data, Lyrics, lang: fixed_string(3), description: string(true),
                    text: string(false));
 }
+
+/// Attempts to parse the data as a chapter frame, including its embedded frames.
+/// Returns a `Content::Chapter`.
+fn parse_chap(data: &[u8], version: tag::Version) -> ::Result<DecoderResult> {
+    let params = DecodingParams::for_encoding(Encoding::Latin1);
+    let mut i = 0;
+    let element_id = decode_part!(data, params, i, string(true));
+    if i + 16 > data.len() {
+        return Err(::Error::new(::ErrorKind::Parsing, "insufficient data"));
+    }
+    let frames = parse_sub_frames(&data[i + 16..], version)?;
+    let chapter = Chapter {
+        element_id,
+        start_time: BigEndian::read_u32(&data[i..]),
+        end_time: BigEndian::read_u32(&data[i + 4..]),
+        start_offset: BigEndian::read_u32(&data[i + 8..]),
+        end_offset: BigEndian::read_u32(&data[i + 12..]),
+        frames,
+    };
+    Ok(DecoderResult::new(Encoding::Latin1, Content::Chapter(chapter)))
+}
+
+/// Attempts to parse the data as a table of contents frame, including its embedded frames.
+/// Returns a `Content::TableOfContents`.
+fn parse_ctoc(data: &[u8], version: tag::Version) -> ::Result<DecoderResult> {
+    let params = DecodingParams::for_encoding(Encoding::Latin1);
+    let mut i = 0;
+    let element_id = decode_part!(data, params, i, string(true));
+    if i + 2 > data.len() {
+        return Err(::Error::new(::ErrorKind::Parsing, "insufficient data"));
+    }
+    let flags = data[i];
+    let entry_count = data[i + 1];
+    i += 2;
+    let mut elements = Vec::with_capacity(entry_count as usize);
+    for _ in 0..entry_count {
+        elements.push(decode_part!(data, params, i, string(true)));
+    }
+    let toc = TableOfContents {
+        element_id,
+        top_level: flags & 0x02 != 0,
+        ordered: flags & 0x01 != 0,
+        elements,
+        frames: parse_sub_frames(&data[i..], version)?,
+    };
+    Ok(DecoderResult::new(Encoding::Latin1, Content::TableOfContents(toc)))
+}
+
+/// Returns whether frames with the ID may be embedded in a chapter or table of contents. The
+/// specification only embeds ordinary frames, nesting chapters would let a crafted tag recurse
+/// until the stack overflows.
+fn is_sub_frame_id(id: &str) -> bool {
+    id != "CHAP" && id != "CTOC"
+}
+
+/// Decodes the frames that are embedded in chapter and table of contents frames. Encrypted frames
+/// are preserved as is, since the decryptors of the decoder are not available here.
+fn parse_sub_frames(mut data: &[u8], version: tag::Version) -> ::Result<Vec<Frame>> {
+    let mut frames = Vec::new();
+    loop {
+        // Check the ID before the frame is decoded, as decoding a nested chapter recurses.
+        let mut header_data = data;
+        match ::stream::frame::decode_header(&mut header_data, version)? {
+            Some(ref header) if !is_sub_frame_id(header.id()) => {
+                return Err(::Error::new(::ErrorKind::Parsing, "chapter frames can not be nested"));
+            },
+            Some(_) => {},
+            None => break,
+        }
+        match ::stream::frame::decode(&mut data, version, false, &::stream::frame::Decryptors::new(), false)? {
+            Some((_, frame)) => frames.push(frame),
+            None => break,
+        }
+    }
+    Ok(frames)
+}
+
+fn sub_frames_to_bytes(buf: &mut Vec<u8>, frames: &[Frame], version: tag::Version) -> ::Result<()> {
+    for frame in frames {
+        if !is_sub_frame_id(frame.id()) {
+            return Err(::Error::new(::ErrorKind::InvalidInput, "chapter frames can not be nested"));
+        }
+        ::stream::frame::encode(buf, frame, version, false, false)?;
+    }
+    Ok(())
+}
+
+/// Attempts to parse the data as an encryption method registration frame.
+/// Returns a `Content::EncryptionMethod`.
+fn parse_encr(data: &[u8]) -> ::Result<DecoderResult> {
+    let params = DecodingParams::for_encoding(Encoding::Latin1);
+    let mut i = 0;
+    let owner_identifier = decode_part!(data, params, i, string(true));
+    if i >= data.len() {
+        return Err(::Error::new(::ErrorKind::Parsing, "insufficient data"));
+    }
+    let symbol = data[i];
+    i += 1;
+    let method = EncryptionMethod {
+        owner_identifier,
+        symbol,
+        data: decode_part!(data, params, i, bytes()),
+    };
+    Ok(DecoderResult::new(Encoding::Latin1, Content::EncryptionMethod(method)))
+}
+
+/// Attempts to parse the data as a group identification registration frame.
+/// Returns a `Content::GroupIdentifier`.
+fn parse_grid(data: &[u8]) -> ::Result<DecoderResult> {
+    let params = DecodingParams::for_encoding(Encoding::Latin1);
+    let mut i = 0;
+    let owner_identifier = decode_part!(data, params, i, string(true));
+    if i >= data.len() {
+        return Err(::Error::new(::ErrorKind::Parsing, "insufficient data"));
+    }
+    let symbol = data[i];
+    i += 1;
+    let group = GroupIdentifier {
+        owner_identifier,
+        symbol,
+        data: decode_part!(data, params, i, bytes()),
+    };
+    Ok(DecoderResult::new(Encoding::Latin1, Content::GroupIdentifier(group)))
+}
+
+/// Attempts to parse the data as a popularimeter frame.
+/// Returns a `Content::Popularimeter`. The counter may be omitted or be longer than 4 bytes,
+/// counters that do not fit in 64 bits are saturated.
+fn parse_popm(data: &[u8]) -> ::Result<DecoderResult> {
+    let params = DecodingParams::for_encoding(Encoding::Latin1);
+    let mut i = 0;
+    let email = decode_part!(data, params, i, string(true));
+    if i >= data.len() {
+        return Err(::Error::new(::ErrorKind::Parsing, "insufficient data"));
+    }
+    let popm = Popularimeter {
+        email,
+        rating: data[i],
+        counter: parse_counter(&data[i + 1..]),
+    };
+    Ok(DecoderResult::new(Encoding::Latin1, Content::Popularimeter(popm)))
+}
+
+/// Attempts to parse the data as a unique file identifier frame.
+/// Returns a `Content::UniqueFileIdentifier`.
+fn parse_ufid(data: &[u8]) -> ::Result<DecoderResult> {
+    let params = DecodingParams::for_encoding(Encoding::Latin1);
+    let mut i = 0;
+    let ufid = UniqueFileIdentifier {
+        owner_identifier: decode_part!(data, params, i, string(true)),
+        identifier: decode_part!(data, params, i, bytes()),
+    };
+    Ok(DecoderResult::new(Encoding::Latin1, Content::UniqueFileIdentifier(ufid)))
+}
+
+/// Attempts to parse the data as a private frame.
+/// Returns a `Content::Private`.
+fn parse_priv(data: &[u8]) -> ::Result<DecoderResult> {
+    let params = DecodingParams::for_encoding(Encoding::Latin1);
+    let mut i = 0;
+    let private = Private {
+        owner: decode_part!(data, params, i, string(true)),
+        data: decode_part!(data, params, i, bytes()),
+    };
+    Ok(DecoderResult::new(Encoding::Latin1, Content::Private(private)))
+}
+
+/// Attempts to parse the data as a relative volume adjustment frame.
+/// Returns a `Content::RelativeVolumeAdjustment`.
+fn parse_rva2(data: &[u8]) -> ::Result<DecoderResult> {
+    let params = DecodingParams::for_encoding(Encoding::Latin1);
+    let mut i = 0;
+    let identification = decode_part!(data, params, i, string(true));
+    let mut channels = Vec::new();
+    while i < data.len() {
+        if i + 4 > data.len() {
+            return Err(::Error::new(::ErrorKind::Parsing, "insufficient data"));
+        }
+        let channel = match data[i] {
+            0 => ChannelType::Other,
+            1 => ChannelType::MasterVolume,
+            2 => ChannelType::FrontRight,
+            3 => ChannelType::FrontLeft,
+            4 => ChannelType::BackRight,
+            5 => ChannelType::BackLeft,
+            6 => ChannelType::FrontCentre,
+            7 => ChannelType::BackCentre,
+            8 => ChannelType::Subwoofer,
+            _ => return Err(::Error::new(::ErrorKind::Parsing, "invalid channel type")),
+        };
+        let adjustment = BigEndian::read_i16(&data[i + 1..i + 3]);
+        let peak_bits = data[i + 3];
+        i += 4;
+        let peak_len = (peak_bits as usize).div_ceil(8);
+        if i + peak_len > data.len() {
+            return Err(::Error::new(::ErrorKind::Parsing, "insufficient data"));
+        }
+        channels.push(VolumeAdjustment {
+            channel,
+            adjustment,
+            peak_bits,
+            peak: data[i..i + peak_len].to_vec(),
+        });
+        i += peak_len;
+    }
+    let rva2 = RelativeVolumeAdjustment { identification, channels };
+    Ok(DecoderResult::new(Encoding::Latin1, Content::RelativeVolumeAdjustment(rva2)))
+}
+
+/// Attempts to parse the data as an equalisation frame.
+/// Returns a `Content::Equalisation`.
+fn parse_equ2(data: &[u8]) -> ::Result<DecoderResult> {
+    assert_data!(data);
+    let method = match data[0] {
+        0 => InterpolationMethod::Band,
+        1 => InterpolationMethod::Linear,
+        _ => return Err(::Error::new(::ErrorKind::Parsing, "invalid interpolation method")),
+    };
+    let params = DecodingParams::for_encoding(Encoding::Latin1);
+    let mut i = 1;
+    let identification = decode_part!(data, params, i, string(true));
+    if (data.len() - i) % 4 != 0 {
+        return Err(::Error::new(::ErrorKind::Parsing, "insufficient data"));
+    }
+    let adjustments = data[i..].chunks(4)
+        .map(|pair| (BigEndian::read_u16(&pair[0..2]), BigEndian::read_i16(&pair[2..4])))
+        .collect();
+    let equ2 = Equalisation { method, identification, adjustments };
+    Ok(DecoderResult::new(Encoding::Latin1, Content::Equalisation(equ2)))
+}
+
+fn timestamp_format_from_byte(byte: u8) -> ::Result<TimestampFormat> {
+    match byte {
+        1 => Ok(TimestampFormat::Mpeg),
+        2 => Ok(TimestampFormat::Ms),
+        _ => Err(::Error::new(::ErrorKind::Parsing, "invalid timestamp format")),
+    }
+}
+
+/// Attempts to parse the data as an event timing codes frame.
+/// Returns a `Content::EventTimingCodes`.
+fn parse_etco(data: &[u8]) -> ::Result<DecoderResult> {
+    assert_data!(data);
+    let timestamp_format = timestamp_format_from_byte(data[0])?;
+    if (data.len() - 1) % 5 != 0 {
+        return Err(::Error::new(::ErrorKind::Parsing, "insufficient data"));
+    }
+    let events = data[1..].chunks(5)
+        .map(|event| {
+            let event_type = match event[0] {
+                0x00 => EventType::Padding,
+                0x01 => EventType::EndOfInitialSilence,
+                0x02 => EventType::IntroStart,
+                0x03 => EventType::MainPartStart,
+                0x04 => EventType::OutroStart,
+                0x05 => EventType::OutroEnd,
+                0x06 => EventType::VerseStart,
+                0x07 => EventType::RefrainStart,
+                0x08 => EventType::InterludeStart,
+                0x09 => EventType::ThemeStart,
+                0x0a => EventType::VariationStart,
+                0x0b => EventType::KeyChange,
+                0x0c => EventType::TimeChange,
+                0x0d => EventType::MomentaryUnwantedNoise,
+                0x0e => EventType::SustainedNoise,
+                0x0f => EventType::SustainedNoiseEnd,
+                0x10 => EventType::IntroEnd,
+                0x11 => EventType::MainPartEnd,
+                0x12 => EventType::VerseEnd,
+                0x13 => EventType::RefrainEnd,
+                0x14 => EventType::ThemeEnd,
+                0x15 => EventType::Profanity,
+                0x16 => EventType::ProfanityEnd,
+                0xfd => EventType::AudioEnd,
+                0xfe => EventType::AudioFileEnd,
+                value @ 0xe0..=0xef => EventType::NotPredefinedSync(value & 0x0f),
+                value => EventType::Reserved(value),
+            };
+            Event { event_type, timestamp: BigEndian::read_u32(&event[1..]) }
+        })
+        .collect();
+    let etco = EventTimingCodes { timestamp_format, events };
+    Ok(DecoderResult::new(Encoding::Latin1, Content::EventTimingCodes(etco)))
+}
+
+/// Attempts to parse the data as a linked information frame.
+/// Returns a `Content::LinkedInfo`.
+fn parse_link(data: &[u8], version: tag::Version) -> ::Result<DecoderResult> {
+    let id_len = frame_id_len(version);
+    if data.len() < id_len {
+        return Err(::Error::new(::ErrorKind::Parsing, "insufficient data"));
+    }
+    let params = DecodingParams::for_encoding(Encoding::Latin1);
+    let mut i = id_len;
+    let link = LinkedInfo {
+        frame_id: ::util::string_from_latin1(&data[..id_len])?,
+        url: decode_part!(data, params, i, string(true)),
+        additional_data: data[i..].to_vec(),
+    };
+    Ok(DecoderResult::new(Encoding::Latin1, Content::LinkedInfo(link)))
+}
+
+/// Attempts to parse the data as a terms of use frame.
+/// Returns a `Content::TermsOfUse`.
+fn parse_user(data: &[u8]) -> ::Result<DecoderResult> {
+    decode!(data, TermsOfUse, lang: fixed_string(3), text: string(false))
+}
+
+/// Attempts to parse the data as a signature frame.
+/// Returns a `Content::Signature`.
+fn parse_sign(mut data: Vec<u8>) -> ::Result<DecoderResult> {
+    assert_data!(data);
+    let group_symbol = data.remove(0);
+    let sign = Signature { group_symbol, signature: data };
+    Ok(DecoderResult::new(Encoding::Latin1, Content::Signature(sign)))
+}
+
+/// Attempts to parse the data as a commercial frame.
+/// Returns a `Content::Commercial`. The logo is optional.
+fn parse_comr(data: &[u8]) -> ::Result<DecoderResult> {
+    assert_data!(data);
+    let encoding = encoding_from_byte(data[0])?;
+    let params = DecodingParams::for_encoding(encoding);
+    let mut i = 1;
+    let price = decode_part!(data, params, i, latin1(true));
+    let valid_until = decode_part!(data, params, i, fixed_string(8));
+    let contact_url = decode_part!(data, params, i, latin1(true));
+    if i >= data.len() {
+        return Err(::Error::new(::ErrorKind::Parsing, "insufficient data"));
+    }
+    let received_as = match data[i] {
+        0 => ReceivedAs::Other,
+        1 => ReceivedAs::StandardCdAlbum,
+        2 => ReceivedAs::CompressedAudioOnCd,
+        3 => ReceivedAs::FileOverInternet,
+        4 => ReceivedAs::StreamOverInternet,
+        5 => ReceivedAs::AsNoteSheets,
+        6 => ReceivedAs::AsNoteSheetsInBook,
+        7 => ReceivedAs::MusicOnOtherMedia,
+        8 => ReceivedAs::NonMusicalMerchandise,
+        _ => return Err(::Error::new(::ErrorKind::Parsing, "invalid received as value")),
+    };
+    i += 1;
+    let seller = decode_part!(data, params, i, string(true));
+    let description = decode_part!(data, params, i, string(true));
+    let logo = if i < data.len() {
+        let mime_type = decode_part!(data, params, i, latin1(true));
+        Some((mime_type, data[i..].to_vec()))
+    } else {
+        None
+    };
+    let commercial = Commercial { price, valid_until, contact_url, received_as, seller, description, logo };
+    Ok(DecoderResult::new(encoding, Content::Commercial(commercial)))
+}
+
+/// Attempts to parse the data as a recommended buffer size frame.
+/// Returns a `Content::RecommendedBufferSize`. The offset to the next tag is optional.
+fn parse_rbuf(data: &[u8]) -> ::Result<DecoderResult> {
+    let next_tag_offset = match data.len() {
+        4 => None,
+        8 => Some(BigEndian::read_u32(&data[4..8])),
+        _ => return Err(::Error::new(::ErrorKind::Parsing, "recommended buffer size frame must contain 4 or 8 bytes")),
+    };
+    let rbuf = RecommendedBufferSize {
+        buffer_size: BigEndian::read_u24(&data[0..3]),
+        embedded_info: data[3] & 0x01 != 0,
+        next_tag_offset,
+    };
+    Ok(DecoderResult::new(Encoding::Latin1, Content::RecommendedBufferSize(rbuf)))
+}
+
+/// Attempts to parse the data as an audio seek point index frame.
+/// Returns a `Content::AudioSeekPointIndex`.
+fn parse_aspi(data: &[u8]) -> ::Result<DecoderResult> {
+    if data.len() < 11 {
+        return Err(::Error::new(::ErrorKind::Parsing, "insufficient data"));
+    }
+    let count = BigEndian::read_u16(&data[8..10]) as usize;
+    let bits_per_point = data[10];
+    let points_data = &data[11..];
+    let points = match bits_per_point {
+        8 if points_data.len() >= count => points_data[..count].iter().map(|b| u16::from(*b)).collect(),
+        16 if points_data.len() >= count * 2 => points_data[..count * 2].chunks(2).map(BigEndian::read_u16).collect(),
+        8 | 16 => return Err(::Error::new(::ErrorKind::Parsing, "insufficient data")),
+        _ => return Err(::Error::new(::ErrorKind::Parsing, "index points must have 8 or 16 bits")),
+    };
+    let aspi = AudioSeekPointIndex {
+        start: BigEndian::read_u32(&data[0..4]),
+        length: BigEndian::read_u32(&data[4..8]),
+        bits_per_point,
+        points,
+    };
+    Ok(DecoderResult::new(Encoding::Latin1, Content::AudioSeekPointIndex(aspi)))
+}
+
+/// Attempts to parse the data as a seek frame.
+/// Returns a `Content::SeekOffset`.
+fn parse_seek(data: &[u8]) -> ::Result<DecoderResult> {
+    if data.len() != 4 {
+        return Err(::Error::new(::ErrorKind::Parsing, "seek frame must contain 4 bytes"));
+    }
+    Ok(DecoderResult::new(Encoding::Latin1, Content::SeekOffset(BigEndian::read_u32(data))))
+}
+
+/// Decodes a big-endian play counter of any length. Counters that do not fit in 64 bits are
+/// saturated.
+fn parse_counter(data: &[u8]) -> u64 {
+    match data.len() {
+        0 => 0,
+        n if n > 8 => u64::MAX,
+        n => BigEndian::read_uint(data, n),
+    }
+}
+
+/// Attempts to parse the data as a synchronised lyrics frame.
+/// Returns a `Content::SynchronisedLyrics`.
+fn parse_sylt(data: &[u8]) -> ::Result<DecoderResult> {
+    assert_data!(data);
+
+    let encoding = encoding_from_byte(data[0])?;
+    let params = DecodingParams::for_encoding(encoding);
+    let mut i = 1;
+    let lang = decode_part!(data, params, i, fixed_string(3));
+    if i + 2 > data.len() {
+        return Err(::Error::new(::ErrorKind::Parsing, "insufficient data"));
+    }
+    let timestamp_format = timestamp_format_from_byte(data[i])?;
+    let content_type = match data[i + 1] {
+        0 => SynchronisedLyricsType::Other,
+        1 => SynchronisedLyricsType::Lyrics,
+        2 => SynchronisedLyricsType::Transcription,
+        3 => SynchronisedLyricsType::PartName,
+        4 => SynchronisedLyricsType::Events,
+        5 => SynchronisedLyricsType::Chord,
+        6 => SynchronisedLyricsType::Trivia,
+        7 => SynchronisedLyricsType::WebpageUrls,
+        8 => SynchronisedLyricsType::ImageUrls,
+        _ => return Err(::Error::new(::ErrorKind::Parsing, "invalid synchronised lyrics type")),
+    };
+    i += 2;
+    let description = decode_part!(data, params, i, string(true));
+
+    let mut content = Vec::new();
+    while i < data.len() {
+        let text = decode_part!(data, params, i, string(true));
+        if i + 4 > data.len() {
+            return Err(::Error::new(::ErrorKind::Parsing, "insufficient data"));
+        }
+        let timestamp = BigEndian::read_u32(&data[i..i + 4]);
+        i += 4;
+        content.push((timestamp, text));
+    }
+
+    let lyrics = SynchronisedLyrics {
+        lang,
+        timestamp_format,
+        content_type,
+        description,
+        content,
+    };
+    Ok(DecoderResult::new(encoding, Content::SynchronisedLyrics(lyrics)))
+}
 // }}}
 
 // Tests {{{
@@ -487,7 +1360,7 @@ mod tests {
 
     #[test]
     fn test_apic_v2() {
-        assert!(decode("PIC",&[][..]).is_err());
+        assert!(decode("PIC", tag::Id3v22, &[][..]).is_err());
 
         let mut format_map = HashMap::new();
         format_map.insert("image/jpeg", "JPG");
@@ -514,7 +1387,7 @@ mod tests {
                     data.extend(delim_for_encoding(*encoding).into_iter());
                     data.extend(picture_data.iter().cloned());
 
-                    assert_eq!(*decode("PIC", &data[..]).unwrap().content.picture().unwrap(), picture);
+                    assert_eq!(*decode("PIC", tag::Id3v22, &data[..]).unwrap().content.picture().unwrap(), picture);
                     let mut data_out = Vec::new();
                     encode(&mut data_out, &Content::Picture(picture.clone()), tag::Id3v22, *encoding).unwrap();
                     assert_eq!(data, data_out);
@@ -525,7 +1398,7 @@ mod tests {
 
     #[test]
     fn test_apic_v3() {
-        assert!(decode("APIC", &[][..]).is_err());
+        assert!(decode("APIC", tag::Id3v24, &[][..]).is_err());
 
         for mime_type in &["", "image/jpeg"] {
             for description in &["", "description"] {
@@ -549,7 +1422,7 @@ mod tests {
                     data.extend(delim_for_encoding(*encoding).into_iter());
                     data.extend(picture_data.iter().cloned());
 
-                    assert_eq!(*decode("APIC", &data[..]).unwrap().content.picture().unwrap(), picture);
+                    assert_eq!(*decode("APIC", tag::Id3v24, &data[..]).unwrap().content.picture().unwrap(), picture);
                     let mut data_out = Vec::new();
                     encode(&mut data_out, &Content::Picture(picture.clone()), tag::Id3v23, *encoding).unwrap();
                     assert_eq!(data, data_out);
@@ -560,7 +1433,7 @@ mod tests {
 
     #[test]
     fn test_comm() {
-        assert!(decode("COMM", &[][..]).is_err());
+        assert!(decode("COMM", tag::Id3v24, &[][..]).is_err());
 
         println!("valid");
         for description in &["", "description"] {
@@ -579,7 +1452,7 @@ mod tests {
                         description: description.to_string(),
                         text: comment.to_string()
                     };
-                    assert_eq!(*decode("COMM", &data[..]).unwrap().content.comment().unwrap(), content);
+                    assert_eq!(*decode("COMM", tag::Id3v24, &data[..]).unwrap().content.comment().unwrap(), content);
                     let mut data_out = Vec::new();
                     encode(&mut data_out, &Content::Comment(content), tag::Id3v23, *encoding).unwrap();
                     assert_eq!(data, data_out);
@@ -597,7 +1470,7 @@ mod tests {
             data.extend(b"eng".iter().cloned());
             data.extend(bytes_for_encoding(description, *encoding).into_iter());
             data.extend(bytes_for_encoding(comment, *encoding).into_iter());
-            assert!(decode("COMM", &data[..]).is_err());
+            assert!(decode("COMM", tag::Id3v24, &data[..]).is_err());
         }
         println!("Empty description");
         let comment = "comment";
@@ -615,13 +1488,502 @@ mod tests {
             };
             println!("data == {:?}", data);
             println!("content == {:?}", content);
-            assert_eq!(*decode("COMM", &data[..]).unwrap().content.comment().unwrap(), content);
+            assert_eq!(*decode("COMM", tag::Id3v24, &data[..]).unwrap().content.comment().unwrap(), content);
+        }
+    }
+
+    #[test]
+    fn test_sylt() {
+        assert!(decode("SYLT", tag::Id3v24, &[][..]).is_err());
+
+        let syllables = [(0, "Strang"), (500, "ers "), (800, "in"), (1200, "\nthe night")];
+        for description in &["", "description"] {
+            for encoding in &[Encoding::Latin1, Encoding::UTF8, Encoding::UTF16, Encoding::UTF16BE] {
+                println!("`{}`, `{:?}`", description, encoding);
+                let mut data = Vec::new();
+                data.push(*encoding as u8);
+                data.extend(b"eng".iter().cloned());
+                data.push(2);
+                data.push(1);
+                data.extend(bytes_for_encoding(description, *encoding));
+                data.extend(delim_for_encoding(*encoding));
+                for &(timestamp, text) in &syllables {
+                    data.extend(bytes_for_encoding(text, *encoding));
+                    data.extend(delim_for_encoding(*encoding));
+                    data.extend(&[0, 0, (timestamp >> 8) as u8, timestamp as u8]);
+                }
+
+                let content = frame::SynchronisedLyrics {
+                    lang: "eng".to_string(),
+                    timestamp_format: frame::TimestampFormat::Ms,
+                    content_type: frame::SynchronisedLyricsType::Lyrics,
+                    description: description.to_string(),
+                    content: syllables.iter().map(|&(t, s)| (t, s.to_string())).collect(),
+                };
+                let decoded = decode("SYLT", tag::Id3v24, &data[..]).unwrap().content;
+                let decoded = decoded.synchronised_lyrics().unwrap();
+                assert_eq!(content.content, decoded.content);
+                assert_eq!(content.timestamp_format, decoded.timestamp_format);
+                assert_eq!(content.content_type, decoded.content_type);
+                assert_eq!(content, *decoded);
+                let mut data_out = Vec::new();
+                encode(&mut data_out, &Content::SynchronisedLyrics(content), tag::Id3v23, *encoding).unwrap();
+                assert_eq!(data, data_out);
+            }
+        }
+
+        // A syllable without its timestamp.
+        let data = b"\x00eng\x02\x01\x00text\x00\x00\x00";
+        assert!(decode("SYLT", tag::Id3v24, &data[..]).is_err());
+        // An unknown timestamp format.
+        let data = b"\x00eng\x03\x01\x00";
+        assert!(decode("SYLT", tag::Id3v24, &data[..]).is_err());
+    }
+
+    #[test]
+    fn test_chap() {
+        assert!(decode("CHAP", tag::Id3v24, &b"chp0\x00\x00\x00"[..]).is_err());
+
+        for &version in &[tag::Id3v23, tag::Id3v24] {
+            let content = frame::Chapter {
+                element_id: "chp0".to_string(),
+                start_time: 1000,
+                end_time: 2000,
+                start_offset: frame::Chapter::NO_OFFSET,
+                end_offset: frame::Chapter::NO_OFFSET,
+                frames: vec![
+                    frame::Frame::with_content("TIT2", Content::Text("Intro".into())),
+                    frame::Frame::with_content("WXXX", Content::ExtendedLink(frame::ExtendedLink {
+                        description: "".to_string(),
+                        link: "http://example.com".to_string(),
+                    })),
+                ],
+            };
+            let mut data = Vec::new();
+            encode(&mut data, &Content::Chapter(content.clone()), version, Encoding::UTF8).unwrap();
+            assert_eq!(&data[..25], b"chp0\x00\x00\x00\x03\xe8\x00\x00\x07\xd0\xff\xff\xff\xff\xff\xff\xff\xffTIT2");
+
+            let decoded = decode("CHAP", version, &data[..]).unwrap().content;
+            let chapter = decoded.chapter().unwrap();
+            assert_eq!(content, *chapter);
+            assert_eq!((1000, 2000), (chapter.start_time, chapter.end_time));
+            assert_eq!(content.end_offset, chapter.end_offset);
+            assert_eq!(Some("Intro"), chapter.title());
+            assert_eq!(content.frames[1].content(), chapter.frames[1].content());
+        }
+    }
+
+    #[test]
+    fn test_chap_nested() {
+        // Deeply nested chapters must not overflow the stack.
+        let header = b"chp\x00\x00\x00\x00\x00\x00\x00\x00\x01\xff\xff\xff\xff\xff\xff\xff\xff";
+        let mut data = header.to_vec();
+        for _ in 0..1000 {
+            let mut chapter = header.to_vec();
+            chapter.extend_from_slice(b"CHAP");
+            chapter.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            chapter.extend_from_slice(&[0, 0]);
+            chapter.extend_from_slice(&data);
+            data = chapter;
+        }
+        match decode("CHAP", tag::Id3v23, &data[..]) {
+            Err(err) => assert_eq!("chapter frames can not be nested", err.description),
+            Ok(_) => panic!("nested chapters were decoded"),
+        }
+
+        let nested = frame::Chapter {
+            element_id: "chp0".to_string(),
+            start_time: 0,
+            end_time: 1000,
+            start_offset: frame::Chapter::NO_OFFSET,
+            end_offset: frame::Chapter::NO_OFFSET,
+            frames: Vec::new(),
+        };
+        let mut chapter = nested.clone();
+        chapter.frames.push(frame::Frame::with_content("CHAP", Content::Chapter(nested)));
+        assert!(encode(&mut Vec::new(), &Content::Chapter(chapter), tag::Id3v24, Encoding::UTF8).is_err());
+    }
+
+    #[test]
+    fn test_popm() {
+        assert!(decode("POPM", tag::Id3v24, &b"no terminator"[..]).is_err());
+
+        let content = decode("POPM", tag::Id3v24, &b"a@b\x00\x80\x00\x00\x01\x00"[..]).unwrap().content;
+        let popm = content.popularimeter().unwrap();
+        assert_eq!(("a@b", 0x80, 0x100), (&popm.email[..], popm.rating, popm.counter));
+        let mut data = Vec::new();
+        encode(&mut data, &content, tag::Id3v24, Encoding::UTF8).unwrap();
+        assert_eq!(b"a@b\x00\x80\x00\x00\x01\x00".to_vec(), data);
+
+        let content = decode("POPM", tag::Id3v23, &b"\x00\x01"[..]).unwrap().content;
+        let popm = content.popularimeter().unwrap();
+        assert_eq!(("", 1, 0), (&popm.email[..], popm.rating, popm.counter));
+        let mut data = Vec::new();
+        encode(&mut data, &content, tag::Id3v23, Encoding::UTF8).unwrap();
+        assert_eq!(b"\x00\x01".to_vec(), data);
+
+        let content = decode("POPM", tag::Id3v24, &b"\x00\x01\x01\x02\x03\x04\x05\x06\x07\x08\x09"[..]).unwrap().content;
+        assert_eq!(u64::MAX, content.popularimeter().unwrap().counter);
+    }
+
+    #[test]
+    fn test_geob() {
+        assert!(decode("GEOB", tag::Id3v24, &[][..]).is_err());
+
+        let content = frame::EncapsulatedObject {
+            mime_type: "application/octet-stream".to_string(),
+            filename: "cues.bin".to_string(),
+            description: "Serato Markers2".to_string(),
+            data: vec![0, 1, 2, 3],
+        };
+        for &(version, encoding) in &[(tag::Id3v22, Encoding::Latin1), (tag::Id3v23, Encoding::UTF16), (tag::Id3v24, Encoding::UTF8)] {
+            let mut data = Vec::new();
+            encode(&mut data, &Content::EncapsulatedObject(content.clone()), version, encoding).unwrap();
+            let decoded = decode("GEOB", version, &data[..]).unwrap();
+            assert_eq!(encoding, decoded.encoding);
+            let object = decoded.content.encapsulated_object().unwrap();
+            assert_eq!((&content.mime_type, &content.filename, &content.description, &content.data),
+                       (&object.mime_type, &object.filename, &object.description, &object.data));
+        }
+    }
+
+    #[test]
+    fn test_ufid() {
+        assert!(decode("UFID", tag::Id3v24, &b"no terminator"[..]).is_err());
+
+        let data = b"http://musicbrainz.org\x00a8f3b1e2-0000-4000-8000-000000000000";
+        let content = decode("UFID", tag::Id3v24, &data[..]).unwrap().content;
+        let ufid = content.unique_file_identifier().unwrap();
+        assert_eq!("http://musicbrainz.org", ufid.owner_identifier);
+        assert_eq!(&b"a8f3b1e2-0000-4000-8000-000000000000"[..], &ufid.identifier[..]);
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &content, tag::Id3v23, Encoding::UTF16).unwrap();
+        assert_eq!(&data[..], &encoded[..]);
+    }
+
+    #[test]
+    fn test_priv() {
+        assert!(decode("PRIV", tag::Id3v24, &b"no terminator"[..]).is_err());
+
+        let data = b"www.amazon.com\x00\x01\x02\x00\x03";
+        let content = decode("PRIV", tag::Id3v23, &data[..]).unwrap().content;
+        assert_eq!(Some(&frame::Private { owner: "www.amazon.com".to_string(), data: vec![1, 2, 0, 3] }), content.private());
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &content, tag::Id3v24, Encoding::UTF8).unwrap();
+        assert_eq!(&data[..], &encoded[..]);
+    }
+
+    #[test]
+    fn test_rva2() {
+        // mp3gain style track gain of -6.5 dB with a 16 bit peak.
+        let data = b"track\x00\x01\xf3\x00\x10\x7f\xff";
+        let content = decode("RVA2", tag::Id3v24, &data[..]).unwrap().content;
+        let rva2 = content.relative_volume_adjustment().unwrap();
+        assert_eq!("track", rva2.identification);
+        assert_eq!(1, rva2.channels.len());
+        assert_eq!(ChannelType::MasterVolume, rva2.channels[0].channel);
+        assert_eq!(-6.5, rva2.channels[0].adjustment_db());
+        assert_eq!(vec![0x7f, 0xff], rva2.channels[0].peak);
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &content, tag::Id3v24, Encoding::UTF8).unwrap();
+        assert_eq!(&data[..], &encoded[..]);
+
+        let mut rva2 = rva2.clone();
+        rva2.channels[0].set_adjustment_db(100.0);
+        assert_eq!(i16::MAX, rva2.channels[0].adjustment);
+        rva2.channels[0].peak_bits = 8;
+        assert!(encode(Vec::new(), &Content::RelativeVolumeAdjustment(rva2), tag::Id3v24, Encoding::UTF8).is_err());
+
+        assert!(decode("RVA2", tag::Id3v24, &b"track\x00\x09\x00\x00\x00"[..]).is_err());
+        assert!(decode("RVA2", tag::Id3v24, &b"track\x00\x01\x00\x00\x10\x7f"[..]).is_err());
+    }
+
+    #[test]
+    fn test_equ2() {
+        // +2 dB at 100 Hz and -1 dB at 10 kHz.
+        let data = b"\x01Room\x00\x00\xc8\x04\x00\x4e\x20\xfe\x00";
+        let content = decode("EQU2", tag::Id3v24, &data[..]).unwrap().content;
+        let equ2 = content.equalisation().unwrap();
+        assert_eq!(InterpolationMethod::Linear, equ2.method);
+        assert_eq!("Room", equ2.identification);
+        assert_eq!(vec![(200, 1024), (20000, -512)], equ2.adjustments);
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &content, tag::Id3v24, Encoding::UTF8).unwrap();
+        assert_eq!(&data[..], &encoded[..]);
+
+        assert!(decode("EQU2", tag::Id3v24, &b"\x02Room\x00"[..]).is_err());
+        assert!(decode("EQU2", tag::Id3v24, &b"\x00Room\x00\x00\xc8\x04"[..]).is_err());
+    }
+
+    #[test]
+    fn test_etco() {
+        let data = b"\x02\x02\x00\x00\x00\x00\x03\x00\x00\x27\x10\xe5\x00\x00\x4e\x20\x42\x00\x00\x75\x30";
+        let content = decode("ETCO", tag::Id3v23, &data[..]).unwrap().content;
+        let etco = content.event_timing_codes().unwrap();
+        assert_eq!(TimestampFormat::Ms, etco.timestamp_format);
+        let events: Vec<_> = etco.events.iter().map(|event| (event.event_type, event.timestamp)).collect();
+        assert_eq!(vec![
+            (EventType::IntroStart, 0),
+            (EventType::MainPartStart, 10000),
+            (EventType::NotPredefinedSync(5), 20000),
+            (EventType::Reserved(0x42), 30000),
+        ], events);
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &content, tag::Id3v23, Encoding::UTF16).unwrap();
+        assert_eq!(&data[..], &encoded[..]);
+
+        assert!(decode("ETCO", tag::Id3v24, &b"\x03"[..]).is_err());
+        assert!(decode("ETCO", tag::Id3v24, &b"\x01\x02\x00\x00"[..]).is_err());
+    }
+
+    #[test]
+    fn test_link() {
+        let data = b"COMMhttp://example.com/album.mp3\x00eng";
+        let content = decode("LINK", tag::Id3v24, &data[..]).unwrap().content;
+        let link = content.linked_info().unwrap();
+        assert_eq!(("COMM", "http://example.com/album.mp3", &b"eng"[..]),
+                   (&link.frame_id[..], &link.url[..], &link.additional_data[..]));
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &content, tag::Id3v24, Encoding::UTF8).unwrap();
+        assert_eq!(&data[..], &encoded[..]);
+
+        let mut v2 = Vec::new();
+        encode(&mut v2, &content, tag::Id3v22, Encoding::Latin1).unwrap();
+        assert_eq!(b"COMhttp", &v2[..7]);
+        let content = decode("LNK", tag::Id3v22, &v2[..]).unwrap().content;
+        let mut v3 = Vec::new();
+        encode(&mut v3, &content, tag::Id3v23, Encoding::Latin1).unwrap();
+        assert_eq!(&data[..], &v3[..]);
+
+        let unknown = LinkedInfo { frame_id: "XXXX".to_string(), url: String::new(), additional_data: Vec::new() };
+        assert!(encode(Vec::new(), &Content::LinkedInfo(unknown), tag::Id3v22, Encoding::Latin1).is_err());
+        assert!(decode("LINK", tag::Id3v24, &b"COMMno terminator"[..]).is_err());
+    }
+
+    #[test]
+    fn test_user() {
+        assert!(decode("USER", tag::Id3v24, &[][..]).is_err());
+
+        let content = frame::TermsOfUse {
+            lang: "eng".to_string(),
+            text: "All rights reserved".to_string(),
+        };
+        for &(version, encoding) in &[(tag::Id3v23, Encoding::UTF16), (tag::Id3v24, Encoding::UTF8)] {
+            let mut data = Vec::new();
+            encode(&mut data, &Content::TermsOfUse(content.clone()), version, encoding).unwrap();
+            assert_eq!(b"eng", &data[1..4]);
+            let decoded = decode("USER", version, &data[..]).unwrap().content;
+            let user = decoded.terms_of_use().unwrap();
+            assert_eq!((&content.lang, &content.text), (&user.lang, &user.text));
+        }
+    }
+
+    #[test]
+    fn test_sign() {
+        assert!(decode("SIGN", tag::Id3v24, &[][..]).is_err());
+
+        let content = decode("SIGN", tag::Id3v24, &b"\x80sig"[..]).unwrap().content;
+        assert_eq!(Some(&Signature { group_symbol: 0x80, signature: b"sig".to_vec() }), content.signature());
+        let mut data = Vec::new();
+        encode(&mut data, &content, tag::Id3v24, Encoding::UTF8).unwrap();
+        assert_eq!(b"\x80sig".to_vec(), data);
+    }
+
+    #[test]
+    fn test_comr() {
+        let mut content = frame::Commercial {
+            price: "USD9.99/EUR8.99".to_string(),
+            valid_until: "20251231".to_string(),
+            contact_url: "https://shop.example.com".to_string(),
+            received_as: ReceivedAs::FileOverInternet,
+            seller: "Example Records".to_string(),
+            description: "Digital album".to_string(),
+            logo: Some(("image/png".to_string(), vec![0x89, b'P', b'N', b'G'])),
+        };
+        for &(version, encoding) in &[(tag::Id3v23, Encoding::UTF16), (tag::Id3v24, Encoding::UTF8), (tag::Id3v24, Encoding::Latin1)] {
+            for logo in [content.logo.clone(), None] {
+                content.logo = logo;
+                let mut data = Vec::new();
+                encode(&mut data, &Content::Commercial(content.clone()), version, encoding).unwrap();
+                let decoded = decode("COMR", version, &data[..]).unwrap();
+                assert_eq!(encoding, decoded.encoding);
+                assert_eq!(Some(&content), decoded.content.commercial());
+            }
+            content.logo = Some(("image/png".to_string(), vec![0x89, b'P', b'N', b'G']));
+        }
+
+        content.valid_until = "2025-12-31".to_string();
+        assert!(encode(Vec::new(), &Content::Commercial(content), tag::Id3v24, Encoding::UTF8).is_err());
+        assert!(decode("COMR", tag::Id3v24, &b"\x00USD1\x0020251231\x00\x09Seller\x00Item\x00"[..]).is_err());
+    }
+
+    #[test]
+    fn test_mcdi() {
+        let mcdi = frame::MusicCdIdentifier::new(1, &[0, 15000], 30000);
+        assert_eq!(&[0, 26, 1, 2][..], &mcdi.toc[..4]);
+        let mut data = Vec::new();
+        encode(&mut data, &Content::MusicCdIdentifier(mcdi.clone()), tag::Id3v23, Encoding::UTF16).unwrap();
+        let decoded = decode("MCDI", tag::Id3v23, &data[..]).unwrap().content;
+        let decoded = decoded.music_cd_identifier().unwrap();
+        assert_eq!(mcdi, *decoded);
+        assert_eq!(Some(vec![(1, 0), (2, 15000), (0xaa, 30000)]), decoded.tracks());
+
+        // A lead-out is required.
+        let truncated = frame::MusicCdIdentifier { toc: mcdi.toc[..20].to_vec() };
+        assert_eq!(None, truncated.freedb_disc_id());
+        assert_eq!(None, truncated.musicbrainz_disc_id());
+        assert_eq!(None, frame::MusicCdIdentifier { toc: vec![0, 1, 2] }.tracks());
+
+        // Offsets that overflow when the pregap is added.
+        let overflowing = frame::MusicCdIdentifier::new(1, &[0], u32::MAX);
+        assert_eq!(None, overflowing.freedb_disc_id());
+        assert_eq!(None, overflowing.musicbrainz_disc_id());
+        let overflowing = frame::MusicCdIdentifier::new(1, &[u32::MAX - 1], u32::MAX);
+        assert_eq!(None, overflowing.freedb_disc_id());
+        assert_eq!(None, overflowing.musicbrainz_disc_id());
+    }
+
+    #[test]
+    fn test_rbuf() {
+        for &(data, offset) in &[(&b"\x01\x00\x00\x01"[..], None), (b"\x01\x00\x00\x01\x00\x00\x10\x00", Some(0x1000))] {
+            let content = decode("RBUF", tag::Id3v24, data).unwrap().content;
+            assert_eq!(Some(&RecommendedBufferSize { buffer_size: 0x10000, embedded_info: true, next_tag_offset: offset }),
+                       content.recommended_buffer_size());
+            let mut encoded = Vec::new();
+            encode(&mut encoded, &content, tag::Id3v24, Encoding::UTF8).unwrap();
+            assert_eq!(data, &encoded[..]);
         }
+        assert!(decode("RBUF", tag::Id3v24, &[0, 0, 0][..]).is_err());
+        let too_large = RecommendedBufferSize { buffer_size: 0x100_0000, embedded_info: false, next_tag_offset: None };
+        assert!(encode(Vec::new(), &Content::RecommendedBufferSize(too_large), tag::Id3v24, Encoding::UTF8).is_err());
+    }
+
+    #[test]
+    fn test_aspi() {
+        for &bits in &[8, 16] {
+            let content = AudioSeekPointIndex { start: 1024, length: 4_000_000, bits_per_point: bits, points: vec![0, 64, 128, 192] };
+            let mut data = Vec::new();
+            encode(&mut data, &Content::AudioSeekPointIndex(content.clone()), tag::Id3v24, Encoding::UTF8).unwrap();
+            assert_eq!(11 + 4 * bits as usize / 8, data.len());
+            let decoded = decode("ASPI", tag::Id3v24, &data[..]).unwrap().content;
+            assert_eq!(Some(&content), decoded.audio_seek_point_index());
+        }
+
+        let too_large = AudioSeekPointIndex { start: 0, length: 0, bits_per_point: 8, points: vec![0x100] };
+        assert!(encode(Vec::new(), &Content::AudioSeekPointIndex(too_large), tag::Id3v24, Encoding::UTF8).is_err());
+        assert!(decode("ASPI", tag::Id3v24, &b"\x00\x00\x00\x00\x00\x00\x00\x01\x00\x02\x10\x00"[..]).is_err());
+        assert!(decode("ASPI", tag::Id3v24, &b"\x00\x00\x00\x00\x00\x00\x00\x01\x00\x01\x04\x00"[..]).is_err());
+    }
+
+    #[test]
+    fn test_seek() {
+        assert!(decode("SEEK", tag::Id3v24, &[0, 1][..]).is_err());
+        let content = decode("SEEK", tag::Id3v24, &[0, 1, 0, 0][..]).unwrap().content;
+        assert_eq!(Some(0x10000), content.seek_offset());
+        let mut data = Vec::new();
+        encode(&mut data, &content, tag::Id3v24, Encoding::UTF8).unwrap();
+        assert_eq!(vec![0, 1, 0, 0], data);
+    }
+
+    #[test]
+    fn test_pcnt() {
+        for &(counter, bytes) in &[(0, &b"\x00\x00\x00\x00"[..]), (0x1234, b"\x00\x00\x12\x34"), (0x01_0000_0000, b"\x01\x00\x00\x00\x00")] {
+            let mut data = Vec::new();
+            encode(&mut data, &Content::PlayCounter(counter), tag::Id3v24, Encoding::UTF8).unwrap();
+            assert_eq!(bytes, &data[..]);
+            assert_eq!(Some(counter), decode("PCNT", tag::Id3v24, bytes).unwrap().content.play_counter());
+        }
+        let saturated = decode("PCNT", tag::Id3v23, &[1; 9][..]).unwrap().content;
+        assert_eq!(Some(u64::MAX), saturated.play_counter());
+    }
+
+    #[test]
+    fn test_ctoc() {
+        assert!(decode("CTOC", tag::Id3v24, &b"toc\x00\x03\x02chp0\x00"[..]).is_err());
+
+        let content = frame::TableOfContents {
+            element_id: "toc".to_string(),
+            top_level: true,
+            ordered: true,
+            elements: vec!["chp0".to_string(), "toc1".to_string()],
+            frames: vec![frame::Frame::with_content("TIT2", Content::Text("Book".into()))],
+        };
+        let mut data = Vec::new();
+        encode(&mut data, &Content::TableOfContents(content.clone()), tag::Id3v24, Encoding::UTF8).unwrap();
+        assert_eq!(&data[..20], b"toc\x00\x03\x02chp0\x00toc1\x00TIT2");
+
+        let decoded = decode("CTOC", tag::Id3v24, &data[..]).unwrap().content;
+        let toc = decoded.table_of_contents().unwrap();
+        assert_eq!(content, *toc);
+        assert_eq!((true, true), (toc.top_level, toc.ordered));
+        assert_eq!(content.elements, toc.elements);
+        assert_eq!(content.frames[0].content(), toc.frames[0].content());
+    }
+
+    #[test]
+    fn test_ctoc_nested() {
+        let mut data = b"toc\x00\x00\x00".to_vec();
+        for _ in 0..1000 {
+            let mut toc = b"toc\x00\x00\x00CTOC".to_vec();
+            toc.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            toc.extend_from_slice(&[0, 0]);
+            toc.extend_from_slice(&data);
+            data = toc;
+        }
+        assert!(decode("CTOC", tag::Id3v23, &data[..]).is_err());
+        // A chapter inside a table of contents is rejected as well.
+        let data = b"toc\x00\x00\x00CHAP\x00\x00\x00\x15\x00\x00chp\x00\x00\x00\x00\x00\x00\x00\x00\x01\xff\xff\xff\xff\xff\xff\xff\xff";
+        assert!(decode("CTOC", tag::Id3v23, &data[..]).is_err());
+        assert!(decode("CTOC", tag::Id3v23, &data[..6]).is_ok());
+
+        let toc = frame::TableOfContents {
+            element_id: "toc".to_string(),
+            top_level: true,
+            ordered: true,
+            elements: Vec::new(),
+            frames: Vec::new(),
+        };
+        let mut nested = toc.clone();
+        nested.frames.push(frame::Frame::with_content("CTOC", Content::TableOfContents(toc)));
+        assert!(encode(&mut Vec::new(), &Content::TableOfContents(nested), tag::Id3v24, Encoding::UTF8).is_err());
+    }
+
+    #[test]
+    fn test_encr() {
+        assert!(decode("ENCR", tag::Id3v24, &b"owner\x00"[..]).is_err());
+
+        let data = b"mailto:crypto@example.com\x00\x80key";
+        let content = decode("ENCR", tag::Id3v23, &data[..]).unwrap().content;
+        let method = content.encryption_method().unwrap();
+        assert_eq!(("mailto:crypto@example.com", 0x80, &b"key"[..]),
+                   (&method.owner_identifier[..], method.symbol, &method.data[..]));
+        let mut encoded = Vec::new();
+        encode(&mut encoded, &content, tag::Id3v23, Encoding::UTF16).unwrap();
+        assert_eq!(&data[..], &encoded[..]);
+    }
+
+    #[test]
+    fn test_grid() {
+        assert!(decode("GRID", tag::Id3v24, &[][..]).is_err());
+        assert!(decode("GRID", tag::Id3v24, &b"http://example.com\x00"[..]).is_err());
+
+        let data = b"http://example.com\x00\x80\x01\x02";
+        let content = frame::GroupIdentifier {
+            owner_identifier: "http://example.com".to_string(),
+            symbol: 0x80,
+            data: vec![1, 2],
+        };
+        let decoded = decode("GRID", tag::Id3v24, &data[..]).unwrap().content;
+        assert_eq!(Some(&content), decoded.group_identifier());
+        assert_eq!(content.data, decoded.group_identifier().unwrap().data);
+        let mut data_out = Vec::new();
+        encode(&mut data_out, &Content::GroupIdentifier(content), tag::Id3v24, Encoding::UTF8).unwrap();
+        assert_eq!(&data[..], &data_out[..]);
     }
 
     #[test]
     fn test_text() {
-        assert!(decode("TALB", &[][..]).is_err());
+        assert!(decode("TALB", tag::Id3v24, &[][..]).is_err());
 
         for text in &["", "text"] {
             for encoding in &[Encoding::Latin1, Encoding::UTF8, Encoding::UTF16, Encoding::UTF16BE] {
@@ -630,17 +1992,24 @@ mod tests {
                 data.push(*encoding as u8);
                 data.extend(bytes_for_encoding(text, *encoding).into_iter());
 
-                assert_eq!(decode("TALB", &data[..]).unwrap().content.text().unwrap(), *text);
+                assert_eq!(decode("TALB", tag::Id3v24, &data[..]).unwrap().content.text().unwrap(), *text);
                 let mut data_out = Vec::new();
-                encode(&mut data_out, &Content::Text(text.to_string()), tag::Id3v23, *encoding).unwrap();
+                encode(&mut data_out, &Content::Text(text.to_string().into()), tag::Id3v23, *encoding).unwrap();
                 assert_eq!(data, data_out);
             }
         }
     }
 
+    #[test]
+    fn test_text_latin1_non_ascii() {
+        let mut data = vec![Encoding::Latin1 as u8];
+        data.extend(b"caf\xE9\x00".iter().cloned());
+        assert_eq!(decode("TALB", tag::Id3v24, &data[..]).unwrap().content.text().unwrap(), "café");
+    }
+
     #[test]
     fn test_null_terminated_text() {
-        assert!(decode("TRCK", &[][..]).is_err());
+        assert!(decode("TRCK", tag::Id3v24, &[][..]).is_err());
         let text = "text\u{0}\u{0}";
         for encoding in &[Encoding::Latin1, Encoding::UTF8, Encoding::UTF16, Encoding::UTF16BE] {
             println!("`{}`, `{:?}`", text, encoding);
@@ -648,16 +2017,32 @@ mod tests {
             data.push(*encoding as u8);
             data.extend(bytes_for_encoding(text, *encoding).into_iter());
 
-            assert_eq!(decode("TALB", &data[..]).unwrap().content.text().unwrap(), "text");
+            assert_eq!(decode("TALB", tag::Id3v24, &data[..]).unwrap().content.text().unwrap(), "text");
             let mut data_out = Vec::new();
-            encode(&mut data_out, &Content::Text(text.to_string()), tag::Id3v23, *encoding).unwrap();
+            encode(&mut data_out, &Content::Text(text.to_string().into()), tag::Id3v23, *encoding).unwrap();
             assert_eq!(data, data_out);
         }
     }
 
+    #[test]
+    fn test_text_values() {
+        let text = "Artist 1\0\0Artist 2";
+        for encoding in &[Encoding::Latin1, Encoding::UTF8, Encoding::UTF16, Encoding::UTF16BE] {
+            let mut data = Vec::new();
+            encode(&mut data, &Content::Text(text.to_string().into()), tag::Id3v24, *encoding).unwrap();
+            let content = decode("TPE1", tag::Id3v24, &data[..]).unwrap().content;
+            assert_eq!(vec!["Artist 1", "", "Artist 2"], content.text_values().collect::<Vec<_>>());
+        }
+
+        // Every UTF-16 value has its own byte order mark, or the byte order of the value before it.
+        let data = [Encoding::UTF16 as u8, 0xff, 0xfe, b'A', 0, 0, 0, 0xfe, 0xff, 0, b'B', 0, 0, 0, b'C', 0, 0];
+        let content = decode("TPE1", tag::Id3v24, &data[..]).unwrap().content;
+        assert_eq!("A\0B\0C", content.text().unwrap());
+    }
+
     #[test]
     fn test_txxx() {
-        assert!(decode("TXXX", &[][..]).is_err());
+        assert!(decode("TXXX", tag::Id3v24, &[][..]).is_err());
 
         println!("valid");
         for key in &["", "key"] {
@@ -674,7 +2059,7 @@ mod tests {
                         description: key.to_string(),
                         value: value.to_string()
                     };
-                    assert_eq!(*decode("TXXX", &data[..]).unwrap().content.extended_text().unwrap(), content);
+                    assert_eq!(*decode("TXXX", tag::Id3v24, &data[..]).unwrap().content.extended_text().unwrap(), content);
                     let mut data_out = Vec::new();
                     encode(&mut data_out, &Content::ExtendedText(content), tag::Id3v23, *encoding).unwrap();
                     assert_eq!(data, data_out);
@@ -691,7 +2076,7 @@ mod tests {
             data.push(*encoding as u8);
             data.extend(bytes_for_encoding(key, *encoding).into_iter());
             data.extend(bytes_for_encoding(value, *encoding).into_iter());
-            assert!(decode("TXXX", &data[..]).is_err());
+            assert!(decode("TXXX", tag::Id3v24, &data[..]).is_err());
         }
     }
 
@@ -701,7 +2086,7 @@ mod tests {
             println!("`{:?}`", link);
             let data = link.as_bytes().to_vec();
 
-            assert_eq!(decode("WOAF", &data[..]).unwrap().content.link().unwrap(), *link);
+            assert_eq!(decode("WOAF", tag::Id3v24, &data[..]).unwrap().content.link().unwrap(), *link);
             let mut data_out = Vec::new();
             encode(&mut data_out, &Content::Link(link.to_string()), tag::Id3v23, Encoding::Latin1).unwrap();
             assert_eq!(data, data_out);
@@ -710,7 +2095,7 @@ mod tests {
 
     #[test]
     fn test_wxxx() {
-        assert!(decode("WXXX", &[][..]).is_err());
+        assert!(decode("WXXX", tag::Id3v24, &[][..]).is_err());
 
         println!("valid");
         for description in &["", "rust"] {
@@ -727,7 +2112,7 @@ mod tests {
                         description: description.to_string(),
                         link: link.to_string()
                     };
-                    assert_eq!(*decode("WXXX", &data[..]).unwrap().content.extended_link().unwrap(), content);
+                    assert_eq!(*decode("WXXX", tag::Id3v24, &data[..]).unwrap().content.extended_link().unwrap(), content);
                     let mut data_out = Vec::new();
                     encode(&mut data_out, &Content::ExtendedLink(content), tag::Id3v23, *encoding).unwrap();
                     assert_eq!(data, data_out);
@@ -744,13 +2129,13 @@ mod tests {
             data.push(*encoding as u8);
             data.extend(bytes_for_encoding(description, *encoding).into_iter());
             data.extend(bytes_for_encoding(link, Encoding::Latin1).into_iter());
-            assert!(decode("WXXX", &data[..]).is_err());
+            assert!(decode("WXXX", tag::Id3v24, &data[..]).is_err());
         }
     }
 
     #[test]
     fn test_uslt() {
-        assert!(decode("USLT", &[][..]).is_err());
+        assert!(decode("USLT", tag::Id3v24, &[][..]).is_err());
 
         println!("valid");
         for description in &["", "description"] {
@@ -769,7 +2154,7 @@ mod tests {
                         description: description.to_string(),
                         text: text.to_string(),
                     };
-                    assert_eq!(*decode("USLT", &data[..]).unwrap().content.lyrics().unwrap(), content);
+                    assert_eq!(*decode("USLT", tag::Id3v24, &data[..]).unwrap().content.lyrics().unwrap(), content);
                     let mut data_out = Vec::new();
                     encode(&mut data_out, &Content::Lyrics(content), tag::Id3v23, *encoding).unwrap();
                     assert_eq!(data, data_out);
@@ -787,7 +2172,7 @@ mod tests {
             data.extend(b"eng".iter().cloned());
             data.extend(bytes_for_encoding(description, *encoding).into_iter());
             data.extend(bytes_for_encoding(lyrics, *encoding).into_iter());
-            assert!(decode("USLT", &data[..]).is_err());
+            assert!(decode("USLT", tag::Id3v24, &data[..]).is_err());
         }
     }
 }
@@ -1,6 +1,12 @@
-use std::io;
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::ops::Range;
+use std::str;
+use std::sync::Arc;
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
 use flate2::read::ZlibDecoder;
 use ::frame::Content;
+use ::stream::encoding::Encoding;
 use ::stream::unsynch;
 use ::tag;
 use ::frame::Frame;
@@ -10,53 +16,317 @@ mod v3;
 mod v4;
 mod content;
 
-pub fn decode<R>(reader: &mut R, version: tag::Version, unsynchronization: bool) -> ::Result<Option<(usize, Frame)>>
+/// The header of a frame. It describes the location of a frame within a tag without requiring
+/// the content of the frame to be decoded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FrameHeader {
+    id: [u8; 4],
+    id_len: usize,
+    flags: u16,
+    offset: u64,
+    size: u32,
+}
+
+impl FrameHeader {
+    fn new(id: &[u8], flags: u16, size: u32) -> ::Result<FrameHeader> {
+        str::from_utf8(id)?;
+        let mut id_buf = [0; 4];
+        id_buf[..id.len()].copy_from_slice(id);
+        Ok(FrameHeader {
+            id: id_buf,
+            id_len: id.len(),
+            flags,
+            offset: 0,
+            size,
+        })
+    }
+
+    /// Returns the ID of the frame as it is stored in the tag. IDs of ID3v2.2 frames are 3 bytes
+    /// long.
+    pub fn id(&self) -> &str {
+        str::from_utf8(&self.id[..self.id_len]).expect("frame ID is not valid UTF-8")
+    }
+
+    /// Returns the raw flags of the frame header. The meaning of the bits depends on the version
+    /// of the tag. ID3v2.2 frames do not have flags, so this is always zero for them.
+    pub fn flags(&self) -> u16 {
+        self.flags
+    }
+
+    /// Returns the offset of the frame header relative to the start of the tag.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the size of the frame body in bytes. This does not include the frame header
+    /// itself.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+}
+
+pub fn decode_header<R>(reader: &mut R, version: tag::Version) -> ::Result<Option<FrameHeader>>
     where R: io::Read {
     match version {
-        tag::Id3v22 => v2::decode(reader, unsynchronization),
-        tag::Id3v23 => v3::decode(reader, unsynchronization),
-        tag::Id3v24 => v4::decode(reader),
+        tag::Id3v22 => v2::decode_header(reader),
+        tag::Id3v23 => v3::decode_header(reader),
+        tag::Id3v24 => v4::decode_header(reader),
     }
 }
 
-pub fn decode_content<R>(reader: R, id: &str, compression: bool, unsynchronisation: bool) -> ::Result<Content>
+/// Reads a frame header like `decode_header` and records the `offset` of the frame relative to the
+/// start of the tag.
+pub fn decode_header_at<R>(reader: &mut R, version: tag::Version, offset: u64) -> ::Result<Option<FrameHeader>>
     where R: io::Read {
-    let result = if unsynchronisation {
-        let reader_unsynch = unsynch::Reader::new(reader);
-        if compression {
-            content::decode(id, ZlibDecoder::new(reader_unsynch))
-        } else {
-            content::decode(id, reader_unsynch)
-        }
-    } else if compression {
-        content::decode(id, ZlibDecoder::new(reader))
+    let header = decode_header(reader, version)?;
+    Ok(header.map(|header| FrameHeader { offset, ..header }))
+}
+
+/// Reads the headers of all frames from the reader, skipping over the contents of the frames.
+///
+/// The `offset` is the position of the first frame relative to the start of the tag.
+pub fn decode_headers<R>(reader: &mut R, version: tag::Version, mut offset: u64) -> ::Result<Vec<FrameHeader>>
+    where R: io::Read {
+    let header_len = match version {
+        tag::Id3v22 => 6,
+        tag::Id3v23 | tag::Id3v24 => 10,
+    };
+    let mut headers = Vec::new();
+    while let Some(mut header) = decode_header(reader, version)? {
+        header.offset = offset;
+        io::copy(&mut reader.take(u64::from(header.size)), &mut io::sink())?;
+        offset += header_len + u64::from(header.size);
+        headers.push(header);
+    }
+    Ok(headers)
+}
+
+/// Decrypts the content of encrypted frames. A decryptor is registered with a `Decoder` for the
+/// encryption method symbol that is registered by an ENCR frame in the tag.
+///
+/// Closures with a matching signature implement this trait.
+pub trait FrameDecryptor {
+    /// Decrypts the data of the frame described by the header. The returned data is decoded like
+    /// the content of an unencrypted frame and is decompressed first if the frame is compressed.
+    fn decrypt(&self, header: &FrameHeader, data: &[u8]) -> ::Result<Vec<u8>>;
+}
+
+impl<F> FrameDecryptor for F
+    where F: Fn(&FrameHeader, &[u8]) -> ::Result<Vec<u8>> {
+    fn decrypt(&self, header: &FrameHeader, data: &[u8]) -> ::Result<Vec<u8>> {
+        self(header, data)
+    }
+}
+
+/// The registered decryptors by encryption method symbol.
+pub type Decryptors = HashMap<u8, Box<dyn FrameDecryptor>>;
+
+/// Decodes a frame. The unsynchronisation flag of the tag only affects ID3v2.4 frames, ID3v2.2 and
+/// ID3v2.3 tags must be synchronised as a whole before their frames are decoded.
+///
+/// If `lazy` is set, the content of unencrypted frames is decoded when it is first accessed.
+pub fn decode<R>(reader: &mut R, version: tag::Version, unsynchronization: bool, decryptors: &Decryptors, lazy: bool) -> ::Result<Option<(usize, Frame)>>
+    where R: io::Read {
+    match version {
+        tag::Id3v22 => v2::decode(reader, lazy),
+        tag::Id3v23 => v3::decode(reader, decryptors, lazy),
+        tag::Id3v24 => v4::decode(reader, unsynchronization, decryptors, lazy),
+    }
+}
+
+/// Decodes the data of an encrypted frame using the decryptor that is registered for the method.
+///
+/// If there is no decryptor, the encrypted data is preserved as unknown content so the frame can
+/// be written back as is. This is not possible for compressed frames because the size of the
+/// decompressed data is lost.
+fn decode_encrypted(header: &FrameHeader, version: tag::Version, method: u8, data: Vec<u8>, compression: bool, decryptors: &Decryptors) -> ::Result<Frame> {
+    match decryptors.get(&method) {
+        Some(decryptor) => {
+            let data = decryptor.decrypt(header, &data)?;
+            let content = decode_content(&data[..], version, header.id(), compression, false)?;
+            Ok(Frame::with_content(header.id(), content))
+        },
+        None if compression => {
+            Err(::Error::new(::ErrorKind::UnsupportedFeature, "no decryptor is registered for the compressed frame"))
+        },
+        None => {
+            let mut frame = Frame::with_content(header.id(), Content::Unknown(data));
+            frame.set_encryption_method(Some(method));
+            Ok(frame)
+        },
+    }
+}
+
+pub fn decode_content<R>(reader: R, version: tag::Version, id: &str, compression: bool, unsynchronisation: bool) -> ::Result<Content>
+    where R: io::Read {
+    let data = read_content(reader, compression, unsynchronisation)?;
+    Ok(content::decode(id, version, &data[..])?.content)
+}
+
+/// Decodes the content of a frame like `decode_content` and creates the frame. If `lazy` is set,
+/// the content is only synchronised and decompressed, it is decoded when it is first accessed.
+fn decode_frame<R>(reader: R, version: tag::Version, id: &str, compression: bool, unsynchronisation: bool, lazy: bool) -> ::Result<Frame>
+    where R: io::Read {
+    let data = read_content(reader, compression, unsynchronisation)?;
+    if lazy {
+        Ok(Frame::with_encoded_content(id, version, data))
+    } else {
+        Ok(Frame::with_content(id, content::decode(id, version, &data[..])?.content))
+    }
+}
+
+/// Creates a lazily decoded frame whose data is the `range` of the tag buffer following the
+/// frame header, without copying it. Returns `None` for frames of which the data has to be
+/// synchronised, decompressed or decrypted first, those are decoded with `decode`.
+pub fn decode_shared(header: &FrameHeader, version: tag::Version, unsynchronisation: bool, buffer: &Arc<Vec<u8>>, range: Range<usize>) -> Option<Frame> {
+    let data = buffer.get(range.clone())?;
+    let (grouped, data_length) = match version {
+        tag::Id3v22 => (false, false),
+        tag::Id3v23 => {
+            let flags = v3::Flags::from_bits(header.flags())?;
+            if flags.intersects(v3::Flags::COMPRESSION | v3::Flags::ENCRYPTION) {
+                return None;
+            }
+            (flags.contains(v3::Flags::GROUPING_IDENTITY), false)
+        },
+        tag::Id3v24 => {
+            let flags = v4::Flags::from_bits(header.flags())?;
+            if flags.intersects(v4::Flags::COMPRESSION | v4::Flags::ENCRYPTION) {
+                return None;
+            }
+            // Unsynchronisation only changes data that contains a false synchronisation.
+            let unsynchronisation = unsynchronisation || flags.contains(v4::Flags::UNSYNCHRONISATION);
+            if unsynchronisation && data.windows(2).any(|pair| pair == [0xff, 0x00]) {
+                return None;
+            }
+            (flags.contains(v4::Flags::GROUPING_IDENTITY), flags.contains(v4::Flags::DATA_LENGTH_INDICATOR))
+        },
+    };
+    let mut start = range.start;
+    let group_identifier = if grouped {
+        start += 1;
+        Some(*data.first()?)
     } else {
-        content::decode(id, reader)
+        None
+    };
+    if data_length {
+        let indicator = buffer.get(start..start + 4)?;
+        start += 4;
+        if unsynch::decode_u32(BigEndian::read_u32(indicator)) as usize != range.end.checked_sub(start)? {
+            return None;
+        }
+    }
+    if start > range.end {
+        return None;
+    }
+    let mut frame = Frame::with_shared_content(header.id(), version, buffer, start..range.end);
+    frame.set_group_identifier(group_identifier);
+    Some(frame)
+}
+
+/// Reads the data of a frame, undoing the unsynchronisation and compression.
+fn read_content<R>(mut reader: R, compression: bool, unsynchronisation: bool) -> ::Result<Vec<u8>>
+    where R: io::Read {
+    let mut data = Vec::new();
+    match (unsynchronisation, compression) {
+        (true, true) => ZlibDecoder::new(unsynch::Reader::new(reader)).read_to_end(&mut data)?,
+        (true, false) => unsynch::Reader::new(reader).read_to_end(&mut data)?,
+        (false, true) => ZlibDecoder::new(reader).read_to_end(&mut data)?,
+        (false, false) => reader.read_to_end(&mut data)?,
     };
-    Ok(result?.content)
+    Ok(data)
 }
 
+/// Encodes the content of a frame as it would be stored in an ID3v2.4 frame with UTF-8 text.
+pub fn encode_content(content: &Content) -> ::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    content::encode(&mut data, content, tag::Id3v24, Encoding::UTF8)?;
+    Ok(data)
+}
 
-pub fn encode<W>(writer: &mut W, frame: &Frame, version: tag::Version, unsynchronization: bool) -> ::Result<usize>
+/// Encodes a frame. Unsynchronisation is only applied to ID3v2.4 frames, ID3v2.2 and ID3v2.3 tags
+/// must be unsynchronised as a whole after their frames are encoded. ID3v2.2 frames can not be
+/// compressed.
+pub fn encode<W>(writer: &mut W, frame: &Frame, version: tag::Version, unsynchronization: bool, compression: bool) -> ::Result<usize>
     where W: io::Write {
     match version {
-        tag::Id3v22 => v2::encode(writer, frame, unsynchronization),
+        tag::Id3v22 => v2::encode(writer, frame),
         tag::Id3v23 => {
             let mut flags = v3::Flags::empty();
             flags.set(v3::Flags::TAG_ALTER_PRESERVATION, frame.tag_alter_preservation());
             flags.set(v3::Flags::FILE_ALTER_PRESERVATION, frame.file_alter_preservation());
-            v3::encode(writer, frame, v3::Flags::empty(), unsynchronization)
+            flags.set(v3::Flags::ENCRYPTION, frame.encryption_method().is_some());
+            flags.set(v3::Flags::GROUPING_IDENTITY, frame.group_identifier().is_some());
+            flags.set(v3::Flags::COMPRESSION, compression);
+            v3::encode(writer, frame, flags)
         },
         tag::Id3v24 => {
             let mut flags = v4::Flags::empty();
             flags.set(v4::Flags::UNSYNCHRONISATION, unsynchronization);
             flags.set(v4::Flags::TAG_ALTER_PRESERVATION, frame.tag_alter_preservation());
             flags.set(v4::Flags::FILE_ALTER_PRESERVATION, frame.file_alter_preservation());
+            flags.set(v4::Flags::ENCRYPTION, frame.encryption_method().is_some());
+            flags.set(v4::Flags::GROUPING_IDENTITY, frame.group_identifier().is_some());
+            flags.set(v4::Flags::COMPRESSION, compression);
             v4::encode(writer, frame, flags)
         },
     }
 }
 
+/// Encodes the header and the fields of a general encapsulated object frame (GEOB) that holds
+/// `data_len` bytes of data. The data itself is not included, it must be written directly after
+/// the returned bytes.
+pub fn encode_geob_prefix(version: tag::Version, mime_type: &str, filename: &str, description: &str, data_len: u64) -> ::Result<Vec<u8>> {
+    let mut fields = Vec::new();
+    match version {
+        tag::Id3v22 | tag::Id3v23 => fields.push(Encoding::UTF16 as u8),
+        tag::Id3v24 => fields.push(Encoding::UTF8 as u8),
+    }
+    fields.extend(::util::string_to_latin1(mime_type));
+    fields.push(0);
+    for text in &[filename, description] {
+        match version {
+            tag::Id3v22 | tag::Id3v23 => {
+                fields.extend(::util::string_to_utf16(text));
+                fields.extend_from_slice(&[0, 0]);
+            },
+            tag::Id3v24 => {
+                fields.extend_from_slice(text.as_bytes());
+                fields.push(0);
+            },
+        }
+    }
+
+    let size = fields.len() as u64 + data_len;
+    let max_size = match version {
+        tag::Id3v22 => 0xff_ffff,
+        tag::Id3v23 => 0xffff_ffff,
+        tag::Id3v24 => 0x0fff_ffff,
+    };
+    if size > max_size {
+        return Err(::Error::new(::ErrorKind::InvalidInput, "encapsulated object is too large for a frame"));
+    }
+    let mut prefix = Vec::with_capacity(10 + fields.len());
+    match version {
+        tag::Id3v22 => {
+            prefix.extend_from_slice(b"GEO");
+            prefix.extend_from_slice(&[(size >> 16) as u8, (size >> 8) as u8, size as u8]);
+        },
+        tag::Id3v23 => {
+            prefix.extend_from_slice(b"GEOB");
+            prefix.write_u32::<BigEndian>(size as u32)?;
+            prefix.extend_from_slice(&[0, 0]);
+        },
+        tag::Id3v24 => {
+            prefix.extend_from_slice(b"GEOB");
+            prefix.write_u32::<BigEndian>(unsynch::encode_u32(size as u32))?;
+            prefix.extend_from_slice(&[0, 0]);
+        },
+    }
+    prefix.extend(fields);
+    Ok(prefix)
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -64,6 +334,7 @@ mod tests {
     use frame::Frame;
     use ::stream::encoding::Encoding;
     use ::stream::unsynch;
+    use byteorder::ByteOrder;
 
     fn u32_to_bytes(n: u32) -> Vec<u8> {
         vec!(((n & 0xFF00_0000) >> 24) as u8,
@@ -83,7 +354,7 @@ mod tests {
         data.push(encoding as u8);
         data.extend(::util::string_to_utf16(text).into_iter());
 
-        let content = decode_content(&data[..], id, false, false).unwrap();
+        let content = decode_content(&data[..], tag::Id3v24, id, false, false).unwrap();
         let frame = Frame::with_content(id, content);
 
         let mut bytes = Vec::new();
@@ -92,7 +363,7 @@ mod tests {
         bytes.extend(data.into_iter());
 
         let mut writer = Vec::new();
-        encode(&mut writer, &frame, tag::Id3v22, false).unwrap();
+        encode(&mut writer, &frame, tag::Id3v22, false, false).unwrap();
         assert_eq!(writer, bytes);
     }
 
@@ -106,7 +377,7 @@ mod tests {
         data.push(encoding as u8);
         data.extend(::util::string_to_utf16(text).into_iter());
 
-        let content = decode_content(&data[..], id, false, false).unwrap();
+        let content = decode_content(&data[..], tag::Id3v24, id, false, false).unwrap();
         let frame = Frame::with_content(id, content);
 
         let mut bytes = Vec::new();
@@ -116,7 +387,7 @@ mod tests {
         bytes.extend(data.into_iter());
 
         let mut writer = Vec::new();
-        encode(&mut writer, &frame, tag::Id3v23, false).unwrap();
+        encode(&mut writer, &frame, tag::Id3v23, false, false).unwrap();
         assert_eq!(writer, bytes);
     }
 
@@ -130,7 +401,7 @@ mod tests {
         data.push(encoding as u8);
         data.extend(text.bytes());
 
-        let content = decode_content(&data[..], id, false, false).unwrap();
+        let content = decode_content(&data[..], tag::Id3v24, id, false, false).unwrap();
         let mut frame = Frame::with_content(id, content);
         frame.set_tag_alter_preservation(true);
         frame.set_file_alter_preservation(true);
@@ -142,7 +413,23 @@ mod tests {
         bytes.extend(data.into_iter());
 
         let mut writer = Vec::new();
-        encode(&mut writer, &frame, tag::Id3v24, false).unwrap();
+        encode(&mut writer, &frame, tag::Id3v24, false, false).unwrap();
         assert_eq!(writer, bytes);
     }
+
+    #[test]
+    fn test_data_length_indicator_v4() {
+        let frame = Frame::with_content("TALB", Content::Text("album".into()));
+        for &(unsynchronisation, compression) in &[(true, false), (false, true), (true, true)] {
+            let mut writer = Vec::new();
+            encode(&mut writer, &frame, tag::Id3v24, unsynchronisation, compression).unwrap();
+            assert_eq!(0x0001, writer[9] & 0x0001);
+            assert_eq!(unsynch::encode_u32(6), BigEndian::read_u32(&writer[10..14]));
+            let (_, decoded) = decode(&mut &writer[..], tag::Id3v24, false, &Decryptors::new(), false).unwrap().unwrap();
+            assert_eq!(frame, decoded);
+
+            writer[13] += 1;
+            assert!(decode(&mut &writer[..], tag::Id3v24, false, &Decryptors::new(), false).is_err());
+        }
+    }
 }
@@ -1,12 +1,10 @@
 use std::io::{self, Read, Write};
-use std::str;
 use byteorder::{BigEndian, ByteOrder, ReadBytesExt, WriteBytesExt};
 use flate2::Compression;
 use flate2::write::ZlibEncoder;
 use frame::Frame;
 use ::stream::encoding::Encoding;
-use ::stream::frame;
-use ::stream::unsynch;
+use ::stream::frame::{self, FrameHeader};
 use ::tag;
 
 
@@ -22,37 +20,66 @@ bitflags! {
 }
 
 
-pub fn decode<R>(reader: &mut R, unsynchronisation: bool) -> ::Result<Option<(usize, Frame)>>
+pub fn decode_header<R>(reader: &mut R) -> ::Result<Option<FrameHeader>>
     where R: io::Read {
     let mut frame_header = [0; 10];
-    let nread = reader.read(&mut frame_header)?;
+    let nread = ::util::read_fully(&mut *reader, &mut frame_header)?;
     if nread < frame_header.len() || frame_header[0] == 0x00 {
         return Ok(None);
     }
-    let id = str::from_utf8(&frame_header[0..4])?;
+    let size = BigEndian::read_u32(&frame_header[4..8]);
+    let flags = BigEndian::read_u16(&frame_header[8..10]);
+    Ok(Some(FrameHeader::new(&frame_header[0..4], flags, size)?))
+}
 
-    let content_size = BigEndian::read_u32(&frame_header[4..8]) as usize;
-    let flags = Flags::from_bits(BigEndian::read_u16(&frame_header[8..10]))
+pub fn decode<R>(reader: &mut R, decryptors: &frame::Decryptors, lazy: bool) -> ::Result<Option<(usize, Frame)>>
+    where R: io::Read {
+    let header = match decode_header(reader)? {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+    let id = header.id();
+    let content_size = header.size() as usize;
+    let flags = Flags::from_bits(header.flags())
         .ok_or_else(|| ::Error::new(::ErrorKind::Parsing, "unknown frame header flags are set"))?;
-    if flags.contains(Flags::ENCRYPTION) {
-        return Err(::Error::new(::ErrorKind::UnsupportedFeature, "encryption is not supported"));
-    } else if flags.contains(Flags::GROUPING_IDENTITY) {
-        return Err(::Error::new(::ErrorKind::UnsupportedFeature, "grouping identity is not supported"));
-    }
 
-    let read_size = if flags.contains(Flags::COMPRESSION) {
+    let mut read_size = content_size;
+    if flags.contains(Flags::COMPRESSION) {
         let _decompressed_size = reader.read_u32::<BigEndian>()?;
-        content_size - 4
+        read_size = read_size.checked_sub(4)
+            .ok_or_else(|| ::Error::new(::ErrorKind::Parsing, "compressed frame is too small"))?;
+    }
+    let encryption_method = if flags.contains(Flags::ENCRYPTION) {
+        read_size = read_size.checked_sub(1)
+            .ok_or_else(|| ::Error::new(::ErrorKind::Parsing, "encrypted frame is too small"))?;
+        Some(reader.read_u8()?)
     } else {
-        content_size
+        None
     };
-    let content = super::decode_content(reader.take(read_size as u64), id, flags.contains(Flags::COMPRESSION), unsynchronisation)?;
-    let frame = Frame::with_content(id, content);
+    let group_identifier = if flags.contains(Flags::GROUPING_IDENTITY) {
+        read_size = read_size.checked_sub(1)
+            .ok_or_else(|| ::Error::new(::ErrorKind::Parsing, "grouped frame is too small"))?;
+        Some(reader.read_u8()?)
+    } else {
+        None
+    };
+
+    let mut frame = match encryption_method {
+        Some(method) => {
+            let mut data = vec![0; read_size];
+            reader.read_exact(&mut data)?;
+            frame::decode_encrypted(&header, tag::Id3v23, method, data, flags.contains(Flags::COMPRESSION), decryptors)?
+        },
+        None => {
+            super::decode_frame(reader.take(read_size as u64), tag::Id3v23, id, flags.contains(Flags::COMPRESSION), false, lazy)?
+        },
+    };
+    frame.set_group_identifier(group_identifier);
     Ok(Some((10 + content_size, frame)))
 }
 
-pub fn encode(writer: &mut Write, frame: &Frame, flags: Flags, unsynchronization: bool) -> ::Result<usize> {
-    let (mut content_buf, comp_hint_delta, decompressed_size) = if flags.contains(Flags::COMPRESSION) {
+pub fn encode(writer: &mut Write, frame: &Frame, flags: Flags) -> ::Result<usize> {
+    let (content_buf, comp_hint_delta, decompressed_size) = if flags.contains(Flags::COMPRESSION) {
         let mut encoder = ZlibEncoder::new(Vec::new(), Compression::Default);
         let content_size = frame::content::encode(&mut encoder, frame.content(), tag::Id3v23, Encoding::UTF16)?;
         let content_buf = encoder.finish()?;
@@ -63,15 +90,21 @@ pub fn encode(writer: &mut Write, frame: &Frame, flags: Flags, unsynchronization
         (content_buf, 0, None)
     };
 
+    let encryption_delta = if frame.encryption_method().is_some() { 1 } else { 0 };
+    let group_delta = if frame.group_identifier().is_some() { 1 } else { 0 };
+
     writer.write_all(frame.id().as_bytes())?;
-    writer.write_u32::<BigEndian>((content_buf.len() + comp_hint_delta) as u32)?;
+    writer.write_u32::<BigEndian>((content_buf.len() + comp_hint_delta + encryption_delta + group_delta) as u32)?;
     writer.write_u16::<BigEndian>(flags.bits())?;
     if let Some(s) = decompressed_size {
         writer.write_u32::<BigEndian>(s as u32)?;
     }
-    if unsynchronization {
-        unsynch::encode_vec(&mut content_buf);
+    if let Some(method) = frame.encryption_method() {
+        writer.write_u8(method)?;
+    }
+    if let Some(symbol) = frame.group_identifier() {
+        writer.write_u8(symbol)?;
     }
     writer.write_all(&content_buf)?;
-    Ok(10 + comp_hint_delta + content_buf.len())
+    Ok(10 + comp_hint_delta + encryption_delta + group_delta + content_buf.len())
 }
@@ -1,11 +1,11 @@
 use std::io::{self, Read, Write};
-use std::str;
 use byteorder::{BigEndian, ByteOrder, ReadBytesExt, WriteBytesExt};
 use flate2::Compression;
+use flate2::read::ZlibDecoder;
 use flate2::write::ZlibEncoder;
 use ::frame::Frame;
 use ::stream::encoding::Encoding;
-use ::stream::frame;
+use ::stream::frame::{self, FrameHeader};
 use ::stream::unsynch;
 use ::tag;
 
@@ -24,63 +24,133 @@ bitflags! {
 }
 
 
-pub fn decode<R>(reader: &mut R) -> ::Result<Option<(usize, Frame)>>
+pub fn decode_header<R>(reader: &mut R) -> ::Result<Option<FrameHeader>>
     where R: io::Read {
     let mut frame_header = [0; 10];
-    let nread = reader.read(&mut frame_header)?;
+    let nread = ::util::read_fully(&mut *reader, &mut frame_header)?;
     if nread < frame_header.len() || frame_header[0] == 0x00 {
         return Ok(None);
     }
-    let id = str::from_utf8(&frame_header[0..4])?;
-    let content_size = BigEndian::read_u32(&frame_header[4..8]) as usize;
-    let flags = Flags::from_bits(BigEndian::read_u16(&frame_header[8..10]))
+    let size = unsynch::decode_u32(BigEndian::read_u32(&frame_header[4..8]));
+    let flags = BigEndian::read_u16(&frame_header[8..10]);
+    Ok(Some(FrameHeader::new(&frame_header[0..4], flags, size)?))
+}
+
+/// Decodes a frame. In ID3v2.4, the unsynchronisation flag of the tag header indicates that all
+/// frames are unsynchronised, regardless of the flags of the frame itself.
+pub fn decode<R>(reader: &mut R, unsynchronisation: bool, decryptors: &frame::Decryptors, lazy: bool) -> ::Result<Option<(usize, Frame)>>
+    where R: io::Read {
+    let header = match decode_header(reader)? {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+    let id = header.id();
+    let content_size = header.size() as usize;
+    let flags = Flags::from_bits(header.flags())
         .ok_or_else(|| ::Error::new(::ErrorKind::Parsing, "unknown frame header flags are set"))?;
-    if flags.contains(Flags::ENCRYPTION) {
-        return Err(::Error::new(::ErrorKind::UnsupportedFeature, "encryption is not supported"));
-    } else if flags.contains(Flags::GROUPING_IDENTITY) {
-        return Err(::Error::new(::ErrorKind::UnsupportedFeature, "grouping identity is not supported"));
-    }
 
-    let read_size = if flags.contains(Flags::DATA_LENGTH_INDICATOR) {
-        let _decompressed_size = unsynch::decode_u32(reader.read_u32::<BigEndian>()?);
-        content_size - 4
+    let unsynchronisation = unsynchronisation || flags.contains(Flags::UNSYNCHRONISATION);
+    let mut read_size = content_size;
+    let group_identifier = if flags.contains(Flags::GROUPING_IDENTITY) {
+        read_size = read_size.checked_sub(1)
+            .ok_or_else(|| ::Error::new(::ErrorKind::Parsing, "grouped frame is too small"))?;
+        Some(reader.read_u8()?)
     } else {
-        content_size
+        None
+    };
+    let encryption_method = if flags.contains(Flags::ENCRYPTION) {
+        read_size = read_size.checked_sub(1)
+            .ok_or_else(|| ::Error::new(::ErrorKind::Parsing, "encrypted frame is too small"))?;
+        Some(reader.read_u8()?)
+    } else {
+        None
+    };
+    let data_length = if flags.contains(Flags::DATA_LENGTH_INDICATOR) {
+        read_size = read_size.checked_sub(4)
+            .ok_or_else(|| ::Error::new(::ErrorKind::Parsing, "frame is too small for its data length indicator"))?;
+        Some(unsynch::decode_u32(reader.read_u32::<BigEndian>()?) as usize)
+    } else {
+        None
     };
 
-    let content = super::decode_content(reader.take(read_size as u64), id, flags.contains(Flags::COMPRESSION), flags.contains(Flags::UNSYNCHRONISATION))?;
-    let frame = Frame::with_content(id, content);
+    let mut frame = match encryption_method {
+        Some(method) => {
+            let mut data = vec![0; read_size];
+            reader.read_exact(&mut data)?;
+            if unsynchronisation {
+                unsynch::decode_vec(&mut data);
+            }
+            frame::decode_encrypted(&header, tag::Id3v24, method, data, flags.contains(Flags::COMPRESSION), decryptors)?
+        },
+        // The data length indicator is checked against the size of the data after it has been
+        // synchronised and decompressed, which catches corrupted frames early.
+        None if data_length.is_some() => {
+            let mut data = vec![0; read_size];
+            reader.read_exact(&mut data)?;
+            if unsynchronisation {
+                unsynch::decode_vec(&mut data);
+            }
+            if flags.contains(Flags::COMPRESSION) {
+                let mut decompressed = Vec::with_capacity(data_length.unwrap());
+                ZlibDecoder::new(&data[..]).read_to_end(&mut decompressed)?;
+                data = decompressed;
+            }
+            if Some(data.len()) != data_length {
+                return Err(::Error::new(::ErrorKind::Parsing, "frame data does not match its data length indicator"));
+            }
+            super::decode_frame(&data[..], tag::Id3v24, id, false, false, lazy)?
+        },
+        None => {
+            super::decode_frame(reader.take(read_size as u64), tag::Id3v24, id, flags.contains(Flags::COMPRESSION), unsynchronisation, lazy)?
+        },
+    };
+    frame.set_group_identifier(group_identifier);
     Ok(Some((10 + content_size, frame)))
 }
 
-pub fn encode(writer: &mut Write, frame: &Frame, flags: Flags) -> ::Result<usize> {
-    let (mut content_buf, comp_hint_delta, decompressed_size) = if flags.contains(Flags::COMPRESSION) {
+pub fn encode(writer: &mut Write, frame: &Frame, mut flags: Flags) -> ::Result<usize> {
+    let (mut content_buf, decompressed_size) = if flags.contains(Flags::COMPRESSION) {
         let mut encoder = ZlibEncoder::new(Vec::new(), Compression::Default);
         let content_size = frame::content::encode(&mut encoder, frame.content(), tag::Id3v24, Encoding::UTF8)?;
         let content_buf = encoder.finish()?;
-        let cd = if flags.contains(Flags::DATA_LENGTH_INDICATOR) {
-            4
-        } else {
-            0
-        };
-        (content_buf, cd, Some(content_size))
+        (content_buf, Some(content_size))
     } else {
         let mut content_buf = Vec::new();
         frame::content::encode(&mut content_buf, frame.content(), tag::Id3v24, Encoding::UTF8)?;
-        (content_buf, 0, None)
+        (content_buf, None)
     };
 
-    writer.write_all(frame.id().as_bytes())?;
-    writer.write_u32::<BigEndian>(unsynch::encode_u32((content_buf.len() + comp_hint_delta) as u32))?;
-    writer.write_u16::<BigEndian>(flags.bits())?;
-    if let Some(s) = decompressed_size {
-        if flags.contains(Flags::DATA_LENGTH_INDICATOR) {
-            writer.write_u32::<BigEndian>(unsynch::encode_u32(s as u32))?;
-        }
+    // Compressed and unsynchronised frames always get a data length indicator, so readers know
+    // the size of the frame before they undo the compression or unsynchronisation.
+    if flags.intersects(Flags::COMPRESSION | Flags::UNSYNCHRONISATION) {
+        flags.insert(Flags::DATA_LENGTH_INDICATOR);
     }
+    let data_length = if flags.contains(Flags::DATA_LENGTH_INDICATOR) {
+        Some(decompressed_size.unwrap_or(content_buf.len()))
+    } else {
+        None
+    };
+    // The frame size includes the bytes that are inserted by the unsynchronisation.
     if flags.contains(Flags::UNSYNCHRONISATION) {
         unsynch::encode_vec(&mut content_buf);
     }
+    let comp_hint_delta = if data_length.is_some() { 4 } else { 0 };
+
+    let encryption_delta = if frame.encryption_method().is_some() { 1 } else { 0 };
+    let group_delta = if frame.group_identifier().is_some() { 1 } else { 0 };
+
+    writer.write_all(frame.id().as_bytes())?;
+    writer.write_u32::<BigEndian>(unsynch::encode_u32((content_buf.len() + comp_hint_delta + encryption_delta + group_delta) as u32))?;
+    writer.write_u16::<BigEndian>(flags.bits())?;
+    if let Some(symbol) = frame.group_identifier() {
+        writer.write_u8(symbol)?;
+    }
+    if let Some(method) = frame.encryption_method() {
+        writer.write_u8(method)?;
+    }
+    if let Some(s) = data_length {
+        writer.write_u32::<BigEndian>(unsynch::encode_u32(s as u32))?;
+    }
     writer.write_all(&content_buf)?;
-    Ok(10 + comp_hint_delta + content_buf.len())
+    Ok(10 + comp_hint_delta + encryption_delta + group_delta + content_buf.len())
 }
@@ -1,9 +1,18 @@
 use std::cmp;
-use std::io::{self, Read};
-use byteorder::{BigEndian, ByteOrder, ReadBytesExt, WriteBytesExt};
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Seek};
+use std::ops;
+use std::path::Path;
+use std::sync::Arc;
+use byteorder::{BigEndian, ByteOrder, ReadBytesExt};
+use flate2::Crc;
+use ::container;
+use ::frame::{Comment, Content, Frame, Lyrics, Picture};
+use ::storage;
 use ::stream::frame;
 use ::stream::unsynch;
-use ::tag::{Tag, Version};
+use ::tag::{self, GenreStyle, ScrubProfile, Tag, Version};
 
 
 static DEFAULT_FILE_DISCARD: &[&str] = &[
@@ -12,7 +21,6 @@ static DEFAULT_FILE_DISCARD: &[&str] = &[
     "EQUA",
     "MLLT",
     "POSS",
-    "SYLT",
     "SYTC",
     "RVAD",
     "TENC",
@@ -32,10 +40,100 @@ bitflags! {
 }
 
 
-pub fn decode<R>(mut reader: R) -> ::Result<Tag>
+/// The fields of a tag header.
+struct Header {
+    version: Version,
+    flags: Flags,
+    tag_size: usize,
+}
+
+/// The header of a tag, see `Tag::peek_header`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TagHeader {
+    version: Version,
+    revision: u8,
+    flags: u8,
+    size: u32,
+}
+
+impl TagHeader {
+    /// Returns the version of the tag.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Returns the revision number of the version of the tag, which is usually zero.
+    pub fn revision(&self) -> u8 {
+        self.revision
+    }
+
+    /// Returns the raw flags of the tag header. The meaning of the bits depends on the version of
+    /// the tag.
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+
+    /// Returns whether the tag is unsynchronised as a whole.
+    pub fn unsynchronisation(&self) -> bool {
+        self.flags & Flags::UNSYNCHRONISATION.bits() != 0
+    }
+
+    /// Returns whether an extended header follows the header. Always false for ID3v2.2 tags.
+    pub fn extended_header(&self) -> bool {
+        self.version != Version::Id3v22 && self.flags & Flags::EXTENDED_HEADER.bits() != 0
+    }
+
+    /// Returns whether the tag is marked as experimental. Always false for ID3v2.2 tags.
+    pub fn experimental(&self) -> bool {
+        self.version != Version::Id3v22 && self.flags & Flags::EXPERIMENTAL.bits() != 0
+    }
+
+    /// Returns whether the tag is followed by a footer. Only ID3v2.4 tags can have a footer.
+    pub fn footer(&self) -> bool {
+        self.version == Version::Id3v24 && self.flags & Flags::FOOTER.bits() != 0
+    }
+
+    /// Returns the size of the tag as stored in the header. This includes the extended header,
+    /// the frames and the padding, but not the header and footer.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Returns the total size of the tag in bytes including its header and footer. Padding after
+    /// the tag that is not included in its size is not taken into account.
+    pub fn total_size(&self) -> u64 {
+        10 + u64::from(self.size) + if self.footer() { 10 } else { 0 }
+    }
+}
+
+/// Reads the tag header at the current position of the reader, see `Tag::peek_header`.
+pub fn peek_header<R>(mut reader: R) -> ::Result<Option<TagHeader>>
+    where R: io::Read + io::Seek {
+    let start = reader.stream_position()?;
+    let mut tag_header = [0; 10];
+    let nread = ::util::read_fully(&mut reader, &mut tag_header)?;
+    reader.seek(io::SeekFrom::Start(start))?;
+    if nread < tag_header.len() || &tag_header[0..3] != b"ID3" {
+        return Ok(None);
+    }
+    let version = match tag_header[3] {
+        2 => Version::Id3v22,
+        3 => Version::Id3v23,
+        4 => Version::Id3v24,
+        _ => return Err(::Error::new(::ErrorKind::UnsupportedVersion(tag_header[4], tag_header[3]), "unsupported id3 tag version")),
+    };
+    Ok(Some(TagHeader {
+        version,
+        revision: tag_header[4],
+        flags: tag_header[5],
+        size: unsynch::decode_u32(BigEndian::read_u32(&tag_header[6..10])),
+    }))
+}
+
+fn decode_header<R>(reader: &mut R) -> ::Result<Header>
     where R: io::Read {
     let mut tag_header = [0; 10];
-    let nread = reader.read(&mut tag_header)?;
+    let nread = ::util::read_fully(&mut *reader, &mut tag_header)?;
     if nread < tag_header.len() || &tag_header[0..3] != b"ID3" {
         return Err(::Error::new(::ErrorKind::NoTag, "reader does not contain an id3 tag"));
     }
@@ -52,81 +150,1370 @@ pub fn decode<R>(mut reader: R) -> ::Result<Tag>
         .ok_or_else(|| ::Error::new(::ErrorKind::Parsing, "unknown tag header flags are set"))?;
     let tag_size = unsynch::decode_u32(BigEndian::read_u32(&tag_header[6..10])) as usize;
 
-    if flags.contains(Flags::COMPRESSION) {
+    if version == Version::Id3v22 && flags.contains(Flags::COMPRESSION) {
         return Err(::Error::new(::ErrorKind::UnsupportedFeature, "id3v2.2 compression is not supported"));
     }
+    Ok(Header { version, flags, tag_size })
+}
+
+/// The fields of the extended header of an ID3v2.3 or ID3v2.4 tag.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ExtendedHeader {
+    /// Whether the tag is an update of a tag found earlier in the stream. Only used by ID3v2.4.
+    pub update: bool,
+    /// The CRC-32 of the frame data, see `Tag::crc_valid`.
+    pub crc: Option<u32>,
+    /// The restrictions that the tag was written within. Only used by ID3v2.4.
+    pub restrictions: Option<Restrictions>,
+}
+
+/// The limits that an ID3v2.4 tag was written within, as stored in its extended header. The
+/// restrictions are informational, they are not enforced when reading or writing.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Restrictions {
+    /// The maximum number of frames and size of the tag.
+    pub tag_size: TagSizeRestriction,
+    /// Whether text is only encoded as ISO-8859-1 or UTF-8.
+    pub text_encoding: bool,
+    /// The maximum length of text fields.
+    pub text_fields_size: TextSizeRestriction,
+    /// Whether images are only encoded as PNG or JPEG.
+    pub image_encoding: bool,
+    /// The maximum dimensions of images.
+    pub image_size: ImageSizeRestriction,
+}
+
+/// The maximum number of frames and size of a tag.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TagSizeRestriction {
+    /// No more than 128 frames and 1 MB.
+    Frames128Size1M,
+    /// No more than 64 frames and 128 KB.
+    Frames64Size128K,
+    /// No more than 32 frames and 40 KB.
+    Frames32Size40K,
+    /// No more than 32 frames and 4 KB.
+    Frames32Size4K,
+}
+
+/// The maximum length of text fields.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TextSizeRestriction {
+    /// Text fields are not restricted.
+    Unrestricted,
+    /// No more than 1024 characters.
+    Chars1024,
+    /// No more than 128 characters.
+    Chars128,
+    /// No more than 30 characters.
+    Chars30,
+}
+
+/// The maximum dimensions of images.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ImageSizeRestriction {
+    /// Images are not restricted.
+    Unrestricted,
+    /// No larger than 256x256 pixels.
+    Max256,
+    /// No larger than 64x64 pixels.
+    Max64,
+    /// Exactly 64x64 pixels, unless required otherwise.
+    Exactly64,
+}
+
+impl Restrictions {
+    /// Decodes the restrictions from the byte in the extended header.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{ImageSizeRestriction, Restrictions, TagSizeRestriction};
+    ///
+    /// let restrictions = Restrictions::from_byte(0x85);
+    /// assert_eq!(TagSizeRestriction::Frames32Size40K, restrictions.tag_size);
+    /// assert_eq!(ImageSizeRestriction::Max256, restrictions.image_size);
+    /// assert!(restrictions.image_encoding);
+    /// assert_eq!(0x85, restrictions.to_byte());
+    /// ```
+    pub fn from_byte(b: u8) -> Restrictions {
+        use self::ImageSizeRestriction as I;
+        use self::TagSizeRestriction as T;
+        use self::TextSizeRestriction as X;
+        Restrictions {
+            tag_size: [T::Frames128Size1M, T::Frames64Size128K, T::Frames32Size40K, T::Frames32Size4K][(b >> 6) as usize],
+            text_encoding: b & 0x20 != 0,
+            text_fields_size: [X::Unrestricted, X::Chars1024, X::Chars128, X::Chars30][(b >> 3 & 0x03) as usize],
+            image_encoding: b & 0x04 != 0,
+            image_size: [I::Unrestricted, I::Max256, I::Max64, I::Exactly64][(b & 0x03) as usize],
+        }
+    }
+
+    /// Encodes the restrictions as the byte in the extended header.
+    pub fn to_byte(self) -> u8 {
+        (self.tag_size as u8) << 6
+            | (self.text_encoding as u8) << 5
+            | (self.text_fields_size as u8) << 3
+            | (self.image_encoding as u8) << 2
+            | self.image_size as u8
+    }
+}
+
+/// Returns the length of the extended header given the value of its size field. The size field
+/// of ID3v2.3 excludes itself and is not synchsafe.
+fn extended_header_len(size: u32, version: Version) -> usize {
+    match version {
+        Version::Id3v24 => unsynch::decode_u32(size) as usize,
+        _ => 4 + size as usize,
+    }
+}
+
+/// Returns the length of an extended header without optional data: the size and flags, plus the
+/// padding size for ID3v2.3.
+fn extended_header_min_len(version: Version) -> usize {
+    match version {
+        Version::Id3v24 => 6,
+        _ => 10,
+    }
+}
+
+/// Decodes the extended header at the start of the tag data. Returns the header, its length and
+/// the end of the data that is covered by the CRC.
+fn decode_extended_header(data: &[u8], version: Version) -> ::Result<(ExtendedHeader, usize, usize)> {
+    let truncated = || ::Error::new(::ErrorKind::Parsing, "extended header is truncated");
+    if data.len() < 6 {
+        return Err(truncated());
+    }
+    let ext_len = extended_header_len(BigEndian::read_u32(&data[..4]), version);
+    // The declared size must cover the fixed fields, which are read before the optional data.
+    if ext_len < extended_header_min_len(version) {
+        return Err(truncated());
+    }
+    let ext = data.get(..ext_len).ok_or_else(truncated)?;
+    let mut header = ExtendedHeader::default();
+    if version == Version::Id3v23 {
+        // Size, flags, padding size and the optional CRC.
+        if BigEndian::read_u16(&ext[4..6]) & 0x8000 != 0 {
+            header.crc = Some(BigEndian::read_u32(ext.get(10..14).ok_or_else(truncated)?));
+        }
+        let padding = BigEndian::read_u32(&ext[6..10]) as usize;
+        let crc_end = cmp::max(data.len().saturating_sub(padding), ext_len);
+        return Ok((header, ext_len, crc_end));
+    }
+
+    // Size, number of flag bytes, the flags and the data of each set flag prefixed by its length.
+    let flags = ext[5];
+    let mut i = 5 + ext[4] as usize;
+    let mut flag_data = |flag: u8| -> ::Result<Option<&[u8]>> {
+        if flags & flag == 0 {
+            return Ok(None);
+        }
+        let len = *ext.get(i).ok_or_else(truncated)? as usize;
+        let data = ext.get(i + 1..i + 1 + len).ok_or_else(truncated)?;
+        i += 1 + len;
+        Ok(Some(data))
+    };
+    header.update = flag_data(0x40)?.is_some();
+    if let Some(crc) = flag_data(0x20)? {
+        // A 35 bit synchsafe integer.
+        header.crc = Some(crc.iter().fold(0u64, |crc, &b| crc << 7 | u64::from(b & 0x7f)) as u32);
+    }
+    header.restrictions = flag_data(0x10)?
+        .and_then(|data| data.first().cloned())
+        .map(Restrictions::from_byte);
+    Ok((header, ext_len, data.len()))
+}
+
+/// Decodes a tag using the default settings.
+pub fn decode<R>(reader: R) -> ::Result<Tag>
+    where R: io::Read {
+    DecoderBuilder::default().build().unwrap().decode(reader)
+}
+
+type FrameHook = Box<dyn Fn(&frame::FrameHeader) -> FrameDecision>;
+
+/// The Decoder may be used to decode tags with custom settings.
+///
+/// # Example
+/// ```
+/// use std::fs::File;
+/// use id3::DecoderBuilder;
+///
+/// // Reading from a plain `io::Read` like a network stream only buffers the tag itself. The
+/// // stream is left positioned at the audio data.
+/// let stream = File::open("testdata/id3v24.id3").unwrap();
+/// let tag = DecoderBuilder::default()
+///     .max_tag_size(Some(1 << 20))
+///     .build()
+///     .unwrap()
+///     .decode(stream)
+///     .unwrap();
+/// assert_eq!(tag.title(), Some("Title"));
+/// ```
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Decoder {
+    /// The maximum tag size in bytes that will be buffered. Tags that declare a larger size are
+    /// rejected before any of their data is read, which guards against untrusted streams.
+    #[builder(default="None")]
+    max_tag_size: Option<usize>,
+    /// Defer decoding the content of frames until it is first accessed through `Frame::content`.
+    /// The frame headers are still decoded right away. This saves decoding large frames like
+    /// pictures when only a few text frames are needed. Content that fails to decode lazily is
+    /// returned as `Content::Unknown` holding the data of the frame instead of failing the tag,
+    /// `Frame::try_content` returns the error instead.
+    ///
+    /// Frames that are neither compressed, encrypted nor unsynchronised refer to a single buffer
+    /// holding the whole tag instead of allocating their own. Such a buffer is freed once all of
+    /// its frames are dropped.
+    #[builder(default="false")]
+    lazy: bool,
+    #[builder(setter(skip))]
+    on_frame: Option<FrameHook>,
+    #[builder(setter(skip))]
+    decryptors: frame::Decryptors,
+}
+
+/// Determines what the decoder does with a frame, see `Decoder::on_frame`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FrameDecision {
+    /// Decode the frame and add it to the tag.
+    Decode,
+    /// Skip over the frame without decoding its content.
+    Skip,
+    /// Stop decoding. The tag contains the frames decoded so far.
+    Stop,
+}
+
+impl fmt::Debug for Decoder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Decoder")
+            .field("max_tag_size", &self.max_tag_size)
+            .field("lazy", &self.lazy)
+            .field("on_frame", &self.on_frame.as_ref().map(|_| "Fn"))
+            .field("decryptors", &self.decryptors.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Decoder {
+    /// Sets a callback that is invoked with the header of every frame before it is decoded. The
+    /// returned decision determines whether the frame is decoded, skipped or whether decoding
+    /// stops altogether. This can be used to report progress or to cheaply skip frames that are
+    /// not of interest.
+    ///
+    /// The reader is always left positioned directly after the tag, also when decoding stops
+    /// early.
+    ///
+    /// # Example
+    /// ```
+    /// use std::fs::File;
+    /// use id3::{DecoderBuilder, FrameDecision};
+    ///
+    /// let decoder = DecoderBuilder::default()
+    ///     .build()
+    ///     .unwrap()
+    ///     .on_frame(|header| match header.id() {
+    ///         "APIC" => FrameDecision::Skip,
+    ///         _ => FrameDecision::Decode,
+    ///     });
+    /// let tag = decoder.decode(File::open("testdata/id3v24.id3").unwrap()).unwrap();
+    /// assert_eq!(0, tag.pictures().count());
+    /// assert_eq!(Some("Title"), tag.title());
+    /// ```
+    pub fn on_frame<F>(mut self, callback: F) -> Decoder
+        where F: Fn(&frame::FrameHeader) -> FrameDecision + 'static {
+        self.on_frame = Some(Box::new(callback));
+        self
+    }
 
-    let mut offset = tag_header.len();
+    /// Registers a decryptor for frames that are encrypted with the specified method symbol.
+    ///
+    /// Encrypted frames without a registered decryptor are preserved, see
+    /// `Frame::encryption_method`.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{DecoderBuilder, FrameHeader};
+    ///
+    /// let decoder = DecoderBuilder::default()
+    ///     .build()
+    ///     .unwrap()
+    ///     .decryptor(0x80, |_: &FrameHeader, data: &[u8]| {
+    ///         Ok(data.iter().map(|b| b ^ 0x5a).collect())
+    ///     });
+    /// ```
+    pub fn decryptor<D>(mut self, method: u8, decryptor: D) -> Decoder
+        where D: frame::FrameDecryptor + 'static {
+        self.decryptors.insert(method, Box::new(decryptor));
+        self
+    }
 
-    // TODO: actually use the extended header data.
-    if flags.contains(Flags::EXTENDED_HEADER) {
-        let ext_size = unsynch::decode_u32(reader.read_u32::<BigEndian>()?) as usize;
-        offset += 4 + ext_size;
-        let mut ext_header = Vec::with_capacity(cmp::min(ext_size, 0xffff));
-        reader.by_ref()
-            .take(ext_size as u64)
-            .read_to_end(&mut ext_header)?;
-        if flags.contains(Flags::UNSYNCHRONISATION) {
-            unsynch::decode_vec(&mut ext_header);
+    /// Decodes a tag from the reader. Exactly the size declared in the tag header is read, the
+    /// reader does not need to be seekable.
+    pub fn decode<R>(&self, mut reader: R) -> ::Result<Tag>
+        where R: io::Read {
+        let header = self.decode_header(&mut reader)?;
+        // Most frames are text frames of a few dozen bytes. Large tags usually owe their size to
+        // pictures, so the estimate is capped to not overallocate for those.
+        let mut tag = Tag::with_capacity(cmp::min(header.tag_size / 32, 256));
+        let extended_header = self.visit_body(reader, header, |_, frame| {
+            tag.add_frame(frame);
+        })?;
+        if let Some((extended_header, crc_valid)) = extended_header {
+            tag.set_read_extended_header(extended_header, crc_valid);
         }
+        Ok(tag)
+    }
+
+    /// Decodes the frames of a tag from the reader and passes each of them to the visitor along
+    /// with its header, without building a `Tag`. Frames are visited in the order in which they
+    /// are stored, duplicates are not removed. The callback set with `on_frame` is still used to
+    /// decide which frames are decoded.
+    ///
+    /// # Example
+    /// ```
+    /// use std::fs::File;
+    /// use id3::DecoderBuilder;
+    ///
+    /// let mut title = None;
+    /// let decoder = DecoderBuilder::default().build().unwrap();
+    /// decoder.visit(File::open("testdata/id3v24.id3").unwrap(), |header, frame| {
+    ///     if header.id() == "TIT2" {
+    ///         title = Some(frame.to_string());
+    ///     }
+    /// }).unwrap();
+    /// assert_eq!(Some("Title".to_string()), title);
+    /// ```
+    pub fn visit<R, V>(&self, mut reader: R, visitor: V) -> ::Result<()>
+        where R: io::Read, V: FnMut(&frame::FrameHeader, Frame) {
+        let header = self.decode_header(&mut reader)?;
+        self.visit_body(reader, header, visitor)?;
+        Ok(())
     }
 
+    /// Returns an iterator over all tags in the stream, for example the tags that an internet
+    /// radio station embeds in its audio stream to announce the current song. The data between
+    /// the tags is skipped.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{DecoderBuilder, Tag, Version};
+    ///
+    /// let mut stream = vec![0xff, 0xfb, 0x90, 0x00];
+    /// for title in &["First", "Second"] {
+    ///     let mut tag = Tag::new();
+    ///     tag.set_title(*title);
+    ///     tag.write_to(&mut stream, Version::Id3v24).unwrap();
+    ///     stream.extend_from_slice(&[0xff, 0xfb, 0x90, 0x00]);
+    /// }
+    ///
+    /// let decoder = DecoderBuilder::default().build().unwrap();
+    /// let titles: Vec<_> = decoder.tags(&stream[..])
+    ///     .map(|tag| tag.unwrap().title().unwrap().to_string())
+    ///     .collect();
+    /// assert_eq!(vec!["First", "Second"], titles);
+    /// ```
+    pub fn tags<R>(self, reader: R) -> TagStream<R>
+        where R: io::Read {
+        TagStream {
+            decoder: self,
+            reader: io::BufReader::new(reader),
+            window: Vec::with_capacity(10),
+        }
+    }
+
+    fn decode_header<R>(&self, reader: &mut R) -> ::Result<Header>
+        where R: io::Read {
+        let header = decode_header(reader)?;
+        if self.max_tag_size.is_some_and(|max| header.tag_size > max) {
+            return Err(::Error::new(::ErrorKind::InvalidInput, "tag exceeds the maximum size"));
+        }
+        Ok(header)
+    }
+
+    /// Decodes the frames of the tag. Returns the extended header, if there is one, and whether
+    /// its CRC matches the frame data.
+    fn visit_body<R, V>(&self, mut reader: R, header: Header, mut visitor: V) -> ::Result<Option<(ExtendedHeader, Option<bool>)>>
+        where R: io::Read, V: FnMut(&frame::FrameHeader, Frame) {
+        let Header { version, flags, tag_size } = header;
+
+        // The complete tag is read into a single buffer up front. Frames are decoded from slices
+        // of this buffer which saves a read call and allocation for every frame header. This also
+        // leaves the reader positioned directly after the tag, including its footer.
+        let mut tag_data = Vec::with_capacity(cmp::min(tag_size, 0x10_0000));
+        reader.by_ref().take(tag_size as u64).read_to_end(&mut tag_data)?;
+        if version == Version::Id3v24 && flags.contains(Flags::FOOTER) {
+            let mut footer = [0; 10];
+            reader.read_exact(&mut footer)?;
+            if &footer[..3] != b"3DI" {
+                return Err(::Error::new(::ErrorKind::Parsing, "tag footer is missing"));
+            }
+        }
+        // ID3v2.2 and ID3v2.3 apply the unsynchronisation to everything after the tag header,
+        // including the frame headers whose sizes refer to the synchronised data.
+        if version != Version::Id3v24 && flags.contains(Flags::UNSYNCHRONISATION) {
+            unsynch::decode_vec(&mut tag_data);
+        }
+        // Lazily decoded frames keep a reference to the buffer instead of copying their data.
+        let tag_data = Arc::new(tag_data);
+        let mut frame_data = &tag_data[..];
+
+        let mut extended_header = None;
+        if version != Version::Id3v22 && flags.contains(Flags::EXTENDED_HEADER) {
+            let (header, ext_len, crc_end) = decode_extended_header(frame_data, version)?;
+            let crc_valid = header.crc.map(|crc| {
+                let mut sum = Crc::new();
+                sum.update(&tag_data[ext_len..crc_end]);
+                sum.sum() == crc
+            });
+            extended_header = Some((header, crc_valid));
+            frame_data = &frame_data[ext_len..];
+        }
+
+        while !frame_data.is_empty() {
+            let mut body = frame_data;
+            let offset = (tag_data.len() - frame_data.len()) as u64 + 10;
+            let frame_header = match frame::decode_header_at(&mut body, version, offset)? {
+                Some(frame_header) => frame_header,
+                None => break, // Padding.
+            };
+            let decision = match self.on_frame {
+                Some(ref on_frame) => on_frame(&frame_header),
+                None => FrameDecision::Decode,
+            };
+            match decision {
+                FrameDecision::Decode => (),
+                FrameDecision::Skip => {
+                    frame_data = &body[cmp::min(frame_header.size() as usize, body.len())..];
+                    continue;
+                },
+                FrameDecision::Stop => break,
+            }
+            if self.lazy {
+                let start = tag_data.len() - body.len();
+                let range = start..start + frame_header.size() as usize;
+                let unsynchronisation = flags.contains(Flags::UNSYNCHRONISATION);
+                if let Some(frame) = frame::decode_shared(&frame_header, version, unsynchronisation, &tag_data, range.clone()) {
+                    visitor(&frame_header, frame);
+                    frame_data = &tag_data[range.end..];
+                    continue;
+                }
+            }
+            match frame::decode(&mut frame_data, version, flags.contains(Flags::UNSYNCHRONISATION), &self.decryptors, self.lazy)? {
+                Some((_, frame)) => visitor(&frame_header, frame),
+                None => break, // Padding.
+            };
+        }
+        Ok(extended_header)
+    }
+}
+
+/// Reads the headers of all frames in the tag without decoding their contents.
+///
+/// The offsets of frames in unsynchronised ID3v2.2 and ID3v2.3 tags are positions in the
+/// synchronised data.
+pub fn decode_index<R>(mut reader: R) -> ::Result<Vec<frame::FrameHeader>>
+    where R: io::Read {
+    let header = decode_header(&mut reader)?;
+    let tag_reader = reader.take(header.tag_size as u64);
+    if header.version != Version::Id3v24 && header.flags.contains(Flags::UNSYNCHRONISATION) {
+        decode_index_body(unsynch::Reader::new(tag_reader), header)
+    } else {
+        decode_index_body(tag_reader, header)
+    }
+}
+
+fn decode_index_body<R>(mut tag_reader: R, header: Header) -> ::Result<Vec<frame::FrameHeader>>
+    where R: io::Read {
+    let Header { version, flags, .. } = header;
+    let mut offset = 10;
+    if version != Version::Id3v22 && flags.contains(Flags::EXTENDED_HEADER) {
+        let ext_len = extended_header_len(tag_reader.read_u32::<BigEndian>()?, version) as u64;
+        if ext_len < extended_header_min_len(version) as u64 {
+            return Err(::Error::new(::ErrorKind::Parsing, "extended header is truncated"));
+        }
+        let rest = ext_len - 4;
+        io::copy(&mut tag_reader.by_ref().take(rest), &mut io::sink())?;
+        offset += ext_len;
+    }
+    frame::decode_headers(&mut tag_reader, version, offset)
+}
+
+/// Decodes a tag from the seekable reader, only decoding the frames for which the filter returns
+/// true. The bodies of the other frames are seeked past instead of being read. The filter is
+/// called with the ID3v2.3/ID3v2.4 ID of each frame. The reader is left positioned directly after
+/// the tag.
+///
+/// Unsynchronised ID3v2.2 and ID3v2.3 tags are read as a whole, because the sizes of their
+/// frames refer to the synchronised data.
+pub fn decode_filtered<R, F>(mut reader: R, mut filter: F) -> ::Result<Tag>
+    where R: io::Read + io::Seek, F: FnMut(&str) -> bool {
+    let header = decode_header(&mut reader)?;
+    let footer_len = if header.flags.contains(Flags::FOOTER) { 10 } else { 0 };
+    let tag_end = reader.stream_position()? + header.tag_size as u64 + footer_len;
+    let tag = if header.version != Version::Id3v24 && header.flags.contains(Flags::UNSYNCHRONISATION) {
+        let mut tag_data = Vec::with_capacity(cmp::min(header.tag_size, 0x10_0000));
+        reader.by_ref().take(header.tag_size as u64).read_to_end(&mut tag_data)?;
+        unsynch::decode_vec(&mut tag_data);
+        let size = tag_data.len() as u64;
+        decode_filtered_body(io::Cursor::new(tag_data), &header, size, &mut filter)?
+    } else {
+        decode_filtered_body(&mut reader, &header, header.tag_size as u64, &mut filter)?
+    };
+    reader.seek(io::SeekFrom::Start(tag_end))?;
+    Ok(tag)
+}
+
+fn decode_filtered_body<R, F>(mut reader: R, header: &Header, size: u64, filter: &mut F) -> ::Result<Tag>
+    where R: io::Read + io::Seek, F: FnMut(&str) -> bool {
+    let &Header { version, flags, .. } = header;
+    let start = reader.stream_position()?;
+    let end = start + size;
+    if version != Version::Id3v22 && flags.contains(Flags::EXTENDED_HEADER) {
+        let ext_len = extended_header_len(reader.read_u32::<BigEndian>()?, version);
+        if ext_len < extended_header_min_len(version) {
+            return Err(::Error::new(::ErrorKind::Parsing, "extended header is truncated"));
+        }
+        reader.seek(io::SeekFrom::Start(start + ext_len as u64))?;
+    }
+    let header_len = if version == Version::Id3v22 { 6 } else { 10 };
+    let decryptors = frame::Decryptors::new();
     let mut tag = Tag::new();
-    while offset < tag_size + tag_header.len() {
-        let (bytes_read, frame) = match frame::decode(&mut reader, version, flags.contains(Flags::UNSYNCHRONISATION))? {
-            Some(frame) => frame,
+    loop {
+        let pos = reader.stream_position()?;
+        if pos >= end {
+            break;
+        }
+        let frame_header = match frame::decode_header_at(&mut reader.by_ref().take(end - pos), version, pos - start + 10)? {
+            Some(frame_header) => frame_header,
             None => break, // Padding.
         };
-        tag.add_frame(frame);
-        offset += bytes_read;
+        let id = frame_header.id();
+        let id = if id.len() == 3 { ::util::convert_id_2_to_3(id).unwrap_or(id) } else { id };
+        if filter(id) {
+            reader.seek(io::SeekFrom::Start(pos))?;
+            let mut frame_reader = reader.by_ref().take(end - pos);
+            match frame::decode(&mut frame_reader, version, flags.contains(Flags::UNSYNCHRONISATION), &decryptors, false)? {
+                Some((_, frame)) => {
+                    tag.add_frame(frame);
+                },
+                None => break,
+            }
+        }
+        let next = pos + header_len + u64::from(frame_header.size());
+        reader.seek(io::SeekFrom::Start(cmp::min(next, end)))?;
     }
-
     Ok(tag)
 }
 
+/// Returns whether the bytes form a plausible tag header. The size must be synchsafe, which rules
+/// out most accidental occurrences of "ID3" in audio data.
+fn is_header_candidate(header: &[u8]) -> bool {
+    header.len() == 10
+        && &header[..3] == b"ID3"
+        && (2..=4).contains(&header[3])
+        && header[4] != 0xff
+        && header[6..10].iter().all(|b| b & 0x80 == 0)
+}
+
+/// An iterator over the tags embedded in a stream, like the timed metadata of a live audio
+/// stream. This type is created by `Decoder::tags`.
+///
+/// The stream is read sequentially and does not need to be seekable. The data between tags is
+/// skipped and only the tag being decoded is buffered.
+pub struct TagStream<R> {
+    decoder: Decoder,
+    reader: io::BufReader<R>,
+    /// The last bytes that were read, which may be the start of a tag header.
+    window: Vec<u8>,
+}
+
+impl<R> Iterator for TagStream<R>
+    where R: io::Read {
+    type Item = ::Result<Tag>;
+
+    fn next(&mut self) -> Option<::Result<Tag>> {
+        let mut byte = [0];
+        loop {
+            match self.reader.read(&mut byte) {
+                Ok(0) => return None,
+                Ok(_) => (),
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Some(Err(err.into())),
+            }
+            self.window.push(byte[0]);
+            if !b"ID3".starts_with(&self.window[..cmp::min(self.window.len(), 3)]) {
+                // Drop bytes until the window starts with a possible tag header again.
+                let start = (1..self.window.len())
+                    .find(|&i| b"ID3".starts_with(&self.window[i..cmp::min(self.window.len(), i + 3)]))
+                    .unwrap_or(self.window.len());
+                self.window.drain(..start);
+                continue;
+            }
+            if self.window.len() < 10 {
+                continue;
+            }
+            if !is_header_candidate(&self.window) {
+                self.window.remove(0);
+                continue;
+            }
+            let header = std::mem::take(&mut self.window);
+            return Some(self.decoder.decode((&header[..]).chain(&mut self.reader)));
+        }
+    }
+}
+
+/// A general encapsulated object (GEOB) whose data is read from a stream while encoding, see
+/// `Encoder::encode_with_objects`.
+pub struct StreamedObject<'a> {
+    /// The MIME type of the object.
+    pub mime_type: String,
+    /// The file name of the object.
+    pub filename: String,
+    /// The content description of the object.
+    pub description: String,
+    /// The exact number of bytes that are read from `data`.
+    pub len: u64,
+    /// The source of the data.
+    pub data: &'a mut dyn io::Read,
+}
+
+type PictureShrinker = Box<dyn Fn(&Picture, usize) -> Option<Picture>>;
+
+/// A maximum size for encoded tags, see `Encoder::encode_with_budget`.
+///
+/// If a tag does not fit, frames are omitted starting with the lowest priority. Frames that are
+/// not in the priority list have the lowest priority of all, larger frames are omitted first.
+/// Lyrics and comments are truncated, and pictures are shrunk if a function to do so is set,
+/// before they are dropped.
+pub struct SizeBudget {
+    max_size: usize,
+    priorities: Vec<String>,
+    shrink_picture: Option<PictureShrinker>,
+}
+
+impl SizeBudget {
+    /// Creates a budget of `max_size` bytes, including the tag header.
+    pub fn new(max_size: usize) -> SizeBudget {
+        SizeBudget { max_size, priorities: Vec::new(), shrink_picture: None }
+    }
+
+    /// Sets the IDs of the frames to keep, from the highest to the lowest priority.
+    pub fn priorities<I, S>(mut self, ids: I) -> SizeBudget
+        where I: IntoIterator<Item=S>, S: Into<String> {
+        self.priorities = ids.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets a function that shrinks a picture so that its data is at most the specified number
+    /// of bytes, e.g. by downscaling it. Returning `None` drops the picture.
+    pub fn shrink_pictures<F>(mut self, shrink: F) -> SizeBudget
+        where F: Fn(&Picture, usize) -> Option<Picture> + 'static {
+        self.shrink_picture = Some(Box::new(shrink));
+        self
+    }
+
+    fn rank(&self, frame: &Frame) -> usize {
+        self.priorities.iter()
+            .position(|id| id == frame.id())
+            .unwrap_or(self.priorities.len())
+    }
+}
+
+impl fmt::Debug for SizeBudget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SizeBudget")
+            .field("max_size", &self.max_size)
+            .field("priorities", &self.priorities)
+            .field("shrink_picture", &self.shrink_picture.as_ref().map(|_| "Fn"))
+            .finish()
+    }
+}
+
+/// The frames that were omitted to fit a tag within a `SizeBudget`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BudgetReport {
+    /// The frames that were left out entirely.
+    pub dropped: Vec<Frame>,
+    /// The original frames of which a truncated or shrunk version was encoded.
+    pub shrunk: Vec<Frame>,
+}
+
+/// Returns the longest prefix of the text that is at most `len` bytes long.
+fn truncate(text: &str, len: usize) -> &str {
+    if len >= text.len() {
+        return text;
+    }
+    let end = (0..=len).rev().find(|i| text.is_char_boundary(*i)).unwrap_or(0);
+    &text[..end]
+}
+
+/// Estimates the bytes of a picture frame that are not picture data.
+fn frame_overhead(picture: &Picture) -> usize {
+    // The frame header, the encoding and picture type bytes and two terminators of up to 2 bytes.
+    16 + picture.mime_type.len() + picture.description.len() * 2
+}
+
+/// Decides how much padding is reserved after a tag that is written to a file, see `Padding`.
+pub trait PaddingStrategy: Send + Sync {
+    /// Returns the number of bytes of padding to write after an encoded tag of `tag_len` bytes.
+    fn padding(&self, tag_len: usize) -> usize;
+}
+
+impl<F> PaddingStrategy for F
+    where F: Fn(usize) -> usize + Send + Sync {
+    fn padding(&self, tag_len: usize) -> usize {
+        self(tag_len)
+    }
+}
+
+/// What `Encoder::encode_to_path` does with an ID3v2.4 tag that is appended to the end of a file
+/// when the new tag is written to the start of the file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AppendedTag {
+    /// Remove the appended tag, the new tag replaces all tags in the file.
+    Remove,
+    /// Leave the appended tag in place.
+    Keep,
+    /// Add the frames of the appended tag that the new tag lacks to the written tag and remove
+    /// the appended tag, like `TagPolicy::Merge` does when reading.
+    Merge,
+}
+
+/// The padding that is reserved after a tag when it is written to a file and does not fit in the
+/// space of the old tag, see `Encoder::encode_to_path`. Padding allows later writes of a larger
+/// tag to be done in place instead of rewriting the whole file.
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+/// use id3::{EncoderBuilder, Padding, PaddingStrategy};
+///
+/// assert_eq!(256, Padding::Percent(25).padding(1024));
+/// let padding = Padding::Custom(Arc::new(|len: usize| 4096 - len % 4096));
+/// assert_eq!(3072, padding.padding(1024));
+///
+/// let encoder = EncoderBuilder::default().padding(padding).build().unwrap();
+/// ```
+#[derive(Clone)]
+pub enum Padding {
+    /// No padding at all.
+    None,
+    /// A fixed number of bytes.
+    Fixed(usize),
+    /// A percentage of the length of the encoded tag.
+    Percent(u32),
+    /// The padding returned by a user supplied strategy.
+    Custom(Arc<dyn PaddingStrategy>),
+}
+
+impl PaddingStrategy for Padding {
+    fn padding(&self, tag_len: usize) -> usize {
+        match *self {
+            Padding::None => 0,
+            Padding::Fixed(len) => len,
+            Padding::Percent(percent) => tag_len * percent as usize / 100,
+            Padding::Custom(ref strategy) => strategy.padding(tag_len),
+        }
+    }
+}
+
+impl fmt::Debug for Padding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Padding::None => write!(f, "None"),
+            Padding::Fixed(len) => write!(f, "Fixed({})", len),
+            Padding::Percent(percent) => write!(f, "Percent({})", percent),
+            Padding::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
 /// The Encoder may be used to encode tags.
 #[derive(Debug, Builder)]
 #[builder(pattern = "owned")]
 pub struct Encoder {
-    /// The tag version to encode to.
+    /// The tag version to encode to. Frames that have no ID3v2.2 identifier can not be written to
+    /// ID3v2.2 tags, `Tag::convert_to` removes them beforehand.
     #[builder(default="Version::Id3v24")]
     version: Version,
     /// Enable the unsynchronisatin scheme. This avoids patterns that resemble MP3-frame headers
     /// from being encoded. If you are encoding to MP3 files, you probably want this enabled.
     #[builder(default="true")]
     unsynchronisation: bool,
-    /// Enable compression.
+    /// Enable compression. The frames of ID3v2.3 and ID3v2.4 tags are compressed individually,
+    /// ID3v2.2 defines no compression scheme so its tags are never compressed.
     #[builder(default="false")]
     compression: bool,
+    /// Take an exclusive advisory lock on the file while writing to a path. Other processes that
+    /// lock the file too will wait for the write to complete instead of interleaving with it.
+    #[builder(default="false")]
+    lock: bool,
+    /// Restore the modification time of the file after writing to a path. Backup tools and
+    /// library scanners may otherwise treat the file as having new content.
+    #[builder(default="false")]
+    preserve_mtime: bool,
+    /// Restore the access time of the file after writing to a path.
+    #[builder(default="false")]
+    preserve_atime: bool,
+    /// Also write an ID3v1.1 tag derived from the tag to the end of MPEG files when writing to a
+    /// path, replacing an existing ID3v1 tag. Players that only read ID3v1 then still show the
+    /// basic metadata.
+    #[builder(default="false")]
+    id3v1: bool,
+    /// Remove potentially identifying frames before encoding, see `Tag::scrub`.
+    #[builder(default="None")]
+    scrub: Option<ScrubProfile>,
+    /// Rewrite the genre (TCON) in the specified style before encoding, the genre is written as
+    /// is by default.
+    #[builder(default="None")]
+    genre_style: Option<GenreStyle>,
+    /// Append a footer to the tag, which allows it to be located from the end of a file. Only
+    /// ID3v2.4 tags can have a footer, and they are written without padding.
+    #[builder(default="false")]
+    footer: bool,
+    /// Write ID3v2.4 tags to the end of MPEG files when writing to a path, see
+    /// `Encoder::encode_to_path`. Appended tags always have a footer.
+    #[builder(default="false")]
+    append: bool,
+    /// What to do with an ID3v2.4 tag at the end of the file when the tag is written to the start
+    /// of the file by `Encoder::encode_to_path`.
+    #[builder(default="AppendedTag::Merge")]
+    appended_tag: AppendedTag,
+    /// Write an extended header with the CRC-32 of the frame data, which lets readers detect a
+    /// corrupted tag. ID3v2.2 tags have no extended header.
+    #[builder(default="false")]
+    crc: bool,
+    /// Write an extended header with the restrictions that the tag was written within. Only
+    /// ID3v2.4 tags can hold restrictions.
+    #[builder(default="None")]
+    restrictions: Option<Restrictions>,
+    /// The padding to reserve when writing to a path and the tag does not fit in the space of the
+    /// old tag. Tags with a footer are never padded.
+    #[builder(default="Padding::Fixed(2048)")]
+    padding: Padding,
+    #[builder(setter(skip))]
+    on_progress: ProgressHook,
+}
+
+/// The callback that reports the progress of rewriting a file, see `Encoder::on_progress`.
+#[derive(Default)]
+struct ProgressHook(Option<Box<dyn Fn(u64, u64) + Send + Sync>>);
+
+impl fmt::Debug for ProgressHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.0.as_ref().map(|_| "Fn"))
+    }
 }
 
 impl Encoder {
+    /// Sets a callback that reports the progress when `encode_to_path` has to rewrite a file,
+    /// with the number of bytes written so far and the total length of the new file. The file is
+    /// copied in chunks, so the callback is invoked regularly for large files.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::EncoderBuilder;
+    ///
+    /// let encoder = EncoderBuilder::default()
+    ///     .build()
+    ///     .unwrap()
+    ///     .on_progress(|written, total| println!("{}/{} bytes", written, total));
+    /// ```
+    pub fn on_progress<F>(mut self, callback: F) -> Encoder
+        where F: Fn(u64, u64) + Send + Sync + 'static {
+        self.on_progress = ProgressHook(Some(Box::new(callback)));
+        self
+    }
+
     /// Encodes the specified tag using the settings set in the endoder.
     pub fn encode<W>(&self, tag: &Tag, mut writer: W) -> ::Result<()>
         where W: io::Write {
+        let mut data = Vec::new();
+        self.encode_into(tag, &mut data)?;
+        writer.write_all(&data)?;
+        Ok(())
+    }
+
+    /// Encodes the specified tag followed by general encapsulated object frames (GEOB) whose data
+    /// is copied from their source straight into the writer, without buffering it in memory.
+    ///
+    /// Because the data is not buffered, it can not be unsynchronised. Unsynchronisation must be
+    /// disabled for the encoder, otherwise an error is returned before anything is written. An
+    /// error is also returned if a source ends before the declared length, in which case the
+    /// writer holds an incomplete tag.
+    ///
+    /// # Example
+    /// ```
+    /// use std::io;
+    /// use id3::{EncoderBuilder, StreamedObject, Tag};
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_title("Session");
+    /// let mut project = io::repeat(0x42);
+    /// let object = StreamedObject {
+    ///     mime_type: "application/octet-stream".to_string(),
+    ///     filename: "session.prj".to_string(),
+    ///     description: "Project".to_string(),
+    ///     len: 1 << 16,
+    ///     data: &mut project,
+    /// };
+    ///
+    /// let mut data = Vec::new();
+    /// let encoder = EncoderBuilder::default().unsynchronisation(false).build().unwrap();
+    /// encoder.encode_with_objects(&tag, vec![object], &mut data).unwrap();
+    /// let tag = Tag::read_from(&data[..]).unwrap();
+    /// assert_eq!(Some("Session"), tag.title());
+    /// assert!(tag.get("GEOB").is_some());
+    /// ```
+    pub fn encode_with_objects<W>(&self, tag: &Tag, objects: Vec<StreamedObject>, mut writer: W) -> ::Result<()>
+        where W: io::Write {
+        if self.unsynchronisation {
+            return Err(::Error::new(::ErrorKind::InvalidInput, "streamed objects can not be unsynchronised"));
+        }
+        let mut data = Vec::new();
+        self.encode_into(tag, &mut data)?;
+        let prefixes = objects.iter()
+            .map(|object| frame::encode_geob_prefix(self.version, &object.mime_type, &object.filename, &object.description, object.len))
+            .collect::<::Result<Vec<_>>>()?;
+        let size = (data.len() - 10) as u64 + objects.iter().zip(&prefixes)
+            .map(|(object, prefix)| prefix.len() as u64 + object.len)
+            .sum::<u64>();
+        if size > 0x0fff_ffff {
+            return Err(::Error::new(::ErrorKind::InvalidInput, "tag is too large"));
+        }
+        BigEndian::write_u32(&mut data[6..10], unsynch::encode_u32(size as u32));
+
+        writer.write_all(&data)?;
+        for (object, prefix) in objects.into_iter().zip(prefixes) {
+            writer.write_all(&prefix)?;
+            let copied = io::copy(&mut object.data.take(object.len), &mut writer)?;
+            if copied != object.len {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "streamed object ended early").into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes the specified tag such that it does not exceed the size budget, omitting frames
+    /// as needed. Returns the frames that were omitted or shrunk.
+    ///
+    /// An error is returned if even a tag without frames exceeds the budget.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{EncoderBuilder, Frame, SizeBudget, Tag};
+    /// use id3::frame::{Content, Lyrics};
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_title("Title");
+    /// tag.set_artist("Artist");
+    /// tag.add_frame(Frame::with_content("PRIV", Content::Unknown(vec![0; 4096])));
+    /// tag.set_lyrics(Lyrics {
+    ///     lang: "eng".to_string(),
+    ///     description: "".to_string(),
+    ///     text: "la ".repeat(1000),
+    /// });
+    ///
+    /// let budget = SizeBudget::new(1024).priorities(vec!["TIT2", "TPE1", "USLT"]);
+    /// let mut data = Vec::new();
+    /// let encoder = EncoderBuilder::default().build().unwrap();
+    /// let report = encoder.encode_with_budget(&tag, &budget, &mut data).unwrap();
+    /// assert!(data.len() <= 1024);
+    /// assert_eq!("PRIV", report.dropped[0].id());
+    /// assert_eq!("USLT", report.shrunk[0].id());
+    ///
+    /// let tag = Tag::read_from(&data[..]).unwrap();
+    /// assert_eq!(Some("Title"), tag.title());
+    /// assert!(tag.lyrics().next().unwrap().text.starts_with("la la"));
+    /// ```
+    pub fn encode_with_budget<W>(&self, tag: &Tag, budget: &SizeBudget, mut writer: W) -> ::Result<BudgetReport>
+        where W: io::Write {
+        let mut buf = Vec::new();
+        let mut report = BudgetReport::default();
+        self.encode_into(tag, &mut buf)?;
+        if buf.len() <= budget.max_size {
+            writer.write_all(&buf)?;
+            return Ok(report);
+        }
+
+        // The header, and the extended header and footer if enabled, are written once per tag.
+        self.encode_into(&Tag::new(), &mut buf)?;
+        let overhead = buf.len();
+        let mut frames: Vec<Option<Frame>> = tag.frames().cloned().map(Some).collect();
+        let mut sizes = Vec::with_capacity(frames.len());
+        for frame in frames.iter().flatten() {
+            sizes.push(self.encoded_frame_size(frame, overhead, &mut buf)?);
+        }
+        let mut order: Vec<usize> = (0..frames.len()).collect();
+        order.sort_by_key(|&i| (cmp::Reverse(budget.rank(frames[i].as_ref().unwrap())), cmp::Reverse(sizes[i])));
+
+        let mut order = order.into_iter();
+        let mut total = overhead + sizes.iter().sum::<usize>();
+        while total > budget.max_size {
+            let i = match order.next() {
+                Some(i) => i,
+                None => return Err(::Error::new(::ErrorKind::InvalidInput, "tag does not fit within the size budget")),
+            };
+            let frame = frames[i].take().unwrap();
+            let room = (budget.max_size + sizes[i]).saturating_sub(total);
+            match self.shrink_frame(&frame, room, overhead, budget, &mut buf)? {
+                Some((smaller, size)) => {
+                    total = total - sizes[i] + size;
+                    frames[i] = Some(smaller);
+                    report.shrunk.push(frame);
+                },
+                None => {
+                    total -= sizes[i];
+                    report.dropped.push(frame);
+                },
+            }
+        }
+        // The sizes of the frames are estimated separately, the unsynchronisation of a whole
+        // ID3v2.2 or ID3v2.3 tag may still add a few bytes. More frames are dropped until the
+        // encoded tag fits.
+        loop {
+            let tag: Tag = frames.iter().flatten().cloned().collect();
+            self.encode_into(&tag, &mut buf)?;
+            if buf.len() <= budget.max_size {
+                break;
+            }
+            match order.next() {
+                Some(i) => report.dropped.extend(frames[i].take()),
+                None => return Err(::Error::new(::ErrorKind::InvalidInput, "tag does not fit within the size budget")),
+            }
+        }
+        writer.write_all(&buf)?;
+        Ok(report)
+    }
+
+    /// Returns the number of bytes that the frame adds to an encoded tag, given the `overhead` of
+    /// a tag without frames.
+    fn encoded_frame_size(&self, frame: &Frame, overhead: usize, buf: &mut Vec<u8>) -> ::Result<usize> {
+        let tag: Tag = Some(frame.clone()).into_iter().collect();
+        self.encode_into(&tag, buf)?;
+        Ok(buf.len().saturating_sub(overhead))
+    }
+
+    /// Attempts to make the frame fit in `room` bytes by truncating its text or shrinking its
+    /// picture. Returns the new frame and its size.
+    fn shrink_frame(&self, frame: &Frame, room: usize, overhead: usize, budget: &SizeBudget, buf: &mut Vec<u8>) -> ::Result<Option<(Frame, usize)>> {
+        let mut with_content = |content: Content| -> ::Result<Option<(Frame, usize)>> {
+            let mut smaller = Frame::with_content(frame.id(), content);
+            smaller.set_group_identifier(frame.group_identifier());
+            let size = self.encoded_frame_size(&smaller, overhead, buf)?;
+            Ok(if size <= room { Some((smaller, size)) } else { None })
+        };
+        let text = match *frame.content() {
+            Content::Picture(ref picture) => {
+                let shrunk = match budget.shrink_picture {
+                    Some(ref shrink) => shrink(picture, room.saturating_sub(frame_overhead(picture))),
+                    None => None,
+                };
+                return match shrunk {
+                    Some(shrunk) => with_content(Content::Picture(shrunk)),
+                    None => Ok(None),
+                };
+            },
+            Content::Lyrics(ref lyrics) => &lyrics.text,
+            Content::Comment(ref comment) => &comment.text,
+            _ => return Ok(None),
+        };
+        // Text may take up to twice its UTF-8 length when encoded as UTF-16, so the cut is widened
+        // until the frame fits.
+        let mut len = room;
+        while len > 0 {
+            let truncated = truncate(text, len).to_string();
+            if truncated.is_empty() {
+                break;
+            }
+            len = truncated.len() * 3 / 4;
+            let content = match *frame.content() {
+                Content::Lyrics(ref lyrics) => Content::Lyrics(Lyrics { text: truncated, ..lyrics.clone() }),
+                Content::Comment(ref comment) => Content::Comment(Comment { text: truncated, ..comment.clone() }),
+                _ => unreachable!(),
+            };
+            if let Some(result) = with_content(content)? {
+                return Ok(Some(result));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Encodes the specified tag into the buffer, replacing its contents. The capacity of the
+    /// buffer is reused, so encoding many tags with the same buffer avoids an allocation for each
+    /// of them.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{EncoderBuilder, Tag};
+    ///
+    /// let encoder = EncoderBuilder::default().build().unwrap();
+    /// let mut buf = Vec::new();
+    /// for title in &["One", "Two"] {
+    ///     let mut tag = Tag::new();
+    ///     tag.set_title(*title);
+    ///     encoder.encode_into(&tag, &mut buf).unwrap();
+    ///     assert_eq!(&buf[..3], b"ID3");
+    /// }
+    /// ```
+    pub fn encode_into(&self, tag: &Tag, buf: &mut Vec<u8>) -> ::Result<()> {
+        let rewritten;
+        let convert = tag.needs_involved_people_conversion(self.version) || tag.needs_date_conversion(self.version);
+        let tag = if self.scrub.is_some() || self.genre_style.is_some() || convert {
+            let mut tag = tag.clone();
+            tag.convert_involved_people(self.version);
+            tag.convert_dates(self.version);
+            if let Some(ref profile) = self.scrub {
+                tag.scrub(profile);
+            }
+            if let Some(genre) = self.genre_style.and_then(|style| tag.genre_with_style(style, self.version)) {
+                tag.set_genre(genre);
+            }
+            rewritten = tag;
+            &rewritten
+        } else {
+            tag
+        };
+
         // remove frames which have the flags indicating they should be removed
         let saved_frames = tag.frames()
             .filter(|frame| {
                 !(frame.tag_alter_preservation()
                   || (frame.file_alter_preservation()
                       || DEFAULT_FILE_DISCARD.contains(&frame.id())))
+            })
+            // ID3v2.2 has no means to mark frames as encrypted or grouped, so the registrations
+            // are meaningless.
+            .filter(|frame| {
+                self.version != Version::Id3v22
+                    || (frame.encryption_method().is_none() && frame.id() != "ENCR" && frame.id() != "GRID")
             });
 
         let mut flags = Flags::empty();
         flags.set(Flags::UNSYNCHRONISATION, self.unsynchronisation);
-        if self.version == Version::Id3v22 {
-            flags.set(Flags::COMPRESSION, self.compression);
-        }
 
-        let mut frame_data = Vec::new();
+        buf.clear();
+        buf.extend_from_slice(b"ID3");
+        buf.extend_from_slice(&[self.version.minor() as u8, 2]);
+        buf.push(flags.bits());
+        // The size is filled in once the frames have been encoded.
+        buf.extend_from_slice(&[0; 4]);
         for frame in saved_frames {
-            frame::encode(&mut frame_data, frame, self.version, self.unsynchronisation)?;
+            frame::encode(buf, frame, self.version, self.unsynchronisation, self.compression && self.version != Version::Id3v22)?;
+        }
+        if let Some(ext) = self.encode_extended_header(&buf[10..]) {
+            buf.splice(10..10, ext);
+            flags.insert(Flags::EXTENDED_HEADER);
+            buf[5] = flags.bits();
+        }
+        if self.version != Version::Id3v24 && self.unsynchronisation {
+            let mut body = buf.split_off(10);
+            unsynch::encode_vec(&mut body);
+            buf.extend(body);
+        }
+        let size = unsynch::encode_u32((buf.len() - 10) as u32);
+        BigEndian::write_u32(&mut buf[6..10], size);
+        if self.version == Version::Id3v24 && (self.footer || self.append) {
+            buf[5] |= Flags::FOOTER.bits();
+            let mut footer = [0; 10];
+            footer.copy_from_slice(&buf[..10]);
+            footer[..3].copy_from_slice(b"3DI");
+            buf.extend_from_slice(&footer);
+        }
+        Ok(())
+    }
+
+    /// Encodes the extended header for the frame data, if one should be written.
+    fn encode_extended_header(&self, frames: &[u8]) -> Option<Vec<u8>> {
+        let crc = if self.crc {
+            let mut crc = Crc::new();
+            crc.update(frames);
+            Some(crc.sum())
+        } else {
+            None
+        };
+        let mut ext = Vec::new();
+        match self.version {
+            Version::Id3v22 => return None,
+            Version::Id3v23 => {
+                let crc = crc?;
+                // Size, flags, padding size and CRC.
+                ext.extend_from_slice(&10u32.to_be_bytes());
+                ext.extend_from_slice(&[0x80, 0x00]);
+                ext.extend_from_slice(&0u32.to_be_bytes());
+                ext.extend_from_slice(&crc.to_be_bytes());
+            },
+            Version::Id3v24 => {
+                if crc.is_none() && self.restrictions.is_none() {
+                    return None;
+                }
+                let mut flags = 0;
+                let mut flag_data = Vec::new();
+                if let Some(crc) = crc {
+                    // A 35 bit synchsafe integer.
+                    flags |= 0x20;
+                    flag_data.push(5);
+                    flag_data.extend((0..5).rev().map(|i| (u64::from(crc) >> (7 * i)) as u8 & 0x7f));
+                }
+                if let Some(restrictions) = self.restrictions {
+                    flags |= 0x10;
+                    flag_data.extend_from_slice(&[1, restrictions.to_byte()]);
+                }
+                let size = 6 + flag_data.len() as u32;
+                ext.extend_from_slice(&unsynch::encode_u32(size).to_be_bytes());
+                ext.extend_from_slice(&[1, flags]);
+                ext.extend(flag_data);
+            },
+        }
+        Some(ext)
+    }
+
+    /// Returns the edits that write the encoded tag to the end of the file, in front of an ID3v1
+    /// tag, and replace the tag at the start of the file with a seek frame.
+    fn append_edits(&self, file: &mut fs::File, tag: &Tag, data: Vec<u8>) -> ::Result<Vec<(ops::Range<u64>, Vec<u8>)>> {
+        let location = tag::locate_id3v2(&mut *file)?.unwrap_or(0..0);
+        let end = file.seek(io::SeekFrom::End(0))?;
+        let v1_location = ::v1::locate(&mut *file)?;
+        let v1_start = v1_location.as_ref().map_or(end, |v1| v1.start);
+        let appended = tag::locate_id3v2_appended(&mut *file)?
+            .filter(|appended| appended.start >= location.end && appended.start != 0)
+            .unwrap_or(v1_start..v1_start);
+
+        let mut edits = Vec::new();
+        if location.end > location.start {
+            // The seek frame points from the end of the new tag at the start of the file, without
+            // its padding, to the appended tag. Its size does not depend on the offset.
+            let stub_encoder = EncoderBuilder::default().build().unwrap();
+            let mut stub = Tag::new();
+            stub.set_seek_offset(0);
+            let mut stub_data = Vec::new();
+            stub_encoder.encode(&stub, &mut stub_data)?;
+            let region_len = cmp::max(stub_data.len() as u64, location.end - location.start);
+            let offset = region_len - stub_data.len() as u64 + (appended.start - location.end);
+            if offset > u64::from(u32::MAX) {
+                return Err(::Error::new(::ErrorKind::InvalidInput, "the appended tag is too far from the start of the file"));
+            }
+            stub.set_seek_offset(offset as u32);
+            stub_data.clear();
+            stub_encoder.encode(&stub, &mut stub_data)?;
+            stub_data.resize(region_len as usize, 0);
+            edits.push((location, stub_data));
+        }
+        edits.push((appended, data));
+        if self.id3v1 {
+            let v1_location = v1_location.unwrap_or(end..end);
+            edits.push((v1_location, ::v1::Tag::from(tag).to_bytes().to_vec()));
+        }
+        Ok(edits)
+    }
+
+    /// Encodes the specified tag and writes it to the file at the indicated path, replacing any
+    /// existing tag. An ID3v2.4 tag appended to the end of the file is handled as set by
+    /// `appended_tag`, by default its frames that the tag lacks are merged into the tag before it
+    /// is removed, see `TagPolicy::Merge`.
+    ///
+    /// The tag is stored where the container expects it: in the "id3 " chunk of WAV files, the
+    /// "ID3 " chunk of AIFF files, at the metadata pointer of DSF files and in the ID32 box of MP4
+    /// files. Other files get the tag at the start.
+    ///
+    /// If `append` is set, an ID3v2.4 tag is written to the end of MPEG files instead, in front
+    /// of an ID3v1 tag. An existing tag at the start of the file is then replaced by a tag that
+    /// only holds a seek frame (SEEK) pointing to the appended tag, so the appended tag is found
+    /// by `Tag::read_all_from_path` either way.
+    ///
+    /// If the new tag fits in the space of the old tag including its padding, only the tag is
+    /// overwritten in place. Otherwise the new file is written next to the original and then
+    /// renamed over it, so the original file is left intact if writing fails.
+    pub fn encode_to_path<P: AsRef<Path>>(&self, tag: &Tag, path: P) -> ::Result<()> {
+        // Resolve symlinks so the link itself is not replaced.
+        let path = fs::canonicalize(path)?;
+        // The file is kept open, and locked if requested, until the rewrite is complete.
+        let mut file = if self.lock {
+            storage::lock_file(&path)?
+        } else {
+            fs::File::open(&path)?
+        };
+        // Capture the times before reading from the file updates the access time.
+        let metadata = file.metadata()?;
+        let mut data = Vec::new();
+        self.encode(tag, &mut data)?;
+
+        // Tags with a footer must not be followed by padding.
+        let padding = if self.version == Version::Id3v24 && self.footer {
+            0
+        } else {
+            self.padding.padding(data.len())
+        };
+        let edits = match container::sniff(&mut file)? {
+            container::Format::Mp4 => container::mp4_id32_edits(&mut file, &data, padding)?,
+            container::Format::Wav => container::wav_id3_edits(&mut file, &data, padding)?,
+            container::Format::Aiff => container::aiff_id3_edits(&mut file, &data, padding)?,
+            container::Format::Dsf => container::dsf_id3_edits(&mut file, &data, padding)?,
+            container::Format::Mpeg if self.append && self.version == Version::Id3v24 => {
+                self.append_edits(&mut file, tag, data)?
+            },
+            format => {
+                let location = tag::locate_id3v2(&mut file)?
+                    .unwrap_or(0..0); // Create a new tag if none could be located.
+                let appended = tag::locate_id3v2_appended(&mut file)?
+                    .filter(|appended| appended.start >= location.end && appended.start != 0);
+                let mut edits = Vec::new();
+                if let Some(appended) = appended {
+                    if self.appended_tag == AppendedTag::Merge {
+                        file.seek(io::SeekFrom::Start(appended.start))?;
+                        let old = Tag::read_from((&mut file).take(appended.end - appended.start))?;
+                        let tags = tag::FileTags { prepended: Some(tag.clone()), appended: Some(old), v1: None };
+                        let merged = tags.resolve(tag::TagPolicy::Merge).unwrap_or_default();
+                        if merged.frames().count() != tag.frames().count() {
+                            data.clear();
+                            self.encode(&merged, &mut data)?;
+                        }
+                    }
+                    if self.appended_tag != AppendedTag::Keep {
+                        edits.push((appended, Vec::new()));
+                    }
+                }
+
+                // Reuse the space of the old tag, or reserve some padding for future writes.
+                let region_len = (location.end - location.start) as usize;
+                let padding = if padding == 0 {
+                    0
+                } else if data.len() <= region_len {
+                    region_len - data.len()
+                } else {
+                    padding
+                };
+                data.resize(data.len() + padding, 0);
+
+                edits.insert(0, (location, data));
+                if self.id3v1 && format == container::Format::Mpeg {
+                    let end = file.seek(io::SeekFrom::End(0))?;
+                    let v1_location = ::v1::locate(&mut file)?.unwrap_or(end..end);
+                    edits.push((v1_location, ::v1::Tag::from(tag).to_bytes().to_vec()));
+                }
+                edits
+            },
+        };
+        let edits: Vec<_> = edits.iter().map(|(range, data)| (range.clone(), &data[..])).collect();
+        if edits.iter().all(|(range, data)| range.end - range.start == data.len() as u64) {
+            storage::write_regions(&path, &edits)?;
+        } else if let Some(ref progress) = self.on_progress.0 {
+            storage::rewrite_file_with_progress(&path, &edits, &**progress)?;
+        } else {
+            storage::rewrite_file(&path, &edits)?;
+        }
+
+        if self.preserve_mtime || self.preserve_atime {
+            let mut times = fs::FileTimes::new();
+            if self.preserve_mtime {
+                times = times.set_modified(metadata.modified()?);
+            }
+            if self.preserve_atime {
+                times = times.set_accessed(metadata.accessed()?);
+            }
+            fs::OpenOptions::new().write(true).open(&path)?.set_times(times)?;
         }
-        writer.write_all(b"ID3")?;
-        writer.write_all(&[self.version.minor() as u8, 2])?;
-        writer.write_u8(flags.bits())?;
-        writer.write_u32::<BigEndian>(unsynch::encode_u32(frame_data.len() as u32))?;
-        writer.write_all(&frame_data[..])?;
         Ok(())
     }
 }
@@ -162,10 +1549,12 @@ mod benchmarks {
 
 #[cfg(test)]
 mod tests {
+    extern crate tempdir;
     use super::*;
     use std::fs;
     use std::io;
-    use ::frame::{Frame, Content, PictureType};
+    use byteorder::WriteBytesExt;
+    use ::frame::{Frame, Content, PictureType, Private, UniqueFileIdentifier};
 
     fn make_tag() -> Tag {
         let mut tag = Tag::new();
@@ -191,11 +1580,459 @@ mod tests {
         let mut file = fs::File::open("testdata/id3v24.id3").unwrap();
         let tag = decode(&mut file).unwrap();
         assert_eq!("Title", tag.title().unwrap());
+        assert_eq!("Album Artist", tag.album_artist().unwrap());
         assert_eq!(1, tag.disc().unwrap());
         assert_eq!(1, tag.total_discs().unwrap());
         assert_eq!(PictureType::CoverFront, tag.pictures().nth(0).unwrap().picture_type);
     }
 
+    #[test]
+    fn decode_lazily() {
+        let mut tag = make_tag();
+        tag.add_picture(::frame::Picture {
+            mime_type: "image/png".to_string(),
+            picture_type: PictureType::CoverFront,
+            description: "Cover".to_string(),
+            data: vec![0xff; 4096],
+        });
+        tag.add_frame(Frame::with_content("UFID", Content::UniqueFileIdentifier(UniqueFileIdentifier {
+            owner_identifier: "http://www.id3.org/dummy/ufid.html".to_string(),
+            identifier: b"7FZo5fMqyG5Ys1dm8F1FHa".to_vec(),
+        })));
+        let decoder = DecoderBuilder::default().lazy(true).build().unwrap();
+        for &version in &[Version::Id3v22, Version::Id3v23, Version::Id3v24] {
+            for &compression in &[false, true] {
+                let mut data = Vec::new();
+                EncoderBuilder::default()
+                    .version(version)
+                    .compression(compression)
+                    .build()
+                    .unwrap()
+                    .encode(&tag, &mut data)
+                    .unwrap();
+                let eager = decode(&data[..]).unwrap();
+                let lazy = decoder.decode(&data[..]).unwrap();
+                assert_eq!(format!("{:?}", eager.frames().collect::<Vec<_>>()), format!("{:?}", lazy.frames().collect::<Vec<_>>()));
+                assert_eq!(eager, lazy);
+            }
+        }
+
+        // Frames that can not be decoded only fail when they are accessed.
+        let mut data = Vec::new();
+        tag.write_to(&mut data, Version::Id3v24).unwrap();
+        let mut frame = Vec::new();
+        frame.extend_from_slice(b"TXXX");
+        frame.extend_from_slice(&[0, 0, 0, 2, 0, 0, 0x09, 0x00]);
+        data.splice(10..10, frame.iter().cloned());
+        let size = unsynch::encode_u32((data.len() - 10) as u32);
+        BigEndian::write_u32(&mut data[6..10], size);
+        assert!(decode(&data[..]).is_err());
+        let lazy = decoder.decode(&data[..]).unwrap();
+        assert_eq!(Some("Title"), lazy.title());
+        let txxx = lazy.get("TXXX").unwrap();
+        assert!(txxx.try_content().is_err());
+        assert_eq!(&Content::Unknown(vec![0x09, 0x00]), txxx.content());
+        assert!(txxx.try_content().is_err());
+        assert_eq!(Some("Title"), lazy.get("TIT2").unwrap().try_content().unwrap().text());
+    }
+
+    #[test]
+    fn encode_to_path_keeps_audio() {
+        let tmp = tempdir::TempDir::new("id3_encode").unwrap();
+        let path = tmp.path().join("file.mp3");
+        let audio = [0xff, 0xfb, 0x90, 0x00, 0x01, 0x02];
+        fs::write(&path, &audio[..]).unwrap();
+        let encoder = EncoderBuilder::default().build().unwrap();
+
+        let tag = make_tag();
+        encoder.encode_to_path(&tag, &path).unwrap();
+        let data = fs::read(&path).unwrap();
+        assert_eq!(&audio[..], &data[data.len() - audio.len()..]);
+        assert_eq!(tag, decode(&data[..]).unwrap());
+        let len = data.len();
+
+        // The second write fits in the padding of the first.
+        let mut tag = Tag::new();
+        tag.set_title("Shorter");
+        encoder.encode_to_path(&tag, &path).unwrap();
+        let data = fs::read(&path).unwrap();
+        assert_eq!(len, data.len());
+        assert_eq!(&audio[..], &data[data.len() - audio.len()..]);
+        assert_eq!(tag, decode(&data[..]).unwrap());
+        assert_eq!(1, fs::read_dir(tmp.path()).unwrap().count());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn encode_to_path_in_place() {
+        use std::os::unix::fs::MetadataExt;
+
+        let tmp = tempdir::TempDir::new("id3_encode").unwrap();
+        let path = tmp.path().join("file.mp3");
+        let audio = [0xff, 0xfb, 0x90, 0x00, 0x01, 0x02];
+        fs::write(&path, &audio[..]).unwrap();
+        let encoder = EncoderBuilder::default().id3v1(true).build().unwrap();
+        encoder.encode_to_path(&make_tag(), &path).unwrap();
+        let ino = fs::metadata(&path).unwrap().ino();
+
+        // The tag fits in the padding, so the file itself is kept.
+        let mut tag = make_tag();
+        tag.set_album("Album");
+        encoder.encode_to_path(&tag, &path).unwrap();
+        assert_eq!(ino, fs::metadata(&path).unwrap().ino());
+        assert_eq!(tag, Tag::read_from_path(&path).unwrap());
+        assert_eq!("Album", ::v1::Tag::read_from_path(&path).unwrap().album);
+
+        // A tag that outgrows the padding requires a rewrite.
+        tag.set_text("TIT3", "a".repeat(4096));
+        encoder.encode_to_path(&tag, &path).unwrap();
+        assert_ne!(ino, fs::metadata(&path).unwrap().ino());
+        let data = fs::read(&path).unwrap();
+        assert_eq!(&audio[..], &data[data.len() - 128 - audio.len()..data.len() - 128]);
+        assert_eq!(tag, decode(&data[..]).unwrap());
+    }
+
+    #[test]
+    fn encode_to_path_padding() {
+        let tmp = tempdir::TempDir::new("id3_encode").unwrap();
+        let path = tmp.path().join("file.mp3");
+        let audio = [0xff, 0xfb, 0x90, 0x00, 0x01, 0x02];
+        let tag = make_tag();
+        let mut data = Vec::new();
+        EncoderBuilder::default().build().unwrap().encode(&tag, &mut data).unwrap();
+
+        let strategies = vec![
+            (Padding::None, 0),
+            (Padding::Fixed(100), 100),
+            (Padding::Percent(50), data.len() / 2),
+            (Padding::Custom(Arc::new(|len: usize| 512 - len % 512)), 512 - data.len() % 512),
+        ];
+        for (padding, padding_len) in strategies {
+            fs::write(&path, &audio[..]).unwrap();
+            let encoder = EncoderBuilder::default().padding(padding).build().unwrap();
+            encoder.encode_to_path(&tag, &path).unwrap();
+            let written = fs::read(&path).unwrap();
+            assert_eq!(data.len() + padding_len + audio.len(), written.len());
+            assert_eq!(&audio[..], &written[written.len() - audio.len()..]);
+            assert_eq!(tag, decode(&written[..]).unwrap());
+        }
+    }
+
+    #[test]
+    fn encode_to_path_reports_progress() {
+        use std::sync::Mutex;
+
+        let tmp = tempdir::TempDir::new("id3_encode").unwrap();
+        let path = tmp.path().join("file.mp3");
+        let mut audio = vec![0; 3 * storage::COPY_CHUNK_SIZE];
+        audio[..4].copy_from_slice(&[0xff, 0xfb, 0x90, 0x00]);
+        fs::write(&path, &audio).unwrap();
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        let encoder = EncoderBuilder::default()
+            .build()
+            .unwrap()
+            .on_progress(move |written, total| sink.lock().unwrap().push((written, total)));
+        encoder.encode_to_path(&make_tag(), &path).unwrap();
+
+        let reports = reports.lock().unwrap();
+        let len = fs::metadata(&path).unwrap().len();
+        assert!(reports.len() > 3);
+        assert!(reports.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(reports.iter().all(|&(_, total)| total == len));
+        assert_eq!(len, reports.last().unwrap().0);
+        assert_eq!(&audio[..], &fs::read(&path).unwrap()[(len as usize - audio.len())..]);
+    }
+
+    #[test]
+    fn encode_to_path_writes_id3v1() {
+        let tmp = tempdir::TempDir::new("id3_encode").unwrap();
+        let path = tmp.path().join("file.mp3");
+        let audio = [0xff, 0xfb, 0x90, 0x00, 0x01, 0x02];
+        fs::write(&path, &audio[..]).unwrap();
+        let encoder = EncoderBuilder::default().id3v1(true).build().unwrap();
+
+        let mut tag = make_tag();
+        encoder.encode_to_path(&tag, &path).unwrap();
+        let len = fs::metadata(&path).unwrap().len();
+        assert_eq!("Title", ::v1::Tag::read_from_path(&path).unwrap().title);
+
+        tag.set_title("Other");
+        encoder.encode_to_path(&tag, &path).unwrap();
+        let data = fs::read(&path).unwrap();
+        assert_eq!(len, data.len() as u64);
+        assert_eq!(&audio[..], &data[data.len() - 128 - audio.len()..data.len() - 128]);
+        assert_eq!(tag, decode(&data[..]).unwrap());
+        assert_eq!("Other", ::v1::Tag::read_from_path(&path).unwrap().title);
+    }
+
+    #[test]
+    fn encode_to_path_waits_for_lock() {
+        use std::thread;
+        use std::time::Duration;
+
+        let tmp = tempdir::TempDir::new("id3_encode").unwrap();
+        let path = tmp.path().join("file.mp3");
+        fs::write(&path, b"").unwrap();
+        let held = storage::lock_file(&path).unwrap();
+
+        let thread_path = path.clone();
+        let writer = thread::spawn(move || {
+            let encoder = EncoderBuilder::default().lock(true).build().unwrap();
+            encoder.encode_to_path(&make_tag(), &thread_path).unwrap();
+        });
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(0, fs::metadata(&path).unwrap().len());
+        drop(held);
+        writer.join().unwrap();
+        assert_eq!(make_tag(), decode(fs::File::open(&path).unwrap()).unwrap());
+        // The lock has been released.
+        storage::lock_file(&path).unwrap();
+    }
+
+    #[test]
+    fn encode_to_path_preserves_times() {
+        use std::time::{Duration, SystemTime};
+
+        let tmp = tempdir::TempDir::new("id3_encode").unwrap();
+        let path = tmp.path().join("file.mp3");
+        fs::write(&path, b"").unwrap();
+        let past = SystemTime::now() - Duration::from_secs(86_400);
+        fs::OpenOptions::new().write(true).open(&path).unwrap()
+            .set_times(fs::FileTimes::new().set_modified(past).set_accessed(past))
+            .unwrap();
+
+        let encoder = EncoderBuilder::default()
+            .preserve_mtime(true)
+            .preserve_atime(true)
+            .build()
+            .unwrap();
+        encoder.encode_to_path(&make_tag(), &path).unwrap();
+        let metadata = fs::metadata(&path).unwrap();
+        assert_eq!(past, metadata.modified().unwrap());
+        assert_eq!(past, metadata.accessed().unwrap());
+
+        EncoderBuilder::default().build().unwrap()
+            .encode_to_path(&make_tag(), &path)
+            .unwrap();
+        assert!(fs::metadata(&path).unwrap().modified().unwrap() > past);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn encode_to_path_follows_symlinks() {
+        let tmp = tempdir::TempDir::new("id3_encode").unwrap();
+        let path = tmp.path().join("file.mp3");
+        let link = tmp.path().join("link.mp3");
+        fs::write(&path, b"").unwrap();
+        ::std::os::unix::fs::symlink(&path, &link).unwrap();
+        let encoder = EncoderBuilder::default().build().unwrap();
+        encoder.encode_to_path(&make_tag(), &link).unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(make_tag(), decode(fs::File::open(&path).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn write_grouped_frames() {
+        let mut tag = Tag::new();
+        tag.register_group("mailto:grouping@example.com", 0x80, Vec::new());
+        tag.set_title("Title");
+        tag.set_artist("Artist");
+        let mut frame = tag.get("TIT2").unwrap().clone();
+        frame.set_group_identifier(Some(0x80));
+        tag.add_frame(frame);
+        for &version in &[Version::Id3v23, Version::Id3v24] {
+            let mut written = Vec::new();
+            EncoderBuilder::default().version(version).build().unwrap().encode(&tag, &mut written).unwrap();
+            let read = decode(&written[..]).unwrap();
+            assert_eq!(Some("Title"), read.title());
+            assert_eq!(Some(0x80), read.get("TIT2").unwrap().group_identifier());
+            assert_eq!(None, read.get("TPE1").unwrap().group_identifier());
+            assert!(read.group_registration(0x80).is_some());
+        }
+        let mut written = Vec::new();
+        EncoderBuilder::default().version(Version::Id3v22).build().unwrap().encode(&tag, &mut written).unwrap();
+        let read = decode(&written[..]).unwrap();
+        assert_eq!(None, read.get("TIT2").unwrap().group_identifier());
+        assert!(read.group_registration(0x80).is_none());
+    }
+
+    #[test]
+    fn read_encrypted_frames() {
+        let xor = |_: &frame::FrameHeader, data: &[u8]| -> ::Result<Vec<u8>> {
+            Ok(data.iter().map(|b| b ^ 0x5a).collect())
+        };
+        let plain = b"\x03Title";
+        let mut frame_data = b"TIT2".to_vec();
+        frame_data.write_u32::<BigEndian>(unsynch::encode_u32(plain.len() as u32 + 1)).unwrap();
+        frame_data.write_u16::<BigEndian>(0x0004).unwrap();
+        frame_data.push(0x80);
+        frame_data.extend(plain.iter().map(|b| b ^ 0x5a));
+        let encr = b"owner\x00\x80";
+        frame_data.extend_from_slice(b"ENCR");
+        frame_data.write_u32::<BigEndian>(unsynch::encode_u32(encr.len() as u32)).unwrap();
+        frame_data.write_u16::<BigEndian>(0).unwrap();
+        frame_data.extend_from_slice(encr);
+        let mut data = b"ID3\x04\x00\x00".to_vec();
+        data.write_u32::<BigEndian>(unsynch::encode_u32(frame_data.len() as u32)).unwrap();
+        data.extend(frame_data);
+
+        let decoder = DecoderBuilder::default().build().unwrap().decryptor(0x80, xor);
+        let tag = decoder.decode(&data[..]).unwrap();
+        assert_eq!(Some("Title"), tag.title());
+        assert_eq!(Some(0x80), tag.encryption_methods().next().map(|method| method.symbol));
+
+        // Without a decryptor, the frame is preserved and written back encrypted.
+        let tag = decode(&data[..]).unwrap();
+        let frame = tag.get("TIT2").unwrap();
+        assert_eq!(Some(0x80), frame.encryption_method());
+        assert_eq!(None, tag.title());
+        for &version in &[Version::Id3v23, Version::Id3v24] {
+            let mut written = Vec::new();
+            EncoderBuilder::default().version(version).build().unwrap().encode(&tag, &mut written).unwrap();
+            assert_eq!(Some("Title"), decoder.decode(&written[..]).unwrap().title());
+        }
+        let mut written = Vec::new();
+        EncoderBuilder::default().version(Version::Id3v22).build().unwrap().encode(&tag, &mut written).unwrap();
+        assert_eq!(0, decoder.decode(&written[..]).unwrap().frames().count());
+    }
+
+    #[test]
+    fn write_streamed_objects() {
+        let payload: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        for &version in &[Version::Id3v22, Version::Id3v23, Version::Id3v24] {
+            let encoder = EncoderBuilder::default().version(version).unsynchronisation(false).build().unwrap();
+            let mut source = &payload[..];
+            let object = StreamedObject {
+                mime_type: "application/octet-stream".to_string(),
+                filename: "data.bin".to_string(),
+                description: "Data".to_string(),
+                len: payload.len() as u64,
+                data: &mut source,
+            };
+            let mut data = Vec::new();
+            encoder.encode_with_objects(&make_tag(), vec![object], &mut data).unwrap();
+            let tag = decode(&data[..]).unwrap();
+            let object = tag.encapsulated_objects().next().unwrap();
+            assert_eq!(("data.bin", &payload), (object.filename.as_str(), &object.data));
+            assert_eq!(make_tag().title(), tag.title());
+        }
+
+        let encoder = EncoderBuilder::default().unsynchronisation(false).build().unwrap();
+        let mut source = &payload[..10];
+        let object = StreamedObject {
+            mime_type: "".to_string(),
+            filename: "".to_string(),
+            description: "".to_string(),
+            len: 11,
+            data: &mut source,
+        };
+        assert!(encoder.encode_with_objects(&make_tag(), vec![object], io::sink()).is_err());
+        let encoder = EncoderBuilder::default().build().unwrap();
+        assert!(encoder.encode_with_objects(&make_tag(), Vec::new(), io::sink()).is_err());
+    }
+
+    #[test]
+    fn visit_frames() {
+        let data = fs::read("testdata/id3v24.id3").unwrap();
+        let index = decode_index(&data[..]).unwrap();
+        let mut visited = Vec::new();
+        DecoderBuilder::default().build().unwrap().visit(&data[..], |header, frame| {
+            assert_eq!(header.id(), frame.id());
+            visited.push(header.offset());
+        }).unwrap();
+        assert_eq!(index.iter().map(|h| h.offset()).collect::<Vec<_>>(), visited);
+    }
+
+    #[test]
+    fn read_on_frame() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let mut data = Vec::new();
+        EncoderBuilder::default().unsynchronisation(false).build().unwrap().encode(&make_tag(), &mut data).unwrap();
+        data.extend_from_slice(b"audio");
+        let index = decode_index(&data[..]).unwrap();
+
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let seen_cb = seen.clone();
+        let decoder = DecoderBuilder::default().build().unwrap().on_frame(move |header| {
+            seen_cb.borrow_mut().push((header.id().to_string(), header.offset()));
+            if seen_cb.borrow().len() == 2 {
+                FrameDecision::Stop
+            } else {
+                FrameDecision::Decode
+            }
+        });
+        let mut reader = &data[..];
+        let tag = decoder.decode(&mut reader).unwrap();
+        assert_eq!(b"audio", reader);
+        assert_eq!(1, tag.frames().count());
+        let expected: Vec<_> = index.iter().take(2).map(|h| (h.id().to_string(), h.offset())).collect();
+        assert_eq!(expected, *seen.borrow());
+    }
+
+    #[test]
+    fn read_max_tag_size() {
+        let file = fs::File::open("testdata/id3v24.id3").unwrap();
+        let decoder = DecoderBuilder::default().max_tag_size(Some(1024)).build().unwrap();
+        match decoder.decode(file) {
+            Err(::Error{ kind: ::ErrorKind::InvalidInput, .. }) => (),
+            _ => panic!("expected the tag to be rejected"),
+        }
+
+        let mut data = Vec::new();
+        EncoderBuilder::default().build().unwrap().encode(&make_tag(), &mut data).unwrap();
+        data.extend_from_slice(b"audio");
+        let decoder = DecoderBuilder::default().max_tag_size(Some(data.len())).build().unwrap();
+        let mut reader = io::Cursor::new(data).bytes().map(|b| b.unwrap());
+        let mut stream = NonSeekable(&mut reader);
+        assert_eq!(make_tag(), decoder.decode(&mut stream).unwrap());
+        assert_eq!(b"audio".to_vec(), reader.collect::<Vec<u8>>());
+    }
+
+    /// A reader that only implements `io::Read`, like a network stream.
+    struct NonSeekable<'a, I: Iterator<Item=u8> + 'a>(&'a mut I);
+
+    impl<'a, I: Iterator<Item=u8>> io::Read for NonSeekable<'a, I> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match (buf.first_mut(), self.0.next()) {
+                (Some(b), Some(next)) => {
+                    *b = next;
+                    Ok(1)
+                },
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn read_leaves_reader_after_tag() {
+        let tag = make_tag();
+        let mut buffer = Vec::new();
+        EncoderBuilder::default().build().unwrap().encode(&tag, &mut buffer).unwrap();
+        buffer.extend_from_slice(b"audio");
+        let mut reader = io::Cursor::new(buffer);
+        assert_eq!(tag, decode(&mut reader).unwrap());
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(b"audio", &rest[..]);
+    }
+
+    #[test]
+    fn read_index_id3v24() {
+        let file = fs::File::open("testdata/id3v24.id3").unwrap();
+        let index = decode_index(file).unwrap();
+        let ids: Vec<&str> = index.iter().map(|h| h.id()).collect();
+        assert_eq!(ids, ["TALB", "TPE1", "TIT2", "TRCK", "TPOS", "TDRC", "TCON", "APIC", "TPE2"]);
+        assert_eq!(10, index[0].offset());
+        assert_eq!(7, index[0].size());
+        assert_eq!(index[0].offset() + 10 + 7, index[1].offset());
+        // The sizes of ID3v2.4 frames are synchsafe integers.
+        assert_eq!(26_536, index[7].size());
+    }
+
     #[test]
     fn write_id3v22() {
         let tag = make_tag();
@@ -209,6 +2046,41 @@ mod tests {
         assert_eq!(tag, tag_read);
     }
 
+    #[test]
+    fn write_id3v22_frames() {
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+        tag.add_picture(Picture {
+            mime_type: "image/gif".to_string(),
+            picture_type: PictureType::CoverFront,
+            description: "".to_string(),
+            data: vec![1, 2, 3],
+        });
+        let mut buffer = Vec::new();
+        EncoderBuilder::default()
+            .version(Version::Id3v22)
+            .unsynchronisation(false)
+            .compression(true)
+            .build()
+            .unwrap()
+            .encode(&tag, &mut buffer).unwrap();
+        assert_eq!((&b"ID3\x02"[..], 0), (&buffer[..4], buffer[5]));
+        // Frames have a three byte identifier and size, pictures a three letter image format.
+        assert_eq!(b"TT2\x00\x00\x0d", &buffer[10..16]);
+        assert_eq!(b"PIC", &buffer[29..32]);
+        assert_eq!(b"GIF", &buffer[36..39]);
+        assert_eq!(tag, decode(&buffer[..]).unwrap());
+
+        tag.add_picture(Picture {
+            mime_type: "application/octet-stream".to_string(),
+            picture_type: PictureType::Other,
+            description: "".to_string(),
+            data: vec![1, 2, 3],
+        });
+        buffer.clear();
+        assert!(EncoderBuilder::default().version(Version::Id3v22).build().unwrap().encode(&tag, &mut buffer).is_err());
+    }
+
     #[test]
     fn write_id3v22_unsynch() {
         let tag = make_tag();
@@ -320,4 +2192,362 @@ mod tests {
         let tag_read = decode(&mut io::Cursor::new(buffer)).unwrap();
         assert_eq!(tag, tag_read);
     }
+
+    #[test]
+    fn read_extended_header_crc() {
+        let mut frames = Vec::new();
+        EncoderBuilder::default().version(Version::Id3v23).unsynchronisation(false).build().unwrap()
+            .encode(&make_tag(), &mut frames).unwrap();
+        let frames = frames.split_off(10);
+        let mut crc = Crc::new();
+        crc.update(&frames);
+
+        let mut data = b"ID3\x03\x00\x40".to_vec();
+        data.write_u32::<BigEndian>(unsynch::encode_u32(14 + frames.len() as u32 + 16)).unwrap();
+        data.write_u32::<BigEndian>(10).unwrap();
+        data.write_u16::<BigEndian>(0x8000).unwrap();
+        data.write_u32::<BigEndian>(16).unwrap();
+        data.write_u32::<BigEndian>(crc.sum()).unwrap();
+        data.extend_from_slice(&frames);
+        data.extend_from_slice(&[0; 16]);
+        let tag = decode(&data[..]).unwrap();
+        assert_eq!(make_tag(), tag);
+        assert_eq!(Some(crc.sum()), tag.extended_header().unwrap().crc);
+        assert_eq!(Some(true), tag.crc_valid());
+        assert_eq!(make_tag().frames().count(), decode_index(&data[..]).unwrap().len());
+
+        let mut frames = Vec::new();
+        EncoderBuilder::default().version(Version::Id3v24).build().unwrap()
+            .encode(&make_tag(), &mut frames).unwrap();
+        let frames = frames.split_off(10);
+        let mut crc = Crc::new();
+        crc.update(&frames);
+        let sum = u64::from(crc.sum());
+        let mut data = b"ID3\x04\x00\x40".to_vec();
+        data.write_u32::<BigEndian>(unsynch::encode_u32(14 + frames.len() as u32)).unwrap();
+        data.write_u32::<BigEndian>(unsynch::encode_u32(14)).unwrap();
+        data.extend_from_slice(&[1, 0x30, 5]);
+        data.extend((0..5).rev().map(|i| (sum >> (7 * i)) as u8 & 0x7f));
+        data.extend_from_slice(&[1, 0x84]);
+        data.extend_from_slice(&frames);
+        let mut tag = decode(&data[..]).unwrap();
+        assert_eq!(make_tag(), tag);
+        let header = tag.extended_header().unwrap().clone();
+        assert_eq!((false, Some(Restrictions::from_byte(0x84))), (header.update, header.restrictions));
+        assert_eq!(Some(true), tag.crc_valid());
+
+        let last = data.len() - 1;
+        data[last] ^= 0x01;
+        tag = decode(&data[..]).unwrap();
+        assert_eq!(Some(false), tag.crc_valid());
+        assert_eq!(None, make_tag().crc_valid());
+    }
+
+    #[test]
+    fn read_extended_header_truncated() {
+        // The declared sizes do not cover the flags of the extended header.
+        let mut data = b"ID3\x04\x00\x40\x00\x00\x00\x20".to_vec();
+        data.extend_from_slice(&[0, 0, 0, 1, 1, 0]);
+        data.extend_from_slice(&[0; 26]);
+        assert!(decode(&data[..]).is_err());
+        assert!(decode_index(&data[..]).is_err());
+
+        let mut data = b"ID3\x03\x00\x40\x00\x00\x00\x20".to_vec();
+        data.extend_from_slice(&[0, 0, 0, 2, 0, 0]);
+        data.extend_from_slice(&[0; 26]);
+        assert!(decode(&data[..]).is_err());
+        assert!(decode_index(&data[..]).is_err());
+    }
+
+    #[test]
+    fn write_extended_header() {
+        let restrictions = Restrictions {
+            tag_size: TagSizeRestriction::Frames64Size128K,
+            text_encoding: true,
+            text_fields_size: TextSizeRestriction::Chars128,
+            image_encoding: false,
+            image_size: ImageSizeRestriction::Exactly64,
+        };
+        for &version in &[Version::Id3v23, Version::Id3v24] {
+            for &unsynchronisation in &[false, true] {
+                let mut data = Vec::new();
+                EncoderBuilder::default()
+                    .version(version)
+                    .unsynchronisation(unsynchronisation)
+                    .crc(true)
+                    .restrictions(Some(restrictions))
+                    .build()
+                    .unwrap()
+                    .encode(&make_tag(), &mut data).unwrap();
+                let tag = decode(&data[..]).unwrap();
+                assert_eq!(make_tag(), tag);
+                assert_eq!(Some(true), tag.crc_valid());
+                let expected = if version == Version::Id3v24 { Some(restrictions) } else { None };
+                assert_eq!(expected, tag.extended_header().unwrap().restrictions);
+            }
+        }
+
+        let mut data = Vec::new();
+        EncoderBuilder::default()
+            .restrictions(Some(restrictions))
+            .build()
+            .unwrap()
+            .encode(&make_tag(), &mut data).unwrap();
+        let tag = decode(&data[..]).unwrap();
+        assert_eq!(None, tag.crc_valid());
+        assert_eq!(Some(restrictions), tag.extended_header().unwrap().restrictions);
+    }
+
+    #[test]
+    fn read_tag_stream() {
+        // A reader that only implements `Read`, like a network stream, and returns short reads.
+        struct Trickle<'a>(&'a [u8]);
+        impl<'a> io::Read for Trickle<'a> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let n = cmp::min(3, cmp::min(buf.len(), self.0.len()));
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
+            }
+        }
+
+        let mut stream = b"IID3\x04\x00\x00\xff\xff\xff\xffIDID".to_vec();
+        EncoderBuilder::default().footer(true).build().unwrap().encode(&make_tag(), &mut stream).unwrap();
+        stream.extend_from_slice(&[0xff, 0xfb, 0x90, 0x00, b'I', b'D']);
+        let mut second = Tag::new();
+        second.set_title("Second");
+        second.write_to(&mut stream, Version::Id3v23).unwrap();
+        stream.extend_from_slice(&[0xff, 0xfb, 0x90, 0x00]);
+
+        let tags: Vec<_> = DecoderBuilder::default().build().unwrap()
+            .tags(Trickle(&stream))
+            .map(|tag| tag.unwrap())
+            .collect();
+        assert_eq!(vec![make_tag(), second], tags);
+    }
+
+    #[test]
+    fn write_footer() {
+        let mut data = Vec::new();
+        EncoderBuilder::default().footer(true).build().unwrap().encode(&make_tag(), &mut data).unwrap();
+        let footer = &data[data.len() - 10..];
+        assert_eq!(b"3DI", &footer[..3]);
+        assert_eq!(&data[3..10], &footer[3..]);
+        assert_eq!(0x10, data[5] & 0x10);
+        assert_eq!(Some(0..data.len() as u64), tag::locate_id3v2_appended(io::Cursor::new(&data)).unwrap());
+
+        data.extend_from_slice(b"audio");
+        let mut reader = &data[..];
+        assert_eq!(make_tag(), decode(&mut reader).unwrap());
+        assert_eq!(b"audio", reader);
+
+        // Footers are exclusive to ID3v2.4.
+        data.clear();
+        EncoderBuilder::default().version(Version::Id3v23).footer(true).build().unwrap()
+            .encode(&make_tag(), &mut data).unwrap();
+        assert_eq!(0, data[5] & 0x10);
+        assert_eq!(make_tag(), decode(&data[..]).unwrap());
+    }
+
+    #[test]
+    fn id3v23_tag_unsynch() {
+        let mut tag = Tag::new();
+        tag.add_frame(Frame::with_content("UFID", Content::UniqueFileIdentifier(UniqueFileIdentifier {
+            owner_identifier: "owner".to_string(),
+            identifier: vec![0xff, 0xe0, 0xff, 0x00, 0x42, 0xff],
+        })));
+        tag.set_title("Title");
+        for &version in &[Version::Id3v22, Version::Id3v23] {
+            let mut buffer = Vec::new();
+            EncoderBuilder::default()
+                .unsynchronisation(true)
+                .version(version)
+                .build()
+                .unwrap()
+                .encode(&tag, &mut buffer).unwrap();
+            assert_eq!(0x80, buffer[5]);
+            assert!(!buffer.windows(2).any(|w| w[0] == 0xff && w[1] >= 0xe0));
+            assert_eq!(tag, decode(&buffer[..]).unwrap());
+            let index = decode_index(&buffer[..]).unwrap();
+            assert_eq!(2, index.len());
+        }
+
+        // The frame size refers to the synchronised data.
+        let data = b"owner\x00\xff\x00\xe0\xff\x00\x00";
+        let mut buffer = b"ID3\x03\x00\x80".to_vec();
+        buffer.write_u32::<BigEndian>(unsynch::encode_u32(10 + data.len() as u32)).unwrap();
+        buffer.extend_from_slice(b"PRIV");
+        buffer.write_u32::<BigEndian>(data.len() as u32 - 2).unwrap();
+        buffer.write_u16::<BigEndian>(0).unwrap();
+        buffer.extend_from_slice(data);
+        let read = decode(&buffer[..]).unwrap();
+        assert_eq!(&[0xff, 0xe0, 0xff, 0x00][..], &read.privates().next().unwrap().data[..]);
+    }
+
+    #[test]
+    fn id3v24_frame_unsynch() {
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+        tag.add_frame(Frame::with_content("PRIV", Content::Private(Private {
+            owner: "owner".to_string(),
+            data: vec![0xff, 0xe0, 0xff, 0x00, 0x42, 0xff],
+        })));
+        let mut buffer = Vec::new();
+        EncoderBuilder::default()
+            .unsynchronisation(true)
+            .version(Version::Id3v24)
+            .build()
+            .unwrap()
+            .encode(&tag, &mut buffer).unwrap();
+        assert!(!buffer.windows(2).any(|w| w[0] == 0xff && w[1] >= 0xe0));
+        let headers = decode_index(&buffer[..]).unwrap();
+        assert!(headers.iter().all(|h| h.flags() & 0x0003 == 0x0003));
+        assert_eq!(tag, decode(&buffer[..]).unwrap());
+
+        // Frames are also unsynchronised if only the tag header says so.
+        let data = b"owner\x00\xff\x00\xe0\xff\x00";
+        let mut frame_data = b"PRIV".to_vec();
+        frame_data.write_u32::<BigEndian>(unsynch::encode_u32(data.len() as u32)).unwrap();
+        frame_data.write_u16::<BigEndian>(0).unwrap();
+        frame_data.extend_from_slice(data);
+        let mut buffer = b"ID3\x04\x00\x80".to_vec();
+        buffer.write_u32::<BigEndian>(unsynch::encode_u32(frame_data.len() as u32)).unwrap();
+        buffer.extend(frame_data);
+        let read = decode(&buffer[..]).unwrap();
+        assert_eq!(&[0xff, 0xe0, 0xff][..], &read.privates().next().unwrap().data[..]);
+    }
+
+    #[test]
+    fn write_with_budget() {
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+        tag.add_picture(Picture {
+            mime_type: "image/png".to_string(),
+            picture_type: PictureType::CoverFront,
+            description: "".to_string(),
+            data: vec![0xaa; 8192],
+        });
+        tag.add_comment(Comment { lang: "eng".to_string(), description: "".to_string(), text: "c".repeat(100) });
+
+        let encoder = EncoderBuilder::default().version(Version::Id3v23).build().unwrap();
+        let budget = SizeBudget::new(2048)
+            .priorities(vec!["TIT2", "COMM", "APIC"])
+            .shrink_pictures(|picture, max_len| {
+                Some(Picture { data: picture.data[..max_len.min(1024)].to_vec(), ..picture.clone() })
+            });
+        let mut data = Vec::new();
+        let report = encoder.encode_with_budget(&tag, &budget, &mut data).unwrap();
+        assert!(data.len() <= 2048);
+        assert!(report.dropped.is_empty());
+        assert_eq!(vec!["APIC"], report.shrunk.iter().map(|f| f.id()).collect::<Vec<_>>());
+        let read = decode(&data[..]).unwrap();
+        assert_eq!(1024, read.pictures().next().unwrap().data.len());
+        assert_eq!(100, read.comments().next().unwrap().text.len());
+
+        // The comment is the last frame to be omitted and only fits in a truncated form.
+        let budget = SizeBudget::new(64).priorities(vec!["COMM"]);
+        data.clear();
+        let report = encoder.encode_with_budget(&tag, &budget, &mut data).unwrap();
+        assert!(data.len() <= 64);
+        assert_eq!(vec!["APIC", "TIT2"], report.dropped.iter().map(|f| f.id()).collect::<Vec<_>>());
+        let text = decode(&data[..]).unwrap().comments().next().unwrap().text.clone();
+        assert!(!text.is_empty() && text.len() < 100 && text.chars().all(|c| c == 'c'));
+
+        assert!(encoder.encode_with_budget(&tag, &SizeBudget::new(8), &mut data).is_err());
+
+        // The extended header, footer and unsynchronisation count towards the budget.
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+        tag.set_artist("Artist");
+        tag.add_frame(Frame::with_content("PRIV", Content::Unknown(vec![0xff; 32])));
+        let encoders = vec![
+            EncoderBuilder::default().version(Version::Id3v23).build().unwrap(),
+            EncoderBuilder::default().crc(true).footer(true).build().unwrap(),
+        ];
+        for encoder in &encoders {
+            for max_size in 20..160 {
+                let mut data = Vec::new();
+                if encoder.encode_with_budget(&tag, &SizeBudget::new(max_size), &mut data).is_ok() {
+                    assert!(data.len() <= max_size, "{} > {}", data.len(), max_size);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn write_converted_dates() {
+        let mut tag = Tag::new();
+        tag.set_date_recorded("2014-05-04T12:30:15".parse().unwrap());
+        tag.set_text("TDOR", "1999-01-02");
+        let mut data = Vec::new();
+        EncoderBuilder::default().version(Version::Id3v23).build().unwrap().encode(&tag, &mut data).unwrap();
+        let read = decode(&data[..]).unwrap();
+        assert!(read.get("TDRC").is_none() && read.get("TDOR").is_none());
+        let texts: Vec<_> = ["TYER", "TDAT", "TIME", "TORY"].iter()
+            .map(|id| read.get(id).and_then(|f| f.content().text()).unwrap())
+            .collect();
+        assert_eq!(vec!["2014", "0405", "1230", "1999"], texts);
+        assert_eq!("2014-05-04T12:30", read.date_recorded().unwrap().to_string());
+
+        data.clear();
+        EncoderBuilder::default().build().unwrap().encode(&read, &mut data).unwrap();
+        let read = decode(&data[..]).unwrap();
+        assert!(["TYER", "TDAT", "TIME", "TORY"].iter().all(|id| read.get(id).is_none()));
+        assert_eq!(Some("2014-05-04T12:30"), read.get("TDRC").and_then(|f| f.content().text()));
+        assert_eq!(Some("1999"), read.get("TDOR").and_then(|f| f.content().text()));
+
+        // A recording time that can not be split is kept as the recording dates.
+        tag.set_text("TDRC", "summer 2014");
+        data.clear();
+        EncoderBuilder::default().version(Version::Id3v23).build().unwrap().encode(&tag, &mut data).unwrap();
+        assert_eq!(Some("summer 2014"), decode(&data[..]).unwrap().get("TRDA").and_then(|f| f.content().text()));
+    }
+
+    #[test]
+    fn write_involved_people() {
+        let mut tag = Tag::new();
+        tag.set_involved_people(vec![("producer", "Producer")]);
+        tag.set_musician_credits(vec![("bass", "Bassist")]);
+        for &version in &[Version::Id3v22, Version::Id3v23] {
+            let mut data = Vec::new();
+            EncoderBuilder::default().version(version).build().unwrap().encode(&tag, &mut data).unwrap();
+            let read = decode(&data[..]).unwrap();
+            assert!(read.get("TIPL").is_none() && read.get("TMCL").is_none());
+            assert_eq!(vec![("producer", "Producer"), ("bass", "Bassist")], read.involved_people());
+
+            let mut data = Vec::new();
+            EncoderBuilder::default().build().unwrap().encode(&read, &mut data).unwrap();
+            let read = decode(&data[..]).unwrap();
+            assert!(read.get("IPLS").is_none());
+            assert_eq!(vec![("producer", "Producer")], read.involved_people());
+            assert_eq!(vec![("bass", "Bassist")], read.musician_credits());
+        }
+    }
+
+    #[test]
+    fn write_genre_style() {
+        let mut tag = Tag::new();
+        tag.set_genre("(17)(RX)Psytrance");
+        let genre = |tag: &Tag, style, version| {
+            let mut data = Vec::new();
+            EncoderBuilder::default()
+                .version(version)
+                .genre_style(Some(style))
+                .build()
+                .unwrap()
+                .encode(tag, &mut data)
+                .unwrap();
+            decode(&data[..]).unwrap().genre().unwrap().to_string()
+        };
+        assert_eq!("(17)(RX)Psytrance", genre(&tag, GenreStyle::Numeric, Version::Id3v23));
+        assert_eq!("17\0RX\0Psytrance", genre(&tag, GenreStyle::Numeric, Version::Id3v24));
+        assert_eq!("Rock\0Remix\0Psytrance", genre(&tag, GenreStyle::Textual, Version::Id3v24));
+        tag.set_genre("(31)Psytrance");
+        assert_eq!("Trance\0Psytrance", genre(&tag, GenreStyle::Textual, Version::Id3v24));
+        tag.set_genre("Remix\0Metal");
+        assert_eq!("(RX)(9)", genre(&tag, GenreStyle::Numeric, Version::Id3v22));
+        tag.set_genre("(9)");
+        assert_eq!("Metal", genre(&tag, GenreStyle::Textual, Version::Id3v23));
+        assert_eq!("9", genre(&tag, GenreStyle::Numeric, Version::Id3v24));
+    }
 }
@@ -51,17 +51,24 @@ impl<R> Reader<R>
 impl<R> io::Read for Reader<R>
     where R: io::Read {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        if self.next >= self.available {
-            self.available = self.reader.read(&mut self.buf)?;
-            self.next = 0;
-            if self.available == 0 {
-                return Ok(0);
+        loop {
+            if self.next >= self.available {
+                self.available = self.reader.read(&mut self.buf)?;
+                self.next = 0;
+                if self.available == 0 {
+                    return Ok(0);
+                }
             }
+            // The discarded byte may be the last one in the buffer, which must not be mistaken
+            // for the end of the stream.
+            if self.discard_next_null_byte && self.buf[self.next] == 0x00 {
+                self.next += 1;
+                self.discard_next_null_byte = false;
+                continue;
+            }
+            self.discard_next_null_byte = false;
+            break;
         }
-        if self.discard_next_null_byte && self.buf[self.next] == 0x00 {
-            self.next += 1;
-        }
-        self.discard_next_null_byte = false;
 
         let max = cmp::min(self.available - self.next, buf.len());
         let mut copy_range = self.next..self.next;
@@ -82,16 +89,22 @@ impl<R> io::Read for Reader<R>
 }
 
 /// Applies the unsynchronization scheme to a byte buffer.
+///
+/// A null byte is inserted after every 0xFF byte that is followed by a null byte or by a byte that
+/// would make the pair look like an MPEG frame sync. A null byte is also appended if the buffer
+/// ends with 0xFF, because the buffer may be followed by such a byte.
 pub fn encode_vec(buffer: &mut Vec<u8>) {
-    let mut repeat_next_null_byte = false;
-    let mut i = 0;
-    while i < buffer.len() {
-        if buffer[i] == 0x00 && repeat_next_null_byte {
-            buffer.insert(i, 0);
-            i += 1;
+    let needs_guard = buffer.windows(2).any(|w| w[0] == 0xFF && (w[1] == 0x00 || w[1] >= 0xE0))
+        || buffer.last() == Some(&0xFF);
+    if !needs_guard {
+        return;
+    }
+    let from_buf = mem::take(buffer);
+    for (i, &b) in from_buf.iter().enumerate() {
+        buffer.push(b);
+        if b == 0xFF && from_buf.get(i + 1).map_or(true, |&next| next == 0x00 || next >= 0xE0) {
+            buffer.push(0x00);
         }
-        repeat_next_null_byte = buffer[i] == 0xFF;
-        i += 1;
     }
 }
 
@@ -121,5 +134,17 @@ mod tests {
         assert_eq!(v, [66, 0, 255, 0, 0, 255, 0, 0, 0, 255, 66]);
         decode_vec(&mut v);
         assert_eq!(v, [66, 0, 255, 0, 255, 0, 0, 255, 66]);
+
+        let mut v = vec![255, 0xfb, 255, 0x44, 255];
+        encode_vec(&mut v);
+        assert_eq!(v, [255, 0, 0xfb, 255, 0x44, 255, 0]);
+        decode_vec(&mut v);
+        assert_eq!(v, [255, 0xfb, 255, 0x44, 255]);
+
+        // The null bytes fall on the boundaries of the internal buffer of the reader.
+        let mut v = vec![255; 8192];
+        encode_vec(&mut v);
+        decode_vec(&mut v);
+        assert_eq!(vec![255; 8192], v);
     }
 }
@@ -0,0 +1,230 @@
+use std::fmt::Write;
+use ::frame::{SynchronisedLyrics, SynchronisedLyricsType, TimestampFormat};
+
+
+/// A timed piece of text in a subtitle file. Times are in milliseconds.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cue {
+    /// The time at which the text is shown.
+    pub start: u32,
+    /// The time at which the text is hidden.
+    pub end: u32,
+    /// The text, which may span multiple lines.
+    pub text: String,
+}
+
+/// The duration of the last cue, since synchronised lyrics do not store when the last line ends.
+const LAST_CUE_DURATION: u32 = 5000;
+
+/// Converts the lines of synchronised lyrics to cues. Each cue lasts until the next line starts.
+/// Empty lines are not converted, they only end the previous cue.
+///
+/// Returns an error if the timestamps are in MPEG frames, since they can not be converted to
+/// milliseconds without knowing the frame rate of the audio.
+///
+/// # Example
+/// ```
+/// use id3::frame::{SynchronisedLyrics, SynchronisedLyricsType, TimestampFormat};
+/// use id3::subtitles;
+///
+/// let lyrics = SynchronisedLyrics {
+///     lang: "eng".to_string(),
+///     timestamp_format: TimestampFormat::Ms,
+///     content_type: SynchronisedLyricsType::Transcription,
+///     description: "".to_string(),
+///     content: vec![(0, "Welcome".to_string()), (1500, "\nto the show".to_string())],
+/// };
+/// let cues = subtitles::cues(&lyrics).unwrap();
+/// assert_eq!("WEBVTT\n\n00:00:00.000 --> 00:00:01.500\nWelcome\n\n00:00:01.500 --> 00:00:06.500\nto the show\n",
+///            subtitles::write_webvtt(&cues));
+/// ```
+pub fn cues(lyrics: &SynchronisedLyrics) -> ::Result<Vec<Cue>> {
+    if lyrics.timestamp_format != TimestampFormat::Ms {
+        return Err(::Error::new(::ErrorKind::UnsupportedFeature, "timestamps in MPEG frames can not be converted to subtitles"));
+    }
+    let lines = lyrics.lines();
+    let mut cues = Vec::with_capacity(lines.len());
+    for (i, (start, text)) in lines.iter().enumerate() {
+        if text.is_empty() {
+            continue;
+        }
+        let end = match lines.get(i + 1) {
+            Some(next) => next.0,
+            None => start.saturating_add(LAST_CUE_DURATION),
+        };
+        cues.push(Cue { start: *start, end, text: text.clone() });
+    }
+    Ok(cues)
+}
+
+/// Converts cues to synchronised lyrics with millisecond timestamps, one line per cue. If a cue
+/// ends before the next one starts, an empty line is inserted to clear the text.
+pub fn synchronised_lyrics(cues: &[Cue], lang: &str, content_type: SynchronisedLyricsType) -> SynchronisedLyrics {
+    let mut content = Vec::with_capacity(cues.len());
+    for (i, cue) in cues.iter().enumerate() {
+        let marker = if content.is_empty() { "" } else { "\n" };
+        content.push((cue.start, format!("{}{}", marker, cue.text)));
+        if cues.get(i + 1).is_some_and(|next| next.start > cue.end) {
+            content.push((cue.end, "\n".to_string()));
+        }
+    }
+    SynchronisedLyrics {
+        lang: lang.to_string(),
+        timestamp_format: TimestampFormat::Ms,
+        content_type,
+        description: String::new(),
+        content,
+    }
+}
+
+/// Parses a WebVTT file. Comments, style and region blocks and cue settings are ignored.
+pub fn parse_webvtt(s: &str) -> ::Result<Vec<Cue>> {
+    let mut blocks = blocks(s.trim_start_matches('\u{feff}')).into_iter();
+    match blocks.next() {
+        Some(ref header) if header[0] == "WEBVTT" || header[0].starts_with("WEBVTT ") || header[0].starts_with("WEBVTT\t") => (),
+        _ => return Err(::Error::new(::ErrorKind::Parsing, "missing WEBVTT header")),
+    }
+    let mut cues = Vec::new();
+    for block in blocks {
+        if block[0].starts_with("NOTE") || block[0] == "STYLE" || block[0] == "REGION" {
+            continue;
+        }
+        // The cue identifier is optional.
+        let timing = match block.iter().position(|line| line.contains("-->")) {
+            Some(i) if i <= 1 => i,
+            _ => return Err(::Error::new(::ErrorKind::Parsing, "WebVTT cue has no timing")),
+        };
+        cues.push(parse_cue(&block[timing..])?);
+    }
+    Ok(cues)
+}
+
+/// Parses an SRT file.
+pub fn parse_srt(s: &str) -> ::Result<Vec<Cue>> {
+    blocks(s.trim_start_matches('\u{feff}')).into_iter()
+        .map(|block| match block.len() {
+            // The sequence number is not needed, the cues are kept in order.
+            n if n >= 2 => parse_cue(&block[1..]),
+            _ => Err(::Error::new(::ErrorKind::Parsing, "SRT cue has no timing")),
+        })
+        .collect()
+}
+
+/// Formats the cues as a WebVTT file.
+pub fn write_webvtt(cues: &[Cue]) -> String {
+    let mut s = "WEBVTT\n".to_string();
+    for cue in cues {
+        write!(s, "\n{} --> {}\n{}\n", timestamp(cue.start, '.'), timestamp(cue.end, '.'), cue.text).unwrap();
+    }
+    s
+}
+
+/// Formats the cues as an SRT file.
+pub fn write_srt(cues: &[Cue]) -> String {
+    let mut s = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        if i > 0 {
+            s.push('\n');
+        }
+        write!(s, "{}\n{} --> {}\n{}\n", i + 1, timestamp(cue.start, ','), timestamp(cue.end, ','), cue.text).unwrap();
+    }
+    s
+}
+
+/// Splits the text into blocks of non-empty lines.
+fn blocks(s: &str) -> Vec<Vec<&str>> {
+    let lines: Vec<&str> = s.lines().collect();
+    lines.split(|line| line.trim().is_empty())
+        .filter(|block| !block.is_empty())
+        .map(|block| block.to_vec())
+        .collect()
+}
+
+/// Parses a timing line followed by the lines of text.
+fn parse_cue(lines: &[&str]) -> ::Result<Cue> {
+    let mut parts = lines[0].splitn(2, "-->");
+    let start = parse_timestamp(parts.next().unwrap_or(""))?;
+    let end = parse_timestamp(parts.next().unwrap_or("").split_whitespace().next().unwrap_or(""))?;
+    Ok(Cue { start, end, text: lines[1..].join("\n") })
+}
+
+/// Parses a timestamp of the form `[hh:]mm:ss.ttt`. Both a dot (WebVTT) and a comma (SRT) are
+/// accepted as the decimal separator.
+fn parse_timestamp(s: &str) -> ::Result<u32> {
+    let invalid = || ::Error::new(::ErrorKind::Parsing, "invalid subtitle timestamp");
+    let s = s.trim();
+    let (time, millis) = match s.rfind(['.', ',']) {
+        Some(i) => (&s[..i], &s[i + 1..]),
+        None => return Err(invalid()),
+    };
+    if millis.len() != 3 {
+        return Err(invalid());
+    }
+    let mut total = millis.parse::<u64>().map_err(|_| invalid())?;
+    let fields: Vec<&str> = time.split(':').collect();
+    if fields.len() < 2 || fields.len() > 3 {
+        return Err(invalid());
+    }
+    for (field, unit) in fields.iter().rev().zip(&[1000, 60_000, 3_600_000]) {
+        total += field.parse::<u64>().map_err(|_| invalid())? * unit;
+    }
+    if total > u64::from(u32::MAX) {
+        return Err(invalid());
+    }
+    Ok(total as u32)
+}
+
+fn timestamp(ms: u32, separator: char) -> String {
+    format!("{:02}:{:02}:{:02}{}{:03}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, separator, ms % 1000)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_subtitles() {
+        let vtt = "\u{feff}WEBVTT - Episode 1\n\n\
+                   NOTE written by hand\n\n\
+                   intro\n\
+                   00:01.000 --> 00:02.500 align:start\n\
+                   Hello\n\
+                   world\n\n\
+                   01:00:00.000 --> 01:00:01.000\n\
+                   Bye\n";
+        let cues = parse_webvtt(vtt).unwrap();
+        assert_eq!(vec![
+            Cue { start: 1000, end: 2500, text: "Hello\nworld".to_string() },
+            Cue { start: 3_600_000, end: 3_601_000, text: "Bye".to_string() },
+        ], cues);
+        assert_eq!(cues, parse_webvtt(&write_webvtt(&cues)).unwrap());
+        assert_eq!(cues, parse_srt(&write_srt(&cues)).unwrap());
+
+        let srt = "1\r\n00:00:01,000 --> 00:00:02,500\r\nHello\r\nworld\r\n\r\n2\r\n01:00:00,000 --> 01:00:01,000\r\nBye\r\n";
+        assert_eq!(cues, parse_srt(srt).unwrap());
+
+        assert!(parse_webvtt("00:01.000 --> 00:02.000\nNo header\n").is_err());
+        assert!(parse_srt("1\n00:01 --> 00:02\nNo millis\n").is_err());
+    }
+
+    #[test]
+    fn lyrics_roundtrip() {
+        let cues = vec![
+            Cue { start: 1000, end: 2000, text: "One".to_string() },
+            Cue { start: 2000, end: 3000, text: "Two".to_string() },
+            Cue { start: 4000, end: 9000, text: "Three".to_string() },
+        ];
+        let lyrics = synchronised_lyrics(&cues, "eng", SynchronisedLyricsType::Lyrics);
+        assert_eq!(vec![
+            (1000, "One".to_string()),
+            (2000, "\nTwo".to_string()),
+            (3000, "\n".to_string()),
+            (4000, "\nThree".to_string()),
+        ], lyrics.content);
+        assert_eq!(cues, super::cues(&lyrics).unwrap());
+
+        let mpeg = SynchronisedLyrics { timestamp_format: TimestampFormat::Mpeg, ..lyrics };
+        assert!(super::cues(&mpeg).is_err());
+    }
+}
@@ -1,17 +1,29 @@
+use std::collections::BTreeMap;
 use std::fs::{self, File};
 use std::io::{self, Read, Write, Seek, SeekFrom, BufReader};
 use std::iter;
 use std::ops;
 use std::path::Path;
+use std::sync::Arc;
 use byteorder::{ByteOrder, BigEndian, ReadBytesExt};
+use ::container;
+use ::convert::{self, ConversionReport};
+use ::diff::TagDiff;
 use ::frame::Content;
-use ::frame::{Frame, ExtendedText, ExtendedLink, Comment, Lyrics, Picture, PictureType, Timestamp};
+use ::gapless::GaplessInfo;
+use ::musicbrainz::{self, MusicBrainzId};
+use ::rating::RatingConvention;
+use ::frame::{AudioSeekPointIndex, ChannelType, Chapter, EncryptionMethod, LinkedInfo, RecommendedBufferSize, TableOfContents, TermsOfUse, Signature, Commercial, Equalisation, EventTimingCodes, Frame, EncapsulatedObject, MusicCdIdentifier, Popularimeter, Private, RelativeVolumeAdjustment, UniqueFileIdentifier, VolumeAdjustment, ExtendedText, ExtendedLink, Comment, GroupIdentifier, Lyrics, Picture, PictureType, SynchronisedLyrics, Timestamp};
 use ::storage::{PlainStorage, Storage};
 use ::stream::{self, unsynch};
+use ::stream::frame::FrameHeader;
+use ::stream::tag::ExtendedHeader;
+use ::windows_media::WindowsMediaInfo;
 
 
 /// Denotes the version of a tag.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub enum Version {
     /// ID3v2.2
     Id3v22,
@@ -41,11 +53,201 @@ impl Version {
 }
 
 
+/// Determines which tag is used when a file contains more than one tag.
+///
+/// Besides the regular tag at the start, a file may contain an ID3v2.4 tag at the end of the file
+/// which is located by its footer, and an ID3v1 tag.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TagPolicy {
+    /// Use the tag at the start of the file and fall back to the appended tag and then the ID3v1
+    /// tag if it is not present.
+    PreferPrepended,
+    /// Use the tag at the end of the file and fall back to the prepended tag and then the ID3v1
+    /// tag if it is not present.
+    PreferAppended,
+    /// Merge all tags. Frames of the prepended tag take precedence over conflicting frames of the
+    /// appended tag, which in turn take precedence over the fields of the ID3v1 tag.
+    Merge,
+}
+
+/// The style in which the genre (TCON) is written, see `EncoderBuilder::genre_style`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GenreStyle {
+    /// Write genre names, references to ID3v1 genres like "(17)" are replaced by their name.
+    Textual,
+    /// Write references to ID3v1 genres, like "(17)" for ID3v2.2 and ID3v2.3 or "17" for ID3v2.4
+    /// tags. Genres that are not in the ID3v1 genre list are written as names.
+    Numeric,
+}
+
+/// All tags that were found in a file.
+///
+/// This type is returned by `Tag::read_all_from_path`.
+#[derive(Clone, Debug, Default)]
+pub struct FileTags {
+    /// The ID3v2 tag at the start of the file or in the ID3 chunk of the container.
+    pub prepended: Option<Tag>,
+    /// The ID3v2.4 tag at the end of the file.
+    pub appended: Option<Tag>,
+    /// The ID3v1 tag at the end of the file.
+    pub v1: Option<::v1::Tag>,
+}
+
+impl FileTags {
+    /// Combines the tags according to the policy. Returns `None` if the file contained no tags.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{Tag, FileTags, TagPolicy};
+    ///
+    /// let mut prepended = Tag::new();
+    /// prepended.set_title("Prepended");
+    /// let mut appended = Tag::new();
+    /// appended.set_title("Appended");
+    /// appended.set_artist("Artist");
+    ///
+    /// let tags = FileTags { prepended: Some(prepended), appended: Some(appended), v1: None };
+    /// let merged = tags.clone().resolve(TagPolicy::Merge).unwrap();
+    /// assert_eq!(merged.title(), Some("Prepended"));
+    /// assert_eq!(merged.artist(), Some("Artist"));
+    /// let appended = tags.resolve(TagPolicy::PreferAppended).unwrap();
+    /// assert_eq!(appended.title(), Some("Appended"));
+    /// ```
+    pub fn resolve(self, policy: TagPolicy) -> Option<Tag> {
+        let v1 = self.v1.map(Tag::from);
+        match policy {
+            TagPolicy::PreferPrepended => self.prepended.or(self.appended).or(v1),
+            TagPolicy::PreferAppended => self.appended.or(self.prepended).or(v1),
+            TagPolicy::Merge => {
+                let tags: Vec<Tag> = self.prepended.into_iter()
+                    .chain(self.appended)
+                    .chain(v1)
+                    .collect();
+                tags.into_iter().fold(None, |merged: Option<Tag>, tag| match merged {
+                    None => Some(tag),
+                    Some(mut merged) => {
+                        for frame in tag.frames {
+                            if !merged.frames.contains(&frame) {
+                                merged.frames.push(frame);
+                            }
+                        }
+                        Some(merged)
+                    },
+                })
+            },
+        }
+    }
+}
+
+/// Verifies the signature frames (SIGN) of a tag, see `Tag::verify_signatures`.
+///
+/// The ID3 specification does not define what exactly is signed, so it is up to the application
+/// to derive the signed data from the frames, for example by encoding their content with
+/// `Frame::content`.
+///
+/// Closures with a matching signature implement this trait.
+pub trait SignatureVerifier {
+    /// Returns whether the signature is valid for the frames of its group, in tag order.
+    fn verify(&self, signature: &Signature, frames: &[&Frame]) -> bool;
+}
+
+impl<F> SignatureVerifier for F
+    where F: Fn(&Signature, &[&Frame]) -> bool {
+    fn verify(&self, signature: &Signature, frames: &[&Frame]) -> bool {
+        self(signature, frames)
+    }
+}
+
+/// Selects the potentially identifying frames that are removed by `Tag::scrub`.
+///
+/// The default profile removes private frames (PRIV), unique file identifiers (UFID), general
+/// encapsulated objects (GEOB), comments with descriptions that are known to identify the owner of
+/// a file and the email addresses of popularimeters (POPM). Descriptive metadata like the title,
+/// artist and pictures is kept.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScrubProfile {
+    /// The IDs of frames that are removed entirely.
+    pub frame_ids: Vec<String>,
+    /// Comments (COMM) with one of these descriptions are removed.
+    pub comment_descriptions: Vec<String>,
+    /// Removes the email address from popularimeter frames (POPM), keeping the rating and play
+    /// count.
+    pub popularimeter_emails: bool,
+}
+
+impl Default for ScrubProfile {
+    fn default() -> ScrubProfile {
+        ScrubProfile {
+            frame_ids: vec!["PRIV".to_string(), "UFID".to_string(), "GEOB".to_string()],
+            comment_descriptions: vec!["iTunes_CDDB_IDs".to_string(), "iTunes_CDDB_TrackNumber".to_string()],
+            popularimeter_emails: true,
+        }
+    }
+}
+
+/// Resolves a frame that exists in both tags passed to `Tag::merge`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Keep the frame of the tag that is merged into.
+    KeepMine,
+    /// Replace the frame with that of the other tag.
+    KeepTheirs,
+    /// Combine the values of text frames (including user defined text frames), keeping the values
+    /// of the tag that is merged into first. Other frames are kept like `KeepMine`.
+    Combine,
+}
+
+/// Selects how `Tag::merge` resolves frames that exist in both tags.
+///
+/// Frames are conflicting if `Tag::add_frame` would replace one with the other, like text frames
+/// with the same ID or comments with the same language and description.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MergePolicy {
+    /// The strategy for frames without a strategy of their own.
+    pub default: MergeStrategy,
+    /// The strategies for frames with specific IDs, which take precedence over the default.
+    pub frames: Vec<(String, MergeStrategy)>,
+}
+
+impl MergePolicy {
+    /// Creates a policy that resolves all frames with the specified strategy.
+    pub fn new(default: MergeStrategy) -> MergePolicy {
+        MergePolicy { default, frames: Vec::new() }
+    }
+
+    /// Sets the strategy for frames with the specified ID.
+    pub fn with_frame<K: Into<String>>(mut self, id: K, strategy: MergeStrategy) -> MergePolicy {
+        let id = id.into();
+        self.frames.retain(|(frame_id, _)| *frame_id != id);
+        self.frames.push((id, strategy));
+        self
+    }
+
+    /// Returns the strategy for frames with the specified ID.
+    pub fn strategy(&self, id: &str) -> MergeStrategy {
+        self.frames.iter()
+            .find(|(frame_id, _)| frame_id == id)
+            .map_or(self.default, |&(_, strategy)| strategy)
+    }
+}
+
+impl Default for MergePolicy {
+    /// Keeps the frames of the tag that is merged into.
+    fn default() -> MergePolicy {
+        MergePolicy::new(MergeStrategy::KeepMine)
+    }
+}
+
 /// An ID3 tag containing metadata frames.
 #[derive(Clone, Debug, Default, Eq)]
 pub struct Tag {
-    /// A vector of frames included in the tag.
-    frames: Vec<Frame>,
+    /// A vector of frames included in the tag. Frames are shared between clones of the tag and
+    /// are only copied when they are modified.
+    frames: Vec<Arc<Frame>>,
+    /// The extended header of the tag as it was read.
+    extended_header: Option<ExtendedHeader>,
+    /// Whether the CRC in the extended header matched the frame data that was read.
+    crc_valid: Option<bool>,
 }
 
 // Tag {{{
@@ -55,10 +257,52 @@ impl<'a> Tag {
         Tag::default()
     }
 
+    /// Creates a new tag with space for at least `capacity` frames before reallocating.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let tag = Tag::with_capacity(100);
+    /// assert_eq!(0, tag.frames().count());
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Tag {
+        Tag { frames: Vec::with_capacity(capacity), ..Tag::default() }
+    }
+
+    /// Returns the extended header of the tag as it was read, if the tag had one.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let tag = Tag::read_from_path("testdata/id3v24.id3").unwrap();
+    /// assert!(tag.extended_header().is_none());
+    /// ```
+    pub fn extended_header(&self) -> Option<&ExtendedHeader> {
+        self.extended_header.as_ref()
+    }
+
+    /// Returns whether the CRC-32 in the extended header matches the frame data that was read.
+    /// Returns `None` if the tag was not read from a tag with a CRC.
+    pub fn crc_valid(&self) -> Option<bool> {
+        self.crc_valid
+    }
+
+    pub(crate) fn set_read_extended_header(&mut self, header: ExtendedHeader, crc_valid: Option<bool>) {
+        self.extended_header = Some(header);
+        self.crc_valid = crc_valid;
+    }
+
+    /// Reserves space for at least `additional` more frames.
+    pub fn reserve(&mut self, additional: usize) {
+        self.frames.reserve(additional);
+    }
+
     /// Creates a new ID3 tag with the specified version.
     #[deprecated(note = "Tags now use ID3v2.4 for internal storage")]
     pub fn with_version(_: Version) -> Tag {
-        Tag { frames: Vec::new() }
+        Tag::default()
     }
 
     // id3v1 {{{
@@ -105,13 +349,65 @@ impl<'a> Tag {
     ///
     /// let mut tag = Tag::new();
     ///
-    /// tag.add_frame(Frame::with_content("TPE1", Content::Text("".to_string())));
-    /// tag.add_frame(Frame::with_content("APIC", Content::Text("".to_string())));
+    /// tag.add_frame(Frame::with_content("TPE1", Content::Text("".into())));
+    /// tag.add_frame(Frame::with_content("APIC", Content::Text("".into())));
     ///
     /// assert_eq!(tag.frames().count(), 2);
     /// ```
     pub fn frames(&'a self) -> Box<iter::Iterator<Item=&'a Frame> + 'a> {
-        Box::new(self.frames.iter())
+        Box::new(self.frames.iter().map(|frame| &**frame))
+    }
+
+    /// Returns an iterator over all frames with the specified ID, in tag order.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_title("Title");
+    /// tag.add_extended_text("key1", "value1");
+    /// tag.add_extended_text("key2", "value2");
+    ///
+    /// assert_eq!(tag.frames_of("TXXX").count(), 2);
+    /// assert_eq!(tag.frames_of("COMM").count(), 0);
+    /// ```
+    pub fn frames_of(&'a self, id: &'a str) -> impl Iterator<Item = &'a Frame> + 'a {
+        self.frames.iter().map(|frame| &**frame).filter(move |frame| frame.id() == id)
+    }
+
+    /// Returns an iterator over every text value in the tag as `(key, value)` pairs, which is
+    /// useful for building search indexes.
+    ///
+    /// The key is the frame ID, except for user defined text frames (TXXX) which use their
+    /// description as the key. Frames with multiple values separated by null characters yield a
+    /// pair for each value. Comments and lyrics are included, empty values are not.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_artist("Artist 1\u{0}Artist 2");
+    /// tag.add_extended_text("MOOD", "calm");
+    /// let values: Vec<_> = tag.text_values().collect();
+    /// assert_eq!(vec![("TPE1", "Artist 1"), ("TPE1", "Artist 2"), ("MOOD", "calm")], values);
+    /// ```
+    pub fn text_values(&'a self) -> Box<dyn Iterator<Item=(&'a str, &'a str)> + 'a> {
+        let iter = self.frames.iter()
+            .flat_map(|frame| {
+                let (key, text) = match *frame.content() {
+                    Content::Text(ref text) => (frame.id(), text.as_ref()),
+                    Content::ExtendedText(ref ext) => (ext.description.as_str(), ext.value.as_str()),
+                    Content::Comment(ref comment) => (frame.id(), comment.text.as_str()),
+                    Content::Lyrics(ref lyrics) => (frame.id(), lyrics.text.as_str()),
+                    _ => (frame.id(), ""),
+                };
+                text.split('\u{0}')
+                    .filter(|value| !value.is_empty())
+                    .map(move |value| (key, value))
+            });
+        Box::new(iter)
     }
 
     /// Returns an iterator over the extended texts in the tag.
@@ -166,6 +462,165 @@ impl<'a> Tag {
         Box::new(iter)
     }
 
+    /// Returns an iterator over the general encapsulated objects (GEOB) in the tag.
+    pub fn encapsulated_objects(&'a self) -> Box<dyn iter::Iterator<Item=&'a EncapsulatedObject> + 'a> {
+        let iter = self.frames.iter()
+            .filter_map(|frame| frame.content().encapsulated_object());
+        Box::new(iter)
+    }
+
+    /// Returns an iterator over the unique file identifiers (UFID) in the tag.
+    pub fn unique_file_identifiers(&'a self) -> Box<dyn iter::Iterator<Item=&'a UniqueFileIdentifier> + 'a> {
+        let iter = self.frames.iter()
+            .filter_map(|frame| frame.content().unique_file_identifier());
+        Box::new(iter)
+    }
+
+    /// Returns an iterator over the private frames (PRIV) in the tag.
+    pub fn privates(&'a self) -> Box<dyn iter::Iterator<Item=&'a Private> + 'a> {
+        let iter = self.frames.iter()
+            .filter_map(|frame| frame.content().private());
+        Box::new(iter)
+    }
+
+    /// Returns an iterator over the private frames (PRIV) of the specified owner.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    /// use id3::frame::Private;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.add_private(Private { owner: "www.amazon.com".to_string(), data: b"one".to_vec() });
+    /// tag.add_private(Private { owner: "www.amazon.com".to_string(), data: b"two".to_vec() });
+    /// tag.add_private(Private { owner: "AverageLevel".to_string(), data: vec![0x10, 0x27, 0, 0] });
+    /// assert_eq!(2, tag.privates_by_owner("www.amazon.com").count());
+    /// ```
+    pub fn privates_by_owner(&'a self, owner: &'a str) -> Box<dyn iter::Iterator<Item=&'a Private> + 'a> {
+        Box::new(self.privates().filter(move |private| private.owner == owner))
+    }
+
+    /// Returns an iterator over the relative volume adjustments (RVA2) in the tag.
+    pub fn relative_volume_adjustments(&'a self) -> Box<dyn iter::Iterator<Item=&'a RelativeVolumeAdjustment> + 'a> {
+        let iter = self.frames.iter()
+            .filter_map(|frame| frame.content().relative_volume_adjustment());
+        Box::new(iter)
+    }
+
+    /// Returns an iterator over the linked information frames (LINK) in the tag.
+    pub fn linked_infos(&'a self) -> Box<dyn iter::Iterator<Item=&'a LinkedInfo> + 'a> {
+        let iter = self.frames.iter()
+            .filter_map(|frame| frame.content().linked_info());
+        Box::new(iter)
+    }
+
+    /// Returns an iterator over the terms of use (USER) in the tag.
+    pub fn terms_of_use(&'a self) -> Box<dyn iter::Iterator<Item=&'a TermsOfUse> + 'a> {
+        let iter = self.frames.iter()
+            .filter_map(|frame| frame.content().terms_of_use());
+        Box::new(iter)
+    }
+
+    /// Returns an iterator over the encryption method registrations (ENCR) in the tag.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{Tag, Frame, Content};
+    /// use id3::frame::EncryptionMethod;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.add_frame(Frame::with_content("ENCR", Content::EncryptionMethod(EncryptionMethod {
+    ///     owner_identifier: "mailto:crypto@example.com".to_string(),
+    ///     symbol: 0x80,
+    ///     data: Vec::new(),
+    /// })));
+    /// let owner = tag.encryption_methods()
+    ///     .find(|method| method.symbol == 0x80)
+    ///     .map(|method| method.owner_identifier.as_str());
+    /// assert_eq!(Some("mailto:crypto@example.com"), owner);
+    /// ```
+    pub fn encryption_methods(&'a self) -> Box<dyn iter::Iterator<Item=&'a EncryptionMethod> + 'a> {
+        let iter = self.frames.iter()
+            .filter_map(|frame| frame.content().encryption_method());
+        Box::new(iter)
+    }
+
+    /// Returns an iterator over the signatures (SIGN) in the tag.
+    pub fn signatures(&'a self) -> Box<dyn iter::Iterator<Item=&'a Signature> + 'a> {
+        let iter = self.frames.iter()
+            .filter_map(|frame| frame.content().signature());
+        Box::new(iter)
+    }
+
+    /// Verifies each signature (SIGN) in the tag against the frames that are marked with its
+    /// group symbol. Returns the group symbol of each signature with the result of the verifier,
+    /// in tag order.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{Tag, Frame, Content};
+    /// use id3::frame::Signature;
+    ///
+    /// let mut tag = Tag::new();
+    /// let mut title = Frame::with_content("TIT2", Content::Text("Title".into()));
+    /// title.set_group_identifier(Some(0x80));
+    /// tag.add_frame(title);
+    /// tag.add_frame(Frame::with_content("SIGN", Content::Signature(Signature {
+    ///     group_symbol: 0x80,
+    ///     signature: b"Title".to_vec(),
+    /// })));
+    ///
+    /// let results = tag.verify_signatures(&|sign: &Signature, frames: &[&Frame]| {
+    ///     let signed: Vec<u8> = frames.iter()
+    ///         .filter_map(|frame| frame.content().text())
+    ///         .flat_map(|text| text.bytes())
+    ///         .collect();
+    ///     signed == sign.signature
+    /// });
+    /// assert_eq!(vec![(0x80, true)], results);
+    /// ```
+    pub fn verify_signatures<V>(&self, verifier: &V) -> Vec<(u8, bool)>
+        where V: SignatureVerifier + ?Sized {
+        self.signatures()
+            .map(|signature| {
+                let frames: Vec<&Frame> = self.frames.iter()
+                    .filter(|frame| frame.group_identifier() == Some(signature.group_symbol))
+                    .filter(|frame| frame.id() != "SIGN")
+                    .map(|frame| &**frame)
+                    .collect();
+                (signature.group_symbol, verifier.verify(signature, &frames))
+            })
+            .collect()
+    }
+
+    /// Returns an iterator over the commercial frames (COMR) in the tag.
+    pub fn commercials(&'a self) -> Box<dyn iter::Iterator<Item=&'a Commercial> + 'a> {
+        let iter = self.frames.iter()
+            .filter_map(|frame| frame.content().commercial());
+        Box::new(iter)
+    }
+
+    /// Returns an iterator over the equalisations (EQU2) in the tag.
+    pub fn equalisations(&'a self) -> Box<dyn iter::Iterator<Item=&'a Equalisation> + 'a> {
+        let iter = self.frames.iter()
+            .filter_map(|frame| frame.content().equalisation());
+        Box::new(iter)
+    }
+
+    /// Returns an iterator over the popularimeters (POPM) in the tag.
+    pub fn popularimeters(&'a self) -> Box<dyn iter::Iterator<Item=&'a Popularimeter> + 'a> {
+        let iter = self.frames.iter()
+            .filter_map(|frame| frame.content().popularimeter());
+        Box::new(iter)
+    }
+
+    /// Returns an iterator over the synchronised lyrics and texts (SYLT) in the tag.
+    pub fn synchronised_lyrics(&'a self) -> Box<dyn iter::Iterator<Item=&'a SynchronisedLyrics> + 'a> {
+        let iter = self.frames.iter()
+            .filter_map(|frame| frame.content().synchronised_lyrics());
+        Box::new(iter)
+    }
+
     /// Returns an iterator over the pictures in the tag.
     ///
     /// # Example
@@ -192,6 +647,70 @@ impl<'a> Tag {
         Box::new(iter)
     }
 
+    /// Returns the chapters that are listed by the table of contents (CTOC) with the specified
+    /// element ID. Nested tables of contents are expanded in place, so the chapters are returned
+    /// in the order of the hierarchy. Entries that do not refer to a frame in the tag are skipped.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{Tag, Frame};
+    /// use id3::frame::{Chapter, Content, TableOfContents};
+    ///
+    /// let mut tag = Tag::new();
+    /// for (id, elements) in vec![("toc", vec!["part1", "chp2"]), ("part1", vec!["chp0", "chp1"])] {
+    ///     tag.add_frame(Frame::with_content("CTOC", Content::TableOfContents(TableOfContents {
+    ///         element_id: id.to_string(),
+    ///         top_level: id == "toc",
+    ///         ordered: true,
+    ///         elements: elements.into_iter().map(String::from).collect(),
+    ///         frames: Vec::new(),
+    ///     })));
+    /// }
+    /// for (i, id) in vec!["chp0", "chp1", "chp2"].into_iter().enumerate() {
+    ///     tag.add_frame(Frame::with_content("CHAP", Content::Chapter(Chapter {
+    ///         element_id: id.to_string(),
+    ///         start_time: i as u32 * 1000,
+    ///         end_time: (i as u32 + 1) * 1000,
+    ///         start_offset: Chapter::NO_OFFSET,
+    ///         end_offset: Chapter::NO_OFFSET,
+    ///         frames: Vec::new(),
+    ///     })));
+    /// }
+    ///
+    /// let chapters: Vec<&str> = tag.chapters_in_table("toc").iter().map(|c| &c.element_id[..]).collect();
+    /// assert_eq!(vec!["chp0", "chp1", "chp2"], chapters);
+    /// ```
+    pub fn chapters_in_table(&self, element_id: &str) -> Vec<&Chapter> {
+        let mut chapters = Vec::new();
+        let mut visited = Vec::new();
+        self.collect_chapters(element_id, &mut visited, &mut chapters);
+        chapters
+    }
+
+    fn collect_chapters<'b>(&'b self, element_id: &str, visited: &mut Vec<String>, chapters: &mut Vec<&'b Chapter>) {
+        // Guard against tables of contents that (indirectly) contain themselves.
+        if visited.iter().any(|id| id == element_id) {
+            return;
+        }
+        visited.push(element_id.to_string());
+        let toc = self.frames.iter()
+            .filter_map(|frame| frame.content().table_of_contents())
+            .find(|toc| toc.element_id == element_id);
+        let toc = match toc {
+            Some(toc) => toc,
+            None => return,
+        };
+        for element in &toc.elements {
+            let chapter = self.frames.iter()
+                .filter_map(|frame| frame.content().chapter())
+                .find(|chapter| chapter.element_id == *element);
+            match chapter {
+                Some(chapter) => chapters.push(chapter),
+                None => self.collect_chapters(element, visited, chapters),
+            }
+        }
+    }
+
     /// Returns a reference to the first frame with the specified identifier.
     ///
     /// # Example
@@ -200,7 +719,7 @@ impl<'a> Tag {
     ///
     /// let mut tag = Tag::new();
     ///
-    /// tag.add_frame(Frame::with_content("TIT2", Content::Text("".to_string())));
+    /// tag.add_frame(Frame::with_content("TIT2", Content::Text("".into())));
     ///
     /// assert!(tag.get("TIT2").is_some());
     /// assert!(tag.get("TCON").is_none());
@@ -208,6 +727,26 @@ impl<'a> Tag {
     pub fn get(&self, id: &str) -> Option<&Frame> {
         self.frames.iter()
             .find(|frame| frame.id() == id)
+            .map(|frame| &**frame)
+    }
+
+    /// Returns a mutable reference to the first frame with the specified identifier.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_title("Title");
+    ///
+    /// tag.get_mut("TIT2").unwrap().set_tag_alter_preservation(true);
+    /// assert!(tag.get("TIT2").unwrap().tag_alter_preservation());
+    /// assert!(tag.get_mut("TCON").is_none());
+    /// ```
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut Frame> {
+        self.frames.iter_mut()
+            .find(|frame| frame.id() == id)
+            .map(Arc::make_mut)
     }
 
     /// Returns a vector of references to frames with the specified identifier.
@@ -218,8 +757,8 @@ impl<'a> Tag {
     ///
     /// let mut tag = Tag::new();
     ///
-    /// tag.add_frame(Frame::with_content("TPE1", Content::Text("".to_string())));
-    /// tag.add_frame(Frame::with_content("TALB", Content::Text("".to_string())));
+    /// tag.add_frame(Frame::with_content("TPE1", Content::Text("".into())));
+    /// tag.add_frame(Frame::with_content("TALB", Content::Text("".into())));
     ///
     /// assert_eq!(tag.get_all("TPE1").len(), 1);
     /// assert_eq!(tag.get_all("TALB").len(), 1);
@@ -229,7 +768,7 @@ impl<'a> Tag {
         let mut matches = Vec::new();
         for frame in &self.frames {
             if frame.id() == id {
-                matches.push(frame);
+                matches.push(&**frame);
             }
         }
         matches
@@ -247,7 +786,7 @@ impl<'a> Tag {
     /// use id3::{Tag, Frame, Content};
     ///
     /// let mut tag = Tag::new();
-    /// tag.push(Frame::with_content("TALB", Content::Text("".to_string())));
+    /// tag.push(Frame::with_content("TALB", Content::Text("".into())));
     /// assert_eq!(tag.frames().nth(0).unwrap().id(), "TALB");
     /// ```
     #[deprecated(note = "Use add_frame")]
@@ -263,15 +802,15 @@ impl<'a> Tag {
     /// use id3::{Tag, Frame, Content};
     ///
     /// let mut tag = Tag::new();
-    /// tag.add_frame(Frame::with_content("TALB", Content::Text("".to_string())));
-    /// tag.add_frame(Frame::with_content("TALB", Content::Text("".to_string())));
+    /// tag.add_frame(Frame::with_content("TALB", Content::Text("".into())));
+    /// tag.add_frame(Frame::with_content("TALB", Content::Text("".into())));
     /// assert_eq!(tag.frames().nth(0).unwrap().id(), "TALB");
     /// ```
     pub fn add_frame(&mut self, new_frame: Frame) -> Option<Frame> {
         let removed = self.frames.iter()
-            .position(|frame| *frame == new_frame)
-            .map(|conflict_index| self.frames.remove(conflict_index));
-        self.frames.push(new_frame);
+            .position(|frame| **frame == new_frame)
+            .map(|conflict_index| unwrap_frame(self.frames.remove(conflict_index)));
+        self.frames.push(Arc::new(new_frame));
         removed
     }
 
@@ -301,7 +840,25 @@ impl<'a> Tag {
     /// assert_eq!(tag.get("TRCK").unwrap().content().text().unwrap(), "1/13");
     /// ```
     pub fn set_text<K: Into<String>, V: Into<String>>(&mut self, id: K, text: V) {
-        self.add_frame(Frame::with_content(&id.into(), Content::Text(text.into())));
+        self.add_frame(Frame::with_content(&id.into(), Content::Text(text.into().into())));
+    }
+
+    /// Adds a text frame holding multiple values. The values are separated by null characters,
+    /// which only ID3v2.4 supports.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_text_values("TPE1", vec!["Artist 1", "Artist 2"]);
+    /// let values: Vec<_> = tag.get("TPE1").unwrap().content().text_values().collect();
+    /// assert_eq!(vec!["Artist 1", "Artist 2"], values);
+    /// ```
+    pub fn set_text_values<K, I>(&mut self, id: K, values: I)
+        where K: Into<String>, I: IntoIterator, I::Item: Into<String> {
+        let values: Vec<String> = values.into_iter().map(Into::into).collect();
+        self.set_text(id, values.join("\0"));
     }
 
     /// Removes all frames with the specified identifier.
@@ -312,8 +869,8 @@ impl<'a> Tag {
     ///
     /// let mut tag = Tag::new();
     ///
-    /// tag.add_frame(Frame::with_content("TALB", Content::Text("".to_string())));
-    /// tag.add_frame(Frame::with_content("TPE1", Content::Text("".to_string())));
+    /// tag.add_frame(Frame::with_content("TALB", Content::Text("".into())));
+    /// tag.add_frame(Frame::with_content("TPE1", Content::Text("".into())));
     ///
     /// assert_eq!(tag.frames().count(), 2);
     ///
@@ -329,6 +886,28 @@ impl<'a> Tag {
         });
     }
 
+    /// Removes all frames for which the predicate returns true and returns them in tag order.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_title("Title");
+    /// tag.add_extended_text("key1", "value1");
+    /// tag.add_extended_text("key2", "value2");
+    ///
+    /// let removed = tag.remove_where(|frame| frame.content().extended_text().is_some_and(|ext| ext.description == "key1"));
+    /// assert_eq!(removed.len(), 1);
+    /// assert_eq!(tag.frames().count(), 2);
+    /// ```
+    pub fn remove_where<F>(&mut self, mut predicate: F) -> Vec<Frame>
+        where F: FnMut(&Frame) -> bool {
+        let (removed, kept) = self.frames.drain(..).partition(|frame| predicate(frame));
+        self.frames = kept;
+        removed.into_iter().map(unwrap_frame).collect()
+    }
+
     /// Returns the `Content::Text` string for the frame with the specified identifier.
     /// Returns `None` if the frame with the specified ID can't be found or if the content is not
     /// `Content::Text`.
@@ -343,22 +922,56 @@ impl<'a> Tag {
             .and_then(|text| text.parse().ok())
     }
 
-    /// Loads a text frame by its ID and attempt to split it into two parts
-    ///
-    /// Internally used by track and disc getters and setters.
-    fn text_pair(&self, id: &str) -> Option<(u32, Option<u32>)> {
+    /// Assembles the recording time from the frames ID3v2.3 splits it into: the year (TYER), the
+    /// date (TDAT, "DDMM") and the time (TIME, "HHMM"). The recording dates (TRDA) are used if
+    /// there is no year.
+    fn read_split_timestamp(&self) -> Option<Timestamp> {
+        let year = match self.text_for_frame_id("TYER").and_then(|text| text.trim().parse().ok()) {
+            Some(year) => year,
+            None => return self.read_timestamp_frame("TRDA"),
+        };
+        let digit_pair = |id| {
+            self.text_for_frame_id(id)
+                .filter(|text| text.len() == 4 && text.bytes().all(|b| b.is_ascii_digit()))
+                .map(|text| (text[..2].parse().ok(), text[2..].parse().ok()))
+        };
+        let (day, month) = digit_pair("TDAT").unwrap_or((None, None));
+        let (hour, minute) = match month {
+            Some(_) => digit_pair("TIME").unwrap_or((None, None)),
+            None => (None, None),
+        };
+        Some(Timestamp { year, month, day, hour, minute, second: None })
+    }
+
+    /// Parses a number pair in the slash notation of TRCK and TPOS, like "3/12". Either half may
+    /// be missing, like in "3" or "/12". Returns `None` if neither half is a number.
+    fn text_pair(&self, id: &str) -> Option<(Option<u32>, Option<u32>)> {
         self.get(id)
             .and_then(|frame| frame.content().text())
             .and_then(|text| {
                 let mut split = text.splitn(2, '/');
-                if let Ok(num) = split.next().unwrap().parse() {
-                    Some((num, split.next().and_then(|s| s.parse().ok())))
+                let first = split.next().and_then(|s| s.trim().parse().ok());
+                let second = split.next().and_then(|s| s.trim().parse().ok());
+                if first.is_some() || second.is_some() {
+                    Some((first, second))
                 } else {
                     None
                 }
             })
     }
 
+    /// Stores a number pair in the slash notation, see `text_pair`. The frame is removed if both
+    /// halves are `None`.
+    fn set_text_pair(&mut self, id: &str, first: Option<u32>, second: Option<u32>) {
+        let text = match (first, second) {
+            (Some(first), Some(second)) => format!("{}/{}", first, second),
+            (Some(first), None) => format!("{}", first),
+            (None, Some(second)) => format!("/{}", second),
+            (None, None) => return self.remove(id),
+        };
+        self.set_text(id, text);
+    }
+
     // Getters/Setters {{{
     /// Returns a vector of the extended text (TXXX) description/value pairs.
     #[deprecated(note = "Use extended_texts()")]
@@ -393,6 +1006,60 @@ impl<'a> Tag {
         self.add_frame(frame);
     }
 
+    /// Returns all user defined text frames (TXXX) as a map of description to values, ordered by
+    /// description.
+    ///
+    /// ID3v2.4 allows a frame to contain several values separated by a null byte, these are
+    /// returned as separate values.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.add_extended_text("MusicBrainz Album Type", "album");
+    /// tag.add_extended_text("CATALOGNUMBER", "CAT-1\u{0}CAT-2");
+    ///
+    /// let map = tag.user_text_map();
+    /// assert_eq!(map.keys().collect::<Vec<_>>(), vec![&"CATALOGNUMBER", &"MusicBrainz Album Type"]);
+    /// assert_eq!(map["CATALOGNUMBER"], vec!["CAT-1", "CAT-2"]);
+    /// ```
+    pub fn user_text_map(&self) -> BTreeMap<&str, Vec<&str>> {
+        let mut map: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for ext in self.extended_texts() {
+            map.entry(ext.description.as_str())
+                .or_default()
+                .extend(ext.value.split('\u{0}'));
+        }
+        map
+    }
+
+    /// Replaces all user defined text frames (TXXX) with the descriptions and values of the map.
+    /// Multiple values of a description are stored in a single frame, separated by a null byte.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.add_extended_text("stale", "value");
+    /// tag.set_user_text_map(vec![
+    ///     ("CATALOGNUMBER", vec!["CAT-1", "CAT-2"]),
+    ///     ("BARCODE", vec!["0123456789"]),
+    /// ]);
+    ///
+    /// assert_eq!(tag.extended_texts().count(), 2);
+    /// assert_eq!(tag.user_text_map()["CATALOGNUMBER"], vec!["CAT-1", "CAT-2"]);
+    /// ```
+    pub fn set_user_text_map<I, K, V>(&mut self, map: I)
+        where I: IntoIterator<Item=(K, Vec<V>)>, K: Into<String>, V: AsRef<str> {
+        self.remove_extended_text(None, None);
+        for (description, values) in map {
+            let values: Vec<&str> = values.iter().map(|v| v.as_ref()).collect();
+            self.add_extended_text(description, values.join("\u{0}"));
+        }
+    }
+
     /// Adds a user defined text frame (TXXX).
     #[deprecated(note = "Use add_extended_text()")]
     pub fn add_txxx<K: Into<String>, V: Into<String>>(&mut self, description: K, value: V) {
@@ -613,399 +1280,950 @@ impl<'a> Tag {
         });
     }
 
-    /// Returns the year (TYER).
-    /// Returns `None` if the year frame could not be found or if it could not be parsed.
+    /// Removes potentially identifying frames from the tag according to the profile. Use this
+    /// before publishing files.
     ///
     /// # Example
     /// ```
-    /// use id3::{Tag, Frame};
-    /// use id3::frame::Content;
+    /// use id3::{Tag, Frame, Content, ScrubProfile};
     ///
     /// let mut tag = Tag::new();
-    /// assert!(tag.year().is_none());
-    ///
-    /// let frame_valid = Frame::with_content("TYER", Content::Text("2014".to_owned()));
-    /// tag.add_frame(frame_valid);
-    /// assert_eq!(tag.year().unwrap(), 2014);
-    ///
-    /// tag.remove("TYER");
+    /// tag.set_title("Title");
+    /// tag.add_frame(Frame::with_content("PRIV", Content::Unknown(b"owner\0data".to_vec())));
     ///
-    /// let frame_invalid = Frame::with_content("TYER", Content::Text("nope".to_owned()));
-    /// tag.add_frame(frame_invalid);
-    /// assert!(tag.year().is_none());
+    /// tag.scrub(&ScrubProfile::default());
+    /// assert_eq!(tag.frames().count(), 1);
+    /// assert_eq!(tag.title(), Some("Title"));
     /// ```
-    pub fn year(&self) -> Option<i32> {
-        self.get("TYER")
-            .and_then(|frame| frame.content().text())
-            .and_then(|text| text.trim_left_matches('0').parse().ok())
+    pub fn scrub(&mut self, profile: &ScrubProfile) {
+        self.frames.retain(|frame| {
+            if profile.frame_ids.iter().any(|id| id == frame.id()) {
+                return false;
+            }
+            match *frame.content() {
+                Content::Comment(ref comment) => !profile.comment_descriptions.contains(&comment.description),
+                _ => true,
+            }
+        });
+        if profile.popularimeter_emails {
+            for frame in self.frames.iter_mut() {
+                let scrubbed = match *frame.content() {
+                    Content::Popularimeter(ref popm) => Some(Popularimeter { email: String::new(), ..popm.clone() }),
+                    _ => None,
+                };
+                if let Some(popm) = scrubbed {
+                    *frame = Arc::new(Frame::with_content("POPM", Content::Popularimeter(popm)));
+                }
+            }
+        }
     }
 
-    /// Sets the year (TYER).
+    /// Returns a normalized copy of the tag, so that tags which carry the same metadata compare
+    /// equal regardless of how they were written.
+    ///
+    /// Text is trimmed and empty values are dropped. Multiple values of a frame are sorted,
+    /// genre references like `"(31)"` are replaced by the genre name and languages are converted
+    /// to lowercase. Frame flags are cleared and the frames are sorted by ID and content. The
+    /// encoding and padding of the original tag are not part of a `Tag` to begin with.
     ///
     /// # Example
     /// ```
     /// use id3::Tag;
     ///
-    /// let mut tag = Tag::new();
-    /// tag.set_year(2014);
-    /// assert_eq!(tag.year().unwrap(), 2014);
+    /// let mut a = Tag::new();
+    /// a.set_genre("(31)");
+    /// a.set_artist(" B\u{0}A ");
+    ///
+    /// let mut b = Tag::new();
+    /// b.set_artist("A\u{0}B");
+    /// b.set_genre("Trance");
+    ///
+    /// assert_eq!(Some("A\u{0}B"), a.canonicalize().artist());
+    /// assert_eq!(Some("Trance"), a.canonicalize().genre());
+    /// assert_eq!(a.canonical_hash(), b.canonical_hash());
     /// ```
-    pub fn set_year(&mut self, year: i32) {
-        self.set_text("TYER", format!("{:04}", year));
+    pub fn canonicalize(&self) -> Tag {
+        let mut frames: Vec<(Frame, Vec<u8>)> = self.frames.iter()
+            .filter_map(|frame| canonical_frame(frame))
+            .map(|frame| {
+                let data = canonical_content_bytes(&frame);
+                (frame, data)
+            })
+            .collect();
+        frames.sort_by(|a, b| (a.0.id(), &a.1).cmp(&(b.0.id(), &b.1)));
+        frames.into_iter().map(|(frame, _)| frame).collect()
+    }
+
+    /// Returns a hash of the canonical form of the tag, see `Tag::canonicalize`.
+    ///
+    /// The hash is computed with 64-bit FNV-1a over the encoded frames and is stable across
+    /// platforms and releases of this library, so it may be stored by deduplication tools.
+    pub fn canonical_hash(&self) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut feed = |bytes: &[u8]| {
+            for b in bytes {
+                hash = (hash ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+        for frame in self.canonicalize().frames() {
+            let data = canonical_content_bytes(frame);
+            let mut len = [0; 4];
+            BigEndian::write_u32(&mut len, data.len() as u32);
+            feed(frame.id().as_bytes());
+            feed(&len);
+            feed(&data);
+        }
+        hash
     }
 
-    /// Return the content of the TRDC frame, if any
+    /// Registers a group symbol by adding a group identification registration frame (GRID).
+    ///
+    /// An existing registration of the same symbol or by the same owner is replaced, as the
+    /// specification only allows one registration of each.
     ///
     /// # Example
     /// ```
-    /// use id3::Tag;
-    /// use id3::Timestamp;
+    /// use id3::{Tag, Frame, Content};
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_date_recorded(Timestamp{ year: 2014, month: None, day: None, hour: None, minute: None, second: None });
-    /// assert_eq!(tag.date_recorded().unwrap().year, 2014);
+    /// tag.register_group("mailto:grouping@example.com", 0x80, Vec::new());
+    ///
+    /// let mut frame = Frame::with_content("TIT2", Content::Text("Title".into()));
+    /// frame.set_group_identifier(Some(0x80));
+    /// tag.add_frame(frame);
+    ///
+    /// let owner = &tag.group_registration(0x80).unwrap().owner_identifier;
+    /// assert_eq!(owner, "mailto:grouping@example.com");
+    /// assert_eq!(tag.frames_in_group(0x80).count(), 1);
     /// ```
-    pub fn date_recorded(&self) -> Option<Timestamp> {
-        self.read_timestamp_frame("TDRC")
+    ///
+    /// # Panics
+    /// If the symbol is not in the range `0x80..=0xF0`.
+    pub fn register_group<T: Into<String>>(&mut self, owner_identifier: T, symbol: u8, data: Vec<u8>) {
+        assert!((0x80..=0xf0).contains(&symbol), "group symbols must be in the range 0x80..=0xF0");
+        let owner_identifier = owner_identifier.into();
+        self.frames.retain(|frame| {
+            match frame.content().group_identifier() {
+                Some(group) => group.symbol != symbol && group.owner_identifier != owner_identifier,
+                None => true,
+            }
+        });
+        let frame = Frame::with_content("GRID", Content::GroupIdentifier(GroupIdentifier {
+            owner_identifier,
+            symbol,
+            data,
+        }));
+        self.frames.push(Arc::new(frame));
     }
 
-    /// Sets the content of the TDRC frame
+    /// Returns the group identification registration (GRID) of the symbol.
+    pub fn group_registration(&self, symbol: u8) -> Option<&GroupIdentifier> {
+        self.frames.iter()
+            .filter_map(|frame| frame.content().group_identifier())
+            .find(|group| group.symbol == symbol)
+    }
+
+    /// Returns an iterator over the frames that belong to the group with the specified symbol.
+    pub fn frames_in_group(&'a self, symbol: u8) -> Box<dyn Iterator<Item=&'a Frame> + 'a> {
+        let iter = self.frames.iter()
+            .filter(move |frame| frame.group_identifier() == Some(symbol))
+            .map(|frame| &**frame);
+        Box::new(iter)
+    }
+
+    /// Adds a general encapsulated object frame (GEOB). An existing object with the same
+    /// description is replaced.
     ///
     /// # Example
     /// ```
     /// use id3::Tag;
-    /// use id3::Timestamp;
+    /// use id3::frame::EncapsulatedObject;
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_date_recorded(Timestamp{ year: 2014, month: None, day: None, hour: None, minute: None, second: None });
-    /// assert_eq!(tag.date_recorded().unwrap().year, 2014);
+    /// tag.add_encapsulated_object(EncapsulatedObject {
+    ///     mime_type: "application/octet-stream".to_string(),
+    ///     filename: "".to_string(),
+    ///     description: "Serato Overview".to_string(),
+    ///     data: vec![1, 2, 3],
+    /// });
+    /// assert_eq!(vec![1, 2, 3], tag.encapsulated_objects().next().unwrap().data);
+    /// tag.remove_encapsulated_object("Serato Overview");
+    /// assert_eq!(0, tag.encapsulated_objects().count());
     /// ```
-    pub fn set_date_recorded(&mut self, timestamp: Timestamp) {
-        let time_string = timestamp.to_string();
-        self.set_text("TDRC", time_string);
+    pub fn add_encapsulated_object(&mut self, object: EncapsulatedObject) {
+        self.add_frame(Frame::with_content("GEOB", Content::EncapsulatedObject(object)));
     }
 
-    /// Return the content of the TDRL frame, if any
+    /// Removes the general encapsulated object (GEOB) with the specified description.
+    pub fn remove_encapsulated_object(&mut self, description: &str) {
+        self.frames.retain(|frame| {
+            match *frame.content() {
+                Content::EncapsulatedObject(ref object) => object.description != description,
+                _ => true,
+            }
+        });
+    }
+
+    /// Adds a unique file identifier frame (UFID). An existing identifier of the same owner is
+    /// replaced.
     ///
     /// # Example
     /// ```
     /// use id3::Tag;
-    /// use id3::Timestamp;
+    /// use id3::frame::UniqueFileIdentifier;
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_date_released(Timestamp{ year: 2014, month: None, day: None, hour: None, minute: None, second: None });
-    /// assert_eq!(tag.date_released().unwrap().year, 2014);
+    /// tag.add_unique_file_identifier(UniqueFileIdentifier {
+    ///     owner_identifier: "http://musicbrainz.org".to_string(),
+    ///     identifier: b"a8f3b1e2-0000-4000-8000-000000000000".to_vec(),
+    /// });
+    /// let ufid = tag.unique_file_identifiers().next().unwrap();
+    /// assert_eq!("http://musicbrainz.org", ufid.owner_identifier);
+    /// tag.remove_unique_file_identifier("http://musicbrainz.org");
+    /// assert_eq!(0, tag.unique_file_identifiers().count());
     /// ```
-    pub fn date_released(&self) -> Option<Timestamp> {
-        self.read_timestamp_frame("TDRL")
+    pub fn add_unique_file_identifier(&mut self, ufid: UniqueFileIdentifier) {
+        self.add_frame(Frame::with_content("UFID", Content::UniqueFileIdentifier(ufid)));
     }
 
-    /// Sets the content of the TDRL frame
+    /// Removes the unique file identifier (UFID) of the specified owner.
+    pub fn remove_unique_file_identifier(&mut self, owner_identifier: &str) {
+        self.frames.retain(|frame| {
+            match *frame.content() {
+                Content::UniqueFileIdentifier(ref ufid) => ufid.owner_identifier != owner_identifier,
+                _ => true,
+            }
+        });
+    }
+
+    /// Adds a private frame (PRIV). Frames with the same owner are kept, unless their data is the
+    /// same as well.
+    pub fn add_private(&mut self, private: Private) {
+        self.add_frame(Frame::with_content("PRIV", Content::Private(private)));
+    }
+
+    /// Removes all private frames (PRIV) of the specified owner.
+    pub fn remove_privates_by_owner(&mut self, owner: &str) {
+        self.frames.retain(|frame| {
+            match *frame.content() {
+                Content::Private(ref private) => private.owner != owner,
+                _ => true,
+            }
+        });
+    }
+
+    /// Adds a relative volume adjustment frame (RVA2). An existing adjustment with the same
+    /// identification is replaced.
     ///
     /// # Example
     /// ```
     /// use id3::Tag;
-    /// use id3::Timestamp;
+    /// use id3::frame::{ChannelType, RelativeVolumeAdjustment, VolumeAdjustment};
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_date_released(Timestamp{ year: 2014, month: None, day: None, hour: None, minute: None, second: None });
-    /// assert_eq!(tag.date_released().unwrap().year, 2014);
+    /// let mut master = VolumeAdjustment {
+    ///     channel: ChannelType::MasterVolume,
+    ///     adjustment: 0,
+    ///     peak_bits: 0,
+    ///     peak: Vec::new(),
+    /// };
+    /// master.set_adjustment_db(-3.25);
+    /// tag.add_relative_volume_adjustment(RelativeVolumeAdjustment {
+    ///     identification: "album".to_string(),
+    ///     channels: vec![master],
+    /// });
+    /// let rva2 = tag.relative_volume_adjustments().next().unwrap();
+    /// assert_eq!(-3.25, rva2.channels[0].adjustment_db());
     /// ```
-    pub fn set_date_released(&mut self, timestamp: Timestamp) {
-        let time_string = timestamp.to_string();
-        self.set_text("TDRL", time_string);
+    pub fn add_relative_volume_adjustment(&mut self, rva2: RelativeVolumeAdjustment) {
+        self.add_frame(Frame::with_content("RVA2", Content::RelativeVolumeAdjustment(rva2)));
     }
 
-    /// Returns the artist (TPE1).
+    /// Removes the relative volume adjustment (RVA2) with the specified identification.
+    pub fn remove_relative_volume_adjustment(&mut self, identification: &str) {
+        self.frames.retain(|frame| {
+            match *frame.content() {
+                Content::RelativeVolumeAdjustment(ref rva2) => rva2.identification != identification,
+                _ => true,
+            }
+        });
+    }
+
+    /// Returns the ReplayGain track gain in dB. The gain is read from the user defined text
+    /// "REPLAYGAIN_TRACK_GAIN" (TXXX), or otherwise from the master volume of the relative volume
+    /// adjustment (RVA2) identified as "track".
     ///
     /// # Example
     /// ```
-    /// use id3::{Frame, Tag};
-    /// use id3::frame::Content;
+    /// use id3::Tag;
     ///
     /// let mut tag = Tag::new();
-    /// let frame = Frame::with_content("TPE1", Content::Text("artist".to_owned()));
-    /// tag.add_frame(frame);
-    /// assert_eq!(tag.artist().unwrap(), "artist");
+    /// tag.add_extended_text("replaygain_track_gain", "-6.50 dB");
+    /// assert_eq!(Some(-6.5), tag.replaygain_track_gain());
+    ///
+    /// tag.set_replaygain_track_gain(2.1);
+    /// assert_eq!(Some("+2.10 dB"), tag.extended_texts().next().map(|t| t.value.as_str()));
     /// ```
-    pub fn artist(&self) -> Option<&str> {
-        self.text_for_frame_id("TPE1")
+    pub fn replaygain_track_gain(&self) -> Option<f32> {
+        self.replaygain_gain("track")
     }
 
-    /// Sets the artist (TPE1).
+    /// Sets the ReplayGain track gain in dB as the user defined text "REPLAYGAIN_TRACK_GAIN"
+    /// (TXXX).
+    pub fn set_replaygain_track_gain(&mut self, gain: f32) {
+        self.set_replaygain_text("track", "gain", format!("{:+.2} dB", gain));
+    }
+
+    /// Returns the ReplayGain track peak as a fraction of full scale, read like
+    /// `replaygain_track_gain`.
+    pub fn replaygain_track_peak(&self) -> Option<f32> {
+        self.replaygain_peak("track")
+    }
+
+    /// Sets the ReplayGain track peak as the user defined text "REPLAYGAIN_TRACK_PEAK" (TXXX).
+    pub fn set_replaygain_track_peak(&mut self, peak: f32) {
+        self.set_replaygain_text("track", "peak", format!("{:.6}", peak));
+    }
+
+    /// Returns the ReplayGain album gain in dB, read like `replaygain_track_gain` from
+    /// "REPLAYGAIN_ALBUM_GAIN" or the RVA2 frame identified as "album".
+    pub fn replaygain_album_gain(&self) -> Option<f32> {
+        self.replaygain_gain("album")
+    }
+
+    /// Sets the ReplayGain album gain in dB as the user defined text "REPLAYGAIN_ALBUM_GAIN"
+    /// (TXXX).
+    pub fn set_replaygain_album_gain(&mut self, gain: f32) {
+        self.set_replaygain_text("album", "gain", format!("{:+.2} dB", gain));
+    }
+
+    /// Returns the ReplayGain album peak as a fraction of full scale, read like
+    /// `replaygain_album_gain`.
+    pub fn replaygain_album_peak(&self) -> Option<f32> {
+        self.replaygain_peak("album")
+    }
+
+    /// Sets the ReplayGain album peak as the user defined text "REPLAYGAIN_ALBUM_PEAK" (TXXX).
+    pub fn set_replaygain_album_peak(&mut self, peak: f32) {
+        self.set_replaygain_text("album", "peak", format!("{:.6}", peak));
+    }
+
+    /// Removes all ReplayGain information, both the user defined texts (TXXX) and the relative
+    /// volume adjustments (RVA2) identified as "track" and "album".
+    pub fn remove_replaygain(&mut self) {
+        self.frames.retain(|frame| {
+            match *frame.content() {
+                Content::ExtendedText(ref ext) => !ext.description.to_ascii_uppercase().starts_with("REPLAYGAIN_"),
+                Content::RelativeVolumeAdjustment(ref rva2) => {
+                    !rva2.identification.eq_ignore_ascii_case("track") && !rva2.identification.eq_ignore_ascii_case("album")
+                },
+                _ => true,
+            }
+        });
+    }
+
+    /// Returns the value of the ReplayGain user defined text of the scope ("track" or "album")
+    /// and field ("gain" or "peak"). Taggers differ in the case of the description.
+    fn replaygain_text(&self, scope: &str, field: &str) -> Option<&str> {
+        let description = format!("REPLAYGAIN_{}_{}", scope, field);
+        self.extended_texts()
+            .find(|ext| ext.description.eq_ignore_ascii_case(&description))
+            .map(|ext| ext.value.as_str())
+    }
+
+    /// Replaces the ReplayGain user defined text of the scope and field, see `replaygain_text`.
+    fn set_replaygain_text(&mut self, scope: &str, field: &str, value: String) {
+        let description = format!("REPLAYGAIN_{}_{}", scope, field).to_ascii_uppercase();
+        self.frames.retain(|frame| {
+            frame.content().extended_text()
+                .map_or(true, |ext| !ext.description.eq_ignore_ascii_case(&description))
+        });
+        self.add_extended_text(description, value);
+    }
+
+    /// Returns the master volume of the relative volume adjustment (RVA2) of the scope.
+    fn replaygain_adjustment(&self, scope: &str) -> Option<&VolumeAdjustment> {
+        self.relative_volume_adjustments()
+            .filter(|rva2| rva2.identification.eq_ignore_ascii_case(scope))
+            .flat_map(|rva2| rva2.channels.iter())
+            .find(|channel| channel.channel == ChannelType::MasterVolume)
+    }
+
+    fn replaygain_gain(&self, scope: &str) -> Option<f32> {
+        match self.replaygain_text(scope, "gain") {
+            Some(text) => {
+                let text = text.trim();
+                let number = match text.len().checked_sub(2) {
+                    Some(end) if text.is_char_boundary(end) && text[end..].eq_ignore_ascii_case("db") => &text[..end],
+                    _ => text,
+                };
+                number.trim().parse().ok()
+            },
+            None => self.replaygain_adjustment(scope).map(|channel| channel.adjustment_db()),
+        }
+    }
+
+    fn replaygain_peak(&self, scope: &str) -> Option<f32> {
+        match self.replaygain_text(scope, "peak") {
+            Some(text) => text.trim().parse().ok(),
+            // The peak of an RVA2 frame is a sample value, full scale is 2^(bits - 1).
+            None => self.replaygain_adjustment(scope)
+                .filter(|channel| (1..=64).contains(&channel.peak_bits))
+                .map(|channel| {
+                    let peak = channel.peak.iter().fold(0u64, |peak, b| peak << 8 | u64::from(*b));
+                    (peak as f64 / 2f64.powi(i32::from(channel.peak_bits) - 1)) as f32
+                }),
+        }
+    }
+
+    /// Returns the MusicBrainz identifiers of the specified kind. Identifiers of which there may
+    /// be several, like the artist identifiers, are separated by null characters in ID3v2.4 and
+    /// by slashes in ID3v2.3.
     ///
     /// # Example
     /// ```
-    /// use id3::Tag;
+    /// use id3::{MusicBrainzId, Tag};
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_artist("artist");
-    /// assert_eq!(tag.artist().unwrap(), "artist");
+    /// tag.add_extended_text("MusicBrainz Artist Id", "a74b1b7f-71a5-4011-9441-d0b5e4122711/8538e728-ca0b-4321-b7e5-cff6565dd4c0");
+    /// assert_eq!(2, tag.musicbrainz_ids(MusicBrainzId::Artist).len());
     /// ```
-    pub fn set_artist<T: Into<String>>(&mut self, artist: T) {
-        self.set_text("TPE1", artist);
+    pub fn musicbrainz_ids(&self, kind: MusicBrainzId) -> Vec<&str> {
+        let text = match kind.description() {
+            Some(description) => self.extended_texts()
+                .find(|ext| ext.description.eq_ignore_ascii_case(description))
+                .map(|ext| ext.value.as_str()),
+            None => self.unique_file_identifiers()
+                .find(|ufid| ufid.owner_identifier == musicbrainz::UFID_OWNER)
+                .and_then(|ufid| ::std::str::from_utf8(&ufid.identifier).ok()),
+        };
+        text.into_iter()
+            .flat_map(|text| text.split(['\0', '/']))
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .collect()
     }
 
-    /// Removes the artist (TPE1).
+    /// Returns the first MusicBrainz identifier of the specified kind, see `musicbrainz_ids`.
     ///
     /// # Example
     /// ```
-    /// use id3::Tag;
+    /// use id3::{MusicBrainzId, Tag};
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_artist("artist");
-    /// assert!(tag.artist().is_some());
-    ///
-    /// tag.remove_artist();
-    /// assert!(tag.artist().is_none());
+    /// tag.set_musicbrainz_id(MusicBrainzId::Recording, "b1a9c0e9-d987-4042-ae91-78d6a3267d69");
+    /// tag.set_musicbrainz_id(MusicBrainzId::Release, "0f5a1dc6-2f20-4c10-8e2d-4b4ef2b1e0b9");
+    /// assert_eq!(Some("b1a9c0e9-d987-4042-ae91-78d6a3267d69"), tag.musicbrainz_recording_id());
+    /// assert_eq!(1, tag.unique_file_identifiers().count());
+    /// assert_eq!("MusicBrainz Album Id", tag.extended_texts().next().unwrap().description);
     /// ```
-    pub fn remove_artist(&mut self) {
-        self.remove("TPE1");
+    pub fn musicbrainz_id(&self, kind: MusicBrainzId) -> Option<&str> {
+        self.musicbrainz_ids(kind).into_iter().next()
     }
 
-    /// Sets the album artist (TPE2).
+    /// Sets the MusicBrainz identifier of the specified kind, replacing any existing ones.
+    pub fn set_musicbrainz_id<T: Into<String>>(&mut self, kind: MusicBrainzId, id: T) {
+        self.set_musicbrainz_ids(kind, iter::once(id));
+    }
+
+    /// Sets the MusicBrainz identifiers of the specified kind, separated by null characters. The
+    /// identifiers are removed if there are none.
+    pub fn set_musicbrainz_ids<I>(&mut self, kind: MusicBrainzId, ids: I)
+        where I: IntoIterator, I::Item: Into<String> {
+        self.remove_musicbrainz_id(kind);
+        let ids: Vec<String> = ids.into_iter().map(Into::into).collect();
+        if ids.is_empty() {
+            return;
+        }
+        match kind.description() {
+            Some(description) => self.add_extended_text(description, ids.join("\0")),
+            None => self.add_unique_file_identifier(UniqueFileIdentifier {
+                owner_identifier: musicbrainz::UFID_OWNER.to_string(),
+                identifier: ids.join("\0").into_bytes(),
+            }),
+        }
+    }
+
+    /// Removes the MusicBrainz identifiers of the specified kind.
+    pub fn remove_musicbrainz_id(&mut self, kind: MusicBrainzId) {
+        match kind.description() {
+            Some(description) => self.frames.retain(|frame| {
+                frame.content().extended_text()
+                    .map_or(true, |ext| !ext.description.eq_ignore_ascii_case(description))
+            }),
+            None => self.remove_unique_file_identifier(musicbrainz::UFID_OWNER),
+        }
+    }
+
+    /// Returns the MusicBrainz recording identifier, see `musicbrainz_id`.
+    pub fn musicbrainz_recording_id(&self) -> Option<&str> {
+        self.musicbrainz_id(MusicBrainzId::Recording)
+    }
+
+    /// Returns the MusicBrainz release identifier, see `musicbrainz_id`.
+    pub fn musicbrainz_release_id(&self) -> Option<&str> {
+        self.musicbrainz_id(MusicBrainzId::Release)
+    }
+
+    /// Returns the MusicBrainz identifiers of the artists of the track, see `musicbrainz_ids`.
+    pub fn musicbrainz_artist_ids(&self) -> Vec<&str> {
+        self.musicbrainz_ids(MusicBrainzId::Artist)
+    }
+
+    /// Adds a terms of use frame (USER). Existing terms in the same language are replaced.
     ///
     /// # Example
     /// ```
-    /// use id3::{Frame, Tag};
-    /// use id3::frame::Content;
+    /// use id3::Tag;
+    /// use id3::frame::TermsOfUse;
     ///
     /// let mut tag = Tag::new();
-    /// let frame = Frame::with_content("TPE2", Content::Text("artist".to_owned()));
-    /// tag.add_frame(frame);
-    /// assert_eq!(tag.album_artist().unwrap(), "artist");
+    /// tag.add_terms_of_use(TermsOfUse { lang: "eng".to_string(), text: "CC BY 4.0".to_string() });
+    /// tag.add_terms_of_use(TermsOfUse { lang: "eng".to_string(), text: "CC BY-SA 4.0".to_string() });
+    /// assert_eq!(1, tag.terms_of_use().count());
+    /// assert_eq!("CC BY-SA 4.0", tag.terms_of_use().next().unwrap().text);
     /// ```
-    pub fn album_artist(&self) -> Option<&str> {
-        self.text_for_frame_id("TPE2")
+    pub fn add_terms_of_use(&mut self, terms: TermsOfUse) {
+        self.add_frame(Frame::with_content("USER", Content::TermsOfUse(terms)));
     }
 
-    /// Sets the album artist (TPE2).
+    /// Removes the terms of use (USER) in the specified language.
+    pub fn remove_terms_of_use(&mut self, lang: &str) {
+        self.frames.retain(|frame| {
+            match *frame.content() {
+                Content::TermsOfUse(ref terms) => terms.lang != lang,
+                _ => true,
+            }
+        });
+    }
+
+    /// Returns the event timing codes (ETCO).
+    pub fn event_timing_codes(&self) -> Option<&EventTimingCodes> {
+        self.get("ETCO").and_then(|frame| frame.content().event_timing_codes())
+    }
+
+    /// Sets the event timing codes (ETCO).
     ///
     /// # Example
     /// ```
     /// use id3::Tag;
+    /// use id3::frame::{Event, EventTimingCodes, EventType, TimestampFormat};
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_album_artist("artist");
-    /// assert_eq!(tag.album_artist().unwrap(), "artist");
+    /// tag.set_event_timing_codes(EventTimingCodes {
+    ///     timestamp_format: TimestampFormat::Ms,
+    ///     events: vec![Event { event_type: EventType::OutroStart, timestamp: 180_000 }],
+    /// });
+    /// let outro = tag.event_timing_codes().unwrap().events.iter()
+    ///     .find(|event| event.event_type == EventType::OutroStart);
+    /// assert_eq!(Some(180_000), outro.map(|event| event.timestamp));
     /// ```
-    pub fn set_album_artist<T: Into<String>>(&mut self, album_artist: T) {
-        self.set_text("TPE2", album_artist);
+    pub fn set_event_timing_codes(&mut self, etco: EventTimingCodes) {
+        self.add_frame(Frame::with_content("ETCO", Content::EventTimingCodes(etco)));
     }
 
-    /// Removes the album artist (TPE2).
+    /// Removes the event timing codes (ETCO).
+    pub fn remove_event_timing_codes(&mut self) {
+        self.remove("ETCO");
+    }
+
+    /// Returns the music CD identifier (MCDI).
     ///
     /// # Example
     /// ```
     /// use id3::Tag;
+    /// use id3::frame::MusicCdIdentifier;
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_album_artist("artist");
-    /// assert!(tag.album_artist().is_some());
-    ///
-    /// tag.remove_album_artist();
-    /// assert!(tag.album_artist().is_none());
+    /// tag.set_music_cd_identifier(MusicCdIdentifier::new(1, &[0, 15000, 30000], 45000));
+    /// let disc_id = tag.music_cd_identifier().and_then(|mcdi| mcdi.freedb_disc_id());
+    /// assert_eq!(Some(0x0c02_5803), disc_id);
     /// ```
-    pub fn remove_album_artist(&mut self) {
-        self.remove("TPE2");
+    pub fn music_cd_identifier(&self) -> Option<&MusicCdIdentifier> {
+        self.get("MCDI").and_then(|frame| frame.content().music_cd_identifier())
     }
 
-    /// Returns the album (TALB).
+    /// Sets the music CD identifier (MCDI).
+    pub fn set_music_cd_identifier(&mut self, mcdi: MusicCdIdentifier) {
+        self.add_frame(Frame::with_content("MCDI", Content::MusicCdIdentifier(mcdi)));
+    }
+
+    /// Removes the music CD identifier (MCDI).
+    pub fn remove_music_cd_identifier(&mut self) {
+        self.remove("MCDI");
+    }
+
+    /// Returns the recommended buffer size (RBUF).
+    pub fn recommended_buffer_size(&self) -> Option<&RecommendedBufferSize> {
+        self.get("RBUF").and_then(|frame| frame.content().recommended_buffer_size())
+    }
+
+    /// Sets the recommended buffer size (RBUF).
     ///
     /// # Example
     /// ```
-    /// use id3::{Frame, Tag};
-    /// use id3::frame::Content;
+    /// use id3::Tag;
+    /// use id3::frame::RecommendedBufferSize;
     ///
     /// let mut tag = Tag::new();
-    /// let frame = Frame::with_content("TALB", Content::Text("album".to_owned()));
-    /// tag.add_frame(frame);
-    /// assert_eq!(tag.album().unwrap(), "album");
+    /// tag.set_recommended_buffer_size(RecommendedBufferSize {
+    ///     buffer_size: 65536,
+    ///     embedded_info: false,
+    ///     next_tag_offset: None,
+    /// });
+    /// assert_eq!(65536, tag.recommended_buffer_size().unwrap().buffer_size);
     /// ```
-    pub fn album(&self) -> Option<&str> {
-        self.text_for_frame_id("TALB")
+    pub fn set_recommended_buffer_size(&mut self, rbuf: RecommendedBufferSize) {
+        self.add_frame(Frame::with_content("RBUF", Content::RecommendedBufferSize(rbuf)));
     }
 
-    /// Sets the album (TALB).
+    /// Returns the audio seek point index (ASPI).
+    pub fn audio_seek_point_index(&self) -> Option<&AudioSeekPointIndex> {
+        self.get("ASPI").and_then(|frame| frame.content().audio_seek_point_index())
+    }
+
+    /// Sets the audio seek point index (ASPI). Audio seek point index frames are only defined for
+    /// ID3v2.4.
+    pub fn set_audio_seek_point_index(&mut self, aspi: AudioSeekPointIndex) {
+        self.add_frame(Frame::with_content("ASPI", Content::AudioSeekPointIndex(aspi)));
+    }
+
+    /// Returns the offset of the seek frame (SEEK), which points from the end of this tag to the
+    /// next tag in the file.
+    pub fn seek_offset(&self) -> Option<u32> {
+        self.get("SEEK").and_then(|frame| frame.content().seek_offset())
+    }
+
+    /// Sets the offset of the seek frame (SEEK). The offset is the number of bytes from the end of
+    /// this tag, including its padding, to the start of the next tag. Seek frames are only
+    /// defined for ID3v2.4.
+    pub fn set_seek_offset(&mut self, offset: u32) {
+        self.add_frame(Frame::with_content("SEEK", Content::SeekOffset(offset)));
+    }
+
+    /// Removes the seek frame (SEEK).
+    pub fn remove_seek_offset(&mut self) {
+        self.remove("SEEK");
+    }
+
+    /// Returns the play counter (PCNT).
     ///
     /// # Example
     /// ```
     /// use id3::Tag;
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_album("album");
-    /// assert_eq!(tag.album().unwrap(), "album");
+    /// assert_eq!(None, tag.play_count());
+    /// tag.increment_play_count();
+    /// tag.increment_play_count();
+    /// assert_eq!(Some(2), tag.play_count());
     /// ```
-    pub fn set_album<T: Into<String>>(&mut self, album: T) {
-        self.set_text("TALB", album);
+    pub fn play_count(&self) -> Option<u64> {
+        self.get("PCNT").and_then(|frame| frame.content().play_counter())
     }
 
-    /// Removes the album (TALB).
+    /// Sets the play counter (PCNT).
+    pub fn set_play_count(&mut self, count: u64) {
+        self.add_frame(Frame::with_content("PCNT", Content::PlayCounter(count)));
+    }
+
+    /// Increments the play counter (PCNT), adding it if the tag does not have one. The counter
+    /// saturates at the maximum value.
+    pub fn increment_play_count(&mut self) {
+        let count = self.play_count().unwrap_or(0).saturating_add(1);
+        self.set_play_count(count);
+    }
+
+    /// Removes the play counter (PCNT).
+    pub fn remove_play_count(&mut self) {
+        self.remove("PCNT");
+    }
+
+    /// Adds a popularimeter frame (POPM). An existing popularimeter with the same email address is
+    /// replaced.
     ///
     /// # Example
     /// ```
     /// use id3::Tag;
+    /// use id3::frame::Popularimeter;
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_album("album");
-    /// assert!(tag.album().is_some());
-    ///
-    /// tag.remove_album();
-    /// assert!(tag.album().is_none());
+    /// tag.add_popularimeter(Popularimeter { email: "me@example.com".to_string(), rating: 1, counter: 3 });
+    /// tag.add_popularimeter(Popularimeter { email: "me@example.com".to_string(), rating: 255, counter: 4 });
+    /// assert_eq!(1, tag.popularimeters().count());
+    /// assert_eq!(255, tag.popularimeters().next().unwrap().rating);
     /// ```
-    pub fn remove_album(&mut self) {
-        self.remove("TALB");
+    pub fn add_popularimeter(&mut self, popularimeter: Popularimeter) {
+        self.add_frame(Frame::with_content("POPM", Content::Popularimeter(popularimeter)));
     }
 
-    /// Returns the title (TIT2).
+    /// Returns the rating in half stars from 0 to 10.
     ///
-    /// # Example
-    /// ```
-    /// use id3::{Frame, Tag};
-    /// use id3::frame::Content;
+    /// The popularimeter (POPM) with the email address of the convention is preferred. If there is
+    /// none, the first popularimeter is used with the scale of the application that wrote it, if
+    /// known. Finally the "RATING" user defined text frame (TXXX) is used, holding a number of
+    /// stars from 1 to 5.
     ///
-    /// let mut tag = Tag::new();
-    /// let frame = Frame::with_content("TIT2", Content::Text("title".to_owned()));
-    /// tag.add_frame(frame);
-    /// assert_eq!(tag.title().unwrap(), "title");
-    /// ```
-    pub fn title(&self) -> Option<&str> {
-        self.text_for_frame_id("TIT2")
-    }
-
-    /// Sets the title (TIT2).
+    /// Returns `None` if the tag does not contain a rating.
     ///
     /// # Example
     /// ```
-    /// use id3::Tag;
+    /// use id3::{Tag, RatingConvention};
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_title("title");
-    /// assert_eq!(tag.title().unwrap(), "title");
+    /// assert_eq!(None, tag.rating(&RatingConvention::default()));
+    /// tag.add_extended_text("RATING", "4");
+    /// assert_eq!(Some(8), tag.rating(&RatingConvention::default()));
     /// ```
-    pub fn set_title<T: Into<String>>(&mut self, title: T) {
-        self.set_text("TIT2", title);
+    pub fn rating(&self, convention: &RatingConvention) -> Option<u8> {
+        let popms: Vec<_> = self.popularimeters().collect();
+        if let Some(popm) = popms.iter().find(|popm| popm.email == convention.email) {
+            return Some(convention.scale.from_popm(popm.rating));
+        }
+        if let Some(popm) = popms.first() {
+            let scale = RatingConvention::known(&popm.email)
+                .map(|known| known.scale)
+                .unwrap_or(convention.scale);
+            return Some(scale.from_popm(popm.rating));
+        }
+        self.extended_texts()
+            .find(|ext| ext.description == "RATING")
+            .and_then(|ext| ext.value.trim().parse::<u8>().ok())
+            .filter(|stars| *stars <= 5)
+            .map(|stars| stars * 2)
     }
 
-    /// Removes the title (TIT2).
+    /// Sets the rating in half stars from 0 to 10, higher values are clamped.
+    ///
+    /// The popularimeter (POPM) with the email address of the convention is updated or added,
+    /// keeping its play counter. Existing popularimeters of Windows Media Player and MediaMonkey
+    /// are updated using their own scale, so that all of them agree. The "RATING" user defined
+    /// text frame (TXXX) is written if the convention asks for it or if it already exists, with
+    /// half stars rounded up.
     ///
     /// # Example
     /// ```
-    /// use id3::Tag;
+    /// use id3::{Tag, RatingConvention};
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_title("title");
-    /// assert!(tag.title().is_some());
+    /// tag.set_rating(&RatingConvention::media_monkey(), 7);
+    /// assert_eq!(Some(7), tag.rating(&RatingConvention::media_monkey()));
     ///
-    /// tag.remove_title();
-    /// assert!(tag.title().is_none());
+    /// // The Windows Media Player popularimeter is added, without half stars.
+    /// tag.set_rating(&RatingConvention::default(), 7);
+    /// assert_eq!(Some(8), tag.rating(&RatingConvention::default()));
+    /// assert_eq!(Some(7), tag.rating(&RatingConvention::media_monkey()));
     /// ```
-    pub fn remove_title(&mut self) {
-        self.remove("TIT2");
+    pub fn set_rating(&mut self, convention: &RatingConvention, half_stars: u8) {
+        let half_stars = half_stars.min(10);
+        let mut found = false;
+        for frame in self.frames.iter_mut() {
+            let updated = match *frame.content() {
+                Content::Popularimeter(ref popm) => {
+                    let scale = if popm.email == convention.email {
+                        found = true;
+                        Some(convention.scale)
+                    } else {
+                        RatingConvention::known(&popm.email).map(|known| known.scale)
+                    };
+                    scale.map(|scale| Popularimeter { rating: scale.to_popm(half_stars), ..popm.clone() })
+                },
+                _ => None,
+            };
+            if let Some(popm) = updated {
+                *frame = Arc::new(Frame::with_content("POPM", Content::Popularimeter(popm)));
+            }
+        }
+        if !found {
+            self.add_popularimeter(Popularimeter {
+                email: convention.email.clone(),
+                rating: convention.scale.to_popm(half_stars),
+                counter: 0,
+            });
+        }
+        if convention.text_frame || self.extended_texts().any(|ext| ext.description == "RATING") {
+            self.remove_extended_text(Some("RATING"), None);
+            if half_stars > 0 {
+                self.add_extended_text("RATING", half_stars.div_ceil(2).to_string());
+            }
+        }
     }
 
-    /// Returns the duration (TLEN).
-    ///
-    /// # Example
-    /// ```
-    /// use id3::{Frame, Tag};
-    /// use id3::frame::Content;
-    ///
-    /// let mut tag = Tag::new();
-    ///
-    /// let frame = Frame::with_content("TLEN", Content::Text("350".to_owned()));
-    /// tag.add_frame(frame);
-    /// assert_eq!(tag.duration().unwrap(), 350);
-    /// ```
-    pub fn duration(&self) -> Option<u32> {
-        self.text_for_frame_id("TLEN")
-            .and_then(|t| t.parse().ok())
+    /// Removes the popularimeter (POPM) with the email address of the convention and the "RATING"
+    /// user defined text frame (TXXX). Popularimeters of other applications are kept.
+    pub fn remove_rating(&mut self, convention: &RatingConvention) {
+        self.frames.retain(|frame| {
+            match *frame.content() {
+                Content::Popularimeter(ref popm) => popm.email != convention.email,
+                _ => true,
+            }
+        });
+        self.remove_extended_text(Some("RATING"), None);
     }
 
-    /// Sets the duration (TLEN).
+    /// Returns the values that Windows Media Player stores in private frames (PRIV).
     ///
     /// # Example
     /// ```
     /// use id3::Tag;
+    /// use id3::frame::Private;
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_duration(350);
-    /// assert_eq!(tag.duration().unwrap(), 350);
+    /// tag.add_private(Private { owner: "PeakValue".to_string(), data: vec![0x10, 0x27, 0, 0] });
+    /// assert_eq!(Some(10000), tag.windows_media_info().peak_value);
     /// ```
-    pub fn set_duration(&mut self, duration: u32) {
-        self.set_text("TLEN", duration.to_string());
+    pub fn windows_media_info(&self) -> WindowsMediaInfo {
+        WindowsMediaInfo::from_frames(self.frames())
     }
 
-    /// Removes the duration (TLEN).
+    /// Returns the gapless playback information from the "iTunSMPB" comment (COMM). Some taggers
+    /// store it in a user defined text frame (TXXX) instead, which is used as a fallback.
+    ///
+    /// Returns `None` if the information could not be found or if it could not be parsed.
     ///
     /// # Example
     /// ```
-    /// use id3::Tag;
+    /// use id3::{Tag, GaplessInfo};
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_duration(350);
-    /// assert!(tag.duration().is_some());
+    /// assert!(tag.gapless_info().is_none());
     ///
-    /// tag.remove_duration();
-    /// assert!(tag.duration().is_none());
+    /// let info = GaplessInfo { encoder_delay: 2112, padding: 458, sample_count: 4141558 };
+    /// tag.set_gapless_info(info);
+    /// assert_eq!(tag.gapless_info(), Some(info));
     /// ```
-    pub fn remove_duration(&mut self) {
-       self.remove("TLEN");
+    pub fn gapless_info(&self) -> Option<GaplessInfo> {
+        self.comments()
+            .find(|comment| comment.description == "iTunSMPB")
+            .map(|comment| comment.text.as_str())
+            .or_else(|| {
+                self.extended_texts()
+                    .find(|ext| ext.description == "iTunSMPB")
+                    .map(|ext| ext.value.as_str())
+            })
+            .and_then(|text| text.parse().ok())
     }
 
-    /// Returns the genre (TCON).
+    /// Sets the gapless playback information as an "iTunSMPB" comment (COMM), replacing any
+    /// existing information.
+    pub fn set_gapless_info(&mut self, info: GaplessInfo) {
+        self.remove_gapless_info();
+        self.add_comment(Comment {
+            lang: "eng".to_string(),
+            description: "iTunSMPB".to_string(),
+            text: info.to_string(),
+        });
+    }
+
+    /// Removes the gapless playback information.
     ///
     /// # Example
     /// ```
-    /// use id3::{Frame, Tag};
-    /// use id3::frame::Content;
+    /// use id3::{Tag, GaplessInfo};
     ///
     /// let mut tag = Tag::new();
-    /// let frame = Frame::with_content("TCON", Content::Text("genre".to_owned()));
-    /// tag.add_frame(frame);
-    /// assert_eq!(tag.genre().unwrap(), "genre");
+    /// tag.set_gapless_info(GaplessInfo::default());
+    /// tag.add_extended_text("iTunSMPB", GaplessInfo::default().to_string());
+    /// tag.remove_gapless_info();
+    /// assert!(tag.gapless_info().is_none());
     /// ```
-    pub fn genre(&self) -> Option<&str> {
-        self.text_for_frame_id("TCON")
+    pub fn remove_gapless_info(&mut self) {
+        self.remove_comment(Some("iTunSMPB"), None);
+        self.remove_extended_text(Some("iTunSMPB"), None);
     }
 
-    /// Sets the genre (TCON).
+    /// Returns the chapters (CHAP) in the tag, sorted by their start time.
     ///
     /// # Example
     /// ```
-    /// use id3::Tag;
+    /// use id3::{Tag, Frame, Content};
+    /// use id3::frame::Chapter;
+    ///
+    /// let chapter = |start, title: &str| Chapter {
+    ///     element_id: String::new(),
+    ///     start_time: start,
+    ///     end_time: start + 1000,
+    ///     start_offset: Chapter::NO_OFFSET,
+    ///     end_offset: Chapter::NO_OFFSET,
+    ///     frames: vec![Frame::with_content("TIT2", Content::Text(title.to_string().into()))],
+    /// };
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_genre("genre");
-    /// assert_eq!(tag.genre().unwrap(), "genre");
+    /// tag.set_chapters(vec![chapter(1000, "Two"), chapter(0, "One")]);
+    /// let titles: Vec<_> = tag.chapters().iter().map(|c| c.title().unwrap()).collect();
+    /// assert_eq!(vec!["One", "Two"], titles);
+    /// assert_eq!(1, tag.frames().filter(|frame| frame.id() == "CTOC").count());
     /// ```
-    pub fn set_genre<T: Into<String>>(&mut self, genre: T) {
-        self.set_text("TCON", genre);
+    pub fn chapters(&self) -> Vec<&Chapter> {
+        let mut chapters: Vec<&Chapter> = self.frames.iter()
+            .filter_map(|frame| frame.content().chapter())
+            .collect();
+        chapters.sort_by_key(|chapter| chapter.start_time);
+        chapters
     }
 
-    /// Removes the genre (TCON).
+    /// Replaces the chapters of the tag. The chapters are sorted by their start time and are
+    /// listed in a single top-level, ordered table of contents (CTOC). The element IDs of the
+    /// chapters are assigned by this method, any existing IDs are discarded.
+    ///
+    /// Setting an empty list of chapters removes all chapter and table of contents frames.
+    pub fn set_chapters(&mut self, mut chapters: Vec<Chapter>) {
+        self.remove_chapters();
+        if chapters.is_empty() {
+            return;
+        }
+        chapters.sort_by_key(|chapter| chapter.start_time);
+        let mut elements = Vec::with_capacity(chapters.len());
+        for (i, mut chapter) in chapters.into_iter().enumerate() {
+            chapter.element_id = format!("chp{}", i);
+            elements.push(chapter.element_id.clone());
+            self.frames.push(Arc::new(Frame::with_content("CHAP", Content::Chapter(chapter))));
+        }
+        self.frames.push(Arc::new(Frame::with_content("CTOC", Content::TableOfContents(TableOfContents {
+            element_id: "toc".to_string(),
+            top_level: true,
+            ordered: true,
+            elements,
+            frames: Vec::new(),
+        }))));
+    }
+
+    /// Removes all chapters (CHAP) and tables of contents (CTOC) from the tag.
+    pub fn remove_chapters(&mut self) {
+        self.remove("CHAP");
+        self.remove("CTOC");
+    }
+
+    /// Exports the chapters of the tag in the ffmpeg metadata format, so they can be added to
+    /// other files with `ffmpeg -i metadata.txt -map_chapters 1`. Only the times and titles of
+    /// the chapters are exported.
     ///
     /// # Example
     /// ```
     /// use id3::Tag;
+    /// use id3::ffmetadata::{Chapter, FfMetadata};
     ///
+    /// let metadata: FfMetadata = ";FFMETADATA1\n[CHAPTER]\nTIMEBASE=1/1000\nSTART=0\nEND=1500\ntitle=Intro\n".parse().unwrap();
     /// let mut tag = Tag::new();
-    /// tag.set_genre("genre");
-    /// assert!(tag.genre().is_some());
-    ///
-    /// tag.remove_genre();
-    /// assert!(tag.genre().is_none());
+    /// tag.set_chapters_from_ffmetadata(&metadata);
+    /// assert_eq!(Some("Intro"), tag.chapters()[0].title());
+    /// assert_eq!(vec![Chapter { start: 0, end: 1500, title: Some("Intro".to_string()) }], tag.chapters_to_ffmetadata().chapters);
     /// ```
-    pub fn remove_genre(&mut self) {
-        self.remove("TCON");
+    pub fn chapters_to_ffmetadata(&self) -> ::ffmetadata::FfMetadata {
+        ::ffmetadata::FfMetadata {
+            metadata: Vec::new(),
+            chapters: self.chapters().into_iter().map(::ffmetadata::Chapter::from).collect(),
+        }
     }
 
-    /// Returns the (disc, total_discs) tuple.
-    fn disc_pair(&self) -> Option<(u32, Option<u32>)> {
-        self.text_pair("TPOS")
+    /// Replaces the chapters of the tag with those of an ffmpeg metadata file, see `set_chapters`.
+    /// The titles are stored in TIT2 frames of the chapters, global metadata is ignored.
+    pub fn set_chapters_from_ffmetadata(&mut self, metadata: &::ffmetadata::FfMetadata) {
+        self.set_chapters(metadata.chapters.iter().cloned().map(Chapter::from).collect());
     }
 
-    /// Returns the disc number (TPOS).
+    /// Returns the year (TYER).
+    /// Returns `None` if the year frame could not be found or if it could not be parsed.
     ///
     /// # Example
     /// ```
@@ -1013,240 +2231,1173 @@ impl<'a> Tag {
     /// use id3::frame::Content;
     ///
     /// let mut tag = Tag::new();
-    /// assert!(tag.disc().is_none());
+    /// assert!(tag.year().is_none());
     ///
-    /// let mut frame_valid = Frame::with_content("TPOS", Content::Text("4".to_owned()));
+    /// let frame_valid = Frame::with_content("TYER", Content::Text("2014".into()));
     /// tag.add_frame(frame_valid);
-    /// assert_eq!(tag.disc().unwrap(), 4);
+    /// assert_eq!(tag.year().unwrap(), 2014);
     ///
-    /// tag.remove("TPOS");
+    /// tag.remove("TYER");
     ///
-    /// let mut frame_invalid = Frame::with_content("TPOS", Content::Text("nope".to_owned()));
+    /// let frame_invalid = Frame::with_content("TYER", Content::Text("nope".into()));
     /// tag.add_frame(frame_invalid);
-    /// assert!(tag.disc().is_none());
+    /// assert!(tag.year().is_none());
+    ///
+    /// // ID3v2.4 tags store the year in the recording time.
+    /// tag.remove("TYER");
+    /// tag.add_frame(Frame::with_content("TDRC", Content::Text("2016-05-04".into())));
+    /// assert_eq!(tag.year().unwrap(), 2016);
     /// ```
-    pub fn disc(&self) -> Option<u32> {
-        self.disc_pair()
-            .map(|(disc, _)| disc)
+    pub fn year(&self) -> Option<i32> {
+        match self.get("TYER") {
+            Some(frame) => frame.content().text()
+                .and_then(|text| text.trim_left_matches('0').parse().ok()),
+            None => self.date_recorded().map(|timestamp| timestamp.year),
+        }
     }
 
-    /// Sets the disc (TPOS).
+    /// Sets the year (TYER).
     ///
     /// # Example
     /// ```
     /// use id3::Tag;
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_disc(2);
-    /// assert_eq!(tag.disc().unwrap(), 2);
+    /// tag.set_year(2014);
+    /// assert_eq!(tag.year().unwrap(), 2014);
     /// ```
-    pub fn set_disc(&mut self, disc: u32) {
-        let text = match self.text_pair("TPOS").and_then(|(_, total_discs)| total_discs) {
-            Some(n) => format!("{}/{}", disc, n),
-            None => format!("{}", disc),
-        };
-        self.set_text("TPOS", text);
+    pub fn set_year(&mut self, year: i32) {
+        self.set_text("TYER", format!("{:04}", year));
     }
 
-    /// Removes the disc number (TPOS).
+    /// Removes the year (TYER) and the recording time (TDRC) which holds the year in ID3v2.4
+    /// tags.
     ///
     /// # Example
     /// ```
     /// use id3::Tag;
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_disc(3);
-    /// assert!(tag.disc().is_some());
-    ///
-    /// tag.remove_disc();
-    /// assert!(tag.disc().is_none());
+    /// tag.set_year(2014);
+    /// tag.remove_year();
+    /// assert!(tag.year().is_none());
     /// ```
-    pub fn remove_disc(&mut self) {
-        self.remove("TPOS");
+    pub fn remove_year(&mut self) {
+        self.remove("TYER");
+        self.remove("TDRC");
     }
 
-    /// Returns the total number of discs (TPOS).
+    /// Return the content of the TRDC frame, if any. For ID3v2.3 tags the recording time is
+    /// assembled from the year (TYER), date (TDAT) and time (TIME) frames.
     ///
     /// # Example
     /// ```
-    /// use id3::{Tag, Frame};
-    /// use id3::frame::Content;
+    /// use id3::Tag;
+    /// use id3::Timestamp;
     ///
     /// let mut tag = Tag::new();
-    /// assert!(tag.disc().is_none());
-    ///
-    /// let frame_valid = Frame::with_content("TPOS", Content::Text("4/10".to_owned()));
-    /// tag.add_frame(frame_valid);
-    /// assert_eq!(tag.total_discs().unwrap(), 10);
-    ///
-    /// tag.remove("TPOS");
+    /// tag.set_date_recorded(Timestamp{ year: 2014, month: None, day: None, hour: None, minute: None, second: None });
+    /// assert_eq!(tag.date_recorded().unwrap().year, 2014);
     ///
-    /// let frame_invalid = Frame::with_content("TPOS", Content::Text("4/nope".to_owned()));
-    /// tag.add_frame(frame_invalid);
-    /// assert!(tag.total_discs().is_none());
+    /// let mut tag = Tag::new();
+    /// tag.set_text("TYER", "2014");
+    /// tag.set_text("TDAT", "0405");
+    /// assert_eq!("2014-05-04", tag.date_recorded().unwrap().to_string());
     /// ```
-    pub fn total_discs(&self) -> Option<u32> {
-        self.text_pair("TPOS")
-            .and_then(|(_, total_discs)| total_discs)
+    pub fn date_recorded(&self) -> Option<Timestamp> {
+        self.read_timestamp_frame("TDRC")
+            .or_else(|| self.read_split_timestamp())
     }
 
-    /// Sets the total number of discs (TPOS).
+    /// Sets the content of the TDRC frame
     ///
     /// # Example
     /// ```
     /// use id3::Tag;
+    /// use id3::Timestamp;
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_total_discs(10);
-    /// assert_eq!(tag.total_discs().unwrap(), 10);
+    /// tag.set_date_recorded(Timestamp{ year: 2014, month: None, day: None, hour: None, minute: None, second: None });
+    /// assert_eq!(tag.date_recorded().unwrap().year, 2014);
     /// ```
-    pub fn set_total_discs(&mut self, total_discs: u32) {
-        let text = match self.text_pair("TPOS") {
-            Some((disc, _)) => format!("{}/{}", disc, total_discs),
-            None => format!("1/{}", total_discs)
-        };
-        self.set_text("TPOS", text);
+    pub fn set_date_recorded(&mut self, timestamp: Timestamp) {
+        let time_string = timestamp.to_string();
+        self.set_text("TDRC", time_string);
     }
 
-    /// Removes the total number of discs (TPOS).
+    /// Removes the TDRC frame and the ID3v2.3 frames that hold the recording time (TYER, TDAT,
+    /// TIME and TRDA).
     ///
     /// # Example
     /// ```
     /// use id3::Tag;
+    /// use id3::Timestamp;
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_total_discs(10);
-    /// assert!(tag.total_discs().is_some());
-    ///
-    /// tag.remove_total_discs();
-    /// assert!(tag.total_discs().is_none());
+    /// tag.set_date_recorded(Timestamp{ year: 2014, month: None, day: None, hour: None, minute: None, second: None });
+    /// tag.remove_date_recorded();
+    /// assert!(tag.date_recorded().is_none());
     /// ```
-    pub fn remove_total_discs(&mut self) {
-        if let Some((disc, _)) = self.text_pair("TPOS") {
-            self.set_text("TPOS", format!("{}", disc));
+    pub fn remove_date_recorded(&mut self) {
+        for id in &["TDRC", "TYER", "TDAT", "TIME", "TRDA"] {
+            self.remove(id);
         }
     }
 
-    /// Returns the track number (TRCK).
+    /// Return the content of the TDRL frame, if any
     ///
     /// # Example
     /// ```
-    /// use id3::{Tag, Frame};
-    /// use id3::frame::Content;
+    /// use id3::Tag;
+    /// use id3::Timestamp;
     ///
     /// let mut tag = Tag::new();
-    /// assert!(tag.track().is_none());
-    ///
-    /// let frame_valid = Frame::with_content("TRCK", Content::Text("4".to_owned()));
-    /// tag.add_frame(frame_valid);
-    /// assert_eq!(tag.track().unwrap(), 4);
-    ///
-    /// tag.remove("TRCK");
-    ///
-    /// let frame_invalid = Frame::with_content("TRCK", Content::Text("nope".to_owned()));
-    /// tag.add_frame(frame_invalid);
-    /// assert!(tag.track().is_none());
+    /// tag.set_date_released(Timestamp{ year: 2014, month: None, day: None, hour: None, minute: None, second: None });
+    /// assert_eq!(tag.date_released().unwrap().year, 2014);
     /// ```
-    pub fn track(&self) -> Option<u32> {
-        self.text_pair("TRCK")
-            .map(|(track, _)| track)
+    pub fn date_released(&self) -> Option<Timestamp> {
+        self.read_timestamp_frame("TDRL")
     }
 
-    /// Sets the track (TRCK).
+    /// Sets the content of the TDRL frame
     ///
     /// # Example
     /// ```
     /// use id3::Tag;
+    /// use id3::Timestamp;
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_track(10);
-    /// assert_eq!(tag.track().unwrap(), 10);
+    /// tag.set_date_released(Timestamp{ year: 2014, month: None, day: None, hour: None, minute: None, second: None });
+    /// assert_eq!(tag.date_released().unwrap().year, 2014);
     /// ```
-    pub fn set_track(&mut self, track: u32) {
-        let text = match self.text_pair("TRCK").and_then(|(_, total_tracks)| total_tracks) {
-            Some(n) => format!("{}/{}", track, n),
-            None => format!("{}", track)
-        };
-        self.set_text("TRCK", text);
+    pub fn set_date_released(&mut self, timestamp: Timestamp) {
+        let time_string = timestamp.to_string();
+        self.set_text("TDRL", time_string);
     }
 
-    /// Removes the track number (TRCK).
+    /// Removes the TDRL frame.
     ///
     /// # Example
     /// ```
     /// use id3::Tag;
+    /// use id3::Timestamp;
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_track(10);
-    /// assert!(tag.track().is_some());
-    ///
-    /// tag.remove_track();
-    /// assert!(tag.track().is_none());
+    /// tag.set_date_released(Timestamp{ year: 2014, month: None, day: None, hour: None, minute: None, second: None });
+    /// tag.remove_date_released();
+    /// assert!(tag.date_released().is_none());
     /// ```
-    pub fn remove_track(&mut self) {
-        self.remove("TRCK");
+    pub fn remove_date_released(&mut self) {
+        self.remove("TDRL");
     }
 
-    /// Returns the total number of tracks (TRCK).
+    /// Returns the artist (TPE1).
     ///
     /// # Example
     /// ```
-    /// use id3::{Tag, Frame};
+    /// use id3::{Frame, Tag};
     /// use id3::frame::Content;
     ///
     /// let mut tag = Tag::new();
-    /// assert!(tag.total_tracks().is_none());
-    ///
-    /// let frame_valid = Frame::with_content("TRCK", Content::Text("4/10".to_owned()));
-    /// tag.add_frame(frame_valid);
-    /// assert_eq!(tag.total_tracks().unwrap(), 10);
-    ///
-    /// tag.remove("TRCK");
-    ///
-    /// let frame_invalid = Frame::with_content("TRCK", Content::Text("4/nope".to_owned()));
-    /// tag.add_frame(frame_invalid);
-    /// assert!(tag.total_tracks().is_none());
+    /// let frame = Frame::with_content("TPE1", Content::Text("artist".into()));
+    /// tag.add_frame(frame);
+    /// assert_eq!(tag.artist().unwrap(), "artist");
     /// ```
-    pub fn total_tracks(&self) -> Option<u32> {
-        self.text_pair("TRCK")
-            .and_then(|(_, total_tracks)| total_tracks)
+    pub fn artist(&self) -> Option<&str> {
+        self.text_for_frame_id("TPE1")
     }
 
-    /// Sets the total number of tracks (TRCK).
+    /// Sets the artist (TPE1).
     ///
     /// # Example
     /// ```
     /// use id3::Tag;
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_total_tracks(10);
-    /// assert_eq!(tag.total_tracks().unwrap(), 10);
+    /// tag.set_artist("artist");
+    /// assert_eq!(tag.artist().unwrap(), "artist");
     /// ```
-    pub fn set_total_tracks(&mut self, total_tracks: u32) {
-        let text = match self.text_pair("TRCK") {
-            Some((track, _)) => format!("{}/{}", track, total_tracks),
-            None => format!("1/{}", total_tracks)
-        };
-        self.set_text("TRCK", text);
+    pub fn set_artist<T: Into<String>>(&mut self, artist: T) {
+        self.set_text("TPE1", artist);
     }
 
-    /// Removes the total number of tracks (TCON).
+    /// Removes the artist (TPE1).
     ///
     /// # Example
     /// ```
     /// use id3::Tag;
     ///
     /// let mut tag = Tag::new();
-    /// tag.set_total_tracks(10);
-    /// assert!(tag.total_tracks().is_some());
+    /// tag.set_artist("artist");
+    /// assert!(tag.artist().is_some());
     ///
-    /// tag.remove_total_tracks();
-    /// assert!(tag.total_tracks().is_none());
-    /// ```
+    /// tag.remove_artist();
+    /// assert!(tag.artist().is_none());
+    /// ```
+    pub fn remove_artist(&mut self) {
+        self.remove("TPE1");
+    }
+
+    /// Sets the album artist (TPE2).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{Frame, Tag};
+    /// use id3::frame::Content;
+    ///
+    /// let mut tag = Tag::new();
+    /// let frame = Frame::with_content("TPE2", Content::Text("artist".into()));
+    /// tag.add_frame(frame);
+    /// assert_eq!(tag.album_artist().unwrap(), "artist");
+    /// ```
+    pub fn album_artist(&self) -> Option<&str> {
+        self.text_for_frame_id("TPE2")
+    }
+
+    /// Sets the album artist (TPE2).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_album_artist("artist");
+    /// assert_eq!(tag.album_artist().unwrap(), "artist");
+    /// ```
+    pub fn set_album_artist<T: Into<String>>(&mut self, album_artist: T) {
+        self.set_text("TPE2", album_artist);
+    }
+
+    /// Removes the album artist (TPE2).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_album_artist("artist");
+    /// assert!(tag.album_artist().is_some());
+    ///
+    /// tag.remove_album_artist();
+    /// assert!(tag.album_artist().is_none());
+    /// ```
+    pub fn remove_album_artist(&mut self) {
+        self.remove("TPE2");
+    }
+
+    /// Returns the album (TALB).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{Frame, Tag};
+    /// use id3::frame::Content;
+    ///
+    /// let mut tag = Tag::new();
+    /// let frame = Frame::with_content("TALB", Content::Text("album".into()));
+    /// tag.add_frame(frame);
+    /// assert_eq!(tag.album().unwrap(), "album");
+    /// ```
+    pub fn album(&self) -> Option<&str> {
+        self.text_for_frame_id("TALB")
+    }
+
+    /// Sets the album (TALB).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_album("album");
+    /// assert_eq!(tag.album().unwrap(), "album");
+    /// ```
+    pub fn set_album<T: Into<String>>(&mut self, album: T) {
+        self.set_text("TALB", album);
+    }
+
+    /// Removes the album (TALB).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_album("album");
+    /// assert!(tag.album().is_some());
+    ///
+    /// tag.remove_album();
+    /// assert!(tag.album().is_none());
+    /// ```
+    pub fn remove_album(&mut self) {
+        self.remove("TALB");
+    }
+
+    /// Returns the title (TIT2).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{Frame, Tag};
+    /// use id3::frame::Content;
+    ///
+    /// let mut tag = Tag::new();
+    /// let frame = Frame::with_content("TIT2", Content::Text("title".into()));
+    /// tag.add_frame(frame);
+    /// assert_eq!(tag.title().unwrap(), "title");
+    /// ```
+    pub fn title(&self) -> Option<&str> {
+        self.text_for_frame_id("TIT2")
+    }
+
+    /// Sets the title (TIT2).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_title("title");
+    /// assert_eq!(tag.title().unwrap(), "title");
+    /// ```
+    pub fn set_title<T: Into<String>>(&mut self, title: T) {
+        self.set_text("TIT2", title);
+    }
+
+    /// Removes the title (TIT2).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_title("title");
+    /// assert!(tag.title().is_some());
+    ///
+    /// tag.remove_title();
+    /// assert!(tag.title().is_none());
+    /// ```
+    pub fn remove_title(&mut self) {
+        self.remove("TIT2");
+    }
+
+    /// Returns the duration (TLEN).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{Frame, Tag};
+    /// use id3::frame::Content;
+    ///
+    /// let mut tag = Tag::new();
+    ///
+    /// let frame = Frame::with_content("TLEN", Content::Text("350".into()));
+    /// tag.add_frame(frame);
+    /// assert_eq!(tag.duration().unwrap(), 350);
+    /// ```
+    pub fn duration(&self) -> Option<u32> {
+        self.text_for_frame_id("TLEN")
+            .and_then(|t| t.parse().ok())
+    }
+
+    /// Sets the duration (TLEN).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_duration(350);
+    /// assert_eq!(tag.duration().unwrap(), 350);
+    /// ```
+    pub fn set_duration(&mut self, duration: u32) {
+        self.set_text("TLEN", duration.to_string());
+    }
+
+    /// Removes the duration (TLEN).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_duration(350);
+    /// assert!(tag.duration().is_some());
+    ///
+    /// tag.remove_duration();
+    /// assert!(tag.duration().is_none());
+    /// ```
+    pub fn remove_duration(&mut self) {
+       self.remove("TLEN");
+    }
+
+    /// Returns the genre (TCON).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{Frame, Tag};
+    /// use id3::frame::Content;
+    ///
+    /// let mut tag = Tag::new();
+    /// let frame = Frame::with_content("TCON", Content::Text("genre".into()));
+    /// tag.add_frame(frame);
+    /// assert_eq!(tag.genre().unwrap(), "genre");
+    /// ```
+    pub fn genre(&self) -> Option<&str> {
+        self.text_for_frame_id("TCON")
+    }
+
+    /// Sets the genre (TCON).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_genre("genre");
+    /// assert_eq!(tag.genre().unwrap(), "genre");
+    /// ```
+    pub fn set_genre<T: Into<String>>(&mut self, genre: T) {
+        self.set_text("TCON", genre);
+    }
+
+    /// Returns the genre (TCON) with references to ID3v1 genres replaced by their name.
+    ///
+    /// ID3v2.3 tags refer to genres like "(17)", optionally followed by a refinement like
+    /// "(31)Psytrance", while ID3v2.4 tags use plain numbers like "17". The special references
+    /// "(RX)" and "(CR)" stand for remix and cover. If the genre holds multiple genres, the names
+    /// are separated by null characters, with a refinement following the genres it refines.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_genre("(17)");
+    /// assert_eq!(Some("Rock".to_string()), tag.genre_parsed());
+    /// tag.set_genre("(31)Psytrance");
+    /// assert_eq!(Some("Trance\0Psytrance".to_string()), tag.genre_parsed());
+    /// tag.set_genre("(17)(RX)");
+    /// assert_eq!(Some("Rock\0Remix".to_string()), tag.genre_parsed());
+    /// tag.set_genre("Vaporwave");
+    /// assert_eq!(Some("Vaporwave".to_string()), tag.genre_parsed());
+    /// ```
+    pub fn genre_parsed(&self) -> Option<String> {
+        self.genre().map(|genre| parse_genres(genre).join("\0"))
+    }
+
+    /// Returns the genre (TCON) in the specified style for a tag of the specified version.
+    pub(crate) fn genre_with_style(&self, style: GenreStyle, version: Version) -> Option<String> {
+        let genres = parse_genres(self.genre()?);
+        let genre = match style {
+            GenreStyle::Textual => genres.join("\0"),
+            GenreStyle::Numeric => {
+                let (mut references, mut names) = (Vec::new(), Vec::new());
+                for genre in genres {
+                    match genre.as_str() {
+                        "Remix" => references.push("RX".to_string()),
+                        "Cover" => references.push("CR".to_string()),
+                        _ => match ::v1::genre_id_for(&genre) {
+                            255 => names.push(genre),
+                            id => references.push(id.to_string()),
+                        },
+                    }
+                }
+                if version == Version::Id3v24 {
+                    references.extend(names);
+                    references.join("\0")
+                } else {
+                    // Genres that can not be referenced are written as the refinement.
+                    let mut genre: String = references.iter().map(|r| format!("({})", r)).collect();
+                    genre.push_str(&names.join("/"));
+                    genre
+                }
+            },
+        };
+        Some(genre)
+    }
+
+    /// Removes the genre (TCON).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_genre("genre");
+    /// assert!(tag.genre().is_some());
+    ///
+    /// tag.remove_genre();
+    /// assert!(tag.genre().is_none());
+    /// ```
+    pub fn remove_genre(&mut self) {
+        self.remove("TCON");
+    }
+
+    /// Returns the involved people list (TIPL) as pairs of a role and the person in that role.
+    /// The involved people list of ID3v2.3 tags (IPLS) is used if there is no TIPL frame.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_involved_people(vec![("producer", "Producer"), ("mix", "Mixer")]);
+    /// assert_eq!(vec![("producer", "Producer"), ("mix", "Mixer")], tag.involved_people());
+    /// ```
+    pub fn involved_people(&self) -> Vec<(&str, &str)> {
+        match self.get("TIPL") {
+            Some(_) => self.text_pairs("TIPL"),
+            None => self.text_pairs("IPLS"),
+        }
+    }
+
+    /// Sets the involved people list (TIPL) from pairs of a role and the person in that role.
+    /// When the tag is written as ID3v2.2 or ID3v2.3, the list is converted to an IPLS frame.
+    pub fn set_involved_people<I, R, P>(&mut self, people: I)
+        where I: IntoIterator<Item = (R, P)>, R: Into<String>, P: Into<String> {
+        self.remove("IPLS");
+        self.set_text_pairs("TIPL", people);
+    }
+
+    /// Removes the involved people list (TIPL and IPLS).
+    pub fn remove_involved_people(&mut self) {
+        self.remove("TIPL");
+        self.remove("IPLS");
+    }
+
+    /// Returns the musician credits list (TMCL) as pairs of an instrument and the musician that
+    /// plays it.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_musician_credits(vec![("bass", "Bassist"), ("drums", "Drummer")]);
+    /// assert_eq!(vec![("bass", "Bassist"), ("drums", "Drummer")], tag.musician_credits());
+    /// ```
+    pub fn musician_credits(&self) -> Vec<(&str, &str)> {
+        self.text_pairs("TMCL")
+    }
+
+    /// Sets the musician credits list (TMCL) from pairs of an instrument and the musician that
+    /// plays it. ID3v2.2 and ID3v2.3 have no such list, when the tag is written in one of these
+    /// versions the credits are added to the involved people list (IPLS).
+    pub fn set_musician_credits<I, R, P>(&mut self, musicians: I)
+        where I: IntoIterator<Item = (R, P)>, R: Into<String>, P: Into<String> {
+        self.set_text_pairs("TMCL", musicians);
+    }
+
+    /// Removes the musician credits list (TMCL).
+    pub fn remove_musician_credits(&mut self) {
+        self.remove("TMCL");
+    }
+
+    /// Returns whether the album is marked as a compilation by the non-standard TCMP frame of
+    /// iTunes.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// assert!(!tag.compilation());
+    /// tag.set_compilation(true);
+    /// assert!(tag.compilation());
+    /// assert_eq!(Some("1"), tag.get("TCMP").and_then(|frame| frame.content().text()));
+    ///
+    /// tag.set_compilation(false);
+    /// assert!(tag.get("TCMP").is_none());
+    /// ```
+    pub fn compilation(&self) -> bool {
+        self.text_for_frame_id("TCMP")
+            .and_then(|text| text.trim().parse::<u32>().ok())
+            .is_some_and(|value| value != 0)
+    }
+
+    /// Marks the album as a compilation with the TCMP frame, or removes the frame.
+    pub fn set_compilation(&mut self, compilation: bool) {
+        if compilation {
+            self.set_text("TCMP", "1");
+        } else {
+            self.remove("TCMP");
+        }
+    }
+
+    /// Compares the frames of this tag with those of another tag, like the tag that is about to be
+    /// written, and lists the frames that were added, removed or changed in the other tag.
+    ///
+    /// Frames are matched like `add_frame` replaces them: text frames by their ID, comments and
+    /// lyrics by their language and description, user defined frames by their description,
+    /// pictures by their type and so on.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_title("Title");
+    /// tag.set_artist("Artist");
+    /// let mut new = tag.clone();
+    /// new.set_title("New Title");
+    /// new.remove_artist();
+    ///
+    /// let diff = tag.diff(&new);
+    /// assert_eq!("TPE1", diff.removed[0].id());
+    /// assert_eq!("New Title", diff.changed[0].fields[0].new);
+    /// assert_eq!("- TPE1: Artist\n~ TIT2 text: Title -> New Title\n", diff.to_string());
+    /// ```
+    pub fn diff(&self, other: &Tag) -> TagDiff {
+        ::diff::diff(self, other)
+    }
+
+    /// Merges the frames of another tag into this tag, for example to combine the metadata of an
+    /// ID3v1 tag or an online lookup with an existing tag.
+    ///
+    /// Frames of the other tag that do not conflict with a frame of this tag are added, conflicts
+    /// are resolved with the strategy the policy selects for the frame ID.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{MergePolicy, MergeStrategy, Tag};
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_title("Title");
+    /// tag.set_artist("Artist");
+    /// let mut lookup = Tag::new();
+    /// lookup.set_title("Looked Up Title");
+    /// lookup.set_artist("Featured Artist");
+    /// lookup.set_album("Album");
+    ///
+    /// let policy = MergePolicy::new(MergeStrategy::KeepMine)
+    ///     .with_frame("TPE1", MergeStrategy::Combine);
+    /// tag.merge(&lookup, &policy);
+    /// assert_eq!(Some("Title"), tag.title());
+    /// assert_eq!(vec!["Artist", "Featured Artist"], tag.get("TPE1").unwrap().content().text_values().collect::<Vec<_>>());
+    /// assert_eq!(Some("Album"), tag.album());
+    /// ```
+    pub fn merge(&mut self, other: &Tag, policy: &MergePolicy) {
+        for theirs in &other.frames {
+            let i = match self.frames.iter().position(|mine| *mine == *theirs) {
+                Some(i) => i,
+                None => {
+                    self.frames.push(theirs.clone());
+                    continue;
+                },
+            };
+            match policy.strategy(theirs.id()) {
+                MergeStrategy::KeepMine => {},
+                MergeStrategy::KeepTheirs => self.frames[i] = theirs.clone(),
+                MergeStrategy::Combine => {
+                    if let Some(combined) = combine_values(&self.frames[i], theirs) {
+                        self.frames[i] = Arc::new(combined);
+                    }
+                },
+            }
+        }
+    }
+
+    /// Serializes the tag as JSON, so it can be edited by tools like jq and read back with
+    /// `Tag::from_json`.
+    ///
+    /// The schema is stable. The tag is an object with the minor `version` of the binary frame
+    /// data, which is always 4 when writing, and a `frames` array holding an object for each
+    /// frame, in the order of the tag. Every frame object has the frame `id` and, depending on the
+    /// content:
+    ///
+    /// - Text frames: `text`, an array of the values. A single string is accepted as well.
+    /// - Link frames: `link`.
+    /// - User defined text frames (TXXX): `description` and `value`.
+    /// - User defined link frames (WXXX): `description` and `link`.
+    /// - Comments (COMM) and lyrics (USLT): `lang`, `description` and `text`.
+    /// - All other frames: `binary`, the base64 encoded content as it is stored in an ID3v2.4
+    ///   frame with UTF-8 text. When reading, it is decoded according to the `version` of the tag.
+    ///
+    /// Frame flags that are set are stored as `tag_alter_preservation` and
+    /// `file_alter_preservation` booleans and as `group_identifier` and `encryption_method`
+    /// numbers. Missing strings are read as empty strings, a missing `version` as 4 and unknown
+    /// members are ignored.
+    ///
+    /// Requires the `json` feature.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_title("Title");
+    /// let json = tag.to_json().unwrap();
+    /// assert!(json.contains("\"text\": [\n        \"Title\"\n      ]"));
+    /// assert_eq!(tag, Tag::from_json(&json).unwrap());
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> ::Result<String> {
+        ::json::tag_to_json(self)
+    }
+
+    /// Parses a tag from JSON as written by `Tag::to_json`, which describes the schema. Requires
+    /// the `json` feature.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let tag = Tag::from_json(r#"{"frames": [{"id": "TPE1", "text": ["One", "Two"]}]}"#).unwrap();
+    /// assert_eq!(Some("One\0Two"), tag.artist());
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn from_json(json: &str) -> ::Result<Tag> {
+        ::json::tag_from_json(json)
+    }
+
+    /// Converts the frames of the tag to the specified version, so the tag can be written in that
+    /// version without losing information unnoticed. Text frames are re-encoded when the tag is
+    /// written, this converts the frames themselves:
+    ///
+    /// - The recording time (TDRC) is split into the year (TYER), date (TDAT) and time (TIME) of
+    ///   ID3v2.3 and joined again for ID3v2.4, the same goes for the original release time (TDOR
+    ///   and TORY).
+    /// - The involved people list (IPLS) is split into TIPL and the musician credits (TMCL) by
+    ///   role for ID3v2.4, while TIPL and TMCL are joined into IPLS for older versions.
+    /// - The volume adjustment (RVAD) and equalisation (EQUA) of older versions are converted to
+    ///   RVA2 and EQU2 frames.
+    /// - Multiple values of text frames are separated by slashes for older versions.
+    ///
+    /// All frames that can not be represented in the version are removed and reported, as are
+    /// the frames that lost information in the conversion, like the seconds of the recording time.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{Tag, Version};
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_text("TYER", "2014");
+    /// tag.set_text("TSIZ", "1234");
+    /// let report = tag.convert_to(Version::Id3v24);
+    /// assert_eq!(Some("2014"), tag.get("TDRC").and_then(|frame| frame.content().text()));
+    /// assert_eq!("TSIZ", report.dropped[0].id());
+    /// ```
+    pub fn convert_to(&mut self, version: Version) -> ConversionReport {
+        let mut report = ConversionReport::default();
+        report.lossy.extend(self.convert_involved_people(version));
+        report.lossy.extend(self.convert_dates(version));
+        let frames = ::std::mem::take(&mut self.frames);
+        for frame in frames {
+            let converted = match (version, frame.id()) {
+                (Version::Id3v24, "RVAD") => frame.content().unknown()
+                    .and_then(convert::rvad_to_rva2)
+                    .map(|rva2| Arc::new(Frame::with_content("RVA2", Content::RelativeVolumeAdjustment(rva2)))),
+                (Version::Id3v24, "EQUA") => frame.content().unknown()
+                    .and_then(convert::equa_to_equ2)
+                    .map(|equ2| Arc::new(Frame::with_content("EQU2", Content::Equalisation(equ2)))),
+                (Version::Id3v22, "ENCR") | (Version::Id3v22, "GRID") => None,
+                (Version::Id3v22, _) if frame.encryption_method().is_some() => None,
+                (Version::Id3v22, id) | (Version::Id3v23, id) if id != "IPLS" && frame.content().text().is_some_and(|text| text.contains('\0')) => {
+                    let text = frame.content().text().unwrap().replace('\0', "/");
+                    let mut joined = Frame::with_content(id, Content::Text(text.into()));
+                    joined.set_tag_alter_preservation(frame.tag_alter_preservation());
+                    joined.set_file_alter_preservation(frame.file_alter_preservation());
+                    joined.set_group_identifier(frame.group_identifier());
+                    report.lossy.push((id.to_string(), "multiple values are joined with slashes".to_string()));
+                    Some(Arc::new(joined))
+                },
+                (_, id) if convert::is_representable(id, version) => Some(frame.clone()),
+                _ => None,
+            };
+            match converted {
+                Some(converted) => self.frames.push(converted),
+                None => report.dropped.push((*frame).clone()),
+            }
+        }
+        report
+    }
+
+    /// Returns whether the involved people and musician credits lists are stored in frames that
+    /// do not exist in the specified version, see `convert_involved_people`.
+    pub(crate) fn needs_involved_people_conversion(&self, version: Version) -> bool {
+        match version {
+            Version::Id3v24 => self.get("IPLS").is_some(),
+            Version::Id3v22 | Version::Id3v23 => self.get("TIPL").is_some() || self.get("TMCL").is_some(),
+        }
+    }
+
+    /// Moves the involved people list into the frames of the specified version: IPLS for ID3v2.2
+    /// and ID3v2.3, which also receives the musician credits, and TIPL and TMCL for ID3v2.4. The
+    /// pairs of IPLS are split by their role, see `convert::is_involvement_role`. Returns the
+    /// frames that lost information, like `ConversionReport::lossy`.
+    pub(crate) fn convert_involved_people(&mut self, version: Version) -> Vec<(String, String)> {
+        let mut lossy = Vec::new();
+        if !self.needs_involved_people_conversion(version) {
+            return lossy;
+        }
+        let owned = |pairs: Vec<(&str, &str)>| -> Vec<(String, String)> {
+            pairs.into_iter().map(|(first, second)| (first.to_string(), second.to_string())).collect()
+        };
+        match version {
+            Version::Id3v24 => {
+                let (mut people, mut musicians) = (owned(self.text_pairs("TIPL")), owned(self.text_pairs("TMCL")));
+                for (role, person) in owned(self.text_pairs("IPLS")) {
+                    if convert::is_involvement_role(&role) {
+                        people.push((role, person));
+                    } else {
+                        musicians.push((role, person));
+                    }
+                }
+                self.remove("IPLS");
+                self.set_text_pairs("TIPL", people);
+                self.set_text_pairs("TMCL", musicians);
+            },
+            Version::Id3v22 | Version::Id3v23 => {
+                if self.get("TMCL").is_some() {
+                    lossy.push(("TMCL".to_string(), "musician credits are merged into the involved people list (IPLS)".to_string()));
+                }
+                let people: Vec<(String, String)> = ["IPLS", "TIPL", "TMCL"].iter()
+                    .flat_map(|id| owned(self.text_pairs(id)))
+                    .collect();
+                for id in &["IPLS", "TIPL", "TMCL"] {
+                    self.remove(id);
+                }
+                self.set_text_pairs("IPLS", people);
+            },
+        }
+        lossy
+    }
+
+    /// Returns whether the recording time or original release time are stored in frames that do
+    /// not exist in the specified version, see `convert_dates`.
+    pub(crate) fn needs_date_conversion(&self, version: Version) -> bool {
+        let ids: &[&str] = match version {
+            Version::Id3v24 => &["TYER", "TDAT", "TIME", "TRDA", "TORY"],
+            Version::Id3v22 | Version::Id3v23 => &["TDRC", "TDOR"],
+        };
+        ids.iter().any(|id| self.get(id).is_some())
+    }
+
+    /// Converts between the recording time (TDRC) of ID3v2.4 and the year (TYER), date (TDAT)
+    /// and time (TIME) frames of older versions, and between the original release time (TDOR)
+    /// and the original release year (TORY). Older versions can not store seconds, and times
+    /// that can not be split are stored as recording dates (TRDA). Frames that can not be
+    /// converted are kept. Returns the frames that lost information, like
+    /// `ConversionReport::lossy`.
+    pub(crate) fn convert_dates(&mut self, version: Version) -> Vec<(String, String)> {
+        let mut lossy = Vec::new();
+        let mut report = |id: &str, description: &str| lossy.push((id.to_string(), description.to_string()));
+        if !self.needs_date_conversion(version) {
+            return lossy;
+        }
+        match version {
+            Version::Id3v24 => {
+                if self.get("TDRC").is_none() {
+                    if let Some(timestamp) = self.read_split_timestamp() {
+                        self.set_date_recorded(timestamp);
+                        if self.get("TYER").is_some() && self.get("TRDA").is_some() {
+                            report("TRDA", "recording dates are replaced by the recording time (TDRC)");
+                        }
+                    }
+                }
+                if self.get("TDRC").is_some() {
+                    for id in &["TYER", "TDAT", "TIME", "TRDA"] {
+                        self.remove(id);
+                    }
+                }
+                if self.get("TDOR").is_none() {
+                    if let Some(year) = self.text_for_frame_id("TORY").and_then(|text| text.trim().parse::<i32>().ok()) {
+                        self.set_text("TDOR", format!("{:04}", year));
+                    }
+                }
+                if self.get("TDOR").is_some() {
+                    self.remove("TORY");
+                }
+            },
+            Version::Id3v22 | Version::Id3v23 => {
+                match self.read_timestamp_frame("TDRC") {
+                    Some(timestamp) => {
+                        if timestamp.second.is_some() {
+                            report("TDRC", "seconds of the recording time can not be stored");
+                        }
+                        for id in &["TYER", "TDAT", "TIME", "TRDA"] {
+                            self.remove(id);
+                        }
+                        self.set_text("TYER", format!("{:04}", timestamp.year));
+                        if let (Some(month), Some(day)) = (timestamp.month, timestamp.day) {
+                            self.set_text("TDAT", format!("{:02}{:02}", day, month));
+                            if let (Some(hour), Some(minute)) = (timestamp.hour, timestamp.minute) {
+                                self.set_text("TIME", format!("{:02}{:02}", hour, minute));
+                            }
+                        }
+                    },
+                    None => if let Some(text) = self.text_for_frame_id("TDRC").map(str::to_string) {
+                        report("TDRC", "recording time is stored as text in the recording dates (TRDA)");
+                        self.set_text("TRDA", text);
+                    },
+                }
+                self.remove("TDRC");
+                if let Some(timestamp) = self.read_timestamp_frame("TDOR") {
+                    if timestamp.month.is_some() {
+                        report("TDOR", "only the year of the original release time is stored in TORY");
+                    }
+                    self.set_text("TORY", format!("{:04}", timestamp.year));
+                    self.remove("TDOR");
+                }
+            },
+        }
+        lossy
+    }
+
+    /// Splits the null separated values of a text frame into pairs, like the role and person
+    /// pairs of TIPL. A value without a partner is paired with an empty string.
+    fn text_pairs(&self, id: &str) -> Vec<(&str, &str)> {
+        let values: Vec<&str> = self.get(id)
+            .map(|frame| frame.content().text_values().collect())
+            .unwrap_or_default();
+        values.chunks(2)
+            .map(|pair| (pair[0], pair.get(1).cloned().unwrap_or("")))
+            .collect()
+    }
+
+    /// Stores pairs as the null separated values of a text frame, see `text_pairs`. The frame is
+    /// removed if there are no pairs.
+    fn set_text_pairs<I, R, P>(&mut self, id: &str, pairs: I)
+        where I: IntoIterator<Item = (R, P)>, R: Into<String>, P: Into<String> {
+        let values: Vec<String> = pairs.into_iter()
+            .flat_map(|(first, second)| vec![first.into(), second.into()])
+            .collect();
+        if values.is_empty() {
+            self.remove(id);
+        } else {
+            self.set_text_values(id, values);
+        }
+    }
+
+    /// Returns the disc number (TPOS).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{Tag, Frame};
+    /// use id3::frame::Content;
+    ///
+    /// let mut tag = Tag::new();
+    /// assert!(tag.disc().is_none());
+    ///
+    /// let mut frame_valid = Frame::with_content("TPOS", Content::Text("4".into()));
+    /// tag.add_frame(frame_valid);
+    /// assert_eq!(tag.disc().unwrap(), 4);
+    ///
+    /// tag.remove("TPOS");
+    ///
+    /// let mut frame_invalid = Frame::with_content("TPOS", Content::Text("nope".into()));
+    /// tag.add_frame(frame_invalid);
+    /// assert!(tag.disc().is_none());
+    /// ```
+    pub fn disc(&self) -> Option<u32> {
+        self.text_pair("TPOS")
+            .and_then(|(disc, _)| disc)
+    }
+
+    /// Sets the disc (TPOS).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_disc(2);
+    /// assert_eq!(tag.disc().unwrap(), 2);
+    /// ```
+    pub fn set_disc(&mut self, disc: u32) {
+        let total = self.total_discs();
+        self.set_text_pair("TPOS", Some(disc), total);
+    }
+
+    /// Removes the disc number (TPOS). As the frame holds both, this also removes the total
+    /// number of discs, use `remove_total_discs` to only remove the total.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_disc(3);
+    /// assert!(tag.disc().is_some());
+    ///
+    /// tag.remove_disc();
+    /// assert!(tag.disc().is_none());
+    /// ```
+    pub fn remove_disc(&mut self) {
+        self.remove("TPOS");
+    }
+
+    /// Returns the total number of discs (TPOS).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{Tag, Frame};
+    /// use id3::frame::Content;
+    ///
+    /// let mut tag = Tag::new();
+    /// assert!(tag.disc().is_none());
+    ///
+    /// let frame_valid = Frame::with_content("TPOS", Content::Text("4/10".into()));
+    /// tag.add_frame(frame_valid);
+    /// assert_eq!(tag.total_discs().unwrap(), 10);
+    ///
+    /// tag.remove("TPOS");
+    ///
+    /// let frame_invalid = Frame::with_content("TPOS", Content::Text("4/nope".into()));
+    /// tag.add_frame(frame_invalid);
+    /// assert!(tag.total_discs().is_none());
+    /// ```
+    pub fn total_discs(&self) -> Option<u32> {
+        self.text_pair("TPOS")
+            .and_then(|(_, total_discs)| total_discs)
+    }
+
+    /// Sets the total number of discs (TPOS). Without a disc number, this is stored as "/10".
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_total_discs(10);
+    /// assert_eq!(tag.total_discs().unwrap(), 10);
+    /// ```
+    pub fn set_total_discs(&mut self, total_discs: u32) {
+        let number = self.disc();
+        self.set_text_pair("TPOS", number, Some(total_discs));
+    }
+
+    /// Removes the total number of discs (TPOS). The disc number is kept, if there is one.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_total_discs(10);
+    /// assert!(tag.total_discs().is_some());
+    ///
+    /// tag.remove_total_discs();
+    /// assert!(tag.total_discs().is_none());
+    /// ```
+    pub fn remove_total_discs(&mut self) {
+        let number = self.disc();
+        self.set_text_pair("TPOS", number, None);
+    }
+
+    /// Returns the track number (TRCK).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{Tag, Frame};
+    /// use id3::frame::Content;
+    ///
+    /// let mut tag = Tag::new();
+    /// assert!(tag.track().is_none());
+    ///
+    /// let frame_valid = Frame::with_content("TRCK", Content::Text("4".into()));
+    /// tag.add_frame(frame_valid);
+    /// assert_eq!(tag.track().unwrap(), 4);
+    ///
+    /// tag.remove("TRCK");
+    ///
+    /// let frame_invalid = Frame::with_content("TRCK", Content::Text("nope".into()));
+    /// tag.add_frame(frame_invalid);
+    /// assert!(tag.track().is_none());
+    /// ```
+    pub fn track(&self) -> Option<u32> {
+        self.text_pair("TRCK")
+            .and_then(|(track, _)| track)
+    }
+
+    /// Sets the track (TRCK).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_track(10);
+    /// assert_eq!(tag.track().unwrap(), 10);
+    /// ```
+    pub fn set_track(&mut self, track: u32) {
+        let total = self.total_tracks();
+        self.set_text_pair("TRCK", Some(track), total);
+    }
+
+    /// Removes the track number (TRCK). As the frame holds both, this also removes the total
+    /// number of tracks, use `remove_total_tracks` to only remove the total.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_track(10);
+    /// assert!(tag.track().is_some());
+    ///
+    /// tag.remove_track();
+    /// assert!(tag.track().is_none());
+    /// ```
+    pub fn remove_track(&mut self) {
+        self.remove("TRCK");
+    }
+
+    /// Returns the total number of tracks (TRCK).
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{Tag, Frame};
+    /// use id3::frame::Content;
+    ///
+    /// let mut tag = Tag::new();
+    /// assert!(tag.total_tracks().is_none());
+    ///
+    /// let frame_valid = Frame::with_content("TRCK", Content::Text("4/10".into()));
+    /// tag.add_frame(frame_valid);
+    /// assert_eq!(tag.total_tracks().unwrap(), 10);
+    ///
+    /// tag.remove("TRCK");
+    ///
+    /// let frame_invalid = Frame::with_content("TRCK", Content::Text("4/nope".into()));
+    /// tag.add_frame(frame_invalid);
+    /// assert!(tag.total_tracks().is_none());
+    /// ```
+    pub fn total_tracks(&self) -> Option<u32> {
+        self.text_pair("TRCK")
+            .and_then(|(_, total_tracks)| total_tracks)
+    }
+
+    /// Sets the total number of tracks (TRCK). Without a track number, this is stored as
+    /// "/10".
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_total_tracks(10);
+    /// assert_eq!(tag.total_tracks().unwrap(), 10);
+    /// ```
+    pub fn set_total_tracks(&mut self, total_tracks: u32) {
+        let number = self.track();
+        self.set_text_pair("TRCK", number, Some(total_tracks));
+    }
+
+    /// Removes the total number of tracks (TRCK). The track number is kept, if there is one.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_total_tracks(10);
+    /// assert!(tag.total_tracks().is_some());
+    ///
+    /// tag.remove_total_tracks();
+    /// assert!(tag.total_tracks().is_none());
+    /// ```
     pub fn remove_total_tracks(&mut self) {
-        if let Some((track, _)) = self.text_pair("TRCK") {
-            self.set_text("TRCK", format!("{}", track));
-        }
+        let number = self.track();
+        self.set_text_pair("TRCK", number, None);
     }
 
     /// Sets the lyrics (USLT).
@@ -1264,12 +3415,148 @@ impl<'a> Tag {
     /// });
     /// assert_eq!(tag.lyrics().nth(0).unwrap().text, "The lyrics");
     /// ```
-    #[deprecated(note = "There can be more than one lyrics frame")]
+    #[deprecated(note = "Use add_lyrics()")]
     pub fn set_lyrics(&mut self, lyrics: Lyrics) {
         let frame = Frame::with_content("USLT", Content::Lyrics(lyrics));
         self.add_frame(frame);
     }
 
+    /// Adds a lyrics frame (USLT). Only an existing lyrics frame with the same language and
+    /// description is replaced, so lyrics in several languages can be carried side by side.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    /// use id3::frame::Lyrics;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.add_lyrics(Lyrics {
+    ///     lang: "eng".to_string(),
+    ///     description: "".to_string(),
+    ///     text: "The lyrics".to_string(),
+    /// });
+    /// tag.add_lyrics(Lyrics {
+    ///     lang: "deu".to_string(),
+    ///     description: "".to_string(),
+    ///     text: "Der Liedtext".to_string(),
+    /// });
+    /// tag.add_lyrics(Lyrics {
+    ///     lang: "eng".to_string(),
+    ///     description: "".to_string(),
+    ///     text: "The corrected lyrics".to_string(),
+    /// });
+    /// assert_eq!(tag.lyrics().count(), 2);
+    /// assert_eq!(tag.lyrics_by_language()["eng"][0].text, "The corrected lyrics");
+    /// ```
+    pub fn add_lyrics(&mut self, lyrics: Lyrics) {
+        let frame = Frame::with_content("USLT", Content::Lyrics(lyrics));
+        self.add_frame(frame);
+    }
+
+    /// Returns the lyrics (USLT) grouped by their language. A language may have several lyrics
+    /// frames with different descriptions.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    /// use id3::frame::Lyrics;
+    ///
+    /// let mut tag = Tag::new();
+    /// for &(lang, description) in &[("eng", ""), ("eng", "clean"), ("jpn", "")] {
+    ///     tag.add_lyrics(Lyrics {
+    ///         lang: lang.to_string(),
+    ///         description: description.to_string(),
+    ///         text: "...".to_string(),
+    ///     });
+    /// }
+    /// let by_language = tag.lyrics_by_language();
+    /// assert_eq!(by_language.len(), 2);
+    /// assert_eq!(by_language["eng"].len(), 2);
+    /// assert_eq!(by_language["jpn"].len(), 1);
+    /// ```
+    pub fn lyrics_by_language(&self) -> BTreeMap<&str, Vec<&Lyrics>> {
+        let mut map: BTreeMap<&str, Vec<&Lyrics>> = BTreeMap::new();
+        for lyrics in self.lyrics() {
+            map.entry(lyrics.lang.as_str()).or_default().push(lyrics);
+        }
+        map
+    }
+
+    /// Removes the lyrics (USLT) in the specified language. The description may be `None` to
+    /// remove the lyrics with any description.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    /// use id3::frame::Lyrics;
+    ///
+    /// let mut tag = Tag::new();
+    /// for &(lang, description) in &[("eng", ""), ("eng", "clean"), ("jpn", "")] {
+    ///     tag.add_lyrics(Lyrics {
+    ///         lang: lang.to_string(),
+    ///         description: description.to_string(),
+    ///         text: "...".to_string(),
+    ///     });
+    /// }
+    /// tag.remove_lyrics_by_language("eng", Some("clean"));
+    /// assert_eq!(tag.lyrics().count(), 2);
+    /// tag.remove_lyrics_by_language("eng", None);
+    /// assert_eq!(tag.lyrics().count(), 1);
+    /// assert_eq!(tag.lyrics().nth(0).unwrap().lang, "jpn");
+    /// ```
+    pub fn remove_lyrics_by_language(&mut self, lang: &str, description: Option<&str>) {
+        self.frames.retain(|frame| {
+            match *frame.content() {
+                Content::Lyrics(ref lyrics) if frame.id() == "USLT" => {
+                    let descr_match = description.map(|v| v == lyrics.description)
+                        .unwrap_or(true);
+                    // True if we want to keep the frame.
+                    !(lyrics.lang == lang && descr_match)
+                },
+                _ => true,
+            }
+        });
+    }
+
+    /// Adds a synchronised lyrics frame (SYLT). An existing frame with the same language and
+    /// description is replaced.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    /// use id3::frame::{SynchronisedLyrics, SynchronisedLyricsType, TimestampFormat};
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.add_synchronised_lyrics(SynchronisedLyrics {
+    ///     lang: "eng".to_string(),
+    ///     timestamp_format: TimestampFormat::Ms,
+    ///     content_type: SynchronisedLyricsType::Lyrics,
+    ///     description: "".to_string(),
+    ///     content: vec![(0, "Strangers in".to_string()), (1200, "\nthe night".to_string())],
+    /// });
+    /// let lyrics = tag.synchronised_lyrics().next().unwrap();
+    /// assert_eq!(lyrics.lines()[1], (1200, "the night".to_string()));
+    /// ```
+    pub fn add_synchronised_lyrics(&mut self, lyrics: SynchronisedLyrics) {
+        let frame = Frame::with_content("SYLT", Content::SynchronisedLyrics(lyrics));
+        self.add_frame(frame);
+    }
+
+    /// Removes the synchronised lyrics (SYLT) in the specified language. The description may be
+    /// `None` to remove the lyrics with any description.
+    pub fn remove_synchronised_lyrics(&mut self, lang: &str, description: Option<&str>) {
+        self.frames.retain(|frame| {
+            match *frame.content() {
+                Content::SynchronisedLyrics(ref lyrics) => {
+                    let descr_match = description.map(|v| v == lyrics.description)
+                        .unwrap_or(true);
+                    !(lyrics.lang == lang && descr_match)
+                },
+                _ => true,
+            }
+        });
+    }
+
     /// Removes the lyrics text (USLT) from the tag.
     ///
     /// # Exmaple
@@ -1359,10 +3646,181 @@ impl<'a> Tag {
         stream::tag::decode(reader)
     }
 
+    /// Reads the header of the ID3v2 tag at the current position of the reader without decoding
+    /// any frames. This is a cheap way to obtain the version and size of a tag, for example to
+    /// find where the audio starts. The position of the reader is restored afterwards.
+    ///
+    /// Returns `None` if there is no tag at the current position.
+    ///
+    /// # Example
+    /// ```
+    /// use std::fs::File;
+    /// use id3::{Tag, Version};
+    ///
+    /// let mut file = File::open("testdata/id3v24.id3").unwrap();
+    /// let header = Tag::peek_header(&mut file).unwrap().unwrap();
+    /// assert_eq!(Version::Id3v24, header.version());
+    /// assert!(header.total_size() > 10);
+    /// ```
+    pub fn peek_header<R>(reader: R) -> ::Result<Option<stream::tag::TagHeader>>
+        where R: io::Read + io::Seek {
+        stream::tag::peek_header(reader)
+    }
+
+    /// Attempts to read an ID3 tag from the reader, only decoding the frames for which the filter
+    /// returns true. The bodies of the other frames are seeked past without reading them, which
+    /// makes scanning many files for a few frames a lot faster.
+    ///
+    /// The filter is called with the ID3v2.3/ID3v2.4 ID of each frame, also for ID3v2.2 tags.
+    /// See `Decoder::on_frame` for more control.
+    ///
+    /// # Example
+    /// ```
+    /// use std::fs::File;
+    /// use id3::Tag;
+    ///
+    /// let file = File::open("testdata/id3v24.id3").unwrap();
+    /// let tag = Tag::read_from_with_filter(file, |id| id == "TIT2" || id == "TPE1").unwrap();
+    /// assert_eq!(Some("Title"), tag.title());
+    /// assert_eq!(0, tag.pictures().count());
+    /// ```
+    pub fn read_from_with_filter<R, F>(reader: R, filter: F) -> ::Result<Tag>
+        where R: io::Read + io::Seek, F: FnMut(&str) -> bool {
+        stream::tag::decode_filtered(reader, filter)
+    }
+
+    /// Attempts to read an ID3 tag from the asynchronous reader. Only the tag is read, the reader
+    /// is left positioned directly after it.
+    ///
+    /// This requires the `tokio` feature.
+    #[cfg(feature = "tokio")]
+    pub fn async_read_from<R>(reader: R) -> ::async_io::ReadTag<R>
+        where R: ::tokio::io::AsyncRead + Unpin {
+        ::async_io::ReadTag::new(reader)
+    }
+
+    /// Reads the headers of all frames in an ID3 tag without decoding their contents.
+    ///
+    /// This is a lot cheaper than reading the complete tag and can be used to find out which
+    /// frames a tag contains and where they are located. The offsets of the frames are relative
+    /// to the start of the tag.
+    ///
+    /// # Example
+    /// ```
+    /// use std::io::Cursor;
+    /// use id3::{Tag, Version};
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_title("title");
+    /// let mut buf = Vec::new();
+    /// tag.write_to(&mut buf, Version::Id3v24).unwrap();
+    ///
+    /// let index = Tag::read_index_from(Cursor::new(buf)).unwrap();
+    /// assert_eq!(index[0].id(), "TIT2");
+    /// assert_eq!(index[0].offset(), 10);
+    /// ```
+    pub fn read_index_from<R>(reader: R) -> ::Result<Vec<FrameHeader>>
+        where R: io::Read {
+        stream::tag::decode_index(reader)
+    }
+
     /// Attempts to read an ID3 tag from the file at the indicated path.
+    ///
+    /// The format of the file is detected by its magic bytes. Tags are read from the "ID3 " chunk
+    /// of AIFF files, the "id3 " chunk of WAV files, the metadata pointer of DSF files and the
+    /// "ID32" box of MP4 files. All other files, like MPEG audio and ADTS streams, are expected to
+    /// start with the tag.
     pub fn read_from_path<P: AsRef<Path>>(path: P) -> ::Result<Tag> {
-        let file = BufReader::new(File::open(&path)?);
-        Tag::read_from(file)
+        let mut file = BufReader::new(File::open(&path)?);
+        match container::sniff(&mut file)? {
+            container::Format::Mpeg => Tag::read_from(file),
+            format => {
+                let location = container::locate(&mut file, format)?
+                    .ok_or_else(|| ::Error::new(::ErrorKind::NoTag, "the container does not contain a tag"))?;
+                file.seek(SeekFrom::Start(location.start))?;
+                Tag::read_from(file.take(location.end - location.start))
+            },
+        }
+    }
+
+    /// Attempts to read an ID3 tag from the file at the indicated path. Returns an empty tag if
+    /// the file does not contain a tag. Other errors, like failing to open the file or a
+    /// malformed tag, are still returned.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let tag = Tag::read_from_path_or_default("Cargo.toml").unwrap();
+    /// assert_eq!(tag.frames().count(), 0);
+    /// assert!(Tag::read_from_path_or_default("does-not-exist.mp3").is_err());
+    /// ```
+    pub fn read_from_path_or_default<P: AsRef<Path>>(path: P) -> ::Result<Tag> {
+        match Tag::read_from_path(path) {
+            Err(::Error{ kind: ::ErrorKind::NoTag, .. }) => Ok(Tag::new()),
+            result => result,
+        }
+    }
+
+    /// Reads all tags from the file at the indicated path: the tag at the start of the file (or
+    /// in the ID3 chunk of the container), an ID3v2.4 tag appended to the end of the file and an
+    /// ID3v1 tag.
+    ///
+    /// If there is no appended tag with a footer, the seek frame (SEEK) of the tag at the start of
+    /// the file is followed to find a tag further on in the file.
+    ///
+    /// Use `FileTags::resolve` or `Tag::read_from_path_with_policy` to obtain a single tag.
+    pub fn read_all_from_path<P: AsRef<Path>>(path: P) -> ::Result<FileTags> {
+        fn optional(result: ::Result<Tag>) -> ::Result<Option<Tag>> {
+            match result {
+                Ok(tag) => Ok(Some(tag)),
+                Err(::Error{ kind: ::ErrorKind::NoTag, .. }) => Ok(None),
+                Err(err) => Err(err),
+            }
+        }
+
+        let mut tags = FileTags {
+            prepended: optional(Tag::read_from_path(&path))?,
+            appended: None,
+            v1: None,
+        };
+        let mut file = BufReader::new(File::open(&path)?);
+        if container::sniff(&mut file)? != container::Format::Mpeg {
+            return Ok(tags);
+        }
+        if let Some(location) = locate_id3v2_appended(&mut file)? {
+            // A tag-only file with a footer is located both ways.
+            if location.start != 0 {
+                file.seek(SeekFrom::Start(location.start))?;
+                tags.appended = optional(Tag::read_from((&mut file).take(location.end - location.start)))?;
+            }
+        }
+        let seek_offset = tags.prepended.as_ref().and_then(|tag| tag.seek_offset());
+        if let (None, Some(offset)) = (tags.appended.as_ref(), seek_offset) {
+            file.seek(SeekFrom::Start(0))?;
+            let mut header = [0; 10];
+            file.read_exact(&mut header)?;
+            if &header[..3] == b"ID3" {
+                let footer_len = if header[5] & 0x10 != 0 { 10 } else { 0 };
+                let tag_len = 10 + u64::from(unsynch::decode_u32(BigEndian::read_u32(&header[6..10]))) + footer_len;
+                file.seek(SeekFrom::Start(tag_len + u64::from(offset)))?;
+                tags.appended = optional(Tag::read_from(&mut file))?;
+            }
+        }
+        if file.seek(SeekFrom::End(0))? >= 128 && ::v1::Tag::is_candidate(&mut file)? {
+            tags.v1 = Some(::v1::Tag::read_from(&mut file)?);
+        }
+        Ok(tags)
+    }
+
+    /// Reads the tags from the file at the indicated path and combines them using the specified
+    /// policy.
+    ///
+    /// Returns an error with `ErrorKind::NoTag` if the file contains no tags at all.
+    pub fn read_from_path_with_policy<P: AsRef<Path>>(path: P, policy: TagPolicy) -> ::Result<Tag> {
+        Tag::read_all_from_path(path)?
+            .resolve(policy)
+            .ok_or_else(|| ::Error::new(::ErrorKind::NoTag, "the file does not contain a tag"))
     }
 
     /// Attempts to write the ID3 tag to the writer using the specified version.
@@ -1375,46 +3833,404 @@ impl<'a> Tag {
             .encode(self, writer)
     }
 
-    /// Attempts to write the ID3 tag from the file at the indicated path. If the specified path is
-    /// the same path which the tag was read from, then the tag will be written to the padding if
-    /// possible.
-    pub fn write_to_path<P: AsRef<Path>>(&self, path: P, version: Version) -> ::Result<()> {
-        let mut file = fs::OpenOptions::new()
-            .read(true)
-            .write(true)
-            .open(path)?;
-        let location = locate_id3v2(&mut file)?
-            .unwrap_or(0..0); // Create a new tag if none could be located.
+    /// Attempts to write the ID3 tag to the asynchronous writer using the specified version. The
+    /// writer is flushed afterwards.
+    ///
+    /// This requires the `tokio` feature.
+    #[cfg(feature = "tokio")]
+    pub fn async_write_to<W>(&self, writer: W, version: Version) -> ::async_io::WriteTag<W>
+        where W: ::tokio::io::AsyncWrite + Unpin {
+        let mut data = Vec::new();
+        let result = self.write_into(&mut data, version).map(|_| data);
+        ::async_io::WriteTag::new(writer, result)
+    }
+
+    /// Writes the ID3 tag into the buffer using the specified version, replacing the contents of
+    /// the buffer but reusing its capacity.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::{Tag, Version};
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_title("Title");
+    ///
+    /// let mut buf = Vec::with_capacity(1024);
+    /// tag.write_into(&mut buf, Version::Id3v24).unwrap();
+    /// assert_eq!(Some("Title"), Tag::read_from(&buf[..]).unwrap().title());
+    /// ```
+    pub fn write_into(&self, buf: &mut Vec<u8>, version: Version) -> ::Result<()> {
+        stream::tag::EncoderBuilder::default()
+            .version(version)
+            .build()
+            .unwrap()
+            .encode_into(self, buf)
+    }
+
+    /// Attempts to write the ID3 tag to the file at the indicated path, replacing any existing
+    /// tag. The original file is left intact if writing fails, see `Encoder::encode_to_path`.
+    pub fn write_to_path<P: AsRef<Path>>(&self, path: P, version: Version) -> ::Result<()> {
+        stream::tag::EncoderBuilder::default()
+            .version(version)
+            .build()
+            .unwrap()
+            .encode_to_path(self, path)
+    }
+
+    /// Attempts to read an ID3 tag from the "id3 " chunk of the WAV or RF64 file at the indicated
+    /// path. Returns an error with `ErrorKind::NoTag` if the file has no such chunk and an error
+    /// with `ErrorKind::InvalidInput` if it is not a WAV file.
+    pub fn read_from_wav<P: AsRef<Path>>(path: P) -> ::Result<Tag> {
+        let mut file = BufReader::new(File::open(&path)?);
+        if container::sniff(&mut file)? != container::Format::Wav {
+            return Err(::Error::new(::ErrorKind::InvalidInput, "the file is not a WAV file"));
+        }
+        let location = container::locate(&mut file, container::Format::Wav)?
+            .ok_or_else(|| ::Error::new(::ErrorKind::NoTag, "the WAV file does not contain an id3 chunk"))?;
+        file.seek(SeekFrom::Start(location.start))?;
+        Tag::read_from(file.take(location.end - location.start))
+    }
+
+    /// Writes the tag to the "id3 " chunk of the WAV or RF64 file at the indicated path using the
+    /// specified version. An existing chunk is replaced, or resized if the tag does not fit, and a
+    /// new chunk is appended to the RIFF chunk otherwise. The size of the RIFF chunk and the
+    /// alignment of the chunks are kept intact. Returns an error with `ErrorKind::InvalidInput`
+    /// if the file is not a WAV file.
+    pub fn write_to_wav<P: AsRef<Path>>(&self, path: P, version: Version) -> ::Result<()> {
+        let format = container::sniff(&mut BufReader::new(File::open(&path)?))?;
+        if format != container::Format::Wav {
+            return Err(::Error::new(::ErrorKind::InvalidInput, "the file is not a WAV file"));
+        }
+        self.write_to_path(path, version)
+    }
+
+    /// Removes an ID3v2 tag from the specified file.
+    ///
+    /// Returns true if the file initially contained a tag.
+    pub fn remove_from(mut file: &mut fs::File) -> ::Result<bool> {
+        let location = match locate_id3v2(&mut file)? {
+            Some(l) => l,
+            None    => return Ok(false),
+        };
+        // Open the ID3 region for writing with write nothing. With the padding set to zero, this
+        // removes the region in its entirety.
+        let mut storage = PlainStorage::with_padding(file, location, 0, Some(0));
+        storage.writer()?.flush()?;
+        Ok(true)
+    }
+
+    /// Removes all tags from the file at the indicated path: the ID3v2 tag at the start of the
+    /// file and the blocks at the end of the file, which are an appended ID3v2.4 tag, a Lyrics3
+    /// block and an ID3v1 tag. The data in between is moved to the start of the file.
+    ///
+    /// The file is rewritten as a whole, see `Encoder::encode_to_path`. Returns true if the file
+    /// initially contained a tag.
+    pub fn remove_from_path<P: AsRef<Path>>(path: P) -> ::Result<bool> {
+        let mut file = BufReader::new(File::open(&path)?);
+        let prepended = locate_id3v2(&mut file)?.unwrap_or(0..0);
+        let file_len = file.seek(SeekFrom::End(0))?;
+        let mut start = match ::v1::locate(&mut file)? {
+            Some(location) => location.start,
+            None => file_len,
+        };
+        loop {
+            let location = match locate_id3v2_footer(&mut file, start)? {
+                Some(location) => Some(location),
+                None => locate_lyrics3(&mut file, start)?,
+            };
+            match location {
+                // A tag-only file with a footer is located both ways.
+                Some(location) if location.start >= prepended.end => start = location.start,
+                _ => break,
+            }
+        }
+        let start = start.max(prepended.end);
+        drop(file);
+
+        let edits: Vec<(ops::Range<u64>, &[u8])> = vec![prepended, start..file_len]
+            .into_iter()
+            .filter(|range| range.start < range.end)
+            .map(|range| (range, &[][..]))
+            .collect();
+        if edits.is_empty() {
+            return Ok(false);
+        }
+        ::storage::rewrite_file(path.as_ref(), &edits)?;
+        Ok(true)
+    }
+
+    /// Locates an ID3v2 tag that has been prepended to a FLAC or Ogg stream.
+    ///
+    /// Some tools write ID3 tags to FLAC and Ogg files, which confuses decoders that expect the
+    /// file to start with the stream. The returned range spans the tag including its padding, so
+    /// the end of the range is where the actual stream starts.
+    ///
+    /// Returns `None` if the reader does not start with a tag or if the tag is not followed by a
+    /// FLAC or Ogg stream.
+    pub fn locate_misplaced<R>(mut reader: R) -> ::Result<Option<ops::Range<u64>>>
+        where R: io::Read + io::Seek {
+        reader.seek(SeekFrom::Start(0))?;
+        let location = match locate_id3v2(&mut reader)? {
+            Some(l) => l,
+            None    => return Ok(None),
+        };
+        reader.seek(SeekFrom::Start(location.end))?;
+        if container::sniff(&mut reader)?.forbids_id3() {
+            Ok(Some(location))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Removes an ID3v2 tag that has been prepended to a FLAC or Ogg stream in the specified file.
+    /// Tags in other files are left untouched.
+    ///
+    /// Returns true if the file initially contained a misplaced tag.
+    pub fn remove_misplaced_from(mut file: &mut fs::File) -> ::Result<bool> {
+        let location = match Tag::locate_misplaced(&mut file)? {
+            Some(l) => l,
+            None    => return Ok(false),
+        };
+        let mut storage = PlainStorage::with_padding(file, location, 0, Some(0));
+        storage.writer()?.flush()?;
+        Ok(true)
+    }
+    //}}}
+}
+
+/// Splits the text of a genre frame (TCON) into genre names, see `Tag::genre_parsed`.
+fn parse_genres(text: &str) -> Vec<String> {
+    let mut genres = Vec::new();
+    for value in text.split('\0') {
+        let mut rest = value.trim();
+        let mut referenced = false;
+        while rest.starts_with('(') && !rest.starts_with("((") {
+            let end = match rest.find(')') {
+                Some(end) => end,
+                None => break,
+            };
+            genres.push(match &rest[1..end] {
+                "RX" => "Remix".to_string(),
+                "CR" => "Cover".to_string(),
+                reference => match reference.parse().ok().and_then(::v1::genre_name) {
+                    Some(name) => name.to_string(),
+                    None => rest[..=end].to_string(),
+                },
+            });
+            referenced = true;
+            rest = &rest[end + 1..];
+        }
+        if rest.is_empty() {
+            continue;
+        }
+        // A refinement follows the genres it refines, a leading "((" escapes a parenthesis.
+        let name = match rest.strip_prefix("((") {
+            Some(escaped) => format!("({}", escaped),
+            None => match rest.parse().ok().and_then(::v1::genre_name) {
+                Some(name) if !referenced => name.to_string(),
+                _ => rest.to_string(),
+            },
+        };
+        genres.push(name);
+    }
+    genres
+}
+
+/// Returns the bytes that order and hash a frame's content for `Tag::canonicalize`. Content that
+/// can not be encoded is represented by its debug form so that distinct frames still differ.
+fn canonical_content_bytes(frame: &Frame) -> Vec<u8> {
+    match stream::frame::encode_content(frame.content()) {
+        Ok(data) => data,
+        Err(_) => format!("{:?}", frame.content()).into_bytes(),
+    }
+}
+
+/// Normalizes the content of a frame for `Tag::canonicalize`. Returns `None` if nothing remains.
+fn canonical_frame(frame: &Frame) -> Option<Frame> {
+    fn values(text: &str, genre: bool) -> Option<String> {
+        let mut values: Vec<String> = text.split('\0')
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+            .map(|value| match ::v1::genre_name(::v1::genre_id_for(value)) {
+                Some(name) if genre => name.to_string(),
+                _ => value.to_string(),
+            })
+            .collect();
+        values.sort();
+        values.dedup();
+        if values.is_empty() {
+            return None;
+        }
+        Some(values.join("\0"))
+    }
+
+    let content = match *frame.content() {
+        Content::Text(ref text) => Content::Text(values(text, frame.id() == "TCON")?.into()),
+        Content::ExtendedText(ref ext) => Content::ExtendedText(ExtendedText {
+            description: ext.description.trim().to_string(),
+            value: values(&ext.value, false)?,
+        }),
+        Content::Link(ref link) => Content::Link(link.trim().to_string()),
+        Content::ExtendedLink(ref ext) => Content::ExtendedLink(ExtendedLink {
+            description: ext.description.trim().to_string(),
+            link: ext.link.trim().to_string(),
+        }),
+        Content::Comment(ref comment) => Content::Comment(Comment {
+            lang: comment.lang.trim().to_lowercase(),
+            description: comment.description.trim().to_string(),
+            text: comment.text.trim().to_string(),
+        }),
+        Content::Lyrics(ref lyrics) => Content::Lyrics(Lyrics {
+            lang: lyrics.lang.trim().to_lowercase(),
+            description: lyrics.description.trim().to_string(),
+            text: lyrics.text.trim().to_string(),
+        }),
+        ref content => content.clone(),
+    };
+    let mut canonical = Frame::with_content(frame.id(), content);
+    canonical.set_encryption_method(frame.encryption_method());
+    Some(canonical)
+}
+
+impl PartialEq for Tag {
+    fn eq(&self, other: &Tag) -> bool {
+        self.frames.len() == other.frames.len()
+            && self.frames.iter()
+                .all(|frame| other.frames.contains(frame))
+    }
+}
+
+impl iter::FromIterator<Frame> for Tag {
+    /// Creates a tag from frames, following the rules of `Tag::add_frame`.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_title("Title");
+    /// tag.set_artist("Artist");
+    /// tag.add_comment(id3::frame::Comment {
+    ///     lang: "eng".to_string(),
+    ///     description: "".to_string(),
+    ///     text: "Comment".to_string(),
+    /// });
+    ///
+    /// let texts: Tag = tag.frames()
+    ///     .filter(|frame| frame.id().starts_with('T'))
+    ///     .cloned()
+    ///     .collect();
+    /// assert_eq!(2, texts.frames().count());
+    /// ```
+    fn from_iter<I>(frames: I) -> Tag
+        where I: IntoIterator<Item=Frame> {
+        let mut tag = Tag::new();
+        tag.extend(frames);
+        tag
+    }
+}
+
+impl Extend<Frame> for Tag {
+    /// Adds the frames to the tag, following the rules of `Tag::add_frame`.
+    fn extend<I>(&mut self, frames: I)
+        where I: IntoIterator<Item=Frame> {
+        let frames = frames.into_iter();
+        self.reserve(frames.size_hint().0);
+        for frame in frames {
+            self.add_frame(frame);
+        }
+    }
+}
+
+impl IntoIterator for Tag {
+    type Item = Frame;
+    type IntoIter = iter::Map<::std::vec::IntoIter<Arc<Frame>>, fn(Arc<Frame>) -> Frame>;
 
-        let mut storage = PlainStorage::new(file, location);
-        let mut w = storage.writer()?;
-        self.write_to(&mut w, version)?;
-        w.flush()?;
-        Ok(())
+    /// Consumes the tag, yielding its frames in the order in which they were added.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::Tag;
+    ///
+    /// let mut tag = Tag::new();
+    /// tag.set_title("Title");
+    /// let frames: Vec<_> = tag.into_iter().collect();
+    /// assert_eq!("TIT2", frames[0].id());
+    /// ```
+    fn into_iter(self) -> Self::IntoIter {
+        self.frames.into_iter().map(unwrap_frame as fn(Arc<Frame>) -> Frame)
     }
+}
 
-    /// Removes an ID3v2 tag from the specified file.
-    ///
-    /// Returns true if the file initially contained a tag.
-    pub fn remove_from(mut file: &mut fs::File) -> ::Result<bool> {
-        let location = match locate_id3v2(&mut file)? {
-            Some(l) => l,
-            None    => return Ok(false),
-        };
-        // Open the ID3 region for writing with write nothing. With the padding set to zero, this
-        // removes the region in its entirety.
-        let mut storage = PlainStorage::with_padding(file, location, 0, Some(0));
-        storage.writer()?.flush()?;
-        Ok(true)
+impl<'a> IntoIterator for &'a Tag {
+    type Item = &'a Frame;
+    type IntoIter = iter::Map<::std::slice::Iter<'a, Arc<Frame>>, fn(&'a Arc<Frame>) -> &'a Frame>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.frames.iter().map(|frame| &**frame)
     }
-    //}}}
 }
 
-impl PartialEq for Tag {
-    fn eq(&self, other: &Tag) -> bool {
-        self.frames.len() == other.frames.len()
-            && self.frames.iter()
-                .all(|frame| other.frames.contains(frame))
+/// Takes the frame out of the shared pointer, copying it only if it is shared with a clone.
+/// Combines the values of two text or user defined text frames, keeping the flags of the first.
+/// Returns `None` if the frames are of another kind or the second adds no values.
+fn combine_values(mine: &Frame, theirs: &Frame) -> Option<Frame> {
+    let union = |mine: &str, theirs: &str| -> Option<String> {
+        let mut values: Vec<&str> = mine.split('\0').collect();
+        let len = values.len();
+        for value in theirs.split('\0') {
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        }
+        if values.len() == len {
+            return None;
+        }
+        Some(values.join("\0"))
+    };
+    let content = match (mine.content(), theirs.content()) {
+        (Content::Text(a), Content::Text(b)) => Content::Text(union(a, b)?.into()),
+        (Content::ExtendedText(a), Content::ExtendedText(b)) => Content::ExtendedText(ExtendedText {
+            description: a.description.clone(),
+            value: union(&a.value, &b.value)?,
+        }),
+        _ => return None,
+    };
+    let mut combined = Frame::with_content(mine.id(), content);
+    combined.set_tag_alter_preservation(mine.tag_alter_preservation());
+    combined.set_file_alter_preservation(mine.file_alter_preservation());
+    combined.set_group_identifier(mine.group_identifier());
+    Some(combined)
+}
+
+fn unwrap_frame(frame: Arc<Frame>) -> Frame {
+    Arc::try_unwrap(frame).unwrap_or_else(|frame| (*frame).clone())
+}
+
+impl<'a> From<&'a Tag> for ::v1::Tag {
+    /// Converts the basic metadata of the tag to an ID3v1.1 tag. The genre byte is derived from
+    /// the genre (TCON) using `v1::genre_id_for`.
+    ///
+    /// Fields are not truncated here, this happens when the tag is written.
+    fn from(tag: &'a Tag) -> ::v1::Tag {
+        let year = tag.year()
+            .or_else(|| tag.date_recorded().map(|t| t.year))
+            .map(|year| year.to_string())
+            .unwrap_or_default();
+        let comment = tag.comments()
+            .find(|c| c.description.is_empty())
+            .map(|c| c.text.clone())
+            .unwrap_or_default();
+        ::v1::Tag {
+            title: tag.title().unwrap_or_default().to_string(),
+            artist: tag.artist().unwrap_or_default().to_string(),
+            album: tag.album().unwrap_or_default().to_string(),
+            year,
+            comment,
+            track: tag.track().filter(|track| *track <= 0xff).map(|track| track as u8),
+            genre_id: tag.genre().map(::v1::genre_id_for).unwrap_or(255),
+            ..::v1::Tag::default()
+        }
     }
 }
 
@@ -1451,10 +4267,10 @@ impl From<::v1::Tag> for Tag {
 }
 
 
-fn locate_id3v2<R>(mut reader: R) -> ::Result<Option<ops::Range<u64>>>
+pub fn locate_id3v2<R>(mut reader: R) -> ::Result<Option<ops::Range<u64>>>
     where R: io::Read + io::Seek {
     let mut header = [0u8; 10];
-    let nread = reader.read(&mut header)?;
+    let nread = ::util::read_fully(&mut reader, &mut header)?;
     if nread < header.len() || &header[..3] != b"ID3" {
         return Ok(None);
     }
@@ -1463,12 +4279,109 @@ fn locate_id3v2<R>(mut reader: R) -> ::Result<Option<ops::Range<u64>>>
         _ => return Err(::Error::new(::ErrorKind::UnsupportedVersion(header[4], header[3]) , "unsupported id3 tag version")),
     };
 
-    let size = unsynch::decode_u32(BigEndian::read_u32(&header[6..10]));
+    // The size excludes the header and the footer.
+    let mut size = unsynch::decode_u32(BigEndian::read_u32(&header[6..10])) + 10;
+    if header[3] == 4 && header[5] & 0x10 != 0 {
+        size += 10;
+    }
     reader.seek(io::SeekFrom::Start(size as u64))?;
-    let num_padding = reader.bytes()
+    let mut num_padding = reader.by_ref().bytes()
         .take_while(|rs| rs.as_ref().map(|b| *b == 0x00).unwrap_or(false))
-        .count();
-    Ok(Some(0..size as u64 + num_padding as u64))
+        .count() as u64;
+    if num_padding > 0 {
+        // Padding that is not accounted for in the tag size is only included if the audio
+        // evidently starts after it. Otherwise the zeros might be part of the audio data.
+        reader.seek(io::SeekFrom::Start(size as u64 + num_padding))?;
+        let mut next = [0; 6];
+        let nread = ::util::read_fully(&mut reader, &mut next)?;
+        let next = &next[..nread];
+        let audio_start = next.is_empty()
+            || next.starts_with(b"ID3")
+            || next.starts_with(b"TAG")
+            || container::audio_frame(next).is_some();
+        if !audio_start {
+            num_padding = 0;
+        }
+    }
+    Ok(Some(0..size as u64 + num_padding))
+}
+
+/// Locates an ID3v2.4 tag at the end of the file by its footer. The tag may be followed by an
+/// ID3v1 tag.
+pub fn locate_id3v2_appended<R>(mut reader: R) -> ::Result<Option<ops::Range<u64>>>
+    where R: io::Read + io::Seek {
+    let mut end = reader.seek(SeekFrom::End(0))?;
+    if end >= 128 && ::v1::Tag::is_candidate(&mut reader)? {
+        let mut xtag = [0; 4];
+        if end >= 355 {
+            reader.seek(SeekFrom::End(-355))?;
+            reader.read_exact(&mut xtag)?;
+        }
+        end -= if &xtag == b"TAG+" { 355 } else { 128 };
+    }
+    locate_id3v2_footer(reader, end)
+}
+
+/// Locates an ID3v2.4 tag with a footer that ends at `end`.
+fn locate_id3v2_footer<R>(mut reader: R, end: u64) -> ::Result<Option<ops::Range<u64>>>
+    where R: io::Read + io::Seek {
+    if end < 20 {
+        return Ok(None);
+    }
+    let mut footer = [0; 10];
+    reader.seek(SeekFrom::Start(end - 10))?;
+    reader.read_exact(&mut footer)?;
+    if &footer[..3] != b"3DI" || footer[3] != 4 {
+        return Ok(None);
+    }
+    let size = u64::from(unsynch::decode_u32(BigEndian::read_u32(&footer[6..10])));
+    if size + 20 > end {
+        return Ok(None);
+    }
+    let start = end - 20 - size;
+    let mut header = [0; 10];
+    reader.seek(SeekFrom::Start(start))?;
+    reader.read_exact(&mut header)?;
+    if &header[..3] != b"ID3" {
+        return Ok(None);
+    }
+    Ok(Some(start..end))
+}
+
+/// Locates a Lyrics3 block that ends at `end`, which is usually where the ID3v1 tag starts.
+/// Both version 1 blocks, which end with "LYRICSEND", and version 2 blocks, which end with their
+/// size and "LYRICS200", are recognized.
+fn locate_lyrics3<R>(mut reader: R, end: u64) -> ::Result<Option<ops::Range<u64>>>
+    where R: io::Read + io::Seek {
+    const BEGIN: &[u8] = b"LYRICSBEGIN";
+    if end < 15 + BEGIN.len() as u64 {
+        return Ok(None);
+    }
+    let mut trailer = [0; 15];
+    reader.seek(SeekFrom::Start(end - 15))?;
+    reader.read_exact(&mut trailer)?;
+    if &trailer[6..] == b"LYRICS200" {
+        let size = match ::std::str::from_utf8(&trailer[..6]).ok().and_then(|s| s.parse::<u64>().ok()) {
+            Some(size) if size + 15 <= end => size,
+            _ => return Ok(None),
+        };
+        let start = end - 15 - size;
+        let mut begin = [0; 11];
+        reader.seek(SeekFrom::Start(start))?;
+        if ::util::read_fully(&mut reader, &mut begin)? == begin.len() && begin == BEGIN {
+            return Ok(Some(start..end));
+        }
+    } else if &trailer[6..] == b"LYRICSEND" {
+        // Version 1 blocks have no size, but the lyrics are limited to 5100 bytes.
+        let start = end.saturating_sub(9 + 5100 + BEGIN.len() as u64);
+        let mut data = Vec::new();
+        reader.seek(SeekFrom::Start(start))?;
+        reader.by_ref().take(end - 9 - start).read_to_end(&mut data)?;
+        if let Some(i) = data.windows(BEGIN.len()).position(|w| w == BEGIN) {
+            return Ok(Some(start + i as u64..end));
+        }
+    }
+    Ok(None)
 }
 
 
@@ -1477,6 +4390,7 @@ mod tests {
     extern crate tempdir;
     use super::*;
     use std::fs;
+    use frame::{ReceivedAs, SynchronisedLyricsType, TimestampFormat};
 
     #[test]
     fn test_locate_id3v2() {
@@ -1485,6 +4399,122 @@ mod tests {
         assert!(location.is_some());
     }
 
+    #[test]
+    fn clone_shares_frames() {
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+        tag.add_picture(Picture {
+            mime_type: "image/jpeg".to_string(),
+            picture_type: PictureType::CoverFront,
+            description: "".to_string(),
+            data: vec![0; 1024],
+        });
+        let mut copy = tag.clone();
+        assert!(tag.frames.iter().zip(&copy.frames).all(|(a, b)| Arc::ptr_eq(a, b)));
+
+        copy.get_mut("TIT2").unwrap().set_tag_alter_preservation(true);
+        assert!(!tag.get("TIT2").unwrap().tag_alter_preservation());
+        let picture = |tag: &Tag| tag.frames.iter().find(|frame| frame.id() == "APIC").unwrap().clone();
+        assert!(Arc::ptr_eq(&picture(&tag), &picture(&copy)));
+    }
+
+    #[test]
+    fn canonical_form() {
+        let mut a = Tag::new();
+        a.set_title("Title ");
+        a.set_album("  ");
+        a.add_comment(Comment { lang: "ENG".to_string(), description: "".to_string(), text: "Text".to_string() });
+        a.get_mut("TIT2").unwrap().set_file_alter_preservation(true);
+
+        let mut b = Tag::new();
+        b.add_comment(Comment { lang: "eng".to_string(), description: " ".to_string(), text: " Text".to_string() });
+        b.set_title("Title");
+
+        let canonical = a.canonicalize();
+        assert_eq!(None, canonical.album());
+        assert!(!canonical.get("TIT2").unwrap().file_alter_preservation());
+        assert_eq!(vec!["COMM", "TIT2"], canonical.frames().map(|f| f.id()).collect::<Vec<_>>());
+        assert_eq!(a.canonical_hash(), b.canonical_hash());
+
+        b.set_title("Other");
+        assert_ne!(a.canonical_hash(), b.canonical_hash());
+
+        // Frames that can not be encoded must not all hash alike.
+        let commercial = |seller: &str| Commercial {
+            price: "EUR1".to_string(),
+            valid_until: "soon".to_string(),
+            contact_url: String::new(),
+            received_as: ReceivedAs::Other,
+            seller: seller.to_string(),
+            description: String::new(),
+            logo: None,
+        };
+        let mut a = Tag::new();
+        a.add_frame(Frame::with_content("COMR", Content::Commercial(commercial("A"))));
+        let mut b = Tag::new();
+        b.add_frame(Frame::with_content("COMR", Content::Commercial(commercial("B"))));
+        assert_ne!(a.canonical_hash(), b.canonical_hash());
+    }
+
+    #[test]
+    fn collect_replaces_frames() {
+        let frames = vec![
+            Frame::with_content("TIT2", Content::Text("First".into())),
+            Frame::with_content("TPE1", Content::Text("Artist".into())),
+            Frame::with_content("TIT2", Content::Text("Second".into())),
+        ];
+        let mut tag: Tag = frames.into_iter().collect();
+        assert_eq!(2, tag.frames().count());
+        assert_eq!(Some("Second"), tag.title());
+
+        tag.extend(vec![Frame::with_content("TALB", Content::Text("Album".into()))]);
+        assert_eq!(Some("Album"), tag.album());
+
+        let ids: Vec<&str> = (&tag).into_iter().map(|frame| frame.id()).collect();
+        assert_eq!(vec!["TPE1", "TIT2", "TALB"], ids);
+        let copy: Tag = tag.clone().into_iter().collect();
+        assert_eq!(tag, copy);
+    }
+
+    #[test]
+    fn set_rating_updates_all() {
+        let mut tag = Tag::new();
+        let wmp = RatingConvention::windows_media_player();
+        let mm = RatingConvention::media_monkey();
+        tag.set_rating(&mm, 3);
+        tag.add_popularimeter(Popularimeter { email: "Windows Media Player 9 Series".to_string(), rating: 1, counter: 5 });
+        tag.add_popularimeter(Popularimeter { email: "other@example.com".to_string(), rating: 0x10, counter: 0 });
+        tag.set_rating(&wmp, 8);
+        assert_eq!(Some(8), tag.rating(&wmp));
+        assert_eq!(Some(8), tag.rating(&mm));
+        assert_eq!(3, tag.frames().filter(|frame| frame.id() == "POPM").count());
+        let popms: Vec<_> = tag.popularimeters().map(|popm| (popm.email.as_str(), popm.rating, popm.counter)).collect();
+        assert!(popms.contains(&("Windows Media Player 9 Series", 0xc4, 5)));
+        assert!(popms.contains(&("other@example.com", 0x10, 0)));
+        assert_eq!(0, tag.extended_texts().count());
+
+        tag.remove_rating(&wmp);
+        tag.remove_rating(&mm);
+        assert_eq!(Some(2), tag.rating(&wmp));
+    }
+
+    #[test]
+    fn test_locate_id3v2_padding() {
+        let mut tag = Vec::new();
+        ::stream::tag::EncoderBuilder::default().build().unwrap().encode(&Tag::new(), &mut tag).unwrap();
+        let tag_len = tag.len() as u64;
+
+        let mut adts = tag.clone();
+        adts.extend_from_slice(&[0; 16]);
+        adts.extend_from_slice(&[0xff, 0xf1, 0x50, 0x80, 0x2e, 0x7f, 0xfc]);
+        assert_eq!(Some(0..tag_len + 16), locate_id3v2(io::Cursor::new(adts)).unwrap());
+
+        let mut junk = tag.clone();
+        junk.extend_from_slice(&[0; 16]);
+        junk.extend_from_slice(b"not audio");
+        assert_eq!(Some(0..tag_len), locate_id3v2(io::Cursor::new(junk)).unwrap());
+    }
+
     #[test]
     fn remove_id3v2() {
         let tmp = tempdir::TempDir::new("id3_v2").unwrap();
@@ -1504,4 +4534,608 @@ mod tests {
         tag_file.seek(io::SeekFrom::Start(0)).unwrap();
         assert!(!Tag::remove_from(&mut tag_file).unwrap());
     }
+
+    #[test]
+    fn scrub_default_profile() {
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+        tag.add_frame(Frame::with_content("UFID", Content::Unknown(b"owner\0id".to_vec())));
+        tag.add_popularimeter(Popularimeter { email: "me@example.com".to_string(), rating: 0xff, counter: 1 });
+        tag.add_comment(Comment {
+            lang: "eng".to_string(),
+            description: "iTunes_CDDB_IDs".to_string(),
+            text: "12+ABCDEF".to_string(),
+        });
+        tag.add_comment(Comment {
+            lang: "eng".to_string(),
+            description: "".to_string(),
+            text: "Great song".to_string(),
+        });
+
+        tag.scrub(&ScrubProfile::default());
+        assert_eq!(3, tag.frames().count());
+        assert_eq!(1, tag.comments().count());
+        let popm = tag.popularimeters().next().unwrap();
+        assert_eq!(("", 0xff, 1), (popm.email.as_str(), popm.rating, popm.counter));
+
+        let encoder = ::stream::tag::EncoderBuilder::default()
+            .scrub(Some(ScrubProfile { frame_ids: vec!["TIT2".to_string()], ..ScrubProfile::default() }))
+            .unsynchronisation(false)
+            .build()
+            .unwrap();
+        let mut data = Vec::new();
+        encoder.encode(&tag, &mut data).unwrap();
+        assert_eq!(None, Tag::read_from(&data[..]).unwrap().title());
+    }
+
+    #[test]
+    fn convert_v1_genre() {
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+        tag.set_genre("(31)");
+        let tag_v1 = ::v1::Tag::from(&tag);
+        assert_eq!("Title", tag_v1.title);
+        assert_eq!(31, tag_v1.genre_id);
+        assert_eq!(Some("Trance"), Tag::from(tag_v1).genre());
+
+        tag.set_genre("Unknown genre");
+        assert_eq!(255, ::v1::Tag::from(&tag).genre_id);
+        tag.remove_genre();
+        let tag_v1 = ::v1::Tag::from(&tag);
+        assert_eq!(255, tag_v1.genre_id);
+        assert_eq!(None, Tag::from(tag_v1).genre());
+    }
+
+    #[test]
+    fn user_text_map_roundtrip() {
+        let mut tag = Tag::new();
+        tag.set_user_text_map(vec![("CATALOGNUMBER", vec!["CAT-1", "CAT-2"])]);
+        for &version in &[Version::Id3v23, Version::Id3v24] {
+            let mut data = Vec::new();
+            tag.write_to(&mut data, version).unwrap();
+            let read = Tag::read_from(&data[..]).unwrap();
+            assert_eq!(vec!["CAT-1", "CAT-2"], read.user_text_map()["CATALOGNUMBER"]);
+        }
+    }
+
+    #[test]
+    fn synchronised_lyrics_roundtrip() {
+        let mut tag = Tag::new();
+        for &(lang, format) in &[("eng", TimestampFormat::Ms), ("deu", TimestampFormat::Mpeg)] {
+            tag.add_synchronised_lyrics(SynchronisedLyrics {
+                lang: lang.to_string(),
+                timestamp_format: format,
+                content_type: SynchronisedLyricsType::Lyrics,
+                description: String::new(),
+                content: vec![(0, "Strangers in".to_string()), (1200, "\nthe night".to_string())],
+            });
+        }
+        for &version in &[Version::Id3v23, Version::Id3v24] {
+            let mut data = Vec::new();
+            tag.write_to(&mut data, version).unwrap();
+            let read = Tag::read_from(&data[..]).unwrap();
+            let lyrics: Vec<_> = read.synchronised_lyrics().collect();
+            assert_eq!(2, lyrics.len());
+            assert_eq!(TimestampFormat::Mpeg, lyrics[1].timestamp_format);
+            assert_eq!(tag.synchronised_lyrics().next().unwrap().content, lyrics[0].content);
+        }
+        tag.remove_synchronised_lyrics("eng", None);
+        assert_eq!("deu", tag.synchronised_lyrics().next().unwrap().lang);
+        assert_eq!(1, tag.frames().count());
+    }
+
+    #[test]
+    fn chapters_roundtrip() {
+        let chapter = |id: &str, start: u32, title: &str| Chapter {
+            element_id: id.to_string(),
+            start_time: start,
+            end_time: start + 1000,
+            start_offset: Chapter::NO_OFFSET,
+            end_offset: Chapter::NO_OFFSET,
+            frames: vec![Frame::with_content("TIT2", Content::Text(title.to_string().into()))],
+        };
+        let mut tag = Tag::new();
+        tag.add_frame(Frame::with_content("CHAP", Content::Chapter(chapter("old", 0, "Old"))));
+        // Duplicate element IDs are renumbered.
+        tag.set_chapters(vec![chapter("a", 2000, "Three"), chapter("a", 0, "One"), chapter("a", 1000, "Two")]);
+
+        for &version in &[Version::Id3v23, Version::Id3v24] {
+            let mut data = Vec::new();
+            tag.write_to(&mut data, version).unwrap();
+            let read = Tag::read_from(&data[..]).unwrap();
+            let titles: Vec<_> = read.chapters().iter().map(|c| c.title().unwrap()).collect();
+            assert_eq!(vec!["One", "Two", "Three"], titles);
+            let ids: Vec<_> = read.chapters_in_table("toc").iter().map(|c| c.element_id.clone()).collect();
+            assert_eq!(vec!["chp0", "chp1", "chp2"], ids);
+        }
+
+        tag.set_chapters(Vec::new());
+        assert_eq!(0, tag.frames().count());
+    }
+
+    #[test]
+    fn read_seek_target() {
+        let tmp = tempdir::TempDir::new("id3_v2").unwrap();
+        let tmp_name = tmp.path().join("seek.mp3");
+        let audio = [0xff, 0xfb, 0x90, 0x00, 0, 0, 0, 0];
+        let mut prepended = Tag::new();
+        prepended.set_title("Prepended");
+        prepended.set_seek_offset(audio.len() as u32);
+        let mut target = Tag::new();
+        target.set_artist("Target");
+        let mut data = Vec::new();
+        prepended.write_to(&mut data, Version::Id3v24).unwrap();
+        data.extend_from_slice(&audio);
+        target.write_to(&mut data, Version::Id3v24).unwrap();
+        data.extend_from_slice(&audio);
+        fs::write(&tmp_name, &data).unwrap();
+
+        let tags = Tag::read_all_from_path(&tmp_name).unwrap();
+        assert_eq!(Some(audio.len() as u32), tags.prepended.as_ref().unwrap().seek_offset());
+        assert_eq!(Some("Target"), tags.appended.as_ref().unwrap().artist());
+        let tag = Tag::read_from_path_with_policy(&tmp_name, TagPolicy::Merge).unwrap();
+        assert_eq!((Some("Prepended"), Some("Target")), (tag.title(), tag.artist()));
+    }
+
+    fn appended_tag_bytes(tag: &Tag) -> Vec<u8> {
+        let mut data = Vec::new();
+        ::stream::tag::EncoderBuilder::default()
+            .footer(true)
+            .build()
+            .unwrap()
+            .encode(tag, &mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn write_appended() {
+        let tmp = tempdir::TempDir::new("id3_v2").unwrap();
+        let tmp_name = tmp.path().join("appended.mp3");
+        let mut prepended = Tag::new();
+        prepended.set_title("Prepended");
+        let mut data = Vec::new();
+        prepended.write_to(&mut data, Version::Id3v24).unwrap();
+        let audio = [0xff, 0xfb, 0x90, 0x00, 0x00, 0x00];
+        data.extend_from_slice(&audio);
+        let mut v1 = [0; 128];
+        v1[..3].copy_from_slice(b"TAG");
+        data.extend_from_slice(&v1);
+        fs::write(&tmp_name, &data).unwrap();
+
+        let encoder = ::stream::tag::EncoderBuilder::default().append(true).build().unwrap();
+        let mut tag = Tag::new();
+        tag.set_title("Appended");
+        for _ in 0..2 {
+            encoder.encode_to_path(&tag, &tmp_name).unwrap();
+            let tags = Tag::read_all_from_path(&tmp_name).unwrap();
+            let prepended = tags.prepended.unwrap();
+            assert_eq!(vec!["SEEK"], prepended.frames().map(|f| f.id()).collect::<Vec<_>>());
+            assert_eq!(Some(tag.clone()), tags.appended);
+            assert!(tags.v1.is_some());
+            assert_eq!(tag, Tag::read_from_path_with_policy(&tmp_name, TagPolicy::PreferAppended).unwrap());
+        }
+
+        // Without a tag at the start, the file only gains the appended tag.
+        let mut data = audio.to_vec();
+        data.extend_from_slice(&v1);
+        fs::write(&tmp_name, &data).unwrap();
+        encoder.encode_to_path(&tag, &tmp_name).unwrap();
+        let tags = Tag::read_all_from_path(&tmp_name).unwrap();
+        assert!(tags.prepended.is_none());
+        assert_eq!(Some(tag), tags.appended);
+        assert_eq!(&audio[..], &fs::read(&tmp_name).unwrap()[..audio.len()]);
+    }
+
+    #[test]
+    fn track_and_disc_pairs() {
+        let mut tag = Tag::new();
+        tag.set_text("TRCK", " 03 / 12 ");
+        assert_eq!((Some(3), Some(12)), (tag.track(), tag.total_tracks()));
+        tag.set_track(4);
+        assert_eq!(Some("4/12"), tag.get("TRCK").and_then(|f| f.content().text()));
+        tag.remove_total_tracks();
+        assert_eq!(Some("4"), tag.get("TRCK").and_then(|f| f.content().text()));
+        tag.remove_track();
+        assert!(tag.get("TRCK").is_none());
+        tag.set_total_tracks(12);
+        assert_eq!(Some("/12"), tag.get("TRCK").and_then(|f| f.content().text()));
+        assert_eq!((None, Some(12)), (tag.track(), tag.total_tracks()));
+
+        tag.set_total_discs(2);
+        assert_eq!(Some("/2"), tag.get("TPOS").and_then(|f| f.content().text()));
+        tag.set_disc(1);
+        assert_eq!(Some("1/2"), tag.get("TPOS").and_then(|f| f.content().text()));
+        tag.remove_total_discs();
+        assert_eq!(Some("1"), tag.get("TPOS").and_then(|f| f.content().text()));
+        assert_eq!((Some(1), None), (tag.disc(), tag.total_discs()));
+        tag.set_text("TPOS", "a/b");
+        assert_eq!((None, None), (tag.disc(), tag.total_discs()));
+    }
+
+    #[test]
+    fn convert_to() {
+        let mut tag = Tag::new();
+        tag.set_text_values("TPE1", vec!["Artist 1", "Artist 2"]);
+        tag.set_date_recorded("2014-05-04".parse().unwrap());
+        tag.set_text("TMOO", "Calm");
+        tag.add_frame(Frame::with_content("RVAD", Content::Unknown(vec![0x03, 16, 0, 0, 0, 0, 0, 0, 0, 0])));
+        let report = tag.convert_to(Version::Id3v23);
+        assert_eq!(vec!["TMOO"], report.dropped.iter().map(|f| f.id()).collect::<Vec<_>>());
+        assert_eq!(vec!["TPE1"], report.lossy.iter().map(|l| &l.0[..]).collect::<Vec<_>>());
+        assert_eq!(Some("Artist 1/Artist 2"), tag.artist());
+        assert_eq!(Some("0405"), tag.get("TDAT").and_then(|f| f.content().text()));
+
+        let report = tag.convert_to(Version::Id3v24);
+        assert!(report.dropped.is_empty());
+        assert!(tag.get("RVAD").is_none() && tag.get("TYER").is_none());
+        assert_eq!(2, tag.get("RVA2").unwrap().content().relative_volume_adjustment().unwrap().channels.len());
+
+        tag.set_text("TSIZ", "1");
+        tag.set_text("TSO2", "Artist");
+        let report = tag.convert_to(Version::Id3v22);
+        assert_eq!(vec!["RVA2", "TSO2"], report.dropped.iter().map(|f| f.id()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn convert_dates_lossy() {
+        let mut tag = Tag::new();
+        tag.set_text("TDRC", "2014-05-04T10:20:30");
+        tag.set_text("TDOR", "1999-12");
+        let report = tag.convert_to(Version::Id3v23);
+        assert_eq!(vec!["TDRC", "TDOR"], report.lossy.iter().map(|l| &l.0[..]).collect::<Vec<_>>());
+        assert_eq!(Some("1020"), tag.get("TIME").and_then(|f| f.content().text()));
+        assert_eq!(Some("1999"), tag.get("TORY").and_then(|f| f.content().text()));
+
+        tag.set_text("TRDA", "May 4th");
+        let report = tag.convert_to(Version::Id3v24);
+        assert_eq!(vec!["TRDA"], report.lossy.iter().map(|l| &l.0[..]).collect::<Vec<_>>());
+        assert_eq!(Some("2014-05-04T10:20"), tag.get("TDRC").and_then(|f| f.content().text()));
+        assert!(tag.convert_to(Version::Id3v24).lossy.is_empty());
+    }
+
+    #[test]
+    fn musicbrainz_ids() {
+        let mut tag = Tag::new();
+        assert!(tag.musicbrainz_recording_id().is_none());
+        tag.add_extended_text("MUSICBRAINZ ALBUM ID", " release ");
+        assert_eq!(Some("release"), tag.musicbrainz_release_id());
+
+        tag.set_musicbrainz_ids(MusicBrainzId::Artist, vec!["artist 1", "artist 2"]);
+        tag.set_musicbrainz_id(MusicBrainzId::Recording, "recording");
+        tag.set_musicbrainz_id(MusicBrainzId::Recording, "other recording");
+        let mut data = Vec::new();
+        ::stream::tag::EncoderBuilder::default().build().unwrap().encode(&tag, &mut data).unwrap();
+        let read = Tag::read_from(&data[..]).unwrap();
+        assert_eq!(vec!["artist 1", "artist 2"], read.musicbrainz_artist_ids());
+        assert_eq!(Some("other recording"), read.musicbrainz_recording_id());
+
+        tag.set_musicbrainz_ids(MusicBrainzId::Artist, Vec::<String>::new());
+        tag.remove_musicbrainz_id(MusicBrainzId::Recording);
+        tag.remove_musicbrainz_id(MusicBrainzId::Release);
+        assert_eq!(0, tag.frames().count());
+    }
+
+    #[test]
+    fn replaygain() {
+        let mut tag = Tag::new();
+        assert_eq!(None, tag.replaygain_track_gain());
+        let mut master = VolumeAdjustment {
+            channel: ChannelType::MasterVolume,
+            adjustment: 0,
+            peak_bits: 16,
+            peak: vec![0x40, 0x00],
+        };
+        master.set_adjustment_db(-3.5);
+        tag.add_relative_volume_adjustment(RelativeVolumeAdjustment { identification: "album".to_string(), channels: vec![master] });
+        assert_eq!((Some(-3.5), Some(0.5)), (tag.replaygain_album_gain(), tag.replaygain_album_peak()));
+        assert_eq!(None, tag.replaygain_track_gain());
+
+        // User defined texts take precedence over RVA2 frames.
+        tag.add_extended_text("REPLAYGAIN_ALBUM_GAIN", " +1.25DB ");
+        tag.add_extended_text("replaygain_album_peak", "0.988547");
+        assert_eq!((Some(1.25), Some(0.988547)), (tag.replaygain_album_gain(), tag.replaygain_album_peak()));
+        tag.set_replaygain_album_peak(1.0);
+        tag.set_replaygain_track_gain(-0.5);
+        tag.set_replaygain_track_peak(0.25);
+        let texts: Vec<_> = tag.extended_texts().map(|t| (t.description.as_str(), t.value.as_str())).collect();
+        assert_eq!(vec![
+            ("REPLAYGAIN_ALBUM_GAIN", " +1.25DB "),
+            ("REPLAYGAIN_ALBUM_PEAK", "1.000000"),
+            ("REPLAYGAIN_TRACK_GAIN", "-0.50 dB"),
+            ("REPLAYGAIN_TRACK_PEAK", "0.250000"),
+        ], texts);
+
+        tag.set_title("Title");
+        tag.remove_replaygain();
+        assert_eq!(vec!["TIT2"], tag.frames().map(|f| f.id()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn compilation() {
+        let mut tag = Tag::new();
+        for &(text, compilation) in &[("1", true), (" 1 ", true), ("0", false), ("", false), ("yes", false)] {
+            tag.set_text("TCMP", text);
+            assert_eq!(compilation, tag.compilation(), "{:?}", text);
+        }
+
+        // iTunes writes the flag to ID3v2.2 tags as "TCP".
+        tag.set_compilation(true);
+        let mut data = Vec::new();
+        ::stream::tag::EncoderBuilder::default().version(Version::Id3v22).build().unwrap().encode(&tag, &mut data).unwrap();
+        assert!(data.windows(3).any(|id| id == b"TCP"));
+        assert!(Tag::read_from(&data[..]).unwrap().compilation());
+    }
+
+    #[test]
+    fn involved_people() {
+        let mut tag = Tag::new();
+        assert!(tag.involved_people().is_empty());
+        tag.set_text("IPLS", "engineer\0Engineer\0guitar\0Guitarist\0producer");
+        assert_eq!(vec![("engineer", "Engineer"), ("guitar", "Guitarist"), ("producer", "")], tag.involved_people());
+        tag.set_musician_credits(vec![("piano", "Pianist")]);
+
+        assert!(tag.convert_involved_people(Version::Id3v24).is_empty());
+        assert!(tag.get("IPLS").is_none());
+        assert_eq!(Some("engineer\0Engineer\0producer\0"), tag.get("TIPL").and_then(|f| f.content().text()));
+        assert_eq!(vec![("piano", "Pianist"), ("guitar", "Guitarist")], tag.musician_credits());
+
+        assert_eq!(vec!["TMCL"], tag.convert_involved_people(Version::Id3v23).iter().map(|l| &l.0[..]).collect::<Vec<_>>());
+        assert!(tag.get("TIPL").is_none() && tag.get("TMCL").is_none());
+        assert_eq!(vec![("engineer", "Engineer"), ("producer", ""), ("piano", "Pianist"), ("guitar", "Guitarist")], tag.involved_people());
+
+        tag.set_involved_people(Vec::<(String, String)>::new());
+        assert!(tag.get("IPLS").is_none() && tag.get("TIPL").is_none());
+    }
+
+    #[test]
+    fn genre_parsed() {
+        let mut tag = Tag::new();
+        assert_eq!(None, tag.genre_parsed());
+        for &(genre, parsed) in &[
+            ("(17)", "Rock"),
+            ("17", "Rock"),
+            ("(4)(9)", "Disco\0Metal"),
+            ("(4)Eurodisco", "Disco\0Eurodisco"),
+            ("(17)(RX)Psytrance", "Rock\0Remix\0Psytrance"),
+            ("((Mostly) Harmless", "(Mostly) Harmless"),
+            ("(CR)(999)", "Cover\0(999)"),
+            ("24\0Polka\0(24)", "Soundtrack\0Polka\0Soundtrack"),
+            ("1999", "1999"),
+        ] {
+            tag.set_genre(genre);
+            assert_eq!(Some(parsed.to_string()), tag.genre_parsed(), "{}", genre);
+        }
+    }
+
+    #[test]
+    fn peek_header() {
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+        let mut data = b"audio".to_vec();
+        ::stream::tag::EncoderBuilder::default()
+            .footer(true)
+            .build()
+            .unwrap()
+            .encode(&tag, &mut data)
+            .unwrap();
+        let mut reader = io::Cursor::new(&data);
+        assert_eq!(None, Tag::peek_header(&mut reader).unwrap());
+
+        reader.set_position(5);
+        let header = Tag::peek_header(&mut reader).unwrap().unwrap();
+        assert_eq!(5, reader.position());
+        assert_eq!(Version::Id3v24, header.version());
+        assert_eq!(data[5 + 4], header.revision());
+        assert!(header.footer());
+        assert!(!header.extended_header());
+        assert_eq!(data.len() as u64 - 5, header.total_size());
+        assert_eq!(header.total_size() - 20, u64::from(header.size()));
+
+        let mut data = Vec::new();
+        tag.write_to(&mut data, Version::Id3v23).unwrap();
+        let header = Tag::peek_header(io::Cursor::new(&data)).unwrap().unwrap();
+        assert_eq!(Version::Id3v23, header.version());
+        assert!(!header.footer());
+        assert_eq!(data.len() as u64, header.total_size());
+    }
+
+    #[test]
+    fn read_with_filter() {
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+        tag.set_artist("Artist");
+        tag.set_album("Album");
+        tag.add_picture(Picture {
+            mime_type: "image/jpeg".to_string(),
+            picture_type: PictureType::CoverFront,
+            description: "".to_string(),
+            data: vec![0xff; 1024],
+        });
+        for &(version, unsynchronisation) in &[(Version::Id3v22, false), (Version::Id3v23, false), (Version::Id3v23, true), (Version::Id3v24, true)] {
+            let mut data = Vec::new();
+            ::stream::tag::EncoderBuilder::default()
+                .version(version)
+                .unsynchronisation(unsynchronisation)
+                .build()
+                .unwrap()
+                .encode(&tag, &mut data)
+                .unwrap();
+            let mut reader = io::Cursor::new(&data);
+            let filtered = Tag::read_from_with_filter(&mut reader, |id| id == "TIT2" || id == "TALB").unwrap();
+            assert_eq!(data.len() as u64, reader.position());
+            assert_eq!(vec!["TIT2", "TALB"], filtered.frames().map(|f| f.id()).collect::<Vec<_>>());
+            assert_eq!(Some("Album"), filtered.album());
+        }
+    }
+
+    #[test]
+    fn remove_from_path() {
+        let tmp = tempdir::TempDir::new("id3_v2").unwrap();
+        let tmp_name = tmp.path().join("remove.mp3");
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+        let audio = [0xff, 0xfb, 0x90, 0x00, 0x00, 0x00];
+        let mut v1 = [0; 128];
+        v1[..3].copy_from_slice(b"TAG");
+
+        let mut data = Vec::new();
+        tag.write_to(&mut data, Version::Id3v23).unwrap();
+        data.extend_from_slice(&audio);
+        data.extend(appended_tag_bytes(&tag));
+        data.extend_from_slice(b"LYRICSBEGININD0000210LYR00004Text000033LYRICS200");
+        data.extend_from_slice(&v1);
+        fs::write(&tmp_name, &data).unwrap();
+        assert!(Tag::remove_from_path(&tmp_name).unwrap());
+        assert_eq!(&audio[..], &fs::read(&tmp_name).unwrap()[..]);
+        assert!(!Tag::remove_from_path(&tmp_name).unwrap());
+        assert_eq!(&audio[..], &fs::read(&tmp_name).unwrap()[..]);
+
+        let mut data = audio.to_vec();
+        data.extend_from_slice(b"LYRICSBEGINSome lyricsLYRICSEND");
+        data.extend_from_slice(&v1);
+        fs::write(&tmp_name, &data).unwrap();
+        assert!(Tag::remove_from_path(&tmp_name).unwrap());
+        assert_eq!(&audio[..], &fs::read(&tmp_name).unwrap()[..]);
+
+        fs::write(&tmp_name, appended_tag_bytes(&tag)).unwrap();
+        assert!(Tag::remove_from_path(&tmp_name).unwrap());
+        assert!(fs::read(&tmp_name).unwrap().is_empty());
+    }
+
+    #[test]
+    fn read_with_policy() {
+        let tmp = tempdir::TempDir::new("id3_v2").unwrap();
+        let tmp_name = tmp.path().join("both.mp3");
+        let mut prepended = Tag::new();
+        prepended.set_title("Prepended");
+        let mut appended = Tag::new();
+        appended.set_title("Appended");
+        appended.set_artist("Artist");
+        let mut data = Vec::new();
+        prepended.write_to(&mut data, Version::Id3v24).unwrap();
+        data.extend_from_slice(&[0xff, 0xfb, 0x90, 0x00]);
+        data.extend(appended_tag_bytes(&appended));
+        let mut v1 = [0; 128];
+        v1[..3].copy_from_slice(b"TAG");
+        v1[63..68].copy_from_slice(b"Album");
+        data.extend_from_slice(&v1);
+        fs::write(&tmp_name, &data).unwrap();
+
+        let tags = Tag::read_all_from_path(&tmp_name).unwrap();
+        assert_eq!(Some("Prepended"), tags.prepended.as_ref().unwrap().title());
+        assert_eq!(Some("Appended"), tags.appended.as_ref().unwrap().title());
+        assert_eq!("Album", tags.v1.as_ref().unwrap().album);
+
+        let tag = Tag::read_from_path_with_policy(&tmp_name, TagPolicy::PreferPrepended).unwrap();
+        assert_eq!(prepended, tag);
+        let tag = Tag::read_from_path_with_policy(&tmp_name, TagPolicy::PreferAppended).unwrap();
+        assert_eq!(appended, tag);
+        let tag = Tag::read_from_path_with_policy(&tmp_name, TagPolicy::Merge).unwrap();
+        assert_eq!(Some("Prepended"), tag.title());
+        assert_eq!(Some("Artist"), tag.artist());
+        assert_eq!(Some("Album"), tag.album());
+
+        // Writing removes the stale appended tag but keeps the ID3v1 tag.
+        tag.write_to_path(&tmp_name, Version::Id3v24).unwrap();
+        let tags = Tag::read_all_from_path(&tmp_name).unwrap();
+        assert_eq!(Some(tag), tags.prepended);
+        assert!(tags.appended.is_none());
+        assert!(tags.v1.is_some());
+    }
+
+    #[test]
+    fn write_with_appended_tag() {
+        let tmp = tempdir::TempDir::new("id3_v2").unwrap();
+        let tmp_name = tmp.path().join("appended.mp3");
+        let mut appended = Tag::new();
+        appended.set_title("Appended");
+        appended.set_artist("Artist");
+        let mut data = vec![0xff, 0xfb, 0x90, 0x00];
+        data.extend(appended_tag_bytes(&appended));
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+
+        let write = |policy| {
+            fs::write(&tmp_name, &data).unwrap();
+            ::stream::tag::EncoderBuilder::default()
+                .appended_tag(policy)
+                .build()
+                .unwrap()
+                .encode_to_path(&tag, &tmp_name)
+                .unwrap();
+            Tag::read_all_from_path(&tmp_name).unwrap()
+        };
+        let tags = write(::AppendedTag::Merge);
+        assert_eq!((Some("Title"), Some("Artist")), (tags.prepended.as_ref().unwrap().title(), tags.prepended.as_ref().unwrap().artist()));
+        assert!(tags.appended.is_none());
+        let tags = write(::AppendedTag::Keep);
+        assert_eq!(Some(tag.clone()), tags.prepended);
+        assert_eq!(Some(appended), tags.appended);
+        let tags = write(::AppendedTag::Remove);
+        assert_eq!(Some(tag.clone()), tags.prepended);
+        assert!(tags.appended.is_none());
+    }
+
+    #[test]
+    fn remove_misplaced_id3v2() {
+        let tmp = tempdir::TempDir::new("id3_v2").unwrap();
+        let tmp_name = tmp.path().join("misplaced.flac");
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+        let mut data = Vec::new();
+        tag.write_to(&mut data, Version::Id3v23).unwrap();
+        let tag_len = data.len() as u64;
+        data.extend_from_slice(b"fLaC\0\0\0\x22");
+        fs::write(&tmp_name, &data).unwrap();
+
+        let mut tag_file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&tmp_name)
+            .unwrap();
+        assert_eq!(Some(0..tag_len), Tag::locate_misplaced(&mut tag_file).unwrap());
+        assert!(Tag::remove_misplaced_from(&mut tag_file).unwrap());
+        assert!(!Tag::remove_misplaced_from(&mut tag_file).unwrap());
+        drop(tag_file);
+        assert_eq!(&b"fLaC\0\0\0\x22"[..], &fs::read(&tmp_name).unwrap()[..]);
+
+        // A regular MP3 file is left alone.
+        let mut tag_file = fs::File::open("testdata/id3v24.id3").unwrap();
+        assert_eq!(None, Tag::locate_misplaced(&mut tag_file).unwrap());
+    }
+
+    #[test]
+    fn merge_policy() {
+        let mut tag = Tag::new();
+        tag.set_title("Title");
+        tag.set_genre("Rock");
+        tag.add_extended_text("Mood", "Happy");
+        tag.get_mut("TCON").unwrap().set_tag_alter_preservation(true);
+        let mut other = Tag::new();
+        other.set_title("Other Title");
+        other.set_genre("Pop");
+        other.add_extended_text("Mood", "Happy\0Calm");
+        other.set_year(2014);
+
+        let mut merged = tag.clone();
+        merged.merge(&other, &MergePolicy::default());
+        assert_eq!((Some("Title"), Some("Rock"), Some(2014)), (merged.title(), merged.genre(), merged.year()));
+
+        let policy = MergePolicy::new(MergeStrategy::KeepTheirs)
+            .with_frame("TCON", MergeStrategy::Combine)
+            .with_frame("TXXX", MergeStrategy::Combine);
+        assert_eq!(MergeStrategy::Combine, policy.strategy("TCON"));
+        let mut merged = tag.clone();
+        merged.merge(&other, &policy);
+        assert_eq!(Some("Other Title"), merged.title());
+        assert_eq!(Some("Rock\0Pop"), merged.genre());
+        assert!(merged.get("TCON").unwrap().tag_alter_preservation());
+        assert_eq!("Happy\0Calm", merged.extended_texts().next().unwrap().value);
+        assert_eq!(4, merged.frames().count());
+
+        // Merging the same values again changes nothing.
+        let before = merged.clone();
+        merged.merge(&other, &policy.clone().with_frame("TIT2", MergeStrategy::Combine));
+        assert!(before.diff(&merged).is_empty());
+    }
 }
@@ -1,5 +1,7 @@
+use std::char;
 use std::collections::HashMap;
-use encoding::{DecoderTrap, EncoderTrap};
+use std::io;
+use encoding::EncoderTrap;
 use encoding::Encoding as StrEncoding;
 use encoding::all::{UTF_16BE, UTF_16LE};
 use ::stream::encoding::Encoding;
@@ -9,7 +11,19 @@ use ::stream::encoding::Encoding;
 /// bytes.
 /// Can never return None because all sequences of u8s are valid Latin1 strings.
 pub fn string_from_latin1(data: &[u8]) -> ::Result<String> {
-    let value: String = data.iter().take_while(|c| **c != 0).map(|b| *b as char).collect();
+    let data = match data.iter().position(|c| *c == 0) {
+        Some(end) => &data[..end],
+        None => data,
+    };
+    // ASCII is a subset of both Latin1 and UTF-8, so the bytes can be copied as a whole.
+    if data.is_ascii() {
+        return Ok(String::from_utf8(data.to_vec())?);
+    }
+    // Latin1 code points map directly to the first 256 unicode code points. Characters above
+    // 0x7F take up two bytes in UTF-8.
+    let non_ascii = data.iter().filter(|b| !b.is_ascii()).count();
+    let mut value = String::with_capacity(data.len() + non_ascii);
+    value.extend(data.iter().map(|b| *b as char));
     Ok(value)
 }
 
@@ -30,19 +44,29 @@ pub fn string_from_utf16(data: &[u8]) -> ::Result<String> {
 /// Returns a string created from the vector using UTF-16LE encoding.
 /// Returns `None` if the vector is not a valid UTF-16LE string.
 pub fn string_from_utf16le(data: &[u8]) -> ::Result<String> {
-    match UTF_16LE.decode(data, DecoderTrap::Strict) {
-        Ok(string) => Ok(string),
-        Err(_) => Err(::Error::new(::ErrorKind::StringDecoding(data.to_vec()), "data is not valid utf16-le"))
-    }
+    string_from_utf16_units(data, |b| u16::from(b[0]) | u16::from(b[1]) << 8)
+        .ok_or_else(|| ::Error::new(::ErrorKind::StringDecoding(data.to_vec()), "data is not valid utf16-le"))
 }
 
 /// Returns a string created from the vector using UTF-16BE encoding.
 /// Returns `None` if the vector is not a valid UTF-16BE string.
 pub fn string_from_utf16be(data: &[u8]) -> ::Result<String> {
-    match UTF_16BE.decode(data, DecoderTrap::Strict) {
-        Ok(string) => Ok(string),
-        Err(_) => Err(::Error::new(::ErrorKind::StringDecoding(data.to_vec()), "data is not valid utf16-be"))
+    string_from_utf16_units(data, |b| u16::from(b[0]) << 8 | u16::from(b[1]))
+        .ok_or_else(|| ::Error::new(::ErrorKind::StringDecoding(data.to_vec()), "data is not valid utf16-be"))
+}
+
+/// Decodes UTF-16 code units which are assembled from byte pairs by `unit`. Returns `None` if the
+/// data has an odd length or contains unpaired surrogates.
+fn string_from_utf16_units<F>(data: &[u8], unit: F) -> Option<String>
+    where F: Fn(&[u8]) -> u16 {
+    if data.len() % 2 != 0 {
+        return None;
+    }
+    let mut value = String::with_capacity(data.len());
+    for c in char::decode_utf16(data.chunks(2).map(unit)) {
+        value.push(c.ok()?);
     }
+    Some(value)
 }
 
 /// Returns a Latin1 vector representation of the string.
@@ -153,6 +177,8 @@ lazy_static! {
         m.insert("TBP", "TBPM");
         m.insert("TCM", "TCOM");
         m.insert("TCO", "TCON");
+        // iTunes uses "TCP" for its compilation flag.
+        m.insert("TCP", "TCMP");
         m.insert("TCR", "TCOP");
         m.insert("TDA", "TDAT");
         m.insert("TDY", "TDLY");
@@ -214,6 +240,125 @@ pub fn convert_id_3_to_2(id: &str) -> Option<&'static str> {
     ID_3_TO_2.get(id).map(|t| *t)
 }
 
+/// Reads until the buffer is full or the end of the reader has been reached. Returns the number
+/// of bytes read.
+///
+/// Unlike a single call to `io::Read::read`, this does not stop early on readers that return
+/// less data than requested, like network streams.
+pub fn read_fully<R>(mut reader: R, buf: &mut [u8]) -> io::Result<usize>
+    where R: io::Read {
+    let mut nread = 0;
+    while nread < buf.len() {
+        match reader.read(&mut buf[nread..]) {
+            Ok(0) => break,
+            Ok(n) => nread += n,
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => (),
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(nread)
+}
+
+/// Computes the SHA-1 digest of the data.
+pub fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476, 0xc3d2_e1f0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5a82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ed9_eba1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+                _ => (b ^ c ^ d, 0xca62_c1d6),
+            };
+            let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (value, add) in state.iter_mut().zip(&[a, b, c, d, e]) {
+            *value = value.wrapping_add(*add);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (chunk, value) in digest.chunks_mut(4).zip(&state) {
+        chunk.copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
+
+/// The standard base64 alphabet of RFC 4648.
+#[cfg(any(feature = "json", feature = "serato"))]
+pub const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes the data as padded base64 using the specified 64 character alphabet and padding
+/// character.
+pub fn base64(data: &[u8], alphabet: &[u8; 64], pad: char) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = (u32::from(chunk[0]) << 16)
+            | (u32::from(*chunk.get(1).unwrap_or(&0)) << 8)
+            | u32::from(*chunk.get(2).unwrap_or(&0));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(alphabet[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push(pad);
+            }
+        }
+    }
+    out
+}
+
+/// Decodes base64 data using the specified alphabet and padding character, ignoring whitespace.
+/// The padding may be left out. Returns `None` if the data is not valid base64.
+#[cfg(any(feature = "json", feature = "serato"))]
+pub fn base64_decode(text: &str, alphabet: &[u8; 64], pad: char) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let (mut bits, mut nbits, mut len) = (0u32, 0, 0);
+    let mut padded = false;
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        len += 1;
+        if c == pad {
+            padded = true;
+            continue;
+        }
+        if padded {
+            return None;
+        }
+        let value = alphabet.iter().position(|a| u32::from(*a) == c as u32)? as u32;
+        bits = bits << 6 | value;
+        nbits += 6;
+        if nbits >= 8 {
+            nbits -= 8;
+            out.push((bits >> nbits) as u8);
+            bits &= (1 << nbits) - 1;
+        }
+    }
+    // A single character can not encode a byte, and padding must complete the last group.
+    if nbits == 6 || (padded && len % 4 != 0) {
+        return None;
+    }
+    Some(out)
+}
+
 // Tests {{{
 #[cfg(test)]
 mod tests {
@@ -243,11 +388,36 @@ mod tests {
         assert_eq!(&string_from_utf16(b"\xFF\xFE\x5B\x01\xD1\x1E\x3C\x04\xC5\x1E\x20\x00\x5B\x01\x67\x01\x57\x01\xC9\x1E\x48\x01\x1D\x01").unwrap()[..], text);
     }
 
+    #[test]
+    fn test_sha1() {
+        let hex = |digest: [u8; 20]| digest.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        assert_eq!("da39a3ee5e6b4b0d3255bfef95601890afd80709", hex(sha1(b"")));
+        assert_eq!("a9993e364706816aba3e25717850c26c9cd0d89d", hex(sha1(b"abc")));
+        assert_eq!("84983e441c3bd26ebaae4aa1f95129e5e54670f1", hex(sha1(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")));
+    }
+
+    #[test]
+    fn test_base64() {
+        let alphabet = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        assert_eq!("", base64(b"", alphabet, '='));
+        assert_eq!("Zg==", base64(b"f", alphabet, '='));
+        assert_eq!("Zm8=", base64(b"fo", alphabet, '='));
+        assert_eq!("Zm9vYmFy", base64(b"foobar", alphabet, '='));
+    }
+
     #[test]
     fn test_latin1() {
         let text: &str = "stringþ";
         assert_eq!(&string_to_latin1(text)[..], b"string\xFE");
         assert_eq!(&string_from_latin1(b"string\xFE").unwrap()[..], text);
+        assert_eq!(&string_from_latin1(b"string\x00garbage").unwrap()[..], "string");
+    }
+
+    #[test]
+    fn test_invalid_utf16() {
+        assert!(string_from_utf16le(b"\x5B\x01\xD1").is_err());
+        assert!(string_from_utf16be(b"\xD8\x00\x00\x20").is_err());
+        assert_eq!(&string_from_utf16be(b"\xD8\x3D\xDE\x00").unwrap()[..], "\u{1F600}");
     }
 
     #[test]
@@ -261,4 +431,19 @@ mod tests {
         assert_eq!(find_delim(Encoding::UTF16BE, &[0x0, 0xFF, 0x0, 0xFF, 0x0, 0x0, 0xFF, 0xFF], 2).unwrap(), 4);
         assert!(find_delim(Encoding::UTF16BE, &[0x0, 0xFF, 0x0, 0xFF, 0x0, 0xFF, 0xFF, 0xFF], 2).is_none());
     }
+
+    #[test]
+    #[cfg(any(feature = "json", feature = "serato"))]
+    fn test_base64_decode() {
+        let alphabet = BASE64_ALPHABET;
+        for data in &[&b""[..], b"f", b"fo", b"foo", b"foob", b"\xff\x00\xfe"] {
+            let text = base64(data, alphabet, '=');
+            assert_eq!(Some(data.to_vec()), base64_decode(&text, alphabet, '='));
+        }
+        assert_eq!(Some(b"fo".to_vec()), base64_decode("Zm\n8", alphabet, '='));
+        assert!(base64_decode("Zm8", alphabet, '=').is_some());
+        assert!(base64_decode("Zm8=Zm8=", alphabet, '=').is_none());
+        assert!(base64_decode("Zm9vY", alphabet, '=').is_none());
+        assert!(base64_decode("Zm9v!", alphabet, '=').is_none());
+    }
 }
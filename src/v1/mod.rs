@@ -1,14 +1,17 @@
 use std::cmp;
 use std::fs;
-use std::io::{self, Read, Seek};
+use std::io::{self, Seek, Write};
 use std::ops;
+use std::path::Path;
 
 /// Location of the ID3v1 tag chunk relative to the end of the file.
 static TAG_CHUNK: ops::Range<i64> = -128..0;
 /// Location of the ID3v1 extended tag chunk relative to the end of the file.
 static XTAG_CHUNK: ops::Range<i64> = -355..-128;
 
-static GENRE_LIST: &[&str] = &[
+/// The genres that ID3v1 tags and references in ID3v2 genre frames (TCON) refer to by their
+/// index, including the Winamp extensions.
+pub static GENRE_LIST: &[&str] = &[
     "Blues", "Classic Rock", "Country", "Dance", "Disco", "Funk", "Grunge", "Hip-Hop",
     "Jazz", "Metal", "New Age", "Oldies", "Other", "Pop", "R&B", "Rap", "Reggae",
     "Rock", "Techno", "Industrial", "Alternative", "Ska", "Death Metal", "Pranks",
@@ -111,19 +114,21 @@ impl Tag {
         };
 
         // Decodes a string consisting out of a base and possible extension to a String.
-        // The input are one or two null-terminated ISO-8859-1 byte slices.
+        // The input are one or two null-terminated ISO-8859-1 byte slices. Some taggers pad the
+        // fields with spaces instead of null bytes, so trailing spaces are removed.
         fn decode_str(base: &[u8], ext: Option<&[u8]>) -> String {
-            base.into_iter()
+            let s: String = base.iter()
                 .take_while(|c| **c != 0)
                 .chain({
                     ext.into_iter()
-                        .flat_map(|s| s.into_iter())
+                        .flat_map(|s| s.iter())
                         .take_while(|c| **c != 0)
                 })
                 // This works because the ISO 8859-1 code points match the unicode code
                 // points. So,`c as char` will map correctly from ISO to unicode.
                 .map(|c| *c as char)
-                .collect()
+                .collect();
+            s.trim_end_matches(' ').to_string()
         }
         let title = decode_str(&tag[3..33], xtag.as_ref().map(|t| &t[4..64]));
         let artist = decode_str(&tag[33..63], xtag.as_ref().map(|t| &t[64..124]));
@@ -139,8 +144,8 @@ impl Tag {
         let (speed, genre_str, start_time, end_time) = if let Some(xt) = xtag {
             let speed = if xt[184] == 0 { None } else { Some(xt[184]) };
             let genre_str = decode_str(&xt[185..215], None);
-            let start_time = decode_str(&xt[185..215], None);
-            let end_time = decode_str(&xt[185..215], None);
+            let start_time = decode_str(&xt[215..221], None);
+            let end_time = decode_str(&xt[221..227], None);
             (speed, Some(genre_str), Some(start_time), Some(end_time))
         } else {
             (None, None, None, None)
@@ -161,43 +166,96 @@ impl Tag {
         })
     }
 
+    /// Attempts to read an ID3v1 tag from the end of the file at the indicated path.
+    pub fn read_from_path<P: AsRef<Path>>(path: P) -> ::Result<Tag> {
+        Tag::read_from(fs::File::open(path)?)
+    }
+
     /// Removes an ID3v1 tag plus possible extended data if any.
     ///
     /// The file cursor position will be reset back to the previous position before returning.
     ///
     /// Returns true if the file initially contained a tag.
     pub fn remove(file: &mut fs::File) -> ::Result<bool> {
-        let cur_pos = file.seek(io::SeekFrom::Current(0))?;
-        let file_len = file.metadata()?.len();
-        let has_ext_tag = if file_len >= XTAG_CHUNK.start.abs() as u64 {
-            file.seek(io::SeekFrom::End(XTAG_CHUNK.start))?;
-            let mut b = [0; 4];
-            file.read_exact(&mut b)?;
-            &b == b"TAG+"
-        } else {
-            false
-        };
-        let has_tag = if file_len >= TAG_CHUNK.start.abs() as u64 {
-            file.seek(io::SeekFrom::End(TAG_CHUNK.start))?;
-            let mut b = [0; 3];
-            file.read_exact(&mut b)?;
-            &b == b"TAG"
-        } else {
-            false
-        };
+        let cur_pos = file.stream_position()?;
+        let location = locate(&mut *file)?;
+        file.seek(io::SeekFrom::Start(cmp::min(location.as_ref().map_or(cur_pos, |l| l.start), cur_pos)))?;
+        if let Some(ref location) = location {
+            file.set_len(location.start)?;
+        }
+        Ok(location.is_some())
+    }
 
-        let truncate_to = if has_ext_tag && has_tag {
-            Some(file_len - XTAG_CHUNK.start.abs() as u64)
-        } else if has_tag {
-            Some(file_len - TAG_CHUNK.start.abs() as u64)
-        } else {
-            None
-        };
-        file.seek(io::SeekFrom::Start(cmp::min(truncate_to.unwrap_or(cur_pos), cur_pos)))?;
-        if let Some(l) = truncate_to {
-            file.set_len(l)?;
+    /// Removes an ID3v1 tag plus possible extended data from the file at the indicated path.
+    ///
+    /// Returns true if the file initially contained a tag.
+    pub fn remove_from_path<P: AsRef<Path>>(path: P) -> ::Result<bool> {
+        let mut file = fs::OpenOptions::new().read(true).write(true).open(path)?;
+        Tag::remove(&mut file)
+    }
+
+    /// Encodes the tag as a 128 byte ID3v1.1 block.
+    ///
+    /// Text is encoded as ISO-8859-1, characters that can not be represented are replaced by
+    /// `'?'`. Fields that are too long are truncated: 30 bytes for the title, artist and album, 4
+    /// bytes for the year and 28 bytes for the comment, or 30 if there is no track number. The
+    /// extended fields are not written.
+    ///
+    /// # Example
+    /// ```
+    /// use id3::v1;
+    ///
+    /// let tag = v1::Tag {
+    ///     title: "A title that is longer than thirty bytes".to_string(),
+    ///     track: Some(3),
+    ///     genre_id: 31,
+    ///     ..v1::Tag::default()
+    /// };
+    /// let data = tag.to_bytes();
+    /// assert_eq!(&data[..33], b"TAGA title that is longer than th");
+    /// assert_eq!(&data[125..], &[0, 3, 31]);
+    /// ```
+    pub fn to_bytes(&self) -> [u8; 128] {
+        fn encode_str(field: &mut [u8], s: &str) {
+            let latin1 = s.chars().map(|c| if (c as u32) < 0x100 { c as u8 } else { b'?' });
+            for (b, c) in field.iter_mut().zip(latin1) {
+                *b = c;
+            }
+        }
+        let mut data = [0; 128];
+        data[0..3].copy_from_slice(b"TAG");
+        encode_str(&mut data[3..33], &self.title);
+        encode_str(&mut data[33..63], &self.artist);
+        encode_str(&mut data[63..93], &self.album);
+        encode_str(&mut data[93..97], &self.year);
+        match self.track {
+            Some(track) if track != 0 => {
+                encode_str(&mut data[97..125], &self.comment);
+                data[126] = track;
+            },
+            _ => encode_str(&mut data[97..127], &self.comment),
         }
-        Ok(truncate_to.is_some())
+        data[127] = self.genre_id;
+        data
+    }
+
+    /// Writes the tag to the end of the file, replacing an existing ID3v1 tag and its extended
+    /// data if any.
+    pub fn write_to_file(&self, file: &mut fs::File) -> ::Result<()> {
+        let end = match locate(&mut *file)? {
+            Some(location) => location.start,
+            None => file.seek(io::SeekFrom::End(0))?,
+        };
+        file.set_len(end)?;
+        file.seek(io::SeekFrom::Start(end))?;
+        file.write_all(&self.to_bytes())?;
+        Ok(())
+    }
+
+    /// Writes the tag to the end of the file at the indicated path, see `write_to_file`.
+    pub fn write_to_path<P: AsRef<Path>>(&self, path: P) -> ::Result<()> {
+        let mut file = fs::OpenOptions::new().read(true).write(true).open(path)?;
+        self.write_to_file(&mut file)
     }
 
     /// Returns `genre_str`, falling back to translating `genre_id` to a string.
@@ -212,6 +270,76 @@ impl Tag {
     }
 }
 
+/// Locates the ID3v1 tag at the end of the reader, including its extended data if any.
+pub fn locate<R>(mut reader: R) -> ::Result<Option<ops::Range<u64>>>
+    where R: io::Read + io::Seek {
+    let file_len = reader.seek(io::SeekFrom::End(0))?;
+    if file_len < TAG_CHUNK.start.unsigned_abs() || !Tag::is_candidate(&mut reader)? {
+        return Ok(None);
+    }
+    if file_len >= XTAG_CHUNK.start.unsigned_abs() {
+        reader.seek(io::SeekFrom::End(XTAG_CHUNK.start))?;
+        let mut b = [0; 4];
+        reader.read_exact(&mut b)?;
+        if &b == b"TAG+" {
+            return Ok(Some(file_len - XTAG_CHUNK.start.unsigned_abs()..file_len));
+        }
+    }
+    Ok(Some(file_len - TAG_CHUNK.start.unsigned_abs()..file_len))
+}
+
+/// Returns the name of the genre with the ID3v1 genre byte, or `None` if it is not in the genre
+/// list.
+///
+/// # Example
+/// ```
+/// use id3::v1;
+///
+/// assert_eq!(v1::genre_name(31), Some("Trance"));
+/// assert_eq!(v1::genre_name(255), None);
+/// ```
+pub fn genre_name(genre_id: u8) -> Option<&'static str> {
+    GENRE_LIST.get(genre_id as usize).cloned()
+}
+
+/// Derives the ID3v1 genre byte from the text of an ID3v2 genre frame (TCON).
+///
+/// The text may be a genre name, which is looked up in the genre list ignoring case, or a
+/// reference to a genre like `"(31)"`, `"(31)Trance"` or `"31"` as written by various taggers.
+/// Returns 255, meaning no genre, if the genre is not in the list.
+///
+/// # Example
+/// ```
+/// use id3::v1;
+///
+/// assert_eq!(v1::genre_id_for("Trance"), 31);
+/// assert_eq!(v1::genre_id_for("trance"), 31);
+/// assert_eq!(v1::genre_id_for("(31)Trance"), 31);
+/// assert_eq!(v1::genre_id_for("31"), 31);
+/// assert_eq!(v1::genre_id_for("Vaporwave"), 255);
+/// ```
+pub fn genre_id_for(genre: &str) -> u8 {
+    let genre = genre.trim();
+    let reference = match genre.strip_prefix('(') {
+        Some(rest) => rest.split(')').next(),
+        None => Some(genre),
+    };
+    if let Some(id) = reference.and_then(|r| r.parse::<u8>().ok()) {
+        if (id as usize) < GENRE_LIST.len() {
+            return id;
+        }
+    }
+    // A reference may be followed by a refinement, like "(31)Trance".
+    let name = match genre.rfind(')') {
+        Some(i) if genre.starts_with('(') => &genre[i + 1..],
+        _ => genre,
+    };
+    GENRE_LIST.iter()
+        .position(|g| g.eq_ignore_ascii_case(name))
+        .map(|i| i as u8)
+        .unwrap_or(255)
+}
+
 
 #[cfg(all(test, feature = "unstable"))]
 mod benchmarks {
@@ -275,4 +403,62 @@ mod tests {
         tag_file.seek(io::SeekFrom::Start(0)).unwrap();
         assert!(!Tag::remove(&mut tag_file).unwrap());
     }
+
+    #[test]
+    fn read_id3v1_extended() {
+        let mut data = vec![0xff; 16];
+        data.extend_from_slice(b"TAG+");
+        let mut field = |text: &[u8], len: usize| {
+            data.extend_from_slice(text);
+            data.extend(vec![0; len - text.len()]);
+        };
+        field(b" Title", 60);
+        field(b" Artist", 60);
+        field(b"", 60);
+        field(&[3], 1);
+        field(b"Darksynth", 30);
+        field(b"000:10", 6);
+        field(b"003:45", 6);
+        field(b"TAGA long title that goes on a", 33);
+        field(b"An artist that is padded with", 30);
+        field(b"Album                         ", 30);
+        field(b"1999Comment", 32);
+        field(&[0, 7, 255], 3);
+
+        let tag = Tag::read_from(io::Cursor::new(&data)).unwrap();
+        assert_eq!("A long title that goes on a Title", tag.title);
+        assert_eq!("An artist that is padded with Artist", tag.artist);
+        assert_eq!("Album", tag.album);
+        assert_eq!("1999", tag.year);
+        assert_eq!("Comment", tag.comment);
+        assert_eq!(Some(7), tag.track);
+        assert_eq!(Some(3), tag.speed);
+        assert_eq!(Some("Darksynth"), tag.genre());
+        assert_eq!(Some("000:10"), tag.start_time.as_deref());
+        assert_eq!(Some("003:45"), tag.end_time.as_deref());
+    }
+
+    #[test]
+    fn write_id3v1() {
+        let tmp = tempdir::TempDir::new("id3_v1").unwrap();
+        let path = tmp.path().join("write_id3v1");
+        fs::write(&path, vec![0xff; 400]).unwrap();
+
+        let mut tag = Tag::read_from_path("testdata/id3v1.id3").unwrap();
+        tag.write_to_path(&path).unwrap();
+        assert_eq!(528, fs::metadata(&path).unwrap().len());
+        assert_eq!(tag, Tag::read_from_path(&path).unwrap());
+
+        tag.title = "Ünïcode ☃".to_string();
+        tag.track = None;
+        tag.comment = "c".repeat(40);
+        tag.write_to_path(&path).unwrap();
+        let read = Tag::read_from_path(&path).unwrap();
+        assert_eq!(528, fs::metadata(&path).unwrap().len());
+        assert_eq!("Ünïcode ?", read.title);
+        assert_eq!("c".repeat(30), read.comment);
+
+        assert!(Tag::remove_from_path(&path).unwrap());
+        assert_eq!(400, fs::metadata(&path).unwrap().len());
+    }
 }
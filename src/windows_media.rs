@@ -0,0 +1,111 @@
+use byteorder::{ByteOrder, LittleEndian};
+use ::frame::Frame;
+
+
+/// The values that Windows Media Player stores in private frames (PRIV).
+///
+/// Each value is stored in a frame of its own, identified by the owner identifier of the frame.
+/// GUIDs are formatted in their usual textual representation, e.g.
+/// `{D1607DBC-E323-4BE2-86A1-48A42A28441E}`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WindowsMediaInfo {
+    /// The primary media class (WM/MediaClassPrimaryID), e.g. music or audio book.
+    pub media_class_primary_id: Option<String>,
+    /// The secondary media class (WM/MediaClassSecondaryID).
+    pub media_class_secondary_id: Option<String>,
+    /// The provider of the file (WM/Provider).
+    pub provider: Option<String>,
+    /// The identifier assigned by the provider (WM/UniqueFileIdentifier).
+    pub unique_file_identifier: Option<String>,
+    /// The content identifier (WM/WMContentID).
+    pub content_id: Option<String>,
+    /// The collection identifier (WM/WMCollectionID).
+    pub collection_id: Option<String>,
+    /// The collection group identifier (WM/WMCollectionGroupID).
+    pub collection_group_id: Option<String>,
+    /// The average volume level determined by volume leveling (AverageLevel).
+    pub average_level: Option<u32>,
+    /// The peak volume level determined by volume leveling (PeakValue).
+    pub peak_value: Option<u32>,
+}
+
+impl WindowsMediaInfo {
+    /// Collects the values from the private frames. Frames that are not written by Windows Media
+    /// Player or that can not be decoded are ignored.
+    pub fn from_frames<'a, I>(frames: I) -> WindowsMediaInfo
+        where I: IntoIterator<Item=&'a Frame> {
+        let mut info = WindowsMediaInfo::default();
+        for private in frames.into_iter().filter_map(|frame| frame.content().private()) {
+            let data = &private.data[..];
+            match &private.owner[..] {
+                "WM/MediaClassPrimaryID" => info.media_class_primary_id = guid(data),
+                "WM/MediaClassSecondaryID" => info.media_class_secondary_id = guid(data),
+                "WM/Provider" => info.provider = text(data),
+                "WM/UniqueFileIdentifier" => info.unique_file_identifier = text(data),
+                "WM/WMContentID" => info.content_id = guid(data),
+                "WM/WMCollectionID" => info.collection_id = guid(data),
+                "WM/WMCollectionGroupID" => info.collection_group_id = guid(data),
+                "AverageLevel" => info.average_level = level(data),
+                "PeakValue" => info.peak_value = level(data),
+                _ => (),
+            }
+        }
+        info
+    }
+}
+
+/// Formats a GUID. The first three groups are stored little endian.
+fn guid(data: &[u8]) -> Option<String> {
+    if data.len() != 16 {
+        return None;
+    }
+    let tail: Vec<String> = data[8..].iter().map(|b| format!("{:02X}", b)).collect();
+    Some(format!("{{{:08X}-{:04X}-{:04X}-{}-{}}}",
+        LittleEndian::read_u32(&data[0..4]),
+        LittleEndian::read_u16(&data[4..6]),
+        LittleEndian::read_u16(&data[6..8]),
+        tail[..2].concat(),
+        tail[2..].concat()))
+}
+
+/// Decodes a null terminated UTF-16LE string.
+fn text(data: &[u8]) -> Option<String> {
+    let value = ::util::string_from_utf16le(data).ok()?;
+    Some(value.trim_end_matches('\u{0}').to_string())
+}
+
+fn level(data: &[u8]) -> Option<u32> {
+    if data.len() != 4 {
+        return None;
+    }
+    Some(LittleEndian::read_u32(data))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::frame::{Content, Private};
+
+    fn priv_frame(owner: &str, data: &[u8]) -> Frame {
+        Frame::with_content("PRIV", Content::Private(Private { owner: owner.to_string(), data: data.to_vec() }))
+    }
+
+    #[test]
+    fn decode_wm_frames() {
+        let music = [0xbc, 0x7d, 0x60, 0xd1, 0x23, 0xe3, 0xe2, 0x4b, 0x86, 0xa1, 0x48, 0xa4, 0x2a, 0x28, 0x44, 0x1e];
+        let frames = vec![
+            priv_frame("WM/MediaClassPrimaryID", &music),
+            priv_frame("WM/Provider", b"A\0M\0G\0\0\0"),
+            priv_frame("AverageLevel", &[0x10, 0x27, 0, 0]),
+            priv_frame("PeakValue", &[0x01, 0x02]),
+            priv_frame("www.example.com", b"other"),
+        ];
+        let info = WindowsMediaInfo::from_frames(&frames);
+        assert_eq!(Some("{D1607DBC-E323-4BE2-86A1-48A42A28441E}"), info.media_class_primary_id.as_deref());
+        assert_eq!(Some("AMG"), info.provider.as_deref());
+        assert_eq!(Some(10000), info.average_level);
+        assert_eq!(None, info.peak_value);
+        assert_eq!(None, info.content_id);
+    }
+}